@@ -25,7 +25,7 @@ use crate::math::spot_balance::get_token_amount;
 use crate::math::spot_withdraw::{
     get_max_withdraw_for_market_with_token_amount, validate_spot_balances,
 };
-use crate::math::{amm, amm_spread, bn, cp_curve, quote_asset::*};
+use crate::math::{amm, amm_spread, bn, cp_curve, lmsr, quote_asset::*};
 
 use crate::state::events::CurveRecord;
 use crate::state::oracle::OraclePriceData;
@@ -79,6 +79,10 @@ pub fn swap_base_asset(
     base_asset_swap_amount: u64,
     direction: SwapDirection,
 ) -> DriftResult<(u64, i64)> {
+    if market.amm.is_lmsr() {
+        return swap_base_asset_with_lmsr(market, base_asset_swap_amount, direction);
+    }
+
     let (
         new_base_asset_reserve,
         new_quote_asset_reserve,
@@ -95,6 +99,58 @@ pub fn swap_base_asset(
     ))
 }
 
+fn swap_base_asset_with_lmsr(
+    market: &mut PerpMarket,
+    base_asset_swap_amount: u64,
+    direction: SwapDirection,
+) -> DriftResult<(u64, i64)> {
+    let quote_asset_amount =
+        lmsr::calculate_lmsr_quote_asset_amount(&market.amm, base_asset_swap_amount, direction)?;
+
+    let base_asset_amount_with_amm = match direction {
+        SwapDirection::Remove => market
+            .amm
+            .base_asset_amount_with_amm
+            .safe_add(base_asset_swap_amount.cast()?)?,
+        SwapDirection::Add => market
+            .amm
+            .base_asset_amount_with_amm
+            .safe_sub(base_asset_swap_amount.cast()?)?,
+    };
+
+    update_reserves_to_lmsr_price(market, base_asset_amount_with_amm)?;
+
+    // lmsr fills have no spread, fees are charged on top
+    Ok((quote_asset_amount, 0))
+}
+
+/// Moves the constant product reserves so the reserve price tracks the lmsr price
+/// for the given net user position. The terminal reserves sit at the 50/50 price
+/// the lmsr starts from
+pub fn update_reserves_to_lmsr_price(
+    market: &mut PerpMarket,
+    base_asset_amount_with_amm: i128,
+) -> DriftResult {
+    let lmsr_price =
+        lmsr::calculate_lmsr_price(base_asset_amount_with_amm, market.amm.lmsr_liquidity)?;
+
+    let quote_asset_reserve = market.calculate_quote_asset_reserve_for_price(lmsr_price)?;
+
+    let invariant_sqrt_u192 = bn::U192::from(market.amm.sqrt_k);
+    let invariant = invariant_sqrt_u192.safe_mul(invariant_sqrt_u192)?;
+    let base_asset_reserve = invariant
+        .safe_div(bn::U192::from(quote_asset_reserve))?
+        .try_to_u128()?;
+
+    market.amm.quote_asset_reserve = quote_asset_reserve;
+    market.amm.base_asset_reserve = base_asset_reserve;
+    market.amm.terminal_quote_asset_reserve = market.calculate_quote_asset_reserve_for_price(
+        lmsr::calculate_lmsr_price(0, market.amm.lmsr_liquidity)?,
+    )?;
+
+    Ok(())
+}
+
 pub fn calculate_base_swap_output_with_spread(
     amm: &AMM,
     base_asset_swap_amount: u64,
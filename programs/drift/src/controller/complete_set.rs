@@ -0,0 +1,633 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::msg;
+
+use crate::controller;
+use crate::controller::amm::update_pnl_pool_and_user_balance;
+use crate::controller::position::{
+    get_position_index, update_position_and_market, update_quote_asset_amount, update_settled_pnl,
+    PositionDelta,
+};
+use crate::controller::spot_balance::transfer_spot_balances;
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::{
+    MAX_PREDICTION_MARKET_PRICE_I64, MAX_PREDICTION_MARKET_PRICE_U128,
+    PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO,
+};
+use crate::math::safe_math::SafeMath;
+use crate::math::spot_balance::get_token_amount;
+use crate::state::oracle_map::OracleMap;
+use crate::state::perp_market::{MarketStatus, PerpMarket};
+use crate::state::perp_market_map::PerpMarketMap;
+use crate::state::prediction_event::{PredictionEvent, PredictionEventStatus};
+use crate::state::spot_market::SpotMarket;
+use crate::state::user::User;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+/// A complete set is one share of every outcome of a prediction event. Exactly one outcome
+/// resolves to MAX_PREDICTION_MARKET_PRICE so a complete set is always worth $1 per share.
+pub fn calculate_complete_set_quote_asset_amount(base_asset_amount: u64) -> DriftResult<u64> {
+    base_asset_amount
+        .cast::<u128>()?
+        .safe_mul(MAX_PREDICTION_MARKET_PRICE_U128)?
+        .safe_div(PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO)?
+        .cast()
+}
+
+/// Splits the $1 per share cost of a complete set across the outcomes in proportion to their prices
+/// so each leg is entered near its fair value. The last leg absorbs any rounding.
+pub fn calculate_complete_set_leg_quote_asset_amounts(
+    outcome_prices: &[i64],
+    quote_asset_amount: u64,
+) -> DriftResult<Vec<u64>> {
+    let outcome_prices = outcome_prices
+        .iter()
+        .map(|price| {
+            (*price)
+                .clamp(0, MAX_PREDICTION_MARKET_PRICE_I64)
+                .cast::<u128>()
+        })
+        .collect::<DriftResult<Vec<u128>>>()?;
+
+    let mut total_price = outcome_prices.iter().sum::<u128>();
+
+    // without any price information every outcome is treated as equally likely
+    let outcome_prices = if total_price == 0 {
+        total_price = outcome_prices.len().cast()?;
+        vec![1_u128; outcome_prices.len()]
+    } else {
+        outcome_prices
+    };
+
+    let mut leg_quote_asset_amounts = Vec::with_capacity(outcome_prices.len());
+    let mut remaining_quote_asset_amount = quote_asset_amount;
+    for (i, outcome_price) in outcome_prices.iter().enumerate() {
+        let leg_quote_asset_amount = if i == outcome_prices.len() - 1 {
+            remaining_quote_asset_amount
+        } else {
+            quote_asset_amount
+                .cast::<u128>()?
+                .safe_mul(*outcome_price)?
+                .safe_div(total_price)?
+                .cast::<u64>()?
+        };
+
+        remaining_quote_asset_amount =
+            remaining_quote_asset_amount.safe_sub(leg_quote_asset_amount)?;
+        leg_quote_asset_amounts.push(leg_quote_asset_amount);
+    }
+
+    Ok(leg_quote_asset_amounts)
+}
+
+pub fn get_outcome_prices(
+    prediction_event: &PredictionEvent,
+    perp_market_map: &PerpMarketMap,
+    oracle_map: &mut OracleMap,
+) -> DriftResult<Vec<i64>> {
+    let mut outcome_prices = Vec::with_capacity(prediction_event.number_of_outcomes as usize);
+    for market_index in prediction_event.outcome_market_indexes() {
+        let perp_market = perp_market_map.get_ref(market_index)?;
+        let oracle_price = oracle_map.get_price_data(&perp_market.oracle_id())?.price;
+        outcome_prices.push(oracle_price);
+    }
+
+    Ok(outcome_prices)
+}
+
+fn validate_complete_set_market(
+    prediction_event: &PredictionEvent,
+    perp_market: &PerpMarket,
+    base_asset_amount: u64,
+) -> DriftResult {
+    validate!(
+        perp_market.is_prediction_event_outcome()
+            && perp_market.prediction_event_index == prediction_event.event_index,
+        ErrorCode::InvalidPredictionEvent,
+        "perp market {} not linked to prediction event {}",
+        perp_market.market_index,
+        prediction_event.event_index
+    )?;
+
+    validate!(
+        base_asset_amount % perp_market.amm.order_step_size == 0,
+        ErrorCode::InvalidCompleteSet,
+        "complete set amount {} not a multiple of perp market {} step size {}",
+        base_asset_amount,
+        perp_market.market_index,
+        perp_market.amm.order_step_size
+    )?;
+
+    Ok(())
+}
+
+/// Moves a complete set leg's value between the user's quote balance and the market's pnl pool,
+/// so the collateral backing outstanding sets sits in the pools that pay them out. A negative
+/// amount is paid into the pool, a positive amount is paid out up to what the pool holds and the
+/// rest is left as unsettled pnl. Returns the amount settled
+fn settle_complete_set_leg(
+    user: &mut User,
+    perp_market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    quote_asset_amount: i64,
+) -> DriftResult<i64> {
+    let quote_asset_amount = if quote_asset_amount > 0 {
+        let pnl_pool_token_amount = get_token_amount(
+            perp_market.pnl_pool.scaled_balance,
+            quote_spot_market,
+            perp_market.pnl_pool.balance_type(),
+        )?;
+        quote_asset_amount.min(pnl_pool_token_amount.cast()?)
+    } else {
+        quote_asset_amount
+    };
+
+    if quote_asset_amount == 0 {
+        return Ok(0);
+    }
+
+    let settled_quote_asset_amount: i64 = update_pnl_pool_and_user_balance(
+        perp_market,
+        quote_spot_market,
+        user,
+        quote_asset_amount.cast()?,
+    )?
+    .cast()?;
+
+    let position_index = get_position_index(&user.perp_positions, perp_market.market_index)?;
+    update_quote_asset_amount(
+        &mut user.perp_positions[position_index],
+        perp_market,
+        -settled_quote_asset_amount,
+    )?;
+    update_settled_pnl(user, position_index, settled_quote_asset_amount)?;
+
+    Ok(settled_quote_asset_amount)
+}
+
+/// Gives the user a long of `base_asset_amount` in every outcome of the event for $1 per share.
+/// The protocol is the counterparty on every leg, which is fully hedged since only one outcome pays out.
+/// Each leg's cost is paid into its market's pnl pool right away and tracked on the event, so the
+/// winning market's pool can be topped up from the others once the event resolves
+pub fn mint_complete_set(
+    user: &mut User,
+    user_key: &Pubkey,
+    prediction_event: &mut PredictionEvent,
+    perp_market_map: &PerpMarketMap,
+    quote_spot_market: &mut SpotMarket,
+    outcome_prices: &[i64],
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<u64> {
+    validate!(
+        prediction_event.status == PredictionEventStatus::Active,
+        ErrorCode::InvalidCompleteSet,
+        "can only mint complete sets for an active prediction event"
+    )?;
+
+    validate!(
+        base_asset_amount > 0,
+        ErrorCode::InvalidCompleteSet,
+        "complete set amount must be greater than 0"
+    )?;
+
+    validate!(
+        outcome_prices.len() == prediction_event.number_of_outcomes as usize,
+        ErrorCode::InvalidCompleteSet,
+        "{} outcome prices for {} outcomes",
+        outcome_prices.len(),
+        prediction_event.number_of_outcomes
+    )?;
+
+    let quote_asset_amount = calculate_complete_set_quote_asset_amount(base_asset_amount)?;
+    let leg_quote_asset_amounts =
+        calculate_complete_set_leg_quote_asset_amounts(outcome_prices, quote_asset_amount)?;
+
+    for (outcome_index, leg_quote_asset_amount) in leg_quote_asset_amounts.into_iter().enumerate() {
+        let market_index = prediction_event.outcome_market_indexes[outcome_index];
+        let mut perp_market = perp_market_map.get_ref_mut(&market_index)?;
+
+        validate_complete_set_market(prediction_event, &perp_market, base_asset_amount)?;
+
+        validate!(
+            perp_market.status == MarketStatus::Active,
+            ErrorCode::MarketActionPaused,
+            "perp market {} not active",
+            market_index
+        )?;
+
+        validate!(
+            !perp_market.is_trading_halted(now),
+            ErrorCode::MarketActionPaused,
+            "perp market {} trading is halted",
+            market_index
+        )?;
+
+        controller::funding::settle_funding_payment(user, user_key, &mut perp_market, now)?;
+
+        let delta = PositionDelta {
+            base_asset_amount: base_asset_amount.cast()?,
+            quote_asset_amount: -leg_quote_asset_amount.cast::<i64>()?,
+            remainder_base_asset_amount: None,
+        };
+
+        update_position_and_market(
+            user.force_get_perp_position_mut(market_index)?,
+            &mut perp_market,
+            &delta,
+        )?;
+
+        perp_market.amm.base_asset_amount_with_complete_sets = perp_market
+            .amm
+            .base_asset_amount_with_complete_sets
+            .safe_add(delta.base_asset_amount)?;
+
+        settle_complete_set_leg(
+            user,
+            &mut perp_market,
+            quote_spot_market,
+            delta.quote_asset_amount,
+        )?;
+
+        prediction_event.complete_set_quote_asset_amounts[outcome_index] = prediction_event
+            .complete_set_quote_asset_amounts[outcome_index]
+            .safe_add(leg_quote_asset_amount)?;
+    }
+
+    Ok(quote_asset_amount)
+}
+
+/// Burns a long of `base_asset_amount` in every outcome of the event back to $1 per share.
+/// Each leg pays out its pro rata share of the collateral its market's pnl pool holds for
+/// outstanding sets. Returns the quote received and the pnl realized across the legs
+pub fn redeem_complete_set(
+    user: &mut User,
+    user_key: &Pubkey,
+    prediction_event: &mut PredictionEvent,
+    perp_market_map: &PerpMarketMap,
+    quote_spot_market: &mut SpotMarket,
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<(u64, i64)> {
+    validate!(
+        matches!(
+            prediction_event.status,
+            PredictionEventStatus::Active | PredictionEventStatus::Resolved
+        ),
+        ErrorCode::InvalidCompleteSet,
+        "can only redeem complete sets for an active or resolved prediction event"
+    )?;
+
+    validate!(
+        base_asset_amount > 0,
+        ErrorCode::InvalidCompleteSet,
+        "complete set amount must be greater than 0"
+    )?;
+
+    let mut quote_asset_amount = 0_u64;
+    let mut pnl = 0_i64;
+    for outcome_index in 0..prediction_event.number_of_outcomes as usize {
+        let market_index = prediction_event.outcome_market_indexes[outcome_index];
+        let mut perp_market = perp_market_map.get_ref_mut(&market_index)?;
+
+        validate_complete_set_market(prediction_event, &perp_market, base_asset_amount)?;
+
+        validate!(
+            !matches!(
+                perp_market.status,
+                MarketStatus::Settlement | MarketStatus::Delisted
+            ),
+            ErrorCode::MarketActionPaused,
+            "perp market {} already settled, use settle pnl instead",
+            market_index
+        )?;
+
+        let complete_sets_outstanding = perp_market.amm.base_asset_amount_with_complete_sets;
+        validate!(
+            complete_sets_outstanding >= base_asset_amount.cast::<i64>()?,
+            ErrorCode::InvalidCompleteSet,
+            "perp market {} only has {} outstanding complete sets",
+            market_index,
+            complete_sets_outstanding
+        )?;
+
+        controller::funding::settle_funding_payment(user, user_key, &mut perp_market, now)?;
+
+        let leg_quote_asset_amount = prediction_event.complete_set_quote_asset_amounts
+            [outcome_index]
+            .cast::<u128>()?
+            .safe_mul(base_asset_amount.cast()?)?
+            .safe_div(complete_sets_outstanding.cast()?)?
+            .cast::<u64>()?;
+
+        let position = user.get_perp_position_mut(market_index)?;
+
+        validate!(
+            position.base_asset_amount >= base_asset_amount.cast::<i64>()?,
+            ErrorCode::InvalidCompleteSet,
+            "user long {} in perp market {} smaller than complete set amount {}",
+            position.base_asset_amount,
+            market_index,
+            base_asset_amount
+        )?;
+
+        let delta = PositionDelta {
+            base_asset_amount: -base_asset_amount.cast::<i64>()?,
+            quote_asset_amount: leg_quote_asset_amount.cast()?,
+            remainder_base_asset_amount: None,
+        };
+
+        let leg_pnl = update_position_and_market(position, &mut perp_market, &delta)?;
+        pnl = pnl.safe_add(leg_pnl)?;
+
+        perp_market.amm.base_asset_amount_with_complete_sets = perp_market
+            .amm
+            .base_asset_amount_with_complete_sets
+            .safe_add(delta.base_asset_amount)?;
+
+        settle_complete_set_leg(
+            user,
+            &mut perp_market,
+            quote_spot_market,
+            delta.quote_asset_amount,
+        )?;
+
+        prediction_event.complete_set_quote_asset_amounts[outcome_index] = prediction_event
+            .complete_set_quote_asset_amounts[outcome_index]
+            .safe_sub(leg_quote_asset_amount)?;
+        quote_asset_amount = quote_asset_amount.safe_add(leg_quote_asset_amount)?;
+    }
+
+    Ok((quote_asset_amount, pnl))
+}
+
+/// Once the event resolves only the winning outcome pays out on complete sets. Moves the set
+/// collateral held by the losing outcomes' pnl pools to the winning outcome's pnl pool
+pub fn transfer_complete_set_collateral_to_winning_outcome(
+    prediction_event: &mut PredictionEvent,
+    perp_market_map: &PerpMarketMap,
+    quote_spot_market: &mut SpotMarket,
+) -> DriftResult {
+    let winning_market_index = prediction_event.get_winning_market_index()?;
+    let winning_outcome_index = prediction_event.winning_outcome_index as usize;
+    let mut winning_perp_market = perp_market_map.get_ref_mut(&winning_market_index)?;
+
+    for outcome_index in 0..prediction_event.number_of_outcomes as usize {
+        if outcome_index == winning_outcome_index {
+            continue;
+        }
+
+        let market_index = prediction_event.outcome_market_indexes[outcome_index];
+        let mut perp_market = perp_market_map.get_ref_mut(&market_index)?;
+
+        let pnl_pool_token_amount = get_token_amount(
+            perp_market.pnl_pool.scaled_balance,
+            quote_spot_market,
+            perp_market.pnl_pool.balance_type(),
+        )?;
+
+        let transfer_amount = prediction_event.complete_set_quote_asset_amounts[outcome_index]
+            .min(pnl_pool_token_amount.cast()?);
+
+        if transfer_amount == 0 {
+            continue;
+        }
+
+        transfer_spot_balances(
+            transfer_amount.cast()?,
+            quote_spot_market,
+            &mut perp_market.pnl_pool,
+            &mut winning_perp_market.pnl_pool,
+        )?;
+
+        prediction_event.complete_set_quote_asset_amounts[outcome_index] = prediction_event
+            .complete_set_quote_asset_amounts[outcome_index]
+            .safe_sub(transfer_amount)?;
+        prediction_event.complete_set_quote_asset_amounts[winning_outcome_index] = prediction_event
+            .complete_set_quote_asset_amounts[winning_outcome_index]
+            .safe_add(transfer_amount)?;
+
+        msg!(
+            "moved {} of complete set collateral from perp market {} to {}",
+            transfer_amount,
+            market_index,
+            winning_market_index
+        );
+    }
+
+    Ok(())
+}
+
+/// Gives `long_user` a long and `short_user` a short of `base_asset_amount` in a standalone binary
+/// prediction market, a YES and a NO share, for $1 per share between them. The long pays the price
+/// and the short pays the rest into the market's pnl pool, which then holds the $1 each set pays out
+pub fn mint_binary_complete_set(
+    long_user: &mut User,
+    long_user_key: &Pubkey,
+    short_user: &mut User,
+    short_user_key: &Pubkey,
+    perp_market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    oracle_price: i64,
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<u64> {
+    validate_binary_complete_set_market(perp_market, base_asset_amount)?;
+
+    validate!(
+        perp_market.status == MarketStatus::Active,
+        ErrorCode::MarketActionPaused,
+        "perp market {} not active",
+        perp_market.market_index
+    )?;
+
+    validate!(
+        !perp_market.is_trading_halted(now),
+        ErrorCode::MarketActionPaused,
+        "perp market {} trading is halted",
+        perp_market.market_index
+    )?;
+
+    let quote_asset_amount = calculate_complete_set_quote_asset_amount(base_asset_amount)?;
+    let (long_quote_asset_amount, short_quote_asset_amount) =
+        calculate_binary_complete_set_leg_quote_asset_amounts(oracle_price, quote_asset_amount)?;
+
+    controller::funding::settle_funding_payment(long_user, long_user_key, perp_market, now)?;
+    controller::funding::settle_funding_payment(short_user, short_user_key, perp_market, now)?;
+
+    // both legs trade at the same price so the positions offset within the market
+    let long_delta = PositionDelta {
+        base_asset_amount: base_asset_amount.cast()?,
+        quote_asset_amount: -long_quote_asset_amount.cast::<i64>()?,
+        remainder_base_asset_amount: None,
+    };
+    update_position_and_market(
+        long_user.force_get_perp_position_mut(perp_market.market_index)?,
+        perp_market,
+        &long_delta,
+    )?;
+
+    let short_delta = PositionDelta {
+        base_asset_amount: -base_asset_amount.cast::<i64>()?,
+        quote_asset_amount: long_quote_asset_amount.cast()?,
+        remainder_base_asset_amount: None,
+    };
+    update_position_and_market(
+        short_user.force_get_perp_position_mut(perp_market.market_index)?,
+        perp_market,
+        &short_delta,
+    )?;
+
+    settle_complete_set_leg(
+        long_user,
+        perp_market,
+        quote_spot_market,
+        -long_quote_asset_amount.cast::<i64>()?,
+    )?;
+    settle_complete_set_leg(
+        short_user,
+        perp_market,
+        quote_spot_market,
+        -short_quote_asset_amount.cast::<i64>()?,
+    )?;
+
+    Ok(quote_asset_amount)
+}
+
+/// Burns a long of `base_asset_amount` from `long_user` and a short from `short_user` in a
+/// standalone binary prediction market back to $1 per share. The long receives the price and the
+/// short the rest. Returns the quote received between them and the pnl realized by each
+pub fn redeem_binary_complete_set(
+    long_user: &mut User,
+    long_user_key: &Pubkey,
+    short_user: &mut User,
+    short_user_key: &Pubkey,
+    perp_market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    oracle_price: i64,
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<(u64, i64, i64)> {
+    validate_binary_complete_set_market(perp_market, base_asset_amount)?;
+
+    validate!(
+        !matches!(
+            perp_market.status,
+            MarketStatus::Settlement | MarketStatus::Delisted
+        ),
+        ErrorCode::MarketActionPaused,
+        "perp market {} already settled, use settle pnl instead",
+        perp_market.market_index
+    )?;
+
+    let quote_asset_amount = calculate_complete_set_quote_asset_amount(base_asset_amount)?;
+    let (long_quote_asset_amount, short_quote_asset_amount) =
+        calculate_binary_complete_set_leg_quote_asset_amounts(oracle_price, quote_asset_amount)?;
+
+    controller::funding::settle_funding_payment(long_user, long_user_key, perp_market, now)?;
+    controller::funding::settle_funding_payment(short_user, short_user_key, perp_market, now)?;
+
+    let long_position = long_user.get_perp_position_mut(perp_market.market_index)?;
+    validate!(
+        long_position.base_asset_amount >= base_asset_amount.cast::<i64>()?,
+        ErrorCode::InvalidCompleteSet,
+        "user long {} in perp market {} smaller than complete set amount {}",
+        long_position.base_asset_amount,
+        perp_market.market_index,
+        base_asset_amount
+    )?;
+
+    let long_delta = PositionDelta {
+        base_asset_amount: -base_asset_amount.cast::<i64>()?,
+        quote_asset_amount: long_quote_asset_amount.cast()?,
+        remainder_base_asset_amount: None,
+    };
+    let long_pnl = update_position_and_market(long_position, perp_market, &long_delta)?;
+
+    let short_position = short_user.get_perp_position_mut(perp_market.market_index)?;
+    validate!(
+        short_position.base_asset_amount <= -base_asset_amount.cast::<i64>()?,
+        ErrorCode::InvalidCompleteSet,
+        "user short {} in perp market {} smaller than complete set amount {}",
+        short_position.base_asset_amount,
+        perp_market.market_index,
+        base_asset_amount
+    )?;
+
+    let short_delta = PositionDelta {
+        base_asset_amount: base_asset_amount.cast()?,
+        quote_asset_amount: -long_quote_asset_amount.cast::<i64>()?,
+        remainder_base_asset_amount: None,
+    };
+    let short_pnl = update_position_and_market(short_position, perp_market, &short_delta)?;
+
+    settle_complete_set_leg(
+        long_user,
+        perp_market,
+        quote_spot_market,
+        long_quote_asset_amount.cast()?,
+    )?;
+    settle_complete_set_leg(
+        short_user,
+        perp_market,
+        quote_spot_market,
+        short_quote_asset_amount.cast()?,
+    )?;
+
+    Ok((quote_asset_amount, long_pnl, short_pnl))
+}
+
+/// Splits the $1 per share of a binary complete set into what the YES (long) and NO (short) share
+/// are worth at `oracle_price`
+pub fn calculate_binary_complete_set_leg_quote_asset_amounts(
+    oracle_price: i64,
+    quote_asset_amount: u64,
+) -> DriftResult<(u64, u64)> {
+    let long_quote_asset_amount = quote_asset_amount
+        .cast::<u128>()?
+        .safe_mul(
+            oracle_price
+                .clamp(0, MAX_PREDICTION_MARKET_PRICE_I64)
+                .cast()?,
+        )?
+        .safe_div(MAX_PREDICTION_MARKET_PRICE_U128)?
+        .cast::<u64>()?;
+
+    let short_quote_asset_amount = quote_asset_amount.safe_sub(long_quote_asset_amount)?;
+
+    Ok((long_quote_asset_amount, short_quote_asset_amount))
+}
+
+fn validate_binary_complete_set_market(
+    perp_market: &PerpMarket,
+    base_asset_amount: u64,
+) -> DriftResult {
+    validate!(
+        perp_market.is_prediction_market()
+            && !perp_market.is_prediction_event_outcome()
+            && !perp_market.is_scalar_market(),
+        ErrorCode::InvalidCompleteSet,
+        "perp market {} is not a standalone binary prediction market",
+        perp_market.market_index
+    )?;
+
+    validate!(
+        base_asset_amount > 0,
+        ErrorCode::InvalidCompleteSet,
+        "complete set amount must be greater than 0"
+    )?;
+
+    validate!(
+        base_asset_amount % perp_market.amm.order_step_size == 0,
+        ErrorCode::InvalidCompleteSet,
+        "complete set amount {} not a multiple of perp market {} step size {}",
+        base_asset_amount,
+        perp_market.market_index,
+        perp_market.amm.order_step_size
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,668 @@
+mod calculate_complete_set_leg_quote_asset_amounts {
+    use crate::controller::complete_set::{
+        calculate_complete_set_leg_quote_asset_amounts, calculate_complete_set_quote_asset_amount,
+    };
+    use crate::{BASE_PRECISION_U64, PRICE_PRECISION_I64, QUOTE_PRECISION_U64};
+
+    #[test]
+    fn quote_asset_amount() {
+        let quote_asset_amount =
+            calculate_complete_set_quote_asset_amount(3 * BASE_PRECISION_U64).unwrap();
+        assert_eq!(quote_asset_amount, 3 * QUOTE_PRECISION_U64);
+
+        let quote_asset_amount =
+            calculate_complete_set_quote_asset_amount(BASE_PRECISION_U64 / 2).unwrap();
+        assert_eq!(quote_asset_amount, QUOTE_PRECISION_U64 / 2);
+    }
+
+    #[test]
+    fn proportional_to_price() {
+        let leg_quote_asset_amounts = calculate_complete_set_leg_quote_asset_amounts(
+            &[
+                PRICE_PRECISION_I64 / 2,
+                PRICE_PRECISION_I64 / 4,
+                PRICE_PRECISION_I64 / 4,
+            ],
+            4 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(
+            leg_quote_asset_amounts,
+            vec![
+                2 * QUOTE_PRECISION_U64,
+                QUOTE_PRECISION_U64,
+                QUOTE_PRECISION_U64
+            ]
+        );
+
+        // prices dont sum to $1, still split the full $1
+        let leg_quote_asset_amounts = calculate_complete_set_leg_quote_asset_amounts(
+            &[PRICE_PRECISION_I64 / 2, PRICE_PRECISION_I64 / 4],
+            3 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(
+            leg_quote_asset_amounts,
+            vec![2 * QUOTE_PRECISION_U64, QUOTE_PRECISION_U64]
+        );
+    }
+
+    #[test]
+    fn rounding_goes_to_last_leg() {
+        let leg_quote_asset_amounts = calculate_complete_set_leg_quote_asset_amounts(
+            &[PRICE_PRECISION_I64 / 3; 3],
+            QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(leg_quote_asset_amounts, vec![333333, 333333, 333334]);
+        assert_eq!(
+            leg_quote_asset_amounts.iter().sum::<u64>(),
+            QUOTE_PRECISION_U64
+        );
+    }
+
+    #[test]
+    fn no_price_information() {
+        let leg_quote_asset_amounts =
+            calculate_complete_set_leg_quote_asset_amounts(&[0, -1], 2 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(
+            leg_quote_asset_amounts,
+            vec![QUOTE_PRECISION_U64, QUOTE_PRECISION_U64]
+        );
+    }
+}
+
+mod mint_and_redeem_complete_set {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Owner;
+
+    use crate::controller::complete_set::{
+        mint_complete_set, redeem_complete_set, transfer_complete_set_collateral_to_winning_outcome,
+    };
+    use crate::create_anchor_account_info;
+    use crate::state::oracle::OracleSource;
+    use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket, PoolBalance, AMM};
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::prediction_event::{PredictionEvent, PredictionEventStatus};
+    use crate::state::spot_market::{SpotBalanceType, SpotMarket};
+    use crate::state::user::{SpotPosition, User};
+    use crate::test_utils::{create_account_info, get_anchor_account_bytes, get_spot_positions};
+    use crate::validation::perp_market::validate_perp_market;
+    use crate::{
+        AMM_RESERVE_PRECISION, BASE_PRECISION_I128, BASE_PRECISION_I64, BASE_PRECISION_U64,
+        PEG_PRECISION, PRICE_PRECISION_I64, QUOTE_PRECISION_I64, QUOTE_PRECISION_U64,
+        QUOTE_SPOT_MARKET_INDEX, SPOT_BALANCE_PRECISION, SPOT_BALANCE_PRECISION_U64,
+        SPOT_CUMULATIVE_INTEREST_PRECISION,
+    };
+
+    fn outcome_market(market_index: u16) -> PerpMarket {
+        PerpMarket {
+            market_index,
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::Active,
+            prediction_event_index: 0,
+            prediction_event_outcomes: 2,
+            pnl_pool: PoolBalance {
+                market_index: QUOTE_SPOT_MARKET_INDEX,
+                ..PoolBalance::default()
+            },
+            amm: AMM {
+                base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                terminal_quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                sqrt_k: 100 * AMM_RESERVE_PRECISION,
+                peg_multiplier: PEG_PRECISION / 2,
+                order_step_size: BASE_PRECISION_U64 / 10,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        }
+    }
+
+    fn prediction_event() -> PredictionEvent {
+        let mut outcome_market_indexes = [0; 16];
+        outcome_market_indexes[1] = 1;
+
+        PredictionEvent {
+            outcome_market_indexes,
+            number_of_outcomes: 2,
+            status: PredictionEventStatus::Active,
+            ..PredictionEvent::default()
+        }
+    }
+
+    fn quote_spot_market() -> SpotMarket {
+        SpotMarket {
+            market_index: QUOTE_SPOT_MARKET_INDEX,
+            oracle_source: OracleSource::QuoteAsset,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            cumulative_borrow_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            deposit_balance: 200 * SPOT_BALANCE_PRECISION,
+            ..SpotMarket::default()
+        }
+    }
+
+    fn user() -> User {
+        User {
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: QUOTE_SPOT_MARKET_INDEX,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+                ..SpotPosition::default()
+            }),
+            ..User::default()
+        }
+    }
+
+    #[test]
+    fn mint_then_redeem() {
+        let mut yes_market = outcome_market(0);
+        create_anchor_account_info!(yes_market, PerpMarket, yes_market_account_info);
+        let mut no_market = outcome_market(1);
+        create_anchor_account_info!(no_market, PerpMarket, no_market_account_info);
+        let perp_market_map = PerpMarketMap::load_multiple(
+            vec![&yes_market_account_info, &no_market_account_info],
+            true,
+        )
+        .unwrap();
+
+        let mut prediction_event = prediction_event();
+        let mut spot_market = quote_spot_market();
+        let mut user = user();
+        let user_key = Pubkey::default();
+
+        let quote_asset_amount = mint_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            &[PRICE_PRECISION_I64 * 6 / 10, PRICE_PRECISION_I64 * 4 / 10],
+            10 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote_asset_amount, 10 * QUOTE_PRECISION_U64);
+
+        // each leg is paid into its market's pnl pool
+        assert_eq!(
+            user.get_quote_spot_position().scaled_balance,
+            90 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(
+            prediction_event.complete_set_quote_asset_amounts[..2],
+            [6 * QUOTE_PRECISION_U64, 4 * QUOTE_PRECISION_U64]
+        );
+
+        let yes_position = user.get_perp_position(0).unwrap();
+        assert_eq!(yes_position.base_asset_amount, 10 * BASE_PRECISION_I64);
+        assert_eq!(yes_position.quote_asset_amount, 0);
+        assert_eq!(yes_position.quote_entry_amount, -6 * QUOTE_PRECISION_I64);
+        assert_eq!(yes_position.settled_pnl, -6 * QUOTE_PRECISION_I64);
+
+        let no_position = user.get_perp_position(1).unwrap();
+        assert_eq!(no_position.base_asset_amount, 10 * BASE_PRECISION_I64);
+        assert_eq!(no_position.quote_asset_amount, 0);
+        assert_eq!(no_position.quote_entry_amount, -4 * QUOTE_PRECISION_I64);
+
+        for (market_index, pnl_pool_balance) in [(0, 6), (1, 4)] {
+            let perp_market = perp_market_map.get_ref(&market_index).unwrap();
+            assert_eq!(
+                perp_market.amm.base_asset_amount_long,
+                10 * BASE_PRECISION_I128
+            );
+            assert_eq!(
+                perp_market.amm.base_asset_amount_with_complete_sets,
+                10 * BASE_PRECISION_I64
+            );
+            assert_eq!(perp_market.amm.base_asset_amount_with_amm, 0);
+            assert_eq!(perp_market.number_of_users_with_base, 1);
+            assert_eq!(
+                perp_market.pnl_pool.scaled_balance,
+                pnl_pool_balance * SPOT_BALANCE_PRECISION
+            );
+            validate_perp_market(&perp_market).unwrap();
+        }
+
+        // cant redeem more than is held
+        assert!(redeem_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            11 * BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+
+        // each leg pays back its share of the collateral, pnl nets out across the legs
+        let (quote_asset_amount, pnl) = redeem_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            4 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote_asset_amount, 4 * QUOTE_PRECISION_U64);
+        assert_eq!(pnl, 0);
+
+        assert_eq!(
+            user.get_quote_spot_position().scaled_balance,
+            94 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(
+            prediction_event.complete_set_quote_asset_amounts[..2],
+            [36 * QUOTE_PRECISION_U64 / 10, 24 * QUOTE_PRECISION_U64 / 10]
+        );
+
+        let yes_position = user.get_perp_position(0).unwrap();
+        assert_eq!(yes_position.base_asset_amount, 6 * BASE_PRECISION_I64);
+        assert_eq!(
+            yes_position.quote_entry_amount,
+            -36 * QUOTE_PRECISION_I64 / 10
+        );
+        assert_eq!(yes_position.quote_asset_amount, 0);
+
+        let no_position = user.get_perp_position(1).unwrap();
+        assert_eq!(no_position.base_asset_amount, 6 * BASE_PRECISION_I64);
+        assert_eq!(
+            no_position.quote_entry_amount,
+            -24 * QUOTE_PRECISION_I64 / 10
+        );
+        assert_eq!(no_position.quote_asset_amount, 0);
+
+        for (market_index, pnl_pool_balance) in [(0, 36), (1, 24)] {
+            let perp_market = perp_market_map.get_ref(&market_index).unwrap();
+            assert_eq!(
+                perp_market.amm.base_asset_amount_with_complete_sets,
+                6 * BASE_PRECISION_I64
+            );
+            assert_eq!(
+                perp_market.pnl_pool.scaled_balance,
+                pnl_pool_balance * SPOT_BALANCE_PRECISION / 10
+            );
+            validate_perp_market(&perp_market).unwrap();
+        }
+    }
+
+    #[test]
+    fn resolve_funds_winning_outcome() {
+        let mut yes_market = outcome_market(0);
+        create_anchor_account_info!(yes_market, PerpMarket, yes_market_account_info);
+        let mut no_market = outcome_market(1);
+        create_anchor_account_info!(no_market, PerpMarket, no_market_account_info);
+        let perp_market_map = PerpMarketMap::load_multiple(
+            vec![&yes_market_account_info, &no_market_account_info],
+            true,
+        )
+        .unwrap();
+
+        let mut prediction_event = prediction_event();
+        let mut spot_market = quote_spot_market();
+        let mut user = user();
+        let user_key = Pubkey::default();
+
+        mint_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            &[PRICE_PRECISION_I64 * 6 / 10, PRICE_PRECISION_I64 * 4 / 10],
+            10 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+
+        // event must be resolved first
+        assert!(transfer_complete_set_collateral_to_winning_outcome(
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+        )
+        .is_err());
+
+        prediction_event.resolve(0).unwrap();
+        transfer_complete_set_collateral_to_winning_outcome(
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+        )
+        .unwrap();
+
+        // winning outcome's pnl pool holds the full $1 per outstanding set
+        assert_eq!(
+            prediction_event.complete_set_quote_asset_amounts[..2],
+            [10 * QUOTE_PRECISION_U64, 0]
+        );
+        assert_eq!(
+            perp_market_map.get_ref(&0).unwrap().pnl_pool.scaled_balance,
+            10 * SPOT_BALANCE_PRECISION
+        );
+        assert_eq!(
+            perp_market_map.get_ref(&1).unwrap().pnl_pool.scaled_balance,
+            0
+        );
+
+        // redeeming after resolution pays out of the winning outcome alone
+        let (quote_asset_amount, pnl) = redeem_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            10 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote_asset_amount, 10 * QUOTE_PRECISION_U64);
+        assert_eq!(pnl, 0);
+        assert_eq!(
+            user.get_perp_position(0).unwrap().settled_pnl,
+            4 * QUOTE_PRECISION_I64
+        );
+        assert_eq!(
+            user.get_perp_position(1).unwrap().settled_pnl,
+            -4 * QUOTE_PRECISION_I64
+        );
+        assert_eq!(
+            user.get_quote_spot_position().scaled_balance,
+            100 * SPOT_BALANCE_PRECISION_U64
+        );
+
+        for market_index in [0, 1] {
+            let perp_market = perp_market_map.get_ref(&market_index).unwrap();
+            assert_eq!(perp_market.amm.base_asset_amount_with_complete_sets, 0);
+            assert_eq!(perp_market.pnl_pool.scaled_balance, 0);
+            validate_perp_market(&perp_market).unwrap();
+        }
+    }
+
+    #[test]
+    fn invalid_mint() {
+        let mut yes_market = outcome_market(0);
+        create_anchor_account_info!(yes_market, PerpMarket, yes_market_account_info);
+        let mut no_market = PerpMarket {
+            status: MarketStatus::ReduceOnly,
+            ..outcome_market(1)
+        };
+        create_anchor_account_info!(no_market, PerpMarket, no_market_account_info);
+        let perp_market_map = PerpMarketMap::load_multiple(
+            vec![&yes_market_account_info, &no_market_account_info],
+            true,
+        )
+        .unwrap();
+
+        let mut spot_market = quote_spot_market();
+        let mut user = user();
+        let user_key = Pubkey::default();
+        let outcome_prices = [PRICE_PRECISION_I64 / 2, PRICE_PRECISION_I64 / 2];
+
+        // outcome market is reduce only
+        assert!(mint_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event(),
+            &perp_market_map,
+            &mut spot_market,
+            &outcome_prices,
+            BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+
+        // event isnt active
+        let mut prediction_event = PredictionEvent {
+            status: PredictionEventStatus::Resolved,
+            ..prediction_event()
+        };
+        assert!(mint_complete_set(
+            &mut user,
+            &user_key,
+            &mut prediction_event,
+            &perp_market_map,
+            &mut spot_market,
+            &outcome_prices,
+            BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+    }
+}
+
+mod mint_and_redeem_binary_complete_set {
+    use anchor_lang::prelude::Pubkey;
+
+    use crate::controller::complete_set::{mint_binary_complete_set, redeem_binary_complete_set};
+    use crate::state::oracle::OracleSource;
+    use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket, PoolBalance, AMM};
+    use crate::state::spot_market::{SpotBalanceType, SpotMarket};
+    use crate::state::user::{SpotPosition, User};
+    use crate::test_utils::get_spot_positions;
+    use crate::validation::perp_market::validate_perp_market;
+    use crate::{
+        AMM_RESERVE_PRECISION, BASE_PRECISION_I128, BASE_PRECISION_I64, BASE_PRECISION_U64,
+        PEG_PRECISION, PRICE_PRECISION_I64, QUOTE_PRECISION_I64, QUOTE_PRECISION_U64,
+        QUOTE_SPOT_MARKET_INDEX, SPOT_BALANCE_PRECISION, SPOT_BALANCE_PRECISION_U64,
+        SPOT_CUMULATIVE_INTEREST_PRECISION,
+    };
+
+    fn binary_market() -> PerpMarket {
+        PerpMarket {
+            market_index: 0,
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::Active,
+            pnl_pool: PoolBalance {
+                market_index: QUOTE_SPOT_MARKET_INDEX,
+                ..PoolBalance::default()
+            },
+            amm: AMM {
+                base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                terminal_quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                sqrt_k: 100 * AMM_RESERVE_PRECISION,
+                peg_multiplier: PEG_PRECISION / 2,
+                order_step_size: BASE_PRECISION_U64 / 10,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        }
+    }
+
+    fn quote_spot_market() -> SpotMarket {
+        SpotMarket {
+            market_index: QUOTE_SPOT_MARKET_INDEX,
+            oracle_source: OracleSource::QuoteAsset,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            cumulative_borrow_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            deposit_balance: 200 * SPOT_BALANCE_PRECISION,
+            ..SpotMarket::default()
+        }
+    }
+
+    fn user() -> User {
+        User {
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: QUOTE_SPOT_MARKET_INDEX,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+                ..SpotPosition::default()
+            }),
+            ..User::default()
+        }
+    }
+
+    #[test]
+    fn mint_then_redeem() {
+        let mut market = binary_market();
+        let mut spot_market = quote_spot_market();
+        let mut long_user = user();
+        let mut short_user = user();
+        let long_user_key = Pubkey::new_unique();
+        let short_user_key = Pubkey::new_unique();
+
+        let quote_asset_amount = mint_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 * 6 / 10,
+            10 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote_asset_amount, 10 * QUOTE_PRECISION_U64);
+
+        // yes share costs the price, no share the rest, pnl pool holds $1 per set
+        assert_eq!(
+            long_user.get_quote_spot_position().scaled_balance,
+            94 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(
+            short_user.get_quote_spot_position().scaled_balance,
+            96 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(market.pnl_pool.scaled_balance, 10 * SPOT_BALANCE_PRECISION);
+
+        let long_position = long_user.get_perp_position(0).unwrap();
+        assert_eq!(long_position.base_asset_amount, 10 * BASE_PRECISION_I64);
+        assert_eq!(long_position.quote_asset_amount, 0);
+        assert_eq!(long_position.quote_entry_amount, -6 * QUOTE_PRECISION_I64);
+
+        let short_position = short_user.get_perp_position(0).unwrap();
+        assert_eq!(short_position.base_asset_amount, -10 * BASE_PRECISION_I64);
+        assert_eq!(short_position.quote_asset_amount, 10 * QUOTE_PRECISION_I64);
+        assert_eq!(short_position.quote_entry_amount, 6 * QUOTE_PRECISION_I64);
+
+        assert_eq!(market.amm.base_asset_amount_long, 10 * BASE_PRECISION_I128);
+        assert_eq!(
+            market.amm.base_asset_amount_short,
+            -10 * BASE_PRECISION_I128
+        );
+        assert_eq!(market.number_of_users_with_base, 2);
+        validate_perp_market(&market).unwrap();
+
+        // cant redeem more than either side holds
+        assert!(redeem_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 * 9 / 10,
+            11 * BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+
+        // price moved, the yes share gained what the no share lost
+        let (quote_asset_amount, long_pnl, short_pnl) = redeem_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 * 9 / 10,
+            10 * BASE_PRECISION_U64,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote_asset_amount, 10 * QUOTE_PRECISION_U64);
+        assert_eq!(long_pnl, 3 * QUOTE_PRECISION_I64);
+        assert_eq!(short_pnl, -3 * QUOTE_PRECISION_I64);
+
+        assert_eq!(
+            long_user.get_quote_spot_position().scaled_balance,
+            103 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(
+            short_user.get_quote_spot_position().scaled_balance,
+            97 * SPOT_BALANCE_PRECISION_U64
+        );
+        assert_eq!(market.pnl_pool.scaled_balance, 0);
+
+        for user in [&long_user, &short_user] {
+            let position = user.get_perp_position(0).unwrap();
+            assert_eq!(position.base_asset_amount, 0);
+            assert_eq!(position.quote_asset_amount, 0);
+        }
+
+        assert_eq!(market.amm.base_asset_amount_long, 0);
+        assert_eq!(market.amm.base_asset_amount_short, 0);
+        validate_perp_market(&market).unwrap();
+    }
+
+    #[test]
+    fn invalid_market() {
+        let mut spot_market = quote_spot_market();
+        let mut long_user = user();
+        let mut short_user = user();
+        let long_user_key = Pubkey::new_unique();
+        let short_user_key = Pubkey::new_unique();
+
+        // outcome of a prediction event uses the event complete set
+        let mut market = PerpMarket {
+            prediction_event_outcomes: 2,
+            ..binary_market()
+        };
+        assert!(mint_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 / 2,
+            BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+
+        // not a prediction market
+        let mut market = PerpMarket {
+            contract_type: ContractType::Perpetual,
+            ..binary_market()
+        };
+        assert!(mint_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 / 2,
+            BASE_PRECISION_U64,
+            0,
+        )
+        .is_err());
+
+        // trading halt ts reached before a keeper halted the market
+        let mut market = PerpMarket {
+            trading_halt_ts: 100,
+            ..binary_market()
+        };
+        assert!(mint_binary_complete_set(
+            &mut long_user,
+            &long_user_key,
+            &mut short_user,
+            &short_user_key,
+            &mut market,
+            &mut spot_market,
+            PRICE_PRECISION_I64 / 2,
+            BASE_PRECISION_U64,
+            100,
+        )
+        .is_err());
+    }
+}
@@ -25,8 +25,9 @@ use crate::state::events::{FundingPaymentRecord, FundingRateRecord};
 use crate::state::oracle_map::OracleMap;
 use crate::state::perp_market::{PerpMarket, AMM};
 use crate::state::perp_market_map::PerpMarketMap;
+use crate::state::price_history::PriceSampleType;
 use crate::state::state::OracleGuardRails;
-use crate::state::user::User;
+use crate::state::user::{MarketType, User};
 
 pub fn settle_funding_payment(
     user: &mut User,
@@ -167,11 +168,24 @@ pub fn update_funding_rate(
         Some(reserve_price) => reserve_price,
         None => market.amm.reserve_price()?,
     };
+    oracle_map.validate_oracle_guard_rails_override(
+        MarketType::Perp,
+        market_index,
+        market.has_oracle_guard_rails_override(),
+    )?;
+    oracle_map.validate_price_history(
+        MarketType::Perp,
+        market_index,
+        market.has_price_history(),
+    )?;
+    let guard_rails =
+        oracle_map.get_market_oracle_guard_rails(MarketType::Perp, market_index, guard_rails);
+
     // Pause funding if oracle is invalid or if mark/oracle spread is too divergent
     let block_funding_rate_update = oracle::block_operation(
         market,
         oracle_map.get_price_data(&market.oracle_id())?,
-        guard_rails,
+        &guard_rails,
         reserve_price,
         slot,
     )?;
@@ -186,16 +200,26 @@ pub fn update_funding_rate(
         !funding_paused && !block_funding_rate_update && (time_until_next_update == 0);
 
     if valid_funding_update {
+        // prefer the twap over the funding period from the market's price history, if it has one
+        let price_history_oracle_twap = oracle_map.get_price_history_twap(
+            MarketType::Perp,
+            market_index,
+            now,
+            market.amm.funding_period,
+            PriceSampleType::Oracle,
+        )?;
+
         let oracle_price_data = oracle_map.get_price_data(&market.oracle_id())?;
         let sanitize_clamp_denominator = market.get_sanitize_clamp_denominator()?;
 
-        let oracle_price_twap = amm::update_oracle_price_twap(
+        let amm_oracle_price_twap = amm::update_oracle_price_twap(
             &mut market.amm,
             now,
             oracle_price_data,
             Some(reserve_price),
             sanitize_clamp_denominator,
         )?;
+        let oracle_price_twap = price_history_oracle_twap.unwrap_or(amm_oracle_price_twap);
 
         // price relates to execution premium / direction
         let (execution_premium_price, execution_premium_direction) =
@@ -38,7 +38,8 @@ use crate::math::liquidation::{
 };
 use crate::math::margin::{
     calculate_margin_requirement_and_total_collateral_and_liability_info,
-    calculate_user_safest_position_tiers, meets_initial_margin_requirement, MarginRequirementType,
+    calculate_prediction_event_netted_margin_ratio, calculate_user_safest_position_tiers,
+    meets_initial_margin_requirement, MarginRequirementType,
 };
 use crate::math::oracle::DriftAction;
 use crate::math::orders::{
@@ -201,12 +202,17 @@ pub fn liquidate_perp(
     )?;
 
     let mut market = perp_market_map.get_ref_mut(&market_index)?;
-    let oracle_price_data = oracle_map.get_price_data(&market.oracle_id())?;
+    let (oracle_price_data, validity_guard_rails) = oracle_map.get_price_data_and_guard_rails(
+        MarketType::Perp,
+        market_index,
+        &market.oracle_id(),
+    )?;
 
     update_amm_and_check_validity(
         &mut market,
         oracle_price_data,
         state,
+        &validity_guard_rails,
         now,
         slot,
         Some(DriftAction::Liquidate),
@@ -334,7 +340,14 @@ pub fn liquidate_perp(
         user.is_high_leverage_mode(),
     )?;
 
-    let margin_ratio_with_buffer = margin_ratio.safe_add(liquidation_margin_buffer_ratio)?;
+    // closing a prediction event outcome only frees its share of the event's netted liability
+    let margin_ratio_with_buffer = calculate_prediction_event_netted_margin_ratio(
+        user,
+        perp_market_map,
+        oracle_map,
+        market_index,
+        margin_ratio.safe_add(liquidation_margin_buffer_ratio)?,
+    )?;
 
     let margin_shortage = intermediate_margin_calculation.margin_shortage()?;
 
@@ -789,12 +802,17 @@ pub fn liquidate_perp_with_fill(
     )?;
 
     let mut market = perp_market_map.get_ref_mut(&market_index)?;
-    let oracle_price_data = oracle_map.get_price_data(&market.oracle_id())?;
+    let (oracle_price_data, validity_guard_rails) = oracle_map.get_price_data_and_guard_rails(
+        MarketType::Perp,
+        market_index,
+        &market.oracle_id(),
+    )?;
 
     update_amm_and_check_validity(
         &mut market,
         oracle_price_data,
         state,
+        &validity_guard_rails,
         now,
         slot,
         Some(DriftAction::Liquidate),
@@ -911,7 +929,14 @@ pub fn liquidate_perp_with_fill(
         user.is_high_leverage_mode(),
     )?;
 
-    let margin_ratio_with_buffer = margin_ratio.safe_add(liquidation_margin_buffer_ratio)?;
+    // closing a prediction event outcome only frees its share of the event's netted liability
+    let margin_ratio_with_buffer = calculate_prediction_event_netted_margin_ratio(
+        user,
+        perp_market_map,
+        oracle_map,
+        market_index,
+        margin_ratio.safe_add(liquidation_margin_buffer_ratio)?,
+    )?;
 
     let margin_shortage = intermediate_margin_calculation.margin_shortage()?;
 
@@ -1236,13 +1261,16 @@ pub fn liquidate_spot(
 
     let (asset_amount, asset_price, asset_decimals, asset_weight, asset_liquidation_multiplier) = {
         let mut asset_market = spot_market_map.get_ref_mut(&asset_market_index)?;
-        let (asset_price_data, validity_guard_rails) =
-            oracle_map.get_price_data_and_guard_rails(&asset_market.oracle_id())?;
+        let (asset_price_data, validity_guard_rails) = oracle_map.get_price_data_and_guard_rails(
+            MarketType::Spot,
+            asset_market.market_index,
+            &asset_market.oracle_id(),
+        )?;
 
         update_spot_market_and_check_validity(
             &mut asset_market,
             asset_price_data,
-            validity_guard_rails,
+            &validity_guard_rails,
             now,
             Some(DriftAction::Liquidate),
         )?;
@@ -1285,13 +1313,17 @@ pub fn liquidate_spot(
         liability_liquidation_multiplier,
     ) = {
         let mut liability_market = spot_market_map.get_ref_mut(&liability_market_index)?;
-        let (liability_price_data, validity_guard_rails) =
-            oracle_map.get_price_data_and_guard_rails(&liability_market.oracle_id())?;
+        let (liability_price_data, validity_guard_rails) = oracle_map
+            .get_price_data_and_guard_rails(
+                MarketType::Spot,
+                liability_market.market_index,
+                &liability_market.oracle_id(),
+            )?;
 
         update_spot_market_and_check_validity(
             &mut liability_market,
             liability_price_data,
-            validity_guard_rails,
+            &validity_guard_rails,
             now,
             Some(DriftAction::Liquidate),
         )?;
@@ -1851,13 +1883,17 @@ pub fn liquidate_borrow_for_perp_pnl(
         liability_liquidation_multiplier,
     ) = {
         let mut liability_market = spot_market_map.get_ref_mut(&liability_market_index)?;
-        let (liability_price_data, validity_guard_rails) =
-            oracle_map.get_price_data_and_guard_rails(&liability_market.oracle_id())?;
+        let (liability_price_data, validity_guard_rails) = oracle_map
+            .get_price_data_and_guard_rails(
+                MarketType::Spot,
+                liability_market.market_index,
+                &liability_market.oracle_id(),
+            )?;
 
         update_spot_market_and_check_validity(
             &mut liability_market,
             liability_price_data,
-            validity_guard_rails,
+            &validity_guard_rails,
             now,
             Some(DriftAction::Liquidate),
         )?;
@@ -2281,13 +2317,16 @@ pub fn liquidate_perp_pnl_for_deposit(
         asset_liquidation_multiplier,
     ) = {
         let mut asset_market = spot_market_map.get_ref_mut(&asset_market_index)?;
-        let (asset_price_data, validity_guard_rails) =
-            oracle_map.get_price_data_and_guard_rails(&asset_market.oracle_id())?;
+        let (asset_price_data, validity_guard_rails) = oracle_map.get_price_data_and_guard_rails(
+            MarketType::Spot,
+            asset_market.market_index,
+            &asset_market.oracle_id(),
+        )?;
 
         update_spot_market_and_check_validity(
             &mut asset_market,
             asset_price_data,
-            validity_guard_rails,
+            &validity_guard_rails,
             now,
             Some(DriftAction::Liquidate),
         )?;
@@ -3689,6 +3689,7 @@ pub mod liquidate_spot {
         let strict_price_1 = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(oracle_price_data.price / 10),
+            confidence_haircut: 0,
         };
         let strict_token_value_1 =
             get_strict_token_value(token_amount as i128, 6, &strict_price_1).unwrap();
@@ -3696,6 +3697,7 @@ pub mod liquidate_spot {
         let strict_price_2 = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(oracle_price_data.price * 2),
+            confidence_haircut: 0,
         };
         let strict_token_value_2 =
             get_strict_token_value(token_amount as i128, 6, &strict_price_2).unwrap();
@@ -3703,6 +3705,7 @@ pub mod liquidate_spot {
         let strict_price_3 = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(oracle_price_data.price * 2),
+            confidence_haircut: 0,
         };
         let strict_token_value_3 =
             get_strict_token_value(-(token_amount as i128), 6, &strict_price_3).unwrap();
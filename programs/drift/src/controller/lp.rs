@@ -366,6 +366,12 @@ pub fn remove_perp_lp_shares(
 
     let mut market = perp_market_map.get_ref_mut(&market_index)?;
 
+    validate!(
+        !market.is_trading_halted(now),
+        ErrorCode::MarketActionPaused,
+        "Market trading is halted"
+    )?;
+
     let time_since_last_add_liquidity = now.safe_sub(user.last_add_perp_lp_shares_ts)?;
 
     validate!(
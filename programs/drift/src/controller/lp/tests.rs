@@ -761,6 +761,7 @@ fn test_lp_margin_calc() {
         0,
         false,
         false,
+        None,
     )
     .unwrap();
 
@@ -0,0 +1,86 @@
+use crate::controller;
+use crate::error::DriftResult;
+use crate::math::margin::{validate_spot_margin_trading, MarginRequirementType};
+use crate::state::oracle_map::OracleMap;
+use crate::state::perp_market_map::PerpMarketMap;
+use crate::state::spot_market::SpotBalanceType;
+use crate::state::spot_market_map::SpotMarketMap;
+use crate::state::user::{User, UserStats};
+
+/// Moves the bond out of the user's spot balance. The tokens stay in the spot market vault
+/// while the bond is held by the market's resolution.
+#[allow(clippy::too_many_arguments)]
+pub fn post_resolution_bond(
+    user: &mut User,
+    user_stats: &mut UserStats,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    bond_spot_market_index: u16,
+    bond_amount: u64,
+    now: i64,
+) -> DriftResult {
+    {
+        let spot_market = &mut spot_market_map.get_ref_mut(&bond_spot_market_index)?;
+        let oracle_price_data = oracle_map.get_price_data(&spot_market.oracle_id())?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            spot_market,
+            Some(oracle_price_data),
+            now,
+        )?;
+
+        // prevents posting bond when withdraw limits hit
+        controller::spot_position::update_spot_balances_and_cumulative_deposits_with_limits(
+            bond_amount as u128,
+            &SpotBalanceType::Borrow,
+            spot_market,
+            user,
+        )?;
+    }
+
+    user.meets_withdraw_margin_requirement_and_increment_fuel_bonus(
+        perp_market_map,
+        spot_market_map,
+        oracle_map,
+        MarginRequirementType::Initial,
+        bond_spot_market_index,
+        bond_amount as u128,
+        user_stats,
+        now,
+    )?;
+
+    validate_spot_margin_trading(user, perp_market_map, spot_market_map, oracle_map)?;
+
+    Ok(())
+}
+
+/// Credits a returned or won bond back to the user's spot balance
+pub fn pay_resolution_bond(
+    user: &mut User,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    bond_spot_market_index: u16,
+    bond_amount: u64,
+    now: i64,
+) -> DriftResult {
+    let spot_market = &mut spot_market_map.get_ref_mut(&bond_spot_market_index)?;
+    let oracle_price_data = oracle_map.get_price_data(&spot_market.oracle_id())?;
+    controller::spot_balance::update_spot_market_cumulative_interest(
+        spot_market,
+        Some(oracle_price_data),
+        now,
+    )?;
+
+    let spot_position = user.force_get_spot_position_mut(bond_spot_market_index)?;
+
+    controller::spot_position::update_spot_balances_and_cumulative_deposits(
+        bond_amount as u128,
+        &SpotBalanceType::Deposit,
+        spot_market,
+        spot_position,
+        false,
+        None,
+    )?;
+
+    Ok(())
+}
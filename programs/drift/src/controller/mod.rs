@@ -1,12 +1,15 @@
 pub mod amm;
+pub mod complete_set;
 pub mod funding;
 pub mod insurance;
 pub mod liquidation;
 pub mod lp;
+pub mod market_resolution;
 pub mod orders;
 pub mod pda;
 pub mod pnl;
 pub mod position;
+pub mod prediction_shares;
 pub mod repeg;
 pub mod spot_balance;
 pub mod spot_position;
@@ -30,7 +30,9 @@ use crate::math::amm::calculate_amm_available_liquidity;
 use crate::math::amm_jit::calculate_amm_jit_liquidity;
 use crate::math::auction::{calculate_auction_params_for_trigger_order, calculate_auction_prices};
 use crate::math::casting::Cast;
-use crate::math::constants::{BASE_PRECISION_U64, PERP_DECIMALS, QUOTE_SPOT_MARKET_INDEX};
+use crate::math::constants::{
+    BASE_PRECISION_U64, PERCENTAGE_PRECISION_U64, PERP_DECIMALS, QUOTE_SPOT_MARKET_INDEX,
+};
 use crate::math::fees::{determine_user_fee_tier, ExternalFillFees, FillFees};
 use crate::math::fulfillment::{
     determine_perp_fulfillment_methods, determine_spot_fulfillment_methods,
@@ -58,22 +60,26 @@ use crate::state::margin_calculation::{MarginCalculation, MarginContext};
 use crate::state::oracle::{OraclePriceData, StrictOraclePrice};
 use crate::state::oracle_map::OracleMap;
 use crate::state::order_params::{
-    ModifyOrderParams, ModifyOrderPolicy, OrderParams, PlaceOrderOptions, PostOnlyParam, RFQMatch,
+    ModifyOrderParams, ModifyOrderPolicy, OrderLinkType, OrderParams, ParentOrderParams,
+    PlaceOrderOptions, PostOnlyParam, RFQMatch, TrailingStopDistance,
 };
+use crate::state::parent_order::{ParentOrder, ParentOrderType, UserParentOrders};
 use crate::state::paused_operations::{PerpOperation, SpotOperation};
 use crate::state::perp_market::{AMMAvailability, AMMLiquiditySplit, MarketStatus, PerpMarket};
 use crate::state::perp_market_map::PerpMarketMap;
+use crate::state::price_history::PriceSampleType;
 use crate::state::rfq_user::{RFQOrderId, RFQUser};
 use crate::state::spot_fulfillment_params::{ExternalSpotFill, SpotFulfillmentParams};
 use crate::state::spot_market::{SpotBalanceType, SpotMarket};
 use crate::state::spot_market_map::SpotMarketMap;
 use crate::state::state::FeeStructure;
 use crate::state::state::*;
+use crate::state::trailing_stop::{TrailingStop, UserTrailingStops};
 use crate::state::traits::Size;
 use crate::state::user::{
-    AssetType, Order, OrderStatus, OrderTriggerCondition, OrderType, UserStats,
+    AssetType, Order, OrderStatus, OrderTriggerCondition, OrderTriggerSource, OrderType, UserStats,
 };
-use crate::state::user::{MarketType, User};
+use crate::state::user::{MarketType, SelfTradePreventionMode, User};
 use crate::state::user_map::{UserMap, UserStatsMap};
 use crate::validate;
 use crate::validation;
@@ -181,6 +187,12 @@ pub fn place_perp_order(
         "Market is in settlement mode",
     )?;
 
+    validate!(
+        !market.is_trading_halted(now),
+        ErrorCode::MarketPlaceOrderPaused,
+        "Market trading is halted",
+    )?;
+
     let position_index = get_position_index(&user.perp_positions, market_index)
         .or_else(|_| add_new_position(&mut user.perp_positions, market_index))?;
 
@@ -257,7 +269,28 @@ pub fn place_perp_order(
         "must be perp order"
     )?;
 
-    let new_order = Order {
+    if options.trigger_source != OrderTriggerSource::Oracle {
+        validate!(
+            matches!(
+                params.order_type,
+                OrderType::TriggerMarket | OrderType::TriggerLimit
+            ),
+            ErrorCode::InvalidOrderTriggerMarket,
+            "only trigger orders can be triggered by another market"
+        )?;
+
+        validate!(
+            options.trigger_market_index != market_index,
+            ErrorCode::InvalidOrderTriggerMarket,
+            "trigger market must differ from order market {}",
+            market_index
+        )?;
+
+        // trigger market must be passed in so keepers can find it
+        perp_market_map.get_ref(&options.trigger_market_index)?;
+    }
+
+    let mut new_order = Order {
         status: OrderStatus::Open,
         order_type: params.order_type,
         market_type: params.market_type,
@@ -290,9 +323,18 @@ pub fn place_perp_order(
         auction_end_price,
         auction_duration,
         max_ts,
-        padding: [0; 3],
+        trigger_source: options.trigger_source,
+        trigger_market_index: options.trigger_market_index,
     };
 
+    if let Some(trailing_stop_distance) = options.trailing_stop_distance {
+        init_trailing_stop_order(
+            &mut new_order,
+            trailing_stop_distance,
+            oracle_price_data.price,
+        )?;
+    }
+
     let valid_oracle_price = Some(oracle_price_data.price);
     match validate_order(&new_order, market, valid_oracle_price, slot) {
         Ok(()) => {}
@@ -314,6 +356,7 @@ pub fn place_perp_order(
 
     user.increment_open_orders(new_order.has_auction());
     user.orders[new_order_index] = new_order;
+    user.unlink_orders(1 << new_order_index);
     user.perp_positions[position_index].open_orders += 1;
     if !new_order.must_be_triggered() {
         increase_open_bids_and_asks(
@@ -462,6 +505,7 @@ pub fn place_and_match_rfq_orders<'c: 'info, 'info>(
             ..OrderParams::default()
         };
         let mut maker = makers_and_referrer.get_ref_mut(&maker_pubkey)?;
+        let is_self_trade = taker.get_self_trade_prevention_mode(&maker).is_some();
 
         // See if the UUID already exists in the RFQ Account data
         let mut rfq_account =
@@ -549,6 +593,53 @@ pub fn place_and_match_rfq_orders<'c: 'info, 'info>(
                 FillMode::RFQ,
             )?;
 
+            if is_self_trade {
+                // the fill applied the self trade prevention mode instead of matching the orders.
+                // whatever it left of the immediate or cancel orders is canceled
+                let mut taker = load_mut!(taker_account_loader)?;
+                if let Ok(taker_order_index) = taker.get_order_index(taker_order_id) {
+                    cancel_order(
+                        taker_order_index,
+                        &mut taker,
+                        &taker_key,
+                        perp_market_map,
+                        spot_market_map,
+                        oracle_map,
+                        clock.unix_timestamp,
+                        clock.slot,
+                        OrderActionExplanation::None,
+                        None,
+                        0,
+                        false,
+                    )?;
+                }
+
+                let mut maker = makers_and_referrer.get_ref_mut(&maker_pubkey)?;
+                if let Ok(maker_order_index) = maker.get_order_index(maker_order_id) {
+                    cancel_order(
+                        maker_order_index,
+                        &mut maker,
+                        &maker_pubkey,
+                        perp_market_map,
+                        spot_market_map,
+                        oracle_map,
+                        clock.unix_timestamp,
+                        clock.slot,
+                        OrderActionExplanation::None,
+                        None,
+                        0,
+                        false,
+                    )?;
+                }
+
+                msg!(
+                    "RFQ order canceled to prevent self trade for maker {} and taker {}",
+                    maker_pubkey,
+                    taker_key
+                );
+                continue;
+            }
+
             if base_asset_amount_filled != taker_order_params.base_asset_amount {
                 msg!(
                     "RFQ order was partially filled for maker {} and taker {}",
@@ -796,25 +887,68 @@ pub fn cancel_order(
     filler_reward: u64,
     skip_log: bool,
 ) -> DriftResult {
-    let (order_status, order_market_index, order_direction, order_market_type) = get_struct_values!(
-        user.orders[order_index],
-        status,
-        market_index,
-        direction,
-        market_type
-    );
-
-    let is_perp_order = order_market_type == MarketType::Perp;
+    let (order_status, order_market_index, order_market_type) =
+        get_struct_values!(user.orders[order_index], status, market_index, market_type);
 
     validate!(order_status == OrderStatus::Open, ErrorCode::OrderNotOpen)?;
 
-    let oracle_id = if is_perp_order {
+    let oracle_id = if order_market_type == MarketType::Perp {
         perp_market_map.get_ref(&order_market_index)?.oracle_id()
     } else {
         spot_market_map.get_ref(&order_market_index)?.oracle_id()
     };
 
-    if !skip_log {
+    let oracle_price = if skip_log {
+        None
+    } else {
+        Some(oracle_map.get_price_data(&oracle_id)?.price)
+    };
+
+    // canceling an order cancels the rest of its one-cancels-other group. an entry order
+    // canceled before it fills takes its bracket orders with it
+    cancel_linked_orders(
+        order_index,
+        user,
+        user_key,
+        now,
+        filler_key,
+        oracle_price,
+        true,
+    )?;
+
+    _cancel_order(
+        order_index,
+        user,
+        user_key,
+        now,
+        explanation,
+        filler_key,
+        filler_reward,
+        oracle_price,
+    )
+}
+
+/// Removes an open order, logging the cancel if `oracle_price` is passed
+fn _cancel_order(
+    order_index: usize,
+    user: &mut User,
+    user_key: &Pubkey,
+    now: i64,
+    explanation: OrderActionExplanation,
+    filler_key: Option<&Pubkey>,
+    filler_reward: u64,
+    oracle_price: Option<i64>,
+) -> DriftResult {
+    let (order_market_index, order_direction, order_market_type) = get_struct_values!(
+        user.orders[order_index],
+        market_index,
+        direction,
+        market_type
+    );
+
+    let is_perp_order = order_market_type == MarketType::Perp;
+
+    if let Some(oracle_price) = oracle_price {
         let (taker, taker_order, maker, maker_order) =
             get_taker_and_maker_for_order_record(user_key, &user.orders[order_index]);
 
@@ -837,7 +971,7 @@ pub fn cancel_order(
             taker_order,
             maker,
             maker_order,
-            oracle_map.get_price_data(&oracle_id)?.price,
+            oracle_price,
         )?;
         emit_stack::<_, { OrderActionRecord::SIZE }>(order_action_record)?;
     }
@@ -859,7 +993,9 @@ pub fn cancel_order(
         }
 
         user.perp_positions[position_index].open_orders -= 1;
+
         user.orders[order_index] = Order::default();
+        user.unlink_orders(1 << order_index);
     } else {
         let spot_position_index = user.get_spot_position_index(order_market_index)?;
 
@@ -875,8 +1011,466 @@ pub fn cancel_order(
         }
         user.spot_positions[spot_position_index].open_orders -= 1;
         user.orders[order_index] = Order::default();
+        user.unlink_orders(1 << order_index);
+    }
+
+    Ok(())
+}
+
+/// Unlinks the one-cancels-other group of the order at `order_index` and cancels the rest of it.
+/// Linked orders are always in the same market as the order, so `oracle_price` is shared
+fn cancel_linked_orders(
+    order_index: usize,
+    user: &mut User,
+    user_key: &Pubkey,
+    now: i64,
+    filler_key: Option<&Pubkey>,
+    oracle_price: Option<i64>,
+    cancel_pending_bracket_orders: bool,
+) -> DriftResult {
+    let mut linked_order_mask = user.get_linked_order_group_mask(order_index);
+    if cancel_pending_bracket_orders {
+        linked_order_mask |= user.get_pending_bracket_order_mask(order_index);
+    }
+
+    if linked_order_mask == 0 {
+        return Ok(());
+    }
+
+    user.unlink_orders(linked_order_mask);
+
+    for linked_order_index in 0..user.orders.len() {
+        if linked_order_index == order_index
+            || linked_order_mask & (1 << linked_order_index) == 0
+            || user.orders[linked_order_index].status != OrderStatus::Open
+        {
+            continue;
+        }
+
+        _cancel_order(
+            linked_order_index,
+            user,
+            user_key,
+            now,
+            OrderActionExplanation::CanceledByLinkedOrder,
+            filler_key,
+            0,
+            oracle_price,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Shrinks the rest of the bracket of the order at `order_index` to the perp position left after
+/// the order filled. Once the position is closed the rest of the bracket is canceled
+fn resize_bracket_orders(
+    order_index: usize,
+    user: &mut User,
+    user_key: &Pubkey,
+    now: i64,
+    oracle_price: i64,
+) -> DriftResult {
+    let market_index = user.orders[order_index].market_index;
+    let position_index = get_position_index(&user.perp_positions, market_index)?;
+    let position_base_asset_amount = user.perp_positions[position_index]
+        .base_asset_amount
+        .unsigned_abs();
+
+    if position_base_asset_amount == 0 {
+        return cancel_linked_orders(
+            order_index,
+            user,
+            user_key,
+            now,
+            None,
+            Some(oracle_price),
+            false,
+        );
+    }
+
+    let linked_order_mask = user.get_linked_order_group_mask(order_index);
+    if user.orders[order_index].status != OrderStatus::Open {
+        user.unlink_orders(1 << order_index);
+    }
+
+    for linked_order_index in 0..user.orders.len() {
+        if linked_order_index == order_index
+            || linked_order_mask & (1 << linked_order_index) == 0
+            || user.orders[linked_order_index].status != OrderStatus::Open
+        {
+            continue;
+        }
+
+        let linked_order = &mut user.orders[linked_order_index];
+        let base_asset_amount_unfilled = linked_order.get_base_asset_amount_unfilled(None)?;
+        if base_asset_amount_unfilled <= position_base_asset_amount {
+            continue;
+        }
+
+        let excess_base_asset_amount =
+            base_asset_amount_unfilled.safe_sub(position_base_asset_amount)?;
+        linked_order.base_asset_amount = linked_order
+            .base_asset_amount
+            .safe_sub(excess_base_asset_amount)?;
+
+        // only triggered orders count towards open bids/asks
+        if linked_order.triggered() {
+            let direction = linked_order.direction;
+            decrease_open_bids_and_asks(
+                &mut user.perp_positions[position_index],
+                &direction,
+                excess_base_asset_amount,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Links the orders with ids `first_order_id..=last_order_id` into a one-cancels-other group.
+/// A bracket group follows its entry order's id and can't trigger until the entry fills
+pub fn link_orders(
+    user: &mut User,
+    first_order_id: u32,
+    last_order_id: u32,
+    order_link_type: OrderLinkType,
+) -> DriftResult {
+    validate!(
+        first_order_id <= last_order_id,
+        ErrorCode::InvalidOrderLink,
+        "no orders to link"
+    )?;
+
+    let first_order = user.orders[user.get_order_index(first_order_id).map_err(|_| {
+        msg!("order {} to link is not open", first_order_id);
+        ErrorCode::InvalidOrderLink
+    })?];
+
+    let mut linked_order_mask = 0_u32;
+    for order_id in first_order_id..=last_order_id {
+        let order_index = user.get_order_index(order_id).map_err(|_| {
+            msg!("order {} to link is not open", order_id);
+            ErrorCode::InvalidOrderLink
+        })?;
+        let order = &user.orders[order_index];
+
+        validate!(
+            !user.is_linked_order(order_index),
+            ErrorCode::InvalidOrderLink,
+            "order {} is already linked",
+            order_id
+        )?;
+
+        validate!(
+            order.market_type == first_order.market_type
+                && order.market_index == first_order.market_index,
+            ErrorCode::InvalidOrderLink,
+            "linked orders must be in the same market"
+        )?;
+
+        if order_link_type == OrderLinkType::Bracket {
+            validate!(
+                order.must_be_triggered()
+                    && order.reduce_only
+                    && order.direction == first_order.direction,
+                ErrorCode::InvalidOrderLink,
+                "bracket order {} must be a reduce only trigger order in the same direction as the others",
+                order_id
+            )?;
+        }
+
+        linked_order_mask |= 1 << order_index;
+    }
+
+    let prev_order_index = first_order_id
+        .checked_sub(1)
+        .and_then(|prev_order_id| user.get_order_index(prev_order_id).ok());
+
+    match order_link_type {
+        OrderLinkType::OneCancelsOther => {
+            validate!(
+                linked_order_mask.count_ones() >= 2,
+                ErrorCode::InvalidOrderLink,
+                "one-cancels-other group needs at least two orders"
+            )?;
+
+            // the id before a group is left unused so it can't run into an earlier group
+            validate!(
+                prev_order_index.is_none(),
+                ErrorCode::InvalidOrderLink,
+                "order id {} before one-cancels-other group is in use",
+                first_order_id.saturating_sub(1)
+            )?;
+        }
+        OrderLinkType::Bracket => {
+            let entry_order_index = prev_order_index.ok_or_else(|| {
+                msg!(
+                    "bracket entry order {} is not open",
+                    first_order_id.saturating_sub(1)
+                );
+                ErrorCode::InvalidOrderLink
+            })?;
+            let entry_order = user.orders[entry_order_index];
+
+            validate!(
+                !user.is_linked_order(entry_order_index)
+                    && entry_order.base_asset_amount_filled == 0,
+                ErrorCode::InvalidOrderLink,
+                "bracket entry order {} must be unlinked and unfilled",
+                entry_order.order_id
+            )?;
+
+            validate!(
+                entry_order.market_type == first_order.market_type
+                    && entry_order.market_index == first_order.market_index
+                    && entry_order.direction == first_order.direction.opposite(),
+                ErrorCode::InvalidOrderLink,
+                "bracket orders must be in the entry order's market and opposite its direction"
+            )?;
+        }
+    }
+
+    user.link_orders(linked_order_mask);
+
+    Ok(())
+}
+
+pub fn place_parent_order(
+    user: &mut User,
+    user_parent_orders: &mut UserParentOrders,
+    perp_market_map: &PerpMarketMap,
+    params: ParentOrderParams,
+) -> DriftResult {
+    let market = perp_market_map.get_ref(&params.market_index)?;
+    let step_size = market.amm.order_step_size;
+
+    let base_asset_amount = standardize_base_asset_amount(params.base_asset_amount, step_size)?;
+    let slice_base_asset_amount =
+        standardize_base_asset_amount(params.slice_base_asset_amount, step_size)?;
+    let price = standardize_price(params.price, market.amm.order_tick_size, params.direction)?;
+
+    validate!(
+        slice_base_asset_amount >= step_size.max(market.amm.min_order_size),
+        ErrorCode::InvalidParentOrder,
+        "slice size {} below market min order size",
+        slice_base_asset_amount
+    )?;
+
+    validate!(
+        slice_base_asset_amount <= base_asset_amount,
+        ErrorCode::InvalidParentOrder,
+        "slice size {} > total size {}",
+        slice_base_asset_amount,
+        base_asset_amount
+    )?;
+
+    validate!(
+        price > 0,
+        ErrorCode::InvalidParentOrder,
+        "parent order must have a limit price"
+    )?;
+
+    drop(market);
+
+    let parent_order_id = get_then_update_id!(user, next_order_id);
+    user_parent_orders.add_parent_order(ParentOrder {
+        base_asset_amount,
+        slice_base_asset_amount,
+        price,
+        interval_slots: params.interval_slots,
+        parent_order_id,
+        market_index: params.market_index,
+        parent_order_type: params.parent_order_type,
+        direction: params.direction,
+        reduce_only: params.reduce_only,
+        post_only: params.post_only,
+        ..ParentOrder::default()
+    })?;
+
+    msg!("placed parent order {}", parent_order_id);
+
+    Ok(())
+}
+
+pub fn release_parent_order_slice(
+    parent_order_id: u32,
+    state: &State,
+    user: &AccountLoader<User>,
+    user_parent_orders: &AccountLoader<UserParentOrders>,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    filler: &AccountLoader<User>,
+    clock: &Clock,
+) -> DriftResult {
+    let now = clock.unix_timestamp;
+    let slot = clock.slot;
+
+    let filler_key = filler.key();
+    let user_key = user.key();
+    let user = &mut load_mut!(user)?;
+    let user_parent_orders = &mut load_mut!(user_parent_orders)?;
+    let parent_order = user_parent_orders.get_parent_order_mut(parent_order_id)?;
+
+    validate!(
+        !user.is_heartbeat_expired(now),
+        ErrorCode::HeartbeatExpired,
+        "heartbeat deadline {} now {}",
+        user.heartbeat_deadline_ts,
+        now
+    )?;
+
+    let next_release_slot = parent_order.get_next_release_slot()?;
+    validate!(
+        slot >= next_release_slot,
+        ErrorCode::ParentOrderSliceNotReady,
+        "next slice can be released at slot {}",
+        next_release_slot
+    )?;
+
+    // a slice that filled or was canceled outside its parent order counts as released in full.
+    // the unfilled part of a stale twap slice goes back to the parent
+    if let Ok(child_order_index) = user.get_order_index(parent_order.child_order_id) {
+        validate!(
+            parent_order.parent_order_type == ParentOrderType::Twap,
+            ErrorCode::ParentOrderSliceNotReady,
+            "iceberg slice {} has not filled",
+            parent_order.child_order_id
+        )?;
+
+        let base_asset_amount_unfilled =
+            user.orders[child_order_index].get_base_asset_amount_unfilled(None)?;
+
+        cancel_order(
+            child_order_index,
+            user,
+            &user_key,
+            perp_market_map,
+            spot_market_map,
+            oracle_map,
+            now,
+            slot,
+            OrderActionExplanation::ParentOrderSliceReplaced,
+            Some(&filler_key),
+            0,
+            false,
+        )?;
+
+        parent_order.base_asset_amount_released = parent_order
+            .base_asset_amount_released
+            .safe_sub(base_asset_amount_unfilled)?;
+    }
+
+    let base_asset_amount = parent_order.get_next_slice_base_asset_amount()?;
+    if base_asset_amount == 0 {
+        msg!("parent order {} complete", parent_order_id);
+        *parent_order = ParentOrder::default();
+        return Ok(());
+    }
+
+    let market_index = parent_order.market_index;
+    let params = OrderParams {
+        order_type: OrderType::Limit,
+        market_type: MarketType::Perp,
+        direction: parent_order.direction,
+        base_asset_amount,
+        price: parent_order.price,
+        market_index,
+        reduce_only: parent_order.reduce_only,
+        post_only: if parent_order.post_only {
+            PostOnlyParam::MustPostOnly
+        } else {
+            PostOnlyParam::None
+        },
+        ..OrderParams::default()
+    };
+
+    let child_order_id = user.next_order_id;
+    place_perp_order(
+        state,
+        user,
+        user_key,
+        perp_market_map,
+        spot_market_map,
+        oracle_map,
+        clock,
+        params,
+        PlaceOrderOptions::default().explanation(OrderActionExplanation::ParentOrderSliceReleased),
+    )?;
+
+    validate!(
+        user.get_order_index(child_order_id).is_ok(),
+        ErrorCode::InvalidParentOrder,
+        "parent order {} slice was not placed",
+        parent_order_id
+    )?;
+
+    parent_order.base_asset_amount_released = parent_order
+        .base_asset_amount_released
+        .safe_add(base_asset_amount)?;
+    parent_order.child_order_id = child_order_id;
+    parent_order.last_release_slot = slot;
+
+    let is_filler_user = user_key == filler_key;
+    let mut filler = if !is_filler_user {
+        Some(load_mut!(filler)?)
+    } else {
+        None
+    };
+
+    let mut perp_market = perp_market_map.get_ref_mut(&market_index)?;
+    let filler_reward = pay_keeper_flat_reward_for_perps(
+        user,
+        filler.as_deref_mut(),
+        &mut perp_market,
+        state.perp_fee_structure.flat_filler_fee,
+        slot,
+    )?;
+
+    msg!(
+        "released parent order {} slice {} for filler reward {}",
+        parent_order_id,
+        child_order_id,
+        filler_reward
+    );
+
+    Ok(())
+}
+
+/// Cancels the parent order along with its open slice
+pub fn cancel_parent_order(
+    parent_order_id: u32,
+    user: &mut User,
+    user_key: &Pubkey,
+    user_parent_orders: &mut UserParentOrders,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    now: i64,
+    slot: u64,
+) -> DriftResult {
+    let parent_order = user_parent_orders.get_parent_order_mut(parent_order_id)?;
+
+    if let Ok(child_order_index) = user.get_order_index(parent_order.child_order_id) {
+        cancel_order(
+            child_order_index,
+            user,
+            user_key,
+            perp_market_map,
+            spot_market_map,
+            oracle_map,
+            now,
+            slot,
+            OrderActionExplanation::None,
+            None,
+            0,
+            false,
+        )?;
     }
 
+    *parent_order = ParentOrder::default();
+
     Ok(())
 }
 
@@ -925,6 +1519,12 @@ pub fn modify_order(
         },
     };
 
+    validate!(
+        !user.is_linked_order(order_index) && user.get_pending_bracket_order_mask(order_index) == 0,
+        ErrorCode::InvalidOrderLink,
+        "linked orders can not be modified"
+    )?;
+
     let existing_order = user.orders[order_index];
 
     cancel_order(
@@ -958,7 +1558,10 @@ pub fn modify_order(
                 oracle_map,
                 clock,
                 order_params,
-                PlaceOrderOptions::default(),
+                PlaceOrderOptions::default().trigger_market(
+                    existing_order.trigger_source,
+                    existing_order.trigger_market_index,
+                ),
             )?;
         } else {
             place_spot_order(
@@ -1198,6 +1801,20 @@ pub fn fill_perp_order(
             "Market is in settlement mode",
         )?;
 
+        validate!(
+            !market.is_trading_halted(now),
+            ErrorCode::MarketFillOrderPaused,
+            "Market trading is halted",
+        )?;
+
+        let oracle_volatility = oracle_map.get_price_history_volatility(
+            MarketType::Perp,
+            market.market_index,
+            now,
+            market.amm.funding_period,
+            PriceSampleType::Oracle,
+        )?;
+
         let (oracle_price_data, _oracle_validity) = oracle_map.get_price_data_and_validity(
             MarketType::Perp,
             market.market_index,
@@ -1211,7 +1828,8 @@ pub fn fill_perp_order(
 
         amm_is_available &= oracle_valid_for_amm_fill;
         amm_is_available &= !market.is_operation_paused(PerpOperation::AmmFill);
-        amm_is_available &= !market.has_too_much_drawdown()?;
+        amm_is_available &=
+            !market.has_too_much_drawdown_for_oracle_volatility(oracle_volatility)?;
 
         let amm_wants_to_jit_make = market.amm.amm_wants_to_jit_make(order_direction)?;
         amm_lp_allowed_to_jit_make = market
@@ -1437,7 +2055,7 @@ pub fn fill_perp_order(
             state
                 .oracle_guard_rails
                 .max_oracle_twap_5min_percent_divergence(),
-            perp_market.is_prediction_market(),
+            perp_market.get_prediction_market_price_bounds(),
         )?;
     }
 
@@ -1624,7 +2242,7 @@ fn get_maker_orders_info(
             Some(oracle_price),
             slot,
             market.amm.order_tick_size,
-            market.is_prediction_market(),
+            market.get_prediction_market_price_bounds(),
         )?;
 
         if maker_order_price_and_indexes.is_empty() {
@@ -1863,7 +2481,7 @@ fn fulfill_perp_order(
         valid_oracle_price,
         slot,
         perp_market.amm.order_tick_size,
-        perp_market.is_prediction_market(),
+        perp_market.get_prediction_market_price_bounds(),
     )?;
     drop(perp_market);
 
@@ -2318,7 +2936,7 @@ pub fn fulfill_perp_order_with_amm(
         maker_rebate,
     } = fees::calculate_fee_for_fulfillment_with_amm(
         user_stats,
-        quote_asset_amount,
+        fees::calculate_perp_fee_notional(market, base_asset_amount, quote_asset_amount)?,
         fee_structure,
         order_slot,
         slot,
@@ -2434,9 +3052,13 @@ pub fn fulfill_perp_order_with_amm(
     }
 
     update_order_after_fill(
-        &mut user.orders[order_index],
+        user,
+        user_key,
+        order_index,
         base_asset_amount,
         quote_asset_amount,
+        oracle_map.get_price_data(&market.oracle_id())?.price,
+        now,
     )?;
 
     decrease_open_bids_and_asks(
@@ -2485,6 +3107,7 @@ pub fn fulfill_perp_order_with_amm(
     if user.orders[order_index].get_base_asset_amount_unfilled(None)? == 0 {
         user.decrement_open_orders(user.orders[order_index].has_auction());
         user.orders[order_index] = Order::default();
+        user.unlink_orders(1 << order_index);
         let market_position = &mut user.perp_positions[position_index];
         market_position.open_orders -= 1;
     }
@@ -2522,6 +3145,146 @@ pub fn credit_filler_perp_pnl(
     Ok(())
 }
 
+/// Cancels or decrements a taker and maker order of the same authority instead of matching them
+fn prevent_self_trade(
+    self_trade_prevention_mode: SelfTradePreventionMode,
+    taker: &mut User,
+    taker_order_index: usize,
+    taker_key: &Pubkey,
+    maker: &mut User,
+    maker_order_index: usize,
+    maker_key: &Pubkey,
+    filler_key: &Pubkey,
+    now: i64,
+    oracle_price: i64,
+) -> DriftResult {
+    let (cancel_taker, cancel_maker, explanation) = match self_trade_prevention_mode {
+        SelfTradePreventionMode::None => return Ok(()),
+        SelfTradePreventionMode::CancelMaker => (
+            false,
+            true,
+            OrderActionExplanation::SelfTradePreventionCancelMaker,
+        ),
+        SelfTradePreventionMode::CancelTaker => (
+            true,
+            false,
+            OrderActionExplanation::SelfTradePreventionCancelTaker,
+        ),
+        SelfTradePreventionMode::CancelBoth => (
+            true,
+            true,
+            OrderActionExplanation::SelfTradePreventionCancelBoth,
+        ),
+        SelfTradePreventionMode::DecrementAndCancel => {
+            let taker_base_asset_amount_unfilled =
+                taker.orders[taker_order_index].get_base_asset_amount_unfilled(None)?;
+            let maker_base_asset_amount_unfilled =
+                maker.orders[maker_order_index].get_base_asset_amount_unfilled(None)?;
+            let decrement_base_asset_amount =
+                taker_base_asset_amount_unfilled.min(maker_base_asset_amount_unfilled);
+
+            if taker_base_asset_amount_unfilled > decrement_base_asset_amount {
+                decrement_order(taker, taker_order_index, decrement_base_asset_amount)?;
+            }
+
+            if maker_base_asset_amount_unfilled > decrement_base_asset_amount {
+                decrement_order(maker, maker_order_index, decrement_base_asset_amount)?;
+            }
+
+            msg!(
+                "self trade prevention decremented orders by {}",
+                decrement_base_asset_amount
+            );
+
+            (
+                taker_base_asset_amount_unfilled == decrement_base_asset_amount,
+                maker_base_asset_amount_unfilled == decrement_base_asset_amount,
+                OrderActionExplanation::SelfTradePreventionDecrementAndCancel,
+            )
+        }
+    };
+
+    if cancel_maker {
+        cancel_linked_orders(
+            maker_order_index,
+            maker,
+            maker_key,
+            now,
+            Some(filler_key),
+            Some(oracle_price),
+            true,
+        )?;
+
+        _cancel_order(
+            maker_order_index,
+            maker,
+            maker_key,
+            now,
+            explanation,
+            Some(filler_key),
+            0,
+            Some(oracle_price),
+        )?;
+    }
+
+    if cancel_taker {
+        cancel_linked_orders(
+            taker_order_index,
+            taker,
+            taker_key,
+            now,
+            Some(filler_key),
+            Some(oracle_price),
+            true,
+        )?;
+
+        _cancel_order(
+            taker_order_index,
+            taker,
+            taker_key,
+            now,
+            explanation,
+            Some(filler_key),
+            0,
+            Some(oracle_price),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Reduces the size of an open order, along with the open bids/asks it reserves
+fn decrement_order(user: &mut User, order_index: usize, base_asset_amount: u64) -> DriftResult {
+    let (order_market_index, order_direction, order_market_type) = get_struct_values!(
+        user.orders[order_index],
+        market_index,
+        direction,
+        market_type
+    );
+
+    user.orders[order_index].base_asset_amount = user.orders[order_index]
+        .base_asset_amount
+        .safe_sub(base_asset_amount)?;
+
+    if order_market_type == MarketType::Perp {
+        let position_index = get_position_index(&user.perp_positions, order_market_index)?;
+        position::decrease_open_bids_and_asks(
+            &mut user.perp_positions[position_index],
+            &order_direction,
+            base_asset_amount.cast()?,
+        )?;
+    } else {
+        let spot_position_index = user.get_spot_position_index(order_market_index)?;
+        decrease_spot_open_bids_and_asks(
+            &mut user.spot_positions[spot_position_index],
+            &order_direction,
+            base_asset_amount,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn fulfill_perp_order_with_match(
     market: &mut PerpMarket,
     taker: &mut User,
@@ -2547,10 +3310,13 @@ pub fn fulfill_perp_order_with_match(
     is_liquidation: bool,
     amm_lp_allowed_to_jit_make: Option<bool>,
 ) -> DriftResult<(u64, u64, u64)> {
-    if !are_orders_same_market_but_different_sides(
-        &maker.orders[maker_order_index],
-        &taker.orders[taker_order_index],
-    ) {
+    // the maker order may have been canceled by a linked order earlier in the fill
+    if maker.orders[maker_order_index].base_asset_amount == 0
+        || !are_orders_same_market_but_different_sides(
+            &maker.orders[maker_order_index],
+            &taker.orders[taker_order_index],
+        )
+    {
         return Ok((0_u64, 0_u64, 0_u64));
     }
 
@@ -2581,7 +3347,7 @@ pub fn fulfill_perp_order_with_match(
         None,
         slot,
         market.amm.order_tick_size,
-        market.is_prediction_market(),
+        market.get_prediction_market_price_bounds(),
     )?;
     let maker_direction = maker.orders[maker_order_index].direction;
     let maker_existing_position = maker
@@ -2613,6 +3379,25 @@ pub fn fulfill_perp_order_with_match(
         return Ok((0_u64, 0_u64, 0_u64));
     }
 
+    if !is_liquidation {
+        if let Some(self_trade_prevention_mode) = taker.get_self_trade_prevention_mode(maker) {
+            prevent_self_trade(
+                self_trade_prevention_mode,
+                taker,
+                taker_order_index,
+                taker_key,
+                maker,
+                maker_order_index,
+                maker_key,
+                filler_key,
+                now,
+                oracle_price,
+            )?;
+
+            return Ok((0_u64, 0_u64, 0_u64));
+        }
+    }
+
     let sanitize_clamp_denominator = market.get_sanitize_clamp_denominator()?;
     amm::update_mark_twap_from_estimates(
         &mut market.amm,
@@ -2769,7 +3554,11 @@ pub fn fulfill_perp_order_with_match(
     } = fees::calculate_fee_for_fulfillment_with_match(
         taker_stats,
         maker_stats,
-        quote_asset_amount,
+        fees::calculate_perp_fee_notional(
+            market,
+            base_asset_amount_fulfilled_by_maker,
+            quote_asset_amount,
+        )?,
         fee_structure,
         taker.orders[taker_order_index].slot,
         slot,
@@ -2848,9 +3637,13 @@ pub fn fulfill_perp_order_with_match(
     }
 
     update_order_after_fill(
-        &mut taker.orders[taker_order_index],
+        taker,
+        taker_key,
+        taker_order_index,
         base_asset_amount_fulfilled_by_maker,
         quote_asset_amount,
+        oracle_price,
+        now,
     )?;
 
     decrease_open_bids_and_asks(
@@ -2860,9 +3653,13 @@ pub fn fulfill_perp_order_with_match(
     )?;
 
     update_order_after_fill(
-        &mut maker.orders[maker_order_index],
+        maker,
+        maker_key,
+        maker_order_index,
         base_asset_amount_fulfilled_by_maker,
         quote_asset_amount,
+        oracle_price,
+        now,
     )?;
 
     decrease_open_bids_and_asks(
@@ -2905,6 +3702,7 @@ pub fn fulfill_perp_order_with_match(
     if taker.orders[taker_order_index].get_base_asset_amount_unfilled(None)? == 0 {
         taker.decrement_open_orders(taker.orders[taker_order_index].has_auction());
         taker.orders[taker_order_index] = Order::default();
+        taker.unlink_orders(1 << taker_order_index);
         let market_position = &mut taker.perp_positions[taker_position_index];
         market_position.open_orders -= 1;
     }
@@ -2912,6 +3710,7 @@ pub fn fulfill_perp_order_with_match(
     if maker.orders[maker_order_index].get_base_asset_amount_unfilled(None)? == 0 {
         maker.decrement_open_orders(maker.orders[maker_order_index].has_auction());
         maker.orders[maker_order_index] = Order::default();
+        maker.unlink_orders(1 << maker_order_index);
         let market_position = &mut maker.perp_positions[maker_position_index];
         market_position.open_orders -= 1;
     }
@@ -2924,10 +3723,16 @@ pub fn fulfill_perp_order_with_match(
 }
 
 pub fn update_order_after_fill(
-    order: &mut Order,
+    user: &mut User,
+    user_key: &Pubkey,
+    order_index: usize,
     base_asset_amount: u64,
     quote_asset_amount: u64,
+    oracle_price: i64,
+    now: i64,
 ) -> DriftResult {
+    let order = &mut user.orders[order_index];
+
     order.base_asset_amount_filled = order.base_asset_amount_filled.safe_add(base_asset_amount)?;
 
     order.quote_asset_amount_filled = order
@@ -2938,7 +3743,23 @@ pub fn update_order_after_fill(
         order.status = OrderStatus::Filled;
     }
 
-    Ok(())
+    // the rest of a bracket keeps protecting what's left of the position
+    let linked_order_mask = user.get_linked_order_group_mask(order_index);
+    if linked_order_mask != 0 && user.is_bracket_order_group(linked_order_mask) {
+        return resize_bracket_orders(order_index, user, user_key, now, oracle_price);
+    }
+
+    // a fill cancels the rest of the order's one-cancels-other group. bracket orders waiting
+    // on the order stop waiting now that it has a fill
+    cancel_linked_orders(
+        order_index,
+        user,
+        user_key,
+        now,
+        None,
+        Some(oracle_price),
+        false,
+    )
 }
 
 #[allow(clippy::type_complexity)]
@@ -2957,6 +3778,7 @@ pub fn trigger_order(
     order_id: u32,
     state: &State,
     user: &AccountLoader<User>,
+    user_trailing_stops: Option<&AccountLoader<UserTrailingStops>>,
     spot_market_map: &SpotMarketMap,
     perp_market_map: &PerpMarketMap,
     oracle_map: &mut OracleMap,
@@ -2997,6 +3819,12 @@ pub fn trigger_order(
         "Order is already triggered"
     )?;
 
+    validate!(
+        !user.is_pending_bracket_order(order_index),
+        ErrorCode::OrderNotTriggerable,
+        "Bracket order is waiting on its entry order to fill"
+    )?;
+
     validate!(
         market_type == MarketType::Perp,
         ErrorCode::InvalidOrderMarketType,
@@ -3013,6 +3841,42 @@ pub fn trigger_order(
 
     validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
 
+    let trigger_source = user.orders[order_index].trigger_source;
+    let trigger_market = if user.orders[order_index].is_triggered_by_linked_market() {
+        Some(perp_market_map.get_ref(&user.orders[order_index].trigger_market_index)?)
+    } else {
+        None
+    };
+
+    let trigger_market_oracle_price = match &trigger_market {
+        Some(trigger_market) if trigger_source == OrderTriggerSource::LinkedMarketOracle => {
+            let (trigger_market_oracle_price_data, trigger_market_oracle_validity) = oracle_map
+                .get_price_data_and_validity(
+                    MarketType::Perp,
+                    trigger_market.market_index,
+                    &trigger_market.oracle_id(),
+                    trigger_market
+                        .amm
+                        .historical_oracle_data
+                        .last_oracle_price_twap,
+                    trigger_market.get_max_confidence_interval_multiplier()?,
+                )?;
+
+            validate!(
+                is_oracle_valid_for_action(
+                    trigger_market_oracle_validity,
+                    Some(DriftAction::TriggerOrder)
+                )?,
+                ErrorCode::InvalidOracle,
+                "OracleValidity for trigger perp marketIndex={} invalid for TriggerOrder",
+                trigger_market.market_index
+            )?;
+
+            Some(trigger_market_oracle_price_data.price)
+        }
+        _ => None,
+    };
+
     let mut perp_market = perp_market_map.get_ref_mut(&market_index)?;
     let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
         MarketType::Perp,
@@ -3050,15 +3914,46 @@ pub fn trigger_order(
         "oracle price vs twap too divergent"
     )?;
 
+    let is_trailing_stop = match user_trailing_stops {
+        Some(user_trailing_stops) => {
+            let user_trailing_stops = &mut load_mut!(user_trailing_stops)?;
+            update_trailing_stop_orders(user, user_trailing_stops, market_index, oracle_price)?;
+            user_trailing_stops
+                .trailing_stops
+                .iter()
+                .any(|trailing_stop| trailing_stop.order_id == order_id)
+        }
+        None => false,
+    };
+
     let can_trigger = order_satisfies_trigger_condition(
         &user.orders[order_index],
-        oracle_price.unsigned_abs().cast()?,
+        trigger_market_oracle_price
+            .unwrap_or(oracle_price)
+            .unsigned_abs()
+            .cast()?,
+        trigger_market.as_deref(),
     )?;
+
+    // keep the ratcheted trigger price instead of reverting
+    if is_trailing_stop && !can_trigger {
+        msg!(
+            "trailing stop trigger price updated to {}",
+            user.orders[order_index].trigger_price
+        );
+        return Ok(());
+    }
+
     validate!(can_trigger, ErrorCode::OrderDidNotSatisfyTriggerCondition)?;
 
+    drop(trigger_market);
+
     let (_, worst_case_liability_value_before) = user
         .get_perp_position(market_index)?
-        .worst_case_liability_value(oracle_price, perp_market.contract_type)?;
+        .worst_case_liability_value(
+            oracle_price,
+            perp_market.get_prediction_market_price_bounds(),
+        )?;
 
     {
         update_trigger_order_params(
@@ -3120,12 +4015,30 @@ pub fn trigger_order(
 
     let (_, worst_case_liability_value_after) = user
         .get_perp_position(market_index)?
-        .worst_case_liability_value(oracle_price, perp_market.contract_type)?;
+        .worst_case_liability_value(
+            oracle_price,
+            perp_market.get_prediction_market_price_bounds(),
+        )?;
 
     let is_risk_increasing = worst_case_liability_value_after > worst_case_liability_value_before;
 
     drop(perp_market);
 
+    // a triggered order cancels the rest of its one-cancels-other group. the rest of a bracket
+    // stays live and is shrunk to the position as the triggered order fills
+    let linked_order_mask = user.get_linked_order_group_mask(order_index);
+    if !user.is_bracket_order_group(linked_order_mask) {
+        cancel_linked_orders(
+            order_index,
+            user,
+            &user_key,
+            now,
+            Some(&filler_key),
+            Some(oracle_price),
+            false,
+        )?;
+    }
+
     // If order increases risk and user is below initial margin, cancel it
     if is_risk_increasing && !user.orders[order_index].reduce_only {
         let meets_initial_margin_requirement =
@@ -3154,6 +4067,161 @@ pub fn trigger_order(
     Ok(())
 }
 
+fn init_trailing_stop_order(
+    order: &mut Order,
+    trailing_stop_distance: TrailingStopDistance,
+    oracle_price: i64,
+) -> DriftResult {
+    validate!(
+        order.order_type == OrderType::TriggerMarket,
+        ErrorCode::InvalidTrailingStopOrder,
+        "trailing stop must be a trigger market order"
+    )?;
+
+    validate!(
+        !order.is_triggered_by_linked_market(),
+        ErrorCode::InvalidTrailingStopOrder,
+        "trailing stop must be triggered by the order market oracle"
+    )?;
+
+    match trailing_stop_distance {
+        TrailingStopDistance::Offset(offset) => validate!(
+            offset > 0,
+            ErrorCode::InvalidTrailingStopOrder,
+            "trailing stop offset must be > 0"
+        )?,
+        TrailingStopDistance::Percentage(percentage) => validate!(
+            percentage > 0 && percentage.cast::<u64>()? < PERCENTAGE_PRECISION_U64,
+            ErrorCode::InvalidTrailingStopOrder,
+            "trailing stop percentage {} must be > 0 and < {}",
+            percentage,
+            PERCENTAGE_PRECISION_U64
+        )?,
+    }
+
+    let (_, trigger_price) = calculate_trailing_stop_trigger_price(
+        &TrailingStop::new(order.order_id, trailing_stop_distance, oracle_price),
+        order.trigger_condition,
+        oracle_price,
+    )?;
+    order.trigger_price = trigger_price;
+
+    Ok(())
+}
+
+/// Places a perp trigger market order whose trigger price trails the best oracle price seen since
+/// it was placed, tracked in the user's trailing stops
+pub fn place_perp_order_with_trailing_stop(
+    state: &State,
+    user: &mut User,
+    user_key: Pubkey,
+    user_trailing_stops: &mut UserTrailingStops,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    clock: &Clock,
+    params: OrderParams,
+    trailing_stop_distance: TrailingStopDistance,
+) -> DriftResult {
+    let order_id = user.next_order_id;
+    let market_index = params.market_index;
+
+    place_perp_order(
+        state,
+        user,
+        user_key,
+        perp_market_map,
+        spot_market_map,
+        oracle_map,
+        clock,
+        params,
+        PlaceOrderOptions::default().trailing_stop(trailing_stop_distance),
+    )?;
+
+    validate!(
+        user.get_order_index(order_id).is_ok(),
+        ErrorCode::InvalidTrailingStopOrder,
+        "trailing stop {} was not placed",
+        order_id
+    )?;
+
+    let oracle_price = oracle_map
+        .get_price_data(&perp_market_map.get_ref(&market_index)?.oracle_id())?
+        .price;
+
+    user_trailing_stops.add_trailing_stop(
+        user,
+        TrailingStop::new(order_id, trailing_stop_distance, oracle_price),
+    )
+}
+
+/// Ratchets the user's trailing stops in the perp market after the user's order is filled, if the
+/// market's oracle price can be used for order prices
+pub fn update_trailing_stop_orders_after_fill(
+    user: &AccountLoader<User>,
+    user_trailing_stops: &AccountLoader<UserTrailingStops>,
+    market_index: u16,
+    perp_market_map: &PerpMarketMap,
+    oracle_map: &mut OracleMap,
+) -> DriftResult {
+    let market = perp_market_map.get_ref(&market_index)?;
+    let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
+        MarketType::Perp,
+        market.market_index,
+        &market.oracle_id(),
+        market.amm.historical_oracle_data.last_oracle_price_twap,
+        market.get_max_confidence_interval_multiplier()?,
+    )?;
+
+    if !is_oracle_valid_for_action(oracle_validity, Some(DriftAction::OracleOrderPrice))? {
+        return Ok(());
+    }
+
+    let oracle_price = oracle_price_data.price;
+    update_trailing_stop_orders(
+        &mut load_mut!(user)?,
+        &mut load_mut!(user_trailing_stops)?,
+        market_index,
+        oracle_price,
+    )
+}
+
+/// Ratchets the user's untriggered trailing stops in the market with the latest oracle price and
+/// frees the trailing stops whose orders were triggered, filled or canceled
+pub fn update_trailing_stop_orders(
+    user: &mut User,
+    user_trailing_stops: &mut UserTrailingStops,
+    market_index: u16,
+    oracle_price: i64,
+) -> DriftResult {
+    for trailing_stop in user_trailing_stops.trailing_stops.iter_mut() {
+        if !trailing_stop.is_open() {
+            continue;
+        }
+
+        if !trailing_stop.is_for_open_order(user) {
+            *trailing_stop = TrailingStop::default();
+            continue;
+        }
+
+        let order_index = user.get_order_index(trailing_stop.order_id)?;
+        let order = &mut user.orders[order_index];
+        if !order.is_open_order_for_market(market_index, &MarketType::Perp) {
+            continue;
+        }
+
+        let (water_mark, trigger_price) = calculate_trailing_stop_trigger_price(
+            trailing_stop,
+            order.trigger_condition,
+            oracle_price,
+        )?;
+        trailing_stop.water_mark = water_mark;
+        order.trigger_price = trigger_price;
+    }
+
+    Ok(())
+}
+
 fn update_trigger_order_params(
     order: &mut Order,
     oracle_price_data: &OraclePriceData,
@@ -3307,6 +4375,94 @@ pub fn force_cancel_orders(
     Ok(())
 }
 
+pub fn cancel_orders_on_heartbeat_expiry(
+    state: &State,
+    user_account_loader: &AccountLoader<User>,
+    user_parent_orders: Option<&AccountLoader<UserParentOrders>>,
+    spot_market_map: &SpotMarketMap,
+    perp_market_map: &PerpMarketMap,
+    oracle_map: &mut OracleMap,
+    filler: &AccountLoader<User>,
+    clock: &Clock,
+) -> DriftResult {
+    let now = clock.unix_timestamp;
+    let slot = clock.slot;
+
+    let filler_key = filler.key();
+    let user_key = user_account_loader.key();
+    let user = &mut load_mut!(user_account_loader)?;
+
+    validate!(
+        user.is_heartbeat_expired(now),
+        ErrorCode::HeartbeatNotExpired,
+        "heartbeat deadline {} now {}",
+        user.heartbeat_deadline_ts,
+        now
+    )?;
+
+    // the switch stays tripped until the authority or delegate sends the next heartbeat, so parent
+    // orders left out here still can't release slices
+    let canceled_order_ids = cancel_orders(
+        user,
+        &user_key,
+        Some(&filler_key),
+        perp_market_map,
+        spot_market_map,
+        oracle_map,
+        now,
+        slot,
+        OrderActionExplanation::HeartbeatExpired,
+        None,
+        None,
+        None,
+    )?;
+
+    // parent orders stop releasing slices too. their open slices were canceled above
+    let mut canceled_parent_order_ids: Vec<u32> = vec![];
+    if let Some(user_parent_orders) = user_parent_orders {
+        let user_parent_orders = &mut load_mut!(user_parent_orders)?;
+        canceled_parent_order_ids = user_parent_orders
+            .parent_orders
+            .iter()
+            .filter(|parent_order| parent_order.is_open())
+            .map(|parent_order| parent_order.parent_order_id)
+            .collect();
+
+        for parent_order_id in canceled_parent_order_ids.iter() {
+            cancel_parent_order(
+                *parent_order_id,
+                user,
+                &user_key,
+                user_parent_orders,
+                perp_market_map,
+                spot_market_map,
+                oracle_map,
+                now,
+                slot,
+            )?;
+        }
+    }
+
+    if !canceled_order_ids.is_empty() || !canceled_parent_order_ids.is_empty() {
+        let is_filler_user = user_key == filler_key;
+        let mut filler = if !is_filler_user {
+            Some(load_mut!(filler)?)
+        } else {
+            None
+        };
+
+        pay_keeper_flat_reward_for_spot(
+            user,
+            filler.as_deref_mut(),
+            spot_market_map.get_quote_spot_market_mut()?.deref_mut(),
+            state.spot_fee_structure.flat_filler_fee,
+            slot,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn can_reward_user_with_perp_pnl(user: &mut Option<&mut User>, market_index: u16) -> bool {
     match user.as_mut() {
         Some(user) => user.force_get_perp_position_mut(market_index).is_ok(),
@@ -3703,7 +4859,8 @@ pub fn place_spot_order(
         auction_end_price,
         auction_duration,
         max_ts,
-        padding: [0; 3],
+        trigger_source: OrderTriggerSource::Oracle,
+        trigger_market_index: 0,
     };
 
     validate_spot_order(
@@ -3721,6 +4878,7 @@ pub fn place_spot_order(
 
     user.increment_open_orders(new_order.has_auction());
     user.orders[new_order_index] = new_order;
+    user.unlink_orders(1 << new_order_index);
     user.spot_positions[spot_position_index].open_orders += 1;
     if !new_order.must_be_triggered() {
         increase_spot_open_bids_and_asks(
@@ -4052,7 +5210,7 @@ pub fn fill_spot_order(
             state
                 .oracle_guard_rails
                 .max_oracle_twap_5min_percent_divergence(),
-            false,
+            None,
         )?;
     }
 
@@ -4167,7 +5325,7 @@ fn get_spot_maker_orders_info(
             Some(oracle_price),
             slot,
             market.order_tick_size,
-            false,
+            None,
         )?;
 
         if maker_order_price_and_indexes.is_empty() {
@@ -4358,7 +5516,7 @@ fn fulfill_spot_order(
         None,
         slot,
         base_market.order_tick_size,
-        false,
+        None,
     )?;
 
     let fulfillment_methods = determine_spot_fulfillment_methods(
@@ -4658,10 +5816,13 @@ pub fn fulfill_spot_order_with_match(
     oracle_map: &mut OracleMap,
     fee_structure: &FeeStructure,
 ) -> DriftResult<(u64, u64)> {
-    if !are_orders_same_market_but_different_sides(
-        &maker.orders[maker_order_index],
-        &taker.orders[taker_order_index],
-    ) {
+    // the maker order may have been canceled by a linked order earlier in the fill
+    if maker.orders[maker_order_index].base_asset_amount == 0
+        || !are_orders_same_market_but_different_sides(
+            &maker.orders[maker_order_index],
+            &taker.orders[taker_order_index],
+        )
+    {
         return Ok((0_u64, 0_u64));
     }
 
@@ -4672,7 +5833,7 @@ pub fn fulfill_spot_order_with_match(
         None,
         slot,
         base_market.order_tick_size,
-        false,
+        None,
     )? {
         Some(price) => price,
         None => {
@@ -4696,7 +5857,7 @@ pub fn fulfill_spot_order_with_match(
         None,
         slot,
         base_market.order_tick_size,
-        false,
+        None,
     )?;
     let maker_direction = maker.orders[maker_order_index].direction;
     let maker_spot_position_index = maker.get_spot_position_index(market_index)?;
@@ -4771,6 +5932,23 @@ pub fn fulfill_spot_order_with_match(
         return Ok((0_u64, 0_u64));
     }
 
+    if let Some(self_trade_prevention_mode) = taker.get_self_trade_prevention_mode(maker) {
+        prevent_self_trade(
+            self_trade_prevention_mode,
+            taker,
+            taker_order_index,
+            taker_key,
+            maker,
+            maker_order_index,
+            maker_key,
+            filler_key,
+            now,
+            oracle_price,
+        )?;
+
+        return Ok((0_u64, 0_u64));
+    }
+
     let base_precision = base_market.get_precision();
     validate_fill_price(
         quote_asset_amount,
@@ -4843,9 +6021,13 @@ pub fn fulfill_spot_order_with_match(
     taker.update_cumulative_spot_fees(-taker_fee.cast()?)?;
 
     update_order_after_fill(
-        &mut taker.orders[taker_order_index],
+        taker,
+        taker_key,
+        taker_order_index,
         base_asset_amount,
         quote_asset_amount,
+        oracle_price,
+        now,
     )?;
 
     let taker_order_direction = taker.orders[taker_order_index].direction;
@@ -4886,9 +6068,13 @@ pub fn fulfill_spot_order_with_match(
     maker.update_cumulative_spot_fees(maker_rebate.cast()?)?;
 
     update_order_after_fill(
-        &mut maker.orders[maker_order_index],
+        maker,
+        maker_key,
+        maker_order_index,
         base_asset_amount,
         quote_asset_amount,
+        oracle_price,
+        now,
     )?;
 
     let maker_order_direction = maker.orders[maker_order_index].direction;
@@ -4976,12 +6162,14 @@ pub fn fulfill_spot_order_with_match(
     if taker.orders[taker_order_index].get_base_asset_amount_unfilled(None)? == 0 {
         taker.decrement_open_orders(taker.orders[taker_order_index].has_auction());
         taker.orders[taker_order_index] = Order::default();
+        taker.unlink_orders(1 << taker_order_index);
         taker.spot_positions[taker_spot_position_index].open_orders -= 1;
     }
 
     if maker.orders[maker_order_index].get_base_asset_amount_unfilled(None)? == 0 {
         maker.decrement_open_orders(maker.orders[maker_order_index].has_auction());
         maker.orders[maker_order_index] = Order::default();
+        maker.unlink_orders(1 << maker_order_index);
         maker.spot_positions[maker_spot_position_index].open_orders -= 1;
     }
 
@@ -5010,7 +6198,7 @@ pub fn fulfill_spot_order_with_external_market(
         None,
         slot,
         base_market.order_tick_size,
-        false,
+        None,
     )?;
     let taker_token_amount = taker
         .force_get_spot_position_mut(base_market.market_index)?
@@ -5167,9 +6355,13 @@ pub fn fulfill_spot_order_with_external_market(
     taker_stats.increment_total_fees(taker_fee.cast()?)?;
 
     update_order_after_fill(
-        &mut taker.orders[taker_order_index],
+        taker,
+        taker_key,
+        taker_order_index,
         base_asset_amount_filled,
         quote_asset_amount_filled,
+        oracle_price,
+        now,
     )?;
 
     let taker_order_direction = taker.orders[taker_order_index].direction;
@@ -5239,6 +6431,7 @@ pub fn fulfill_spot_order_with_external_market(
     if taker.orders[taker_order_index].get_base_asset_amount_unfilled(None)? == 0 {
         taker.decrement_open_orders(taker.orders[taker_order_index].has_auction());
         taker.orders[taker_order_index] = Order::default();
+        taker.unlink_orders(1 << taker_order_index);
         taker
             .force_get_spot_position_mut(base_market.market_index)?
             .open_orders -= 1;
@@ -5291,6 +6484,12 @@ pub fn trigger_spot_order(
         "Order is already triggered"
     )?;
 
+    validate!(
+        !user.is_pending_bracket_order(order_index),
+        ErrorCode::OrderNotTriggerable,
+        "Bracket order is waiting on its entry order to fill"
+    )?;
+
     validate!(
         market_type == MarketType::Spot,
         ErrorCode::InvalidOrderMarketType,
@@ -5322,6 +6521,7 @@ pub fn trigger_spot_order(
                 .historical_oracle_data
                 .last_oracle_price_twap_5min,
         ),
+        confidence_haircut: 0,
     };
 
     validate!(
@@ -5353,6 +6553,7 @@ pub fn trigger_spot_order(
     let can_trigger = order_satisfies_trigger_condition(
         &user.orders[order_index],
         oracle_price.unsigned_abs().cast()?,
+        None,
     )?;
     validate!(can_trigger, ErrorCode::OrderDidNotSatisfyTriggerCondition)?;
 
@@ -5443,6 +6644,17 @@ pub fn trigger_spot_order(
     let is_risk_increasing =
         worst_case_simulation_before.risk_increasing(worst_case_simulation_after);
 
+    // a triggered order cancels the rest of its one-cancels-other group
+    cancel_linked_orders(
+        order_index,
+        user,
+        &user_key,
+        now,
+        Some(&filler_key),
+        Some(oracle_price),
+        false,
+    )?;
+
     // If order is risk increasing and user is below initial margin, cancel it
     if is_risk_increasing && !user.orders[order_index].reduce_only {
         let meets_initial_margin_requirement =
@@ -2500,7 +2500,7 @@ pub mod amm_jit {
                 slot,
                 1,
                 None,
-                false,
+                None,
             )
             .unwrap();
             let baa = market.amm.order_step_size * 4;
@@ -2784,7 +2784,7 @@ pub mod amm_jit {
                 slot,
                 1,
                 None,
-                false,
+                None,
             )
             .unwrap();
             let baa = 1000 * 4;
@@ -2699,7 +2699,7 @@ pub mod amm_lp_jit {
                 slot,
                 1,
                 None,
-                false,
+                None,
             )
             .unwrap();
             let baa = market.amm.order_step_size * 4;
@@ -2983,7 +2983,7 @@ pub mod amm_lp_jit {
                 slot,
                 1,
                 None,
-                false,
+                None,
             )
             .unwrap();
             let baa = 1000 * 4;
@@ -105,7 +105,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -226,7 +226,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -347,7 +347,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -468,7 +468,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -589,7 +589,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -676,7 +676,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -764,7 +764,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -852,7 +852,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -940,7 +940,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -1048,7 +1048,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -1159,7 +1159,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -1277,7 +1277,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -1396,7 +1396,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -1539,7 +1539,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -1657,7 +1657,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
@@ -1782,7 +1782,7 @@ pub mod fulfill_order_with_maker_order {
                 None,
                 slot,
                 market.amm.order_tick_size,
-                false,
+                None,
             )
             .unwrap();
 
@@ -1918,7 +1918,7 @@ pub mod fulfill_order_with_maker_order {
                 None,
                 slot,
                 1,
-                false,
+                None,
             )
             .unwrap();
         assert_eq!(taker_price, Some(199000000)); // $51
@@ -2076,7 +2076,7 @@ pub mod fulfill_order_with_maker_order {
                 None,
                 slot,
                 market.amm.order_tick_size,
-                false,
+                None,
             )
             .unwrap();
 
@@ -2215,7 +2215,7 @@ pub mod fulfill_order_with_maker_order {
                 None,
                 slot,
                 1,
-                false,
+                None,
             )
             .unwrap();
         assert_eq!(taker_price, Some(51000000)); // $51
@@ -2340,7 +2340,7 @@ pub mod fulfill_order_with_maker_order {
 
         assert_eq!(
             taker.orders[0]
-                .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+                .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
                 .unwrap(),
             Some(55000000)
         );
@@ -2353,7 +2353,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -2469,7 +2469,7 @@ pub mod fulfill_order_with_maker_order {
 
         assert_eq!(
             taker.orders[0]
-                .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+                .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
                 .unwrap(),
             Some(100000000)
         );
@@ -2481,7 +2481,7 @@ pub mod fulfill_order_with_maker_order {
         let mut maker_stats = UserStats::default();
 
         let taker_limit_price = taker.orders[0]
-            .get_limit_price(None, None, slot, market.amm.order_tick_size, false)
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
             .unwrap();
 
         fulfill_perp_order_with_match(
@@ -12259,3 +12259,1313 @@ pub mod protected_maker_oracle_limit_can_fill {
         )); // oracle valid for amm fill is false
     }
 }
+
+pub mod linked_orders {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Owner;
+
+    use crate::controller::orders::{
+        _cancel_order, cancel_order, link_orders, update_order_after_fill,
+    };
+    use crate::controller::position::PositionDirection;
+    use crate::create_anchor_account_info;
+    use crate::math::constants::{BASE_PRECISION_I64, BASE_PRECISION_U64, PRICE_PRECISION_U64};
+    use crate::state::events::OrderActionExplanation;
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::order_params::OrderLinkType;
+    use crate::state::perp_market::PerpMarket;
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::spot_market_map::SpotMarketMap;
+    use crate::state::user::{
+        MarketType, Order, OrderStatus, OrderTriggerCondition, OrderType, PerpPosition, User,
+    };
+    use crate::test_utils::{create_account_info, get_anchor_account_bytes, get_positions};
+
+    fn get_limit_order(order_id: u32, direction: PositionDirection) -> Order {
+        Order {
+            status: OrderStatus::Open,
+            order_type: OrderType::Limit,
+            market_type: MarketType::Perp,
+            order_id,
+            direction,
+            base_asset_amount: BASE_PRECISION_U64,
+            price: 100 * PRICE_PRECISION_U64,
+            ..Order::default()
+        }
+    }
+
+    fn get_trigger_order(order_id: u32, trigger_condition: OrderTriggerCondition) -> Order {
+        Order {
+            status: OrderStatus::Open,
+            order_type: OrderType::TriggerMarket,
+            market_type: MarketType::Perp,
+            order_id,
+            direction: PositionDirection::Short,
+            base_asset_amount: BASE_PRECISION_U64,
+            trigger_price: 100 * PRICE_PRECISION_U64,
+            trigger_condition,
+            reduce_only: true,
+            ..Order::default()
+        }
+    }
+
+    fn get_user(orders: &[Order]) -> User {
+        let mut user_orders = [Order::default(); 32];
+        user_orders[..orders.len()].copy_from_slice(orders);
+
+        User {
+            orders: user_orders,
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: orders.len() as u8,
+                open_bids: BASE_PRECISION_I64,
+                open_asks: -BASE_PRECISION_I64,
+                ..PerpPosition::default()
+            }),
+            next_order_id: orders.iter().map(|order| order.order_id).max().unwrap() + 1,
+            ..User::default()
+        }
+    }
+
+    #[test]
+    fn link_one_cancels_other() {
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Long),
+            get_limit_order(3, PositionDirection::Long),
+            get_trigger_order(4, OrderTriggerCondition::Below),
+            get_limit_order(5, PositionDirection::Short),
+        ]);
+
+        // group needs at least two orders
+        assert!(link_orders(&mut user, 3, 3, OrderLinkType::OneCancelsOther).is_err());
+        // id before the group is in use
+        assert!(link_orders(&mut user, 4, 5, OrderLinkType::OneCancelsOther).is_err());
+        // order 2 doesn't exist
+        assert!(link_orders(&mut user, 1, 3, OrderLinkType::OneCancelsOther).is_err());
+
+        link_orders(&mut user, 3, 4, OrderLinkType::OneCancelsOther).unwrap();
+        assert_eq!(user.linked_order_mask, 0b110);
+        assert_eq!(user.get_linked_order_group_mask(1), 0b110);
+        assert_eq!(user.get_linked_order_group_mask(0), 0);
+        assert!(!user.is_pending_bracket_order(1));
+
+        // already linked
+        assert!(link_orders(&mut user, 3, 4, OrderLinkType::OneCancelsOther).is_err());
+    }
+
+    #[test]
+    fn link_bracket() {
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Long),
+            get_trigger_order(2, OrderTriggerCondition::Above),
+            get_trigger_order(3, OrderTriggerCondition::Below),
+            get_limit_order(4, PositionDirection::Short),
+        ]);
+
+        // bracket orders must be reduce only trigger orders
+        assert!(link_orders(&mut user, 2, 4, OrderLinkType::Bracket).is_err());
+
+        link_orders(&mut user, 2, 3, OrderLinkType::Bracket).unwrap();
+        assert_eq!(user.linked_order_mask, 0b110);
+        assert!(user.is_pending_bracket_order(1));
+        assert!(user.is_pending_bracket_order(2));
+        assert_eq!(user.get_pending_bracket_order_mask(0), 0b110);
+
+        // bracket orders must be opposite the entry
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Short),
+            get_trigger_order(2, OrderTriggerCondition::Above),
+        ]);
+        assert!(link_orders(&mut user, 2, 2, OrderLinkType::Bracket).is_err());
+    }
+
+    #[test]
+    fn fill_cancels_one_cancels_other_group() {
+        let mut user = get_user(&[
+            get_limit_order(2, PositionDirection::Long),
+            get_limit_order(3, PositionDirection::Short),
+            get_limit_order(5, PositionDirection::Long),
+        ]);
+        link_orders(&mut user, 2, 3, OrderLinkType::OneCancelsOther).unwrap();
+
+        update_order_after_fill(
+            &mut user,
+            &Pubkey::default(),
+            0,
+            BASE_PRECISION_U64 / 2,
+            50 * PRICE_PRECISION_U64 / 1000,
+            100 * PRICE_PRECISION_U64 as i64,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(user.linked_order_mask, 0);
+        assert_eq!(user.orders[0].status, OrderStatus::Open);
+        assert_eq!(user.orders[1], Order::default());
+        assert_eq!(user.orders[2].status, OrderStatus::Open);
+        assert_eq!(user.perp_positions[0].open_orders, 2);
+        assert_eq!(user.perp_positions[0].open_asks, 0);
+    }
+
+    #[test]
+    fn fill_activates_bracket() {
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Long),
+            get_trigger_order(2, OrderTriggerCondition::Above),
+            get_trigger_order(3, OrderTriggerCondition::Below),
+        ]);
+        link_orders(&mut user, 2, 3, OrderLinkType::Bracket).unwrap();
+
+        update_order_after_fill(
+            &mut user,
+            &Pubkey::default(),
+            0,
+            BASE_PRECISION_U64 / 2,
+            50 * PRICE_PRECISION_U64 / 1000,
+            100 * PRICE_PRECISION_U64 as i64,
+            0,
+        )
+        .unwrap();
+
+        // take profit and stop loss stay linked to each other but no longer wait on the entry
+        assert_eq!(user.linked_order_mask, 0b110);
+        assert!(!user.is_pending_bracket_order(1));
+        assert!(!user.is_pending_bracket_order(2));
+        assert_eq!(user.get_pending_bracket_order_mask(0), 0);
+    }
+
+    #[test]
+    fn fill_shrinks_bracket() {
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Long),
+            get_trigger_order(2, OrderTriggerCondition::Above),
+            get_trigger_order(3, OrderTriggerCondition::Below),
+        ]);
+        link_orders(&mut user, 2, 3, OrderLinkType::Bracket).unwrap();
+
+        // entry fills
+        user.perp_positions[0].base_asset_amount = BASE_PRECISION_I64;
+        update_order_after_fill(
+            &mut user,
+            &Pubkey::default(),
+            0,
+            BASE_PRECISION_U64,
+            100 * PRICE_PRECISION_U64 / 1000,
+            100 * PRICE_PRECISION_U64 as i64,
+            0,
+        )
+        .unwrap();
+
+        // take profit triggers and fills half the position
+        user.orders[1].trigger_condition = OrderTriggerCondition::TriggeredAbove;
+        user.perp_positions[0].base_asset_amount = BASE_PRECISION_I64 / 2;
+        update_order_after_fill(
+            &mut user,
+            &Pubkey::default(),
+            1,
+            BASE_PRECISION_U64 / 2,
+            50 * PRICE_PRECISION_U64 / 1000,
+            100 * PRICE_PRECISION_U64 as i64,
+            0,
+        )
+        .unwrap();
+
+        // stop loss only covers what's left of the position
+        assert_eq!(user.linked_order_mask, 0b110);
+        assert_eq!(user.orders[1].status, OrderStatus::Open);
+        assert_eq!(user.orders[2].status, OrderStatus::Open);
+        assert_eq!(user.orders[2].base_asset_amount, BASE_PRECISION_U64 / 2);
+
+        // take profit closes the position
+        user.perp_positions[0].base_asset_amount = 0;
+        update_order_after_fill(
+            &mut user,
+            &Pubkey::default(),
+            1,
+            BASE_PRECISION_U64 / 2,
+            50 * PRICE_PRECISION_U64 / 1000,
+            100 * PRICE_PRECISION_U64 as i64,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(user.linked_order_mask, 0);
+        assert_eq!(user.orders[1].status, OrderStatus::Filled);
+        assert_eq!(user.orders[2], Order::default());
+    }
+
+    #[test]
+    fn cancel_unfilled_entry_cancels_bracket() {
+        let mut market = PerpMarket::default();
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+        let spot_market_map = SpotMarketMap::empty();
+        let mut oracle_map = OracleMap::empty();
+
+        let mut user = get_user(&[
+            get_limit_order(1, PositionDirection::Long),
+            get_trigger_order(2, OrderTriggerCondition::Above),
+            get_trigger_order(3, OrderTriggerCondition::Below),
+            get_limit_order(4, PositionDirection::Long),
+        ]);
+        link_orders(&mut user, 2, 3, OrderLinkType::Bracket).unwrap();
+
+        cancel_order(
+            0,
+            &mut user,
+            &Pubkey::default(),
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            0,
+            0,
+            OrderActionExplanation::None,
+            None,
+            0,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(user.linked_order_mask, 0);
+        assert_eq!(user.orders[..3], [Order::default(); 3]);
+        assert_eq!(user.orders[3].status, OrderStatus::Open);
+        assert_eq!(user.perp_positions[0].open_orders, 1);
+    }
+
+    #[test]
+    fn freed_slot_unlinked() {
+        let mut user = get_user(&[
+            get_limit_order(2, PositionDirection::Long),
+            get_limit_order(3, PositionDirection::Short),
+        ]);
+        link_orders(&mut user, 2, 3, OrderLinkType::OneCancelsOther).unwrap();
+
+        // canceled without its group, e.g. when the user is liquidated
+        _cancel_order(
+            0,
+            &mut user,
+            &Pubkey::default(),
+            0,
+            OrderActionExplanation::Liquidation,
+            None,
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(user.orders[0], Order::default());
+        assert_eq!(user.linked_order_mask, 0b10);
+        assert!(!user.is_linked_order(0));
+    }
+}
+
+pub mod trailing_stop {
+    use crate::controller::orders::{init_trailing_stop_order, update_trailing_stop_orders};
+    use crate::math::constants::{
+        PERCENTAGE_PRECISION_U64, PRICE_PRECISION_I64, PRICE_PRECISION_U64,
+    };
+    use crate::state::order_params::TrailingStopDistance;
+    use crate::state::trailing_stop::{TrailingStop, UserTrailingStops};
+    use crate::state::user::{
+        MarketType, Order, OrderStatus, OrderTriggerCondition, OrderTriggerSource, OrderType, User,
+    };
+
+    fn get_stop_order(market_index: u16) -> Order {
+        Order {
+            status: OrderStatus::Open,
+            order_id: market_index as u32 + 1,
+            order_type: OrderType::TriggerMarket,
+            market_type: MarketType::Perp,
+            market_index,
+            trigger_condition: OrderTriggerCondition::Below,
+            ..Order::default()
+        }
+    }
+
+    #[test]
+    fn init() {
+        let mut order = get_stop_order(0);
+        init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(order.trigger_price, 95 * PRICE_PRECISION_U64);
+        assert!(!order.has_auction());
+
+        let mut order = get_stop_order(0);
+        assert!(init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Offset(0),
+            100 * PRICE_PRECISION_I64,
+        )
+        .is_err());
+
+        assert!(init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Percentage(PERCENTAGE_PRECISION_U64 as u32),
+            100 * PRICE_PRECISION_I64,
+        )
+        .is_err());
+
+        let mut order = Order {
+            order_type: OrderType::TriggerLimit,
+            ..get_stop_order(0)
+        };
+        assert!(init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_I64,
+        )
+        .is_err());
+
+        let mut order = Order {
+            trigger_source: OrderTriggerSource::LinkedMarketOracle,
+            trigger_market_index: 1,
+            ..get_stop_order(0)
+        };
+        assert!(init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_I64,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn update_only_ratchets_market_trailing_stops() {
+        let mut user = User::default();
+        let mut user_trailing_stops = UserTrailingStops::default();
+        for (i, market_index) in [0_u16, 1].iter().enumerate() {
+            let mut order = get_stop_order(*market_index);
+            init_trailing_stop_order(
+                &mut order,
+                TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+                100 * PRICE_PRECISION_I64,
+            )
+            .unwrap();
+            user.orders[i] = order;
+            user_trailing_stops
+                .add_trailing_stop(
+                    &user,
+                    TrailingStop::new(
+                        order.order_id,
+                        TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+                        100 * PRICE_PRECISION_I64,
+                    ),
+                )
+                .unwrap();
+        }
+        user.orders[2] = Order {
+            order_id: 3,
+            trigger_price: 95 * PRICE_PRECISION_U64,
+            ..get_stop_order(0)
+        };
+
+        update_trailing_stop_orders(
+            &mut user,
+            &mut user_trailing_stops,
+            0,
+            120 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(
+            user_trailing_stops.trailing_stops[0].water_mark,
+            120 * PRICE_PRECISION_I64
+        );
+        assert_eq!(user.orders[0].trigger_price, 115 * PRICE_PRECISION_U64);
+        assert_eq!(
+            user_trailing_stops.trailing_stops[1].water_mark,
+            100 * PRICE_PRECISION_I64
+        );
+        assert_eq!(user.orders[1].trigger_price, 95 * PRICE_PRECISION_U64);
+        assert_eq!(user.orders[2].trigger_price, 95 * PRICE_PRECISION_U64);
+
+        update_trailing_stop_orders(
+            &mut user,
+            &mut user_trailing_stops,
+            0,
+            110 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(
+            user_trailing_stops.trailing_stops[0].water_mark,
+            120 * PRICE_PRECISION_I64
+        );
+        assert_eq!(user.orders[0].trigger_price, 115 * PRICE_PRECISION_U64);
+    }
+
+    #[test]
+    fn update_frees_stale_trailing_stops() {
+        let mut user = User::default();
+        let mut user_trailing_stops = UserTrailingStops::default();
+        let mut order = get_stop_order(0);
+        init_trailing_stop_order(
+            &mut order,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        user.orders[0] = order;
+        user_trailing_stops
+            .add_trailing_stop(
+                &user,
+                TrailingStop::new(
+                    order.order_id,
+                    TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+                    100 * PRICE_PRECISION_I64,
+                ),
+            )
+            .unwrap();
+
+        // order is canceled, trailing stop no longer updates its slot
+        user.orders[0] = Order::default();
+        update_trailing_stop_orders(
+            &mut user,
+            &mut user_trailing_stops,
+            0,
+            120 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(
+            user_trailing_stops.trailing_stops[0],
+            TrailingStop::default()
+        );
+    }
+}
+
+pub mod parent_orders {
+    use std::str::FromStr;
+
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Owner;
+
+    use crate::controller::orders::{cancel_order, cancel_parent_order, place_parent_order};
+    use crate::controller::position::PositionDirection;
+    use crate::error::ErrorCode;
+    use crate::math::constants::{BASE_PRECISION_I64, BASE_PRECISION_U64, PRICE_PRECISION_U64};
+    use crate::state::events::OrderActionExplanation;
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::order_params::ParentOrderParams;
+    use crate::state::parent_order::{ParentOrder, ParentOrderType, UserParentOrders};
+    use crate::state::perp_market::PerpMarket;
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::spot_market_map::SpotMarketMap;
+    use crate::state::user::{MarketType, Order, OrderStatus, OrderType, PerpPosition, User};
+    use crate::test_utils::{
+        create_account_info, get_account_bytes, get_anchor_account_bytes, get_orders,
+        get_positions, get_pyth_price,
+    };
+    use crate::{create_account_info, create_anchor_account_info};
+
+    fn get_params() -> ParentOrderParams {
+        ParentOrderParams {
+            parent_order_type: ParentOrderType::Iceberg,
+            direction: PositionDirection::Long,
+            market_index: 0,
+            base_asset_amount: 10 * BASE_PRECISION_U64,
+            slice_base_asset_amount: BASE_PRECISION_U64,
+            price: 100 * PRICE_PRECISION_U64,
+            interval_slots: 0,
+            reduce_only: false,
+            post_only: true,
+        }
+    }
+
+    #[test]
+    fn place() {
+        let mut market = PerpMarket::default_test();
+        market.amm.order_tick_size = PRICE_PRECISION_U64 / 100;
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        let mut user = User {
+            next_order_id: 5,
+            ..User::default()
+        };
+        let mut user_parent_orders = UserParentOrders::default();
+
+        place_parent_order(
+            &mut user,
+            &mut user_parent_orders,
+            &perp_market_map,
+            get_params(),
+        )
+        .unwrap();
+
+        // parent order takes an id from the user's order id space
+        assert_eq!(user.next_order_id, 6);
+        assert_eq!(
+            user_parent_orders.parent_orders[0],
+            ParentOrder {
+                base_asset_amount: 10 * BASE_PRECISION_U64,
+                slice_base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                parent_order_id: 5,
+                parent_order_type: ParentOrderType::Iceberg,
+                direction: PositionDirection::Long,
+                post_only: true,
+                ..ParentOrder::default()
+            }
+        );
+
+        // price is rounded to the tick size
+        let params = ParentOrderParams {
+            price: 100 * PRICE_PRECISION_U64 + 1,
+            ..get_params()
+        };
+        place_parent_order(&mut user, &mut user_parent_orders, &perp_market_map, params).unwrap();
+        assert_eq!(
+            user_parent_orders.parent_orders[1].price,
+            100 * PRICE_PRECISION_U64
+        );
+
+        let params = ParentOrderParams {
+            slice_base_asset_amount: 11 * BASE_PRECISION_U64,
+            ..get_params()
+        };
+        assert_eq!(
+            place_parent_order(&mut user, &mut user_parent_orders, &perp_market_map, params),
+            Err(ErrorCode::InvalidParentOrder)
+        );
+
+        let params = ParentOrderParams {
+            price: 0,
+            ..get_params()
+        };
+        assert_eq!(
+            place_parent_order(&mut user, &mut user_parent_orders, &perp_market_map, params),
+            Err(ErrorCode::InvalidParentOrder)
+        );
+    }
+
+    #[test]
+    fn cancel_cancels_open_slice() {
+        let slot = 10;
+
+        let mut oracle_price = get_pyth_price(100, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+        let mut market = PerpMarket::default_test();
+        market.amm.oracle = oracle_price_key;
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+        let spot_market_map = SpotMarketMap::empty();
+
+        let mut user = User {
+            orders: get_orders(Order {
+                status: OrderStatus::Open,
+                order_type: OrderType::Limit,
+                market_type: MarketType::Perp,
+                order_id: 2,
+                direction: PositionDirection::Long,
+                base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_bids: BASE_PRECISION_I64,
+                ..PerpPosition::default()
+            }),
+            next_order_id: 3,
+            ..User::default()
+        };
+
+        let mut user_parent_orders = UserParentOrders::default();
+        user_parent_orders
+            .add_parent_order(ParentOrder {
+                base_asset_amount: 10 * BASE_PRECISION_U64,
+                base_asset_amount_released: BASE_PRECISION_U64,
+                slice_base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                parent_order_id: 1,
+                child_order_id: 2,
+                ..ParentOrder::default()
+            })
+            .unwrap();
+
+        cancel_parent_order(
+            1,
+            &mut user,
+            &Pubkey::default(),
+            &mut user_parent_orders,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            0,
+            slot,
+        )
+        .unwrap();
+
+        assert_eq!(user_parent_orders, UserParentOrders::default());
+        assert_eq!(user.orders[0], Order::default());
+        assert_eq!(user.perp_positions[0].open_orders, 0);
+        assert_eq!(user.perp_positions[0].open_bids, 0);
+
+        assert_eq!(
+            cancel_parent_order(
+                1,
+                &mut user,
+                &Pubkey::default(),
+                &mut user_parent_orders,
+                &perp_market_map,
+                &spot_market_map,
+                &mut oracle_map,
+                0,
+                slot,
+            ),
+            Err(ErrorCode::InvalidParentOrder)
+        );
+    }
+
+    #[test]
+    fn canceled_slice_frees_order_slot() {
+        let slot = 10;
+
+        let mut oracle_price = get_pyth_price(100, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+        let mut market = PerpMarket::default_test();
+        market.amm.oracle = oracle_price_key;
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+        let spot_market_map = SpotMarketMap::empty();
+
+        let slice = Order {
+            status: OrderStatus::Open,
+            order_type: OrderType::Limit,
+            market_type: MarketType::Perp,
+            order_id: 2,
+            direction: PositionDirection::Long,
+            base_asset_amount: BASE_PRECISION_U64,
+            base_asset_amount_filled: BASE_PRECISION_U64 / 4,
+            price: 100 * PRICE_PRECISION_U64,
+            ..Order::default()
+        };
+        let mut user = User {
+            orders: get_orders(slice),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_bids: 3 * BASE_PRECISION_I64 / 4,
+                ..PerpPosition::default()
+            }),
+            next_order_id: 3,
+            ..User::default()
+        };
+
+        let mut user_parent_orders = UserParentOrders::default();
+        user_parent_orders
+            .add_parent_order(ParentOrder {
+                base_asset_amount: 10 * BASE_PRECISION_U64,
+                base_asset_amount_released: BASE_PRECISION_U64,
+                slice_base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                parent_order_id: 1,
+                child_order_id: 2,
+                ..ParentOrder::default()
+            })
+            .unwrap();
+
+        // user cancels the slice directly
+        cancel_order(
+            0,
+            &mut user,
+            &Pubkey::default(),
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            0,
+            slot,
+            OrderActionExplanation::None,
+            None,
+            0,
+            false,
+        )
+        .unwrap();
+
+        // the parent order only tracks the slice by id, so its slot is freed right away
+        assert_eq!(user.orders[0], Order::default());
+        assert_eq!(user.perp_positions[0].open_orders, 0);
+        assert_eq!(user.perp_positions[0].open_bids, 0);
+
+        cancel_parent_order(
+            1,
+            &mut user,
+            &Pubkey::default(),
+            &mut user_parent_orders,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            0,
+            slot,
+        )
+        .unwrap();
+
+        assert_eq!(user_parent_orders, UserParentOrders::default());
+        assert_eq!(user.orders[0], Order::default());
+    }
+}
+
+pub mod self_trade_prevention {
+    use anchor_lang::prelude::Pubkey;
+
+    use crate::controller::orders::fulfill_perp_order_with_match;
+    use crate::controller::position::PositionDirection;
+    use crate::math::constants::{BASE_PRECISION_I64, BASE_PRECISION_U64, PRICE_PRECISION_U64};
+    use crate::state::perp_market::PerpMarket;
+    use crate::state::user::{
+        Order, OrderStatus, OrderType, PerpPosition, SelfTradePreventionMode, User, UserStats,
+    };
+    use crate::test_utils::{get_orders, get_positions};
+
+    use super::*;
+
+    fn get_taker_and_maker(
+        taker_base_asset_amount: u64,
+        maker_base_asset_amount: u64,
+        self_trade_prevention_mode: SelfTradePreventionMode,
+    ) -> (User, User) {
+        let authority = Pubkey::new_unique();
+
+        let taker = User {
+            authority,
+            self_trade_prevention_mode,
+            orders: get_orders(Order {
+                status: OrderStatus::Open,
+                market_index: 0,
+                order_type: OrderType::Limit,
+                direction: PositionDirection::Long,
+                base_asset_amount: taker_base_asset_amount,
+                price: 100 * PRICE_PRECISION_U64,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_bids: taker_base_asset_amount as i64,
+                ..PerpPosition::default()
+            }),
+            ..User::default()
+        };
+
+        let maker = User {
+            authority,
+            orders: get_orders(Order {
+                status: OrderStatus::Open,
+                market_index: 0,
+                post_only: true,
+                order_type: OrderType::Limit,
+                direction: PositionDirection::Short,
+                base_asset_amount: maker_base_asset_amount,
+                price: 100 * PRICE_PRECISION_U64,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_asks: -(maker_base_asset_amount as i64),
+                ..PerpPosition::default()
+            }),
+            ..User::default()
+        };
+
+        (taker, maker)
+    }
+
+    fn fulfill(taker: &mut User, maker: &mut User) -> u64 {
+        let mut market = PerpMarket::default_test();
+        let fee_structure = get_fee_structure();
+        let taker_key = Pubkey::new_unique();
+        let maker_key = Pubkey::new_unique();
+        let filler_key = Pubkey::new_unique();
+        let mut taker_stats = UserStats::default();
+        let mut maker_stats = UserStats::default();
+        let slot = 1_u64;
+
+        let taker_limit_price = taker.orders[0]
+            .get_limit_price(None, None, slot, market.amm.order_tick_size, None)
+            .unwrap();
+
+        let (base_asset_amount, _, _) = fulfill_perp_order_with_match(
+            &mut market,
+            taker,
+            &mut taker_stats,
+            0,
+            &taker_key,
+            maker,
+            &mut Some(&mut maker_stats),
+            0,
+            &maker_key,
+            &mut None,
+            &mut None,
+            &filler_key,
+            &mut None,
+            &mut None,
+            0,
+            None,
+            taker_limit_price,
+            1,
+            slot,
+            &fee_structure,
+            &mut get_oracle_map(),
+            false,
+            None,
+        )
+        .unwrap();
+
+        base_asset_amount
+    }
+
+    #[test]
+    fn cancel_maker() {
+        let (mut taker, mut maker) = get_taker_and_maker(
+            BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::CancelMaker,
+        );
+
+        assert_eq!(fulfill(&mut taker, &mut maker), 0);
+
+        assert_eq!(taker.orders[0].status, OrderStatus::Open);
+        assert_eq!(taker.perp_positions[0].open_bids, BASE_PRECISION_I64);
+        assert_eq!(taker.perp_positions[0].base_asset_amount, 0);
+
+        assert_eq!(maker.orders[0], Order::default());
+        assert_eq!(maker.perp_positions[0].open_asks, 0);
+        assert_eq!(maker.perp_positions[0].open_orders, 0);
+        assert_eq!(maker.perp_positions[0].base_asset_amount, 0);
+    }
+
+    #[test]
+    fn cancel_taker() {
+        let (mut taker, mut maker) = get_taker_and_maker(
+            BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::CancelTaker,
+        );
+
+        assert_eq!(fulfill(&mut taker, &mut maker), 0);
+
+        assert_eq!(taker.orders[0], Order::default());
+        assert_eq!(taker.perp_positions[0].open_bids, 0);
+        assert_eq!(taker.perp_positions[0].open_orders, 0);
+
+        assert_eq!(maker.orders[0].status, OrderStatus::Open);
+        assert_eq!(maker.perp_positions[0].open_asks, -BASE_PRECISION_I64);
+    }
+
+    #[test]
+    fn cancel_both() {
+        let (mut taker, mut maker) = get_taker_and_maker(
+            BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::CancelBoth,
+        );
+
+        assert_eq!(fulfill(&mut taker, &mut maker), 0);
+
+        assert_eq!(taker.orders[0], Order::default());
+        assert_eq!(taker.perp_positions[0].open_bids, 0);
+        assert_eq!(maker.orders[0], Order::default());
+        assert_eq!(maker.perp_positions[0].open_asks, 0);
+    }
+
+    #[test]
+    fn decrement_and_cancel() {
+        let (mut taker, mut maker) = get_taker_and_maker(
+            3 * BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::DecrementAndCancel,
+        );
+
+        assert_eq!(fulfill(&mut taker, &mut maker), 0);
+
+        assert_eq!(taker.orders[0].status, OrderStatus::Open);
+        assert_eq!(taker.orders[0].base_asset_amount, 2 * BASE_PRECISION_U64);
+        assert_eq!(taker.perp_positions[0].open_bids, 2 * BASE_PRECISION_I64);
+        assert_eq!(taker.perp_positions[0].base_asset_amount, 0);
+
+        assert_eq!(maker.orders[0], Order::default());
+        assert_eq!(maker.perp_positions[0].open_asks, 0);
+        assert_eq!(maker.perp_positions[0].open_orders, 0);
+
+        // equal sizes cancel both
+        let (mut taker, mut maker) = get_taker_and_maker(
+            BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::DecrementAndCancel,
+        );
+
+        assert_eq!(fulfill(&mut taker, &mut maker), 0);
+
+        assert_eq!(taker.orders[0], Order::default());
+        assert_eq!(maker.orders[0], Order::default());
+    }
+
+    #[test]
+    fn different_authorities_match() {
+        let (mut taker, mut maker) = get_taker_and_maker(
+            BASE_PRECISION_U64,
+            BASE_PRECISION_U64,
+            SelfTradePreventionMode::CancelBoth,
+        );
+        maker.authority = Pubkey::new_unique();
+
+        assert_eq!(fulfill(&mut taker, &mut maker), BASE_PRECISION_U64);
+
+        assert_eq!(
+            taker.perp_positions[0].base_asset_amount,
+            BASE_PRECISION_I64
+        );
+        assert_eq!(
+            maker.perp_positions[0].base_asset_amount,
+            -BASE_PRECISION_I64
+        );
+    }
+}
+
+pub mod cancel_orders_on_heartbeat_expiry {
+    use std::str::FromStr;
+
+    use anchor_lang::prelude::{AccountLoader, Clock};
+    use anchor_lang::Owner;
+
+    use crate::controller::orders::cancel_orders_on_heartbeat_expiry;
+    use crate::controller::position::PositionDirection;
+    use crate::error::ErrorCode;
+    use crate::math::constants::{
+        BASE_PRECISION_I64, BASE_PRECISION_U64, PRICE_PRECISION_U64, SPOT_BALANCE_PRECISION,
+        SPOT_BALANCE_PRECISION_U64, SPOT_CUMULATIVE_INTEREST_PRECISION, SPOT_WEIGHT_PRECISION,
+    };
+    use crate::state::oracle::OracleSource;
+    use crate::state::parent_order::{ParentOrder, UserParentOrders};
+    use crate::state::perp_market::PerpMarket;
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::spot_market::{SpotBalanceType, SpotMarket};
+    use crate::state::spot_market_map::SpotMarketMap;
+    use crate::state::state::State;
+    use crate::state::user::{OrderStatus, OrderType, SpotPosition, User};
+    use crate::test_utils::{
+        create_account_info, get_account_bytes, get_anchor_account_bytes, get_orders,
+        get_positions, get_pyth_price, get_spot_positions,
+    };
+    use crate::{create_account_info, create_anchor_account_info};
+
+    use super::*;
+
+    fn get_clock(unix_timestamp: i64) -> Clock {
+        Clock {
+            slot: 6,
+            epoch_start_timestamp: 0,
+            epoch: 0,
+            leader_schedule_epoch: 0,
+            unix_timestamp,
+        }
+    }
+
+    #[test]
+    fn cancels_orders_after_deadline() {
+        let mut oracle_price = get_pyth_price(100, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, 6, None).unwrap();
+
+        let mut market = PerpMarket::default_test();
+        market.amm.oracle = oracle_price_key;
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        let mut usdc_spot_market = SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            deposit_balance: SPOT_BALANCE_PRECISION,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            cumulative_borrow_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            initial_asset_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+            ..SpotMarket::default()
+        };
+        create_anchor_account_info!(usdc_spot_market, SpotMarket, usdc_spot_market_account_info);
+        let spot_market_map =
+            SpotMarketMap::load_one(&usdc_spot_market_account_info, true).unwrap();
+
+        let mut user = User {
+            authority: Pubkey::new_unique(),
+            orders: get_orders(Order {
+                status: OrderStatus::Open,
+                order_type: OrderType::Limit,
+                market_type: MarketType::Perp,
+                order_id: 1,
+                post_only: true,
+                direction: PositionDirection::Long,
+                base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_bids: BASE_PRECISION_I64,
+                ..PerpPosition::default()
+            }),
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: 0,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: SPOT_BALANCE_PRECISION_U64,
+                ..SpotPosition::default()
+            }),
+            ..User::default()
+        };
+        user.update_heartbeat(0, 100).unwrap();
+        create_anchor_account_info!(user, User, user_account_info);
+        let user_account_loader: AccountLoader<User> =
+            AccountLoader::try_from(&user_account_info).unwrap();
+
+        let filler_key = Pubkey::new_unique();
+        create_anchor_account_info!(User::default(), &filler_key, User, filler_account_info);
+        let filler_account_loader: AccountLoader<User> =
+            AccountLoader::try_from(&filler_account_info).unwrap();
+
+        let state = State {
+            spot_fee_structure: FeeStructure {
+                flat_filler_fee: 10_000,
+                ..FeeStructure::test_default()
+            },
+            ..State::default()
+        };
+
+        assert_eq!(
+            cancel_orders_on_heartbeat_expiry(
+                &state,
+                &user_account_loader,
+                None,
+                &spot_market_map,
+                &perp_market_map,
+                &mut oracle_map,
+                &filler_account_loader,
+                &get_clock(100),
+            ),
+            Err(ErrorCode::HeartbeatNotExpired)
+        );
+
+        cancel_orders_on_heartbeat_expiry(
+            &state,
+            &user_account_loader,
+            None,
+            &spot_market_map,
+            &perp_market_map,
+            &mut oracle_map,
+            &filler_account_loader,
+            &get_clock(101),
+        )
+        .unwrap();
+
+        {
+            let user = user_account_loader.load().unwrap();
+            assert_eq!(user.orders[0], Order::default());
+            assert_eq!(user.perp_positions[0].open_orders, 0);
+            assert_eq!(user.perp_positions[0].open_bids, 0);
+            assert!(user.is_heartbeat_expired(101));
+            assert_eq!(user.spot_positions[0].scaled_balance, 990000000);
+
+            let filler = filler_account_loader.load().unwrap();
+            assert_eq!(filler.spot_positions[0].scaled_balance, 10000000);
+        }
+
+        // the switch stays tripped but there's nothing left to be paid for
+        cancel_orders_on_heartbeat_expiry(
+            &state,
+            &user_account_loader,
+            None,
+            &spot_market_map,
+            &perp_market_map,
+            &mut oracle_map,
+            &filler_account_loader,
+            &get_clock(102),
+        )
+        .unwrap();
+
+        {
+            let filler = filler_account_loader.load().unwrap();
+            assert_eq!(filler.spot_positions[0].scaled_balance, 10000000);
+        }
+
+        // until the authority re-arms it
+        user_account_loader
+            .load_mut()
+            .unwrap()
+            .update_heartbeat(102, 100)
+            .unwrap();
+        assert_eq!(
+            cancel_orders_on_heartbeat_expiry(
+                &state,
+                &user_account_loader,
+                None,
+                &spot_market_map,
+                &perp_market_map,
+                &mut oracle_map,
+                &filler_account_loader,
+                &get_clock(103),
+            ),
+            Err(ErrorCode::HeartbeatNotExpired)
+        );
+    }
+
+    #[test]
+    fn cancels_parent_orders_when_user_is_filler() {
+        let mut oracle_price = get_pyth_price(100, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, 6, None).unwrap();
+
+        let mut market = PerpMarket::default_test();
+        market.amm.oracle = oracle_price_key;
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        let mut usdc_spot_market = SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            deposit_balance: SPOT_BALANCE_PRECISION,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            cumulative_borrow_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            initial_asset_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+            ..SpotMarket::default()
+        };
+        create_anchor_account_info!(usdc_spot_market, SpotMarket, usdc_spot_market_account_info);
+        let spot_market_map =
+            SpotMarketMap::load_one(&usdc_spot_market_account_info, true).unwrap();
+
+        let mut user = User {
+            authority: Pubkey::new_unique(),
+            orders: get_orders(Order {
+                status: OrderStatus::Open,
+                order_type: OrderType::Limit,
+                market_type: MarketType::Perp,
+                order_id: 2,
+                post_only: true,
+                direction: PositionDirection::Long,
+                base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                open_orders: 1,
+                open_bids: BASE_PRECISION_I64,
+                ..PerpPosition::default()
+            }),
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: 0,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: SPOT_BALANCE_PRECISION_U64,
+                ..SpotPosition::default()
+            }),
+            next_order_id: 3,
+            ..User::default()
+        };
+        user.update_heartbeat(0, 100).unwrap();
+        let user_key = Pubkey::new_unique();
+        create_anchor_account_info!(user, &user_key, User, user_account_info);
+        let user_account_loader: AccountLoader<User> =
+            AccountLoader::try_from(&user_account_info).unwrap();
+
+        let mut user_parent_orders = UserParentOrders {
+            user_pubkey: user_key,
+            ..UserParentOrders::default()
+        };
+        user_parent_orders
+            .add_parent_order(ParentOrder {
+                base_asset_amount: 10 * BASE_PRECISION_U64,
+                base_asset_amount_released: BASE_PRECISION_U64,
+                slice_base_asset_amount: BASE_PRECISION_U64,
+                price: 100 * PRICE_PRECISION_U64,
+                parent_order_id: 1,
+                child_order_id: 2,
+                ..ParentOrder::default()
+            })
+            .unwrap();
+        create_anchor_account_info!(
+            user_parent_orders,
+            UserParentOrders,
+            user_parent_orders_account_info
+        );
+        let user_parent_orders_account_loader: AccountLoader<UserParentOrders> =
+            AccountLoader::try_from(&user_parent_orders_account_info).unwrap();
+
+        let state = State {
+            spot_fee_structure: FeeStructure {
+                flat_filler_fee: 10_000,
+                ..FeeStructure::test_default()
+            },
+            ..State::default()
+        };
+
+        cancel_orders_on_heartbeat_expiry(
+            &state,
+            &user_account_loader,
+            Some(&user_parent_orders_account_loader),
+            &spot_market_map,
+            &perp_market_map,
+            &mut oracle_map,
+            &user_account_loader,
+            &get_clock(101),
+        )
+        .unwrap();
+
+        let user = user_account_loader.load().unwrap();
+        assert_eq!(user.orders[0], Order::default());
+        assert_eq!(user.perp_positions[0].open_orders, 0);
+        assert!(user.is_heartbeat_expired(101));
+        // no reward for cranking your own switch
+        assert_eq!(
+            user.spot_positions[0].scaled_balance,
+            SPOT_BALANCE_PRECISION_U64
+        );
+
+        let user_parent_orders = user_parent_orders_account_loader.load().unwrap();
+        assert_eq!(
+            user_parent_orders.parent_orders,
+            UserParentOrders::default().parent_orders
+        );
+    }
+}
@@ -26,16 +26,19 @@ use crate::math::safe_math::SafeMath;
 use crate::math::spot_balance::get_token_amount;
 use crate::state::margin_calculation::MarginContext;
 
-use crate::state::events::{OrderActionExplanation, SettlePnlExplanation, SettlePnlRecord};
+use crate::state::events::{
+    OrderActionExplanation, SettleExpiredPositionsRecord, SettlePnlExplanation, SettlePnlRecord,
+};
 use crate::state::oracle_map::OracleMap;
 use crate::state::paused_operations::PerpOperation;
-use crate::state::perp_market::MarketStatus;
+use crate::state::perp_market::{MarketStatus, MarketVoidStatus, PerpMarket};
 use crate::state::perp_market_map::PerpMarketMap;
 use crate::state::settle_pnl_mode::SettlePnlMode;
-use crate::state::spot_market::{SpotBalance, SpotBalanceType};
+use crate::state::spot_market::{SpotBalance, SpotBalanceType, SpotMarket};
 use crate::state::spot_market_map::SpotMarketMap;
-use crate::state::state::State;
-use crate::state::user::{MarketType, User};
+use crate::state::state::{FeeStructure, State};
+use crate::state::user::{MarketType, PerpPosition, User};
+use crate::state::user_map::UserMap;
 use crate::validate;
 use anchor_lang::prelude::Pubkey;
 use anchor_lang::prelude::*;
@@ -230,11 +233,12 @@ pub fn settle_pnl(
                 &msg,
             );
         }
-    } else if perp_market.status != MarketStatus::Active
-        && perp_market.status != MarketStatus::ReduceOnly
-    {
+    } else if !matches!(
+        perp_market.status,
+        MarketStatus::Active | MarketStatus::ReduceOnly | MarketStatus::TradingHalted
+    ) {
         let msg = format!(
-            "Cannot settle pnl under current market = {} status (neither Active or ReduceOnly)",
+            "Cannot settle pnl under current market = {} status (neither Active, ReduceOnly or TradingHalted)",
             market_index
         );
         return mode.result(
@@ -441,13 +445,382 @@ pub fn settle_expired_position(
         "User must first burn lp shares for expired market"
     )?;
 
-    let base_asset_value = calculate_base_asset_value_with_expiry_price(
-        &user.perp_positions[position_index],
-        perp_market.expiry_price,
+    let base_asset_amount = user.perp_positions[position_index].base_asset_amount;
+    let quote_entry_amount = user.perp_positions[position_index].quote_entry_amount;
+
+    let (pnl_to_settle_with_user, settle_price, explanation) = close_expired_position(
+        user,
+        position_index,
+        perp_market,
+        quote_spot_market,
+        fee_structure,
+    )?;
+
+    let quote_asset_amount_after = user.perp_positions[position_index].quote_asset_amount;
+
+    emit!(SettlePnlRecord {
+        ts: now,
+        user: *user_key,
+        market_index: perp_market_index,
+        pnl: pnl_to_settle_with_user,
+        base_asset_amount,
+        quote_asset_amount_after,
+        quote_entry_amount,
+        settle_price,
+        explanation,
+    });
+
+    validate!(
+        user.perp_positions[position_index].is_available(),
+        ErrorCode::UnableToSettleExpiredUserPosition,
+        "Issue occurred in expired settlement"
+    )?;
+
+    Ok(())
+}
+
+/// Settles every user's expired position in a market in one pass
+///
+/// Users that can't be settled yet (bankrupt, below maintenance margin, lp shares or open orders
+/// that can't be canceled) are skipped before they are touched. Winners the pnl pool can't cover
+/// are skipped after their funding is settled and orders canceled. Any other error fails the batch.
+/// Losers are settled before winners so their payments into the pnl pool can fund the winners in
+/// the same batch
+pub fn settle_expired_positions(
+    perp_market_index: u16,
+    user_map: &UserMap,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    clock: &Clock,
+    state: &State,
+) -> DriftResult<SettleExpiredPositionsRecord> {
+    let fee_structure = &state.perp_fee_structure;
+    let now = clock.unix_timestamp;
+    let slot = clock.slot;
+
+    {
+        let perp_market = perp_market_map.get_ref(&perp_market_index)?;
+        validate!(
+            perp_market.status == MarketStatus::Settlement,
+            ErrorCode::PerpMarketNotInSettlement,
+            "Perp Market isn't in settlement, expiry_ts={}",
+            perp_market.expiry_ts
+        )?;
+
+        let position_settlement_ts = perp_market
+            .expiry_ts
+            .safe_add(state.settlement_duration.cast()?)?;
+
+        validate!(
+            now > position_settlement_ts,
+            ErrorCode::PerpMarketSettlementBufferNotReached,
+            "Market requires {} seconds buffer to settle after expiry_ts",
+            state.settlement_duration
+        )?;
+    }
+
+    {
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        update_spot_market_cumulative_interest(quote_spot_market, None, now)?;
+    }
+
+    let mut record = SettleExpiredPositionsRecord {
+        ts: now,
+        market_index: perp_market_index,
+        settle_price: perp_market_map.get_ref(&perp_market_index)?.expiry_price,
+        ..SettleExpiredPositionsRecord::default()
+    };
+
+    let mut winners = Vec::with_capacity(user_map.0.len());
+    for user_key in user_map.0.keys() {
+        let mut user = user_map.get_ref_mut(user_key)?;
+
+        let position_index = match get_position_index(&user.perp_positions, perp_market_index) {
+            Ok(position_index) => position_index,
+            Err(_) => continue,
+        };
+
+        if let Some(reason) = get_expired_position_batch_skip_reason(
+            perp_market_index,
+            &user,
+            position_index,
+            perp_market_map,
+            spot_market_map,
+            oracle_map,
+        )? {
+            msg!("Skipping user {}: {:?}", user_key, reason);
+            record.users_skipped = record.users_skipped.safe_add(1)?;
+            continue;
+        }
+
+        prepare_expired_position_for_batch_settlement(
+            perp_market_index,
+            &mut user,
+            user_key,
+            position_index,
+            perp_market_map,
+            spot_market_map,
+            oracle_map,
+            now,
+            slot,
+        )?;
+
+        let perp_market = &mut perp_market_map.get_ref_mut(&perp_market_index)?;
+        let pnl = calculate_expired_position_pnl(
+            &user.perp_positions[position_index],
+            perp_market,
+            fee_structure,
+        )?;
+
+        if pnl > 0 {
+            winners.push((*user_key, position_index, pnl));
+            continue;
+        }
+
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        settle_expired_position_for_batch(
+            &mut user,
+            position_index,
+            perp_market,
+            quote_spot_market,
+            fee_structure,
+            slot,
+            &mut record,
+        )?;
+    }
+
+    for (user_key, position_index, pnl) in winners {
+        let mut user = user_map.get_ref_mut(&user_key)?;
+        let perp_market = &mut perp_market_map.get_ref_mut(&perp_market_index)?;
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+
+        let pnl_pool_token_amount = get_token_amount(
+            perp_market.pnl_pool.scaled_balance,
+            quote_spot_market,
+            perp_market.pnl_pool.balance_type(),
+        )?;
+
+        if pnl.cast::<u128>()? > pnl_pool_token_amount {
+            msg!(
+                "Skipping user {}: pnl pool cannot cover {} ({})",
+                user_key,
+                pnl,
+                pnl_pool_token_amount
+            );
+            record.users_skipped = record.users_skipped.safe_add(1)?;
+            continue;
+        }
+
+        settle_expired_position_for_batch(
+            &mut user,
+            position_index,
+            perp_market,
+            quote_spot_market,
+            fee_structure,
+            slot,
+            &mut record,
+        )?;
+    }
+
+    Ok(record)
+}
+
+/// Checks whether a user's expired position can't be settled in a batch yet. Runs before the user
+/// is mutated so skipped users are left untouched
+fn get_expired_position_batch_skip_reason(
+    perp_market_index: u16,
+    user: &User,
+    position_index: usize,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+) -> DriftResult<Option<ErrorCode>> {
+    if user.is_bankrupt() {
+        return Ok(Some(ErrorCode::UserBankrupt));
+    }
+
+    if user.perp_positions[position_index].lp_shares != 0 {
+        return Ok(Some(ErrorCode::PerpMarketSettlementUserHasActiveLP));
+    }
+
+    // every open order counted on the position must be one cancel_orders will find
+    let cancelable_orders = user
+        .orders
+        .iter()
+        .filter(|order| order.is_open_order_for_market(perp_market_index, &MarketType::Perp))
+        .count();
+    if cancelable_orders
+        != user.perp_positions[position_index]
+            .open_orders
+            .cast::<usize>()?
+    {
+        return Ok(Some(ErrorCode::PerpMarketSettlementUserHasOpenOrders));
+    }
+
+    if !(meets_maintenance_margin_requirement(user, perp_market_map, spot_market_map, oracle_map)?)
+    {
+        return Ok(Some(ErrorCode::InsufficientCollateralForSettlingPNL));
+    }
+
+    Ok(None)
+}
+
+/// Settles funding and cancels the user's orders in the market ahead of batch settlement
+#[allow(clippy::too_many_arguments)]
+fn prepare_expired_position_for_batch_settlement(
+    perp_market_index: u16,
+    user: &mut User,
+    user_key: &Pubkey,
+    position_index: usize,
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    oracle_map: &mut OracleMap,
+    now: i64,
+    slot: u64,
+) -> DriftResult {
+    settle_funding_payment(
+        user,
+        user_key,
+        perp_market_map.get_ref_mut(&perp_market_index)?.deref_mut(),
+        now,
     )?;
 
+    cancel_orders(
+        user,
+        user_key,
+        None,
+        perp_market_map,
+        spot_market_map,
+        oracle_map,
+        now,
+        slot,
+        OrderActionExplanation::MarketExpired,
+        Some(MarketType::Perp),
+        Some(perp_market_index),
+        None,
+    )?;
+
+    validate!(
+        user.perp_positions[position_index].open_orders == 0,
+        ErrorCode::PerpMarketSettlementUserHasOpenOrders,
+        "User must first cancel open orders for expired market"
+    )?;
+
+    Ok(())
+}
+
+fn settle_expired_position_for_batch(
+    user: &mut User,
+    position_index: usize,
+    perp_market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    fee_structure: &FeeStructure,
+    slot: u64,
+    record: &mut SettleExpiredPositionsRecord,
+) -> DriftResult {
     let base_asset_amount = user.perp_positions[position_index].base_asset_amount;
-    let quote_entry_amount = user.perp_positions[position_index].quote_entry_amount;
+
+    let (pnl_to_settle_with_user, _, _) = close_expired_position(
+        user,
+        position_index,
+        perp_market,
+        quote_spot_market,
+        fee_structure,
+    )?;
+
+    validate!(
+        user.perp_positions[position_index].is_available(),
+        ErrorCode::UnableToSettleExpiredUserPosition,
+        "Issue occurred in expired settlement"
+    )?;
+
+    user.update_last_active_slot(slot);
+
+    record.users_settled = record.users_settled.safe_add(1)?;
+    record.base_asset_amount_settled = record
+        .base_asset_amount_settled
+        .safe_add(base_asset_amount.unsigned_abs().cast()?)?;
+    if pnl_to_settle_with_user > 0 {
+        record.pnl_paid = record
+            .pnl_paid
+            .safe_add(pnl_to_settle_with_user.unsigned_abs())?;
+    } else {
+        record.pnl_collected = record
+            .pnl_collected
+            .safe_add(pnl_to_settle_with_user.unsigned_abs())?;
+    }
+
+    Ok(())
+}
+
+/// Returns the base asset value, settle price, explanation and fee an expired position closes with
+fn calculate_expired_position_settlement(
+    position: &PerpPosition,
+    perp_market: &PerpMarket,
+    fee_structure: &FeeStructure,
+) -> DriftResult<(i64, i64, SettlePnlExplanation, i64)> {
+    let (base_asset_value, settle_price, explanation) = if perp_market.is_voided() {
+        // voided positions close at their own cost basis
+        let base_asset_value = if perp_market.void_status == MarketVoidStatus::VoidedWithFeeRefund {
+            -position.quote_break_even_amount
+        } else {
+            -position.quote_entry_amount
+        };
+
+        (
+            base_asset_value,
+            perp_market.get_settlement_price(position)?,
+            SettlePnlExplanation::VoidedPosition,
+        )
+    } else {
+        (
+            calculate_base_asset_value_with_expiry_price(position, perp_market.expiry_price)?,
+            perp_market.expiry_price,
+            SettlePnlExplanation::ExpiredPosition,
+        )
+    };
+
+    let fee = if perp_market.is_voided() {
+        0
+    } else {
+        base_asset_value
+            .safe_mul(fee_structure.fee_tiers[0].fee_numerator as i64)?
+            .safe_div(fee_structure.fee_tiers[0].fee_denominator as i64)?
+    };
+
+    Ok((base_asset_value, settle_price, explanation, fee))
+}
+
+/// The pnl an expired position settles for once closed and charged the settlement fee
+fn calculate_expired_position_pnl(
+    position: &PerpPosition,
+    perp_market: &PerpMarket,
+    fee_structure: &FeeStructure,
+) -> DriftResult<i64> {
+    let (base_asset_value, _, _, fee) =
+        calculate_expired_position_settlement(position, perp_market, fee_structure)?;
+
+    position
+        .quote_asset_amount
+        .safe_add(base_asset_value)?
+        .safe_sub(fee.abs())
+}
+
+/// Closes an expired position at its settle price and settles the resulting pnl with the pnl pool
+/// Returns the pnl settled with the user, the settle price and the explanation
+fn close_expired_position(
+    user: &mut User,
+    position_index: usize,
+    perp_market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    fee_structure: &FeeStructure,
+) -> DriftResult<(i128, i64, SettlePnlExplanation)> {
+    let (base_asset_value, settle_price, explanation, fee) = calculate_expired_position_settlement(
+        &user.perp_positions[position_index],
+        perp_market,
+        fee_structure,
+    )?;
 
     let position_delta = PositionDelta {
         quote_asset_amount: base_asset_value,
@@ -461,10 +834,6 @@ pub fn settle_expired_position(
         &position_delta,
     )?;
 
-    let fee = base_asset_value
-        .safe_mul(fee_structure.fee_tiers[0].fee_numerator as i64)?
-        .safe_div(fee_structure.fee_tiers[0].fee_denominator as i64)?;
-
     update_quote_asset_and_break_even_amount(
         &mut user.perp_positions[position_index],
         perp_market,
@@ -489,25 +858,5 @@ pub fn settle_expired_position(
         .base_asset_amount_with_amm
         .safe_add(position_delta.base_asset_amount.cast()?)?;
 
-    let quote_asset_amount_after = user.perp_positions[position_index].quote_asset_amount;
-
-    emit!(SettlePnlRecord {
-        ts: now,
-        user: *user_key,
-        market_index: perp_market_index,
-        pnl: pnl_to_settle_with_user,
-        base_asset_amount,
-        quote_asset_amount_after,
-        quote_entry_amount,
-        settle_price: perp_market.expiry_price,
-        explanation: SettlePnlExplanation::ExpiredPosition,
-    });
-
-    validate!(
-        user.perp_positions[position_index].is_available(),
-        ErrorCode::UnableToSettleExpiredUserPosition,
-        "Issue occurred in expired settlement"
-    )?;
-
-    Ok(())
+    Ok((pnl_to_settle_with_user, settle_price, explanation))
 }
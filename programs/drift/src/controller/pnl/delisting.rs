@@ -12,15 +12,15 @@ fn get_user_keys() -> (Pubkey, Pubkey, Pubkey) {
 pub mod delisting_test {
     use std::str::FromStr;
 
-    use anchor_lang::prelude::Clock;
+    use anchor_lang::prelude::{AccountLoader, Clock};
 
     use crate::controller::liquidation::{liquidate_perp, liquidate_perp_pnl_for_deposit};
     // use crate::controller::orders::fill_order;
     use crate::controller::liquidation::resolve_perp_bankruptcy;
     use crate::controller::orders::cancel_order;
-    use crate::controller::pnl::settle_expired_position;
+    use crate::controller::pnl::{settle_expired_position, settle_expired_positions};
     use crate::controller::position::PositionDirection;
-    use crate::controller::repeg::settle_expired_market;
+    use crate::controller::repeg::{settle_expired_market, void_expired_market};
     use crate::create_account_info;
     use crate::create_anchor_account_info;
     use crate::math::amm::calculate_net_user_pnl;
@@ -40,12 +40,15 @@ pub mod delisting_test {
     use crate::state::margin_calculation::{MarginCalculation, MarginContext};
     use crate::state::oracle::OracleSource;
     use crate::state::oracle::{HistoricalOracleData, StrictOraclePrice};
-    use crate::state::perp_market::{MarketStatus, PerpMarket, PoolBalance, AMM};
+    use crate::state::perp_market::{
+        ContractType, MarketStatus, MarketVoidStatus, PerpMarket, PoolBalance, AMM,
+    };
     use crate::state::perp_market_map::PerpMarketMap;
     use crate::state::spot_market::{SpotBalanceType, SpotMarket};
     use crate::state::spot_market_map::SpotMarketMap;
     use crate::state::state::{OracleGuardRails, State, ValidityGuardRails};
-    use crate::state::user::{OrderStatus, OrderType, SpotPosition, User, UserStats};
+    use crate::state::user::{MarketType, OrderStatus, OrderType, SpotPosition, User, UserStats};
+    use crate::state::user_map::UserMap;
     use crate::test_utils::*;
     use crate::test_utils::{get_orders, get_positions, get_pyth_price, get_spot_positions};
 
@@ -139,6 +142,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .is_err());
         assert_eq!(market.is_reduce_only().unwrap(), false);
@@ -161,6 +165,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .is_err());
     }
@@ -265,6 +270,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -373,6 +379,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -486,6 +493,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -599,6 +607,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -770,6 +779,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -988,6 +998,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -1193,6 +1204,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -1489,6 +1501,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -1917,6 +1930,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
         assert_eq!(market.is_reduce_only().unwrap(), false);
@@ -2293,6 +2307,7 @@ pub mod delisting_test {
             &spot_market_map,
             &state,
             &clock,
+            None,
         )
         .unwrap();
 
@@ -2346,6 +2361,7 @@ pub mod delisting_test {
                     0,
                     false,
                     false,
+                    None,
                 )
                 .unwrap();
 
@@ -2426,6 +2442,7 @@ pub mod delisting_test {
                         0,
                         false,
                         false,
+                        None,
                     )
                     .unwrap();
 
@@ -2514,6 +2531,7 @@ pub mod delisting_test {
                         0,
                         false,
                         false,
+                        None,
                     )
                     .unwrap();
 
@@ -2606,6 +2624,7 @@ pub mod delisting_test {
                         0,
                         false,
                         false,
+                        None,
                     )
                     .unwrap();
 
@@ -2849,4 +2868,498 @@ pub mod delisting_test {
             assert_eq!(longer.perp_positions[0].last_cumulative_funding_rate, 0);
         }
     }
+
+    #[test]
+    fn void_prediction_market_settles_at_cost_basis() {
+        let slot = 0_u64;
+        let clock = Clock {
+            slot: 6893025720,
+            epoch_start_timestamp: 1662065595 - 1000,
+            epoch: 2424,
+            leader_schedule_epoch: 1662065595 - 1,
+            unix_timestamp: 1662065595,
+        };
+
+        for refund_fees in [false, true] {
+            let mut oracle_price = get_hardcoded_pyth_price(600_000, 6);
+            let oracle_price_key =
+                Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+            let pyth_program = crate::ids::pyth_program::id();
+            create_account_info!(
+                oracle_price,
+                &oracle_price_key,
+                &pyth_program,
+                oracle_account_info
+            );
+            let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+            // user bought 10 shares at $0.40 and paid $0.10 in fees
+            let mut market = PerpMarket {
+                amm: AMM {
+                    base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                    quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                    bid_base_asset_reserve: 101 * AMM_RESERVE_PRECISION,
+                    bid_quote_asset_reserve: 99 * AMM_RESERVE_PRECISION,
+                    ask_base_asset_reserve: 99 * AMM_RESERVE_PRECISION,
+                    ask_quote_asset_reserve: 101 * AMM_RESERVE_PRECISION,
+                    base_asset_amount_with_amm: 10 * AMM_RESERVE_PRECISION as i128,
+                    base_asset_amount_long: 10 * AMM_RESERVE_PRECISION as i128,
+                    quote_asset_amount: -4_100_000,
+                    quote_entry_amount_long: -4_000_000,
+                    quote_break_even_amount_long: -4_100_000,
+                    sqrt_k: 100 * AMM_RESERVE_PRECISION,
+                    peg_multiplier: 600_000,
+                    order_step_size: 10000000,
+                    oracle: oracle_price_key,
+                    historical_oracle_data: HistoricalOracleData {
+                        last_oracle_price_twap: 600_000,
+                        ..HistoricalOracleData::default()
+                    },
+                    fee_pool: PoolBalance {
+                        scaled_balance: SPOT_BALANCE_PRECISION / 10,
+                        market_index: QUOTE_SPOT_MARKET_INDEX,
+                        ..PoolBalance::default()
+                    },
+                    total_fee_minus_distributions: 100_000,
+                    total_exchange_fee: 100_000,
+                    ..AMM::default()
+                },
+                contract_type: ContractType::Prediction,
+                number_of_users_with_base: 1,
+                number_of_users: 1,
+                margin_ratio_initial: 1000,
+                margin_ratio_maintenance: 500,
+                status: MarketStatus::Initialized,
+                pnl_pool: PoolBalance {
+                    scaled_balance: 10 * SPOT_BALANCE_PRECISION,
+                    market_index: QUOTE_SPOT_MARKET_INDEX,
+                    ..PoolBalance::default()
+                },
+                expiry_ts: clock.unix_timestamp - 10, // past expiry time
+                ..PerpMarket::default_test()
+            };
+            market.amm.max_base_asset_reserve = u128::MAX;
+            market.amm.min_base_asset_reserve = 0;
+
+            create_anchor_account_info!(market, PerpMarket, market_account_info);
+            let market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+            let mut spot_market = SpotMarket {
+                market_index: 0,
+                oracle_source: OracleSource::QuoteAsset,
+                cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+                decimals: 6,
+                initial_asset_weight: SPOT_WEIGHT_PRECISION,
+                maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+                initial_liability_weight: SPOT_WEIGHT_PRECISION,
+                maintenance_liability_weight: SPOT_WEIGHT_PRECISION,
+                deposit_balance: 10000 * SPOT_BALANCE_PRECISION,
+                historical_oracle_data: HistoricalOracleData::default_price(QUOTE_PRECISION_I64),
+                ..SpotMarket::default()
+            };
+            create_anchor_account_info!(spot_market, SpotMarket, spot_market_account_info);
+            let spot_market_map = SpotMarketMap::load_one(&spot_market_account_info, true).unwrap();
+
+            let mut user = User {
+                orders: get_orders(Order::default()),
+                perp_positions: get_positions(PerpPosition {
+                    market_index: 0,
+                    base_asset_amount: 10 * BASE_PRECISION_I64,
+                    quote_asset_amount: -4_100_000,
+                    quote_entry_amount: -4_000_000,
+                    quote_break_even_amount: -4_100_000,
+                    ..PerpPosition::default()
+                }),
+                spot_positions: get_spot_positions(SpotPosition {
+                    market_index: 0,
+                    balance_type: SpotBalanceType::Deposit,
+                    scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+                    ..SpotPosition::default()
+                }),
+                ..User::default()
+            };
+
+            let (user_key, _, _) = get_user_keys();
+
+            let state = State::default();
+
+            void_expired_market(0, &market_map, &spot_market_map, &clock, refund_fees).unwrap();
+
+            let market = market_map.get_ref(&0).unwrap();
+            assert_eq!(market.status, MarketStatus::Settlement);
+            assert_eq!(market.expiry_price, 600_000);
+            if refund_fees {
+                assert_eq!(market.void_status, MarketVoidStatus::VoidedWithFeeRefund);
+                assert_eq!(market.amm.fee_pool.scaled_balance, 0);
+                assert_eq!(market.pnl_pool.scaled_balance, 10_100_000_000);
+            } else {
+                assert_eq!(market.void_status, MarketVoidStatus::Voided);
+                assert_eq!(market.amm.fee_pool.scaled_balance, 50_000_000);
+                assert_eq!(market.pnl_pool.scaled_balance, 10_050_000_000);
+            }
+            drop(market);
+
+            settle_expired_position(
+                0,
+                &mut user,
+                &user_key,
+                &market_map,
+                &spot_market_map,
+                &mut oracle_map,
+                &clock,
+                &state,
+            )
+            .unwrap();
+
+            // the oracle price is ignored, the user gets back what they paid
+            let market = market_map.get_ref(&0).unwrap();
+            if refund_fees {
+                assert_eq!(user.spot_positions[0].scaled_balance, 100_000_000_000);
+                assert_eq!(market.pnl_pool.scaled_balance, 10_100_000_000);
+            } else {
+                assert_eq!(user.spot_positions[0].scaled_balance, 99_900_000_000);
+                assert_eq!(market.pnl_pool.scaled_balance, 10_150_000_000);
+            }
+            assert_eq!(market.amm.base_asset_amount_with_amm, 0);
+            drop(market);
+
+            assert!(user.perp_positions[0].is_available());
+        }
+    }
+
+    #[test]
+    fn batch_settle_prediction_market_pays_winners_pnl_pool_can_cover() {
+        let slot = 0_u64;
+        let clock = Clock {
+            slot: 6893025720,
+            epoch_start_timestamp: 1662065595 - 1000,
+            epoch: 2424,
+            leader_schedule_epoch: 1662065595 - 1,
+            unix_timestamp: 1662065595,
+        };
+
+        let mut oracle_price = get_hardcoded_pyth_price(600_000, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+        // two users bought 10 yes shares at $0.40 and one sold 10, market resolved yes
+        let mut market = PerpMarket {
+            amm: AMM {
+                base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                bid_base_asset_reserve: 101 * AMM_RESERVE_PRECISION,
+                bid_quote_asset_reserve: 99 * AMM_RESERVE_PRECISION,
+                ask_base_asset_reserve: 99 * AMM_RESERVE_PRECISION,
+                ask_quote_asset_reserve: 101 * AMM_RESERVE_PRECISION,
+                base_asset_amount_with_amm: 10 * AMM_RESERVE_PRECISION as i128,
+                base_asset_amount_long: 20 * AMM_RESERVE_PRECISION as i128,
+                base_asset_amount_short: -10 * AMM_RESERVE_PRECISION as i128,
+                quote_asset_amount: -4_000_000,
+                quote_entry_amount_long: -8_000_000,
+                quote_entry_amount_short: 4_000_000,
+                quote_break_even_amount_long: -8_000_000,
+                quote_break_even_amount_short: 4_000_000,
+                sqrt_k: 100 * AMM_RESERVE_PRECISION,
+                peg_multiplier: 600_000,
+                order_step_size: 10000000,
+                oracle: oracle_price_key,
+                historical_oracle_data: HistoricalOracleData {
+                    last_oracle_price_twap: 600_000,
+                    ..HistoricalOracleData::default()
+                },
+                ..AMM::default()
+            },
+            contract_type: ContractType::Prediction,
+            number_of_users_with_base: 3,
+            number_of_users: 3,
+            margin_ratio_initial: 1000,
+            margin_ratio_maintenance: 500,
+            status: MarketStatus::Settlement,
+            expiry_price: PRICE_PRECISION_I64,
+            expiry_ts: clock.unix_timestamp - 10, // past expiry time
+            ..PerpMarket::default_test()
+        };
+        market.amm.max_base_asset_reserve = u128::MAX;
+        market.amm.min_base_asset_reserve = 0;
+
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        let mut spot_market = SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            initial_asset_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+            initial_liability_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_liability_weight: SPOT_WEIGHT_PRECISION,
+            deposit_balance: 10000 * SPOT_BALANCE_PRECISION,
+            historical_oracle_data: HistoricalOracleData::default_price(QUOTE_PRECISION_I64),
+            ..SpotMarket::default()
+        };
+        create_anchor_account_info!(spot_market, SpotMarket, spot_market_account_info);
+        let spot_market_map = SpotMarketMap::load_one(&spot_market_account_info, true).unwrap();
+
+        let user_with_position = |base_asset_amount: i64, quote_asset_amount: i64| User {
+            orders: get_orders(Order::default()),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                base_asset_amount,
+                quote_asset_amount,
+                quote_entry_amount: quote_asset_amount,
+                quote_break_even_amount: quote_asset_amount,
+                ..PerpPosition::default()
+            }),
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: 0,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+                ..SpotPosition::default()
+            }),
+            ..User::default()
+        };
+
+        // keys are ordered so a winner comes before the loser that funds it
+        let first_longer_key = Pubkey::new_unique();
+        let shorter_key = Pubkey::new_unique();
+        let second_longer_key = Pubkey::new_unique();
+
+        let mut first_longer = user_with_position(10 * BASE_PRECISION_I64, -4_000_000);
+        create_anchor_account_info!(
+            first_longer,
+            &first_longer_key,
+            User,
+            first_longer_account_info
+        );
+        let mut shorter = user_with_position(-10 * BASE_PRECISION_I64, 4_000_000);
+        create_anchor_account_info!(shorter, &shorter_key, User, shorter_account_info);
+        let mut second_longer = user_with_position(10 * BASE_PRECISION_I64, -4_000_000);
+        create_anchor_account_info!(
+            second_longer,
+            &second_longer_key,
+            User,
+            second_longer_account_info
+        );
+
+        let mut user_map = UserMap::load_one(&first_longer_account_info).unwrap();
+        user_map
+            .insert(
+                shorter_key,
+                AccountLoader::try_from(&shorter_account_info).unwrap(),
+            )
+            .unwrap();
+        user_map
+            .insert(
+                second_longer_key,
+                AccountLoader::try_from(&second_longer_account_info).unwrap(),
+            )
+            .unwrap();
+
+        let state = State::default();
+
+        let record = settle_expired_positions(
+            0,
+            &user_map,
+            &market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            &clock,
+            &state,
+        )
+        .unwrap();
+
+        // shorter pays $6 plus 1c fee, first longer is paid $6 less 1c fee, the pnl pool cant
+        // cover the second longer yet
+        assert_eq!(record.users_settled, 2);
+        assert_eq!(record.users_skipped, 1);
+        assert_eq!(
+            record.base_asset_amount_settled,
+            20 * BASE_PRECISION_U64 as u128
+        );
+        assert_eq!(record.pnl_collected, 6_010_000);
+        assert_eq!(record.pnl_paid, 5_990_000);
+        assert_eq!(record.settle_price, PRICE_PRECISION_I64);
+
+        let shorter = user_map.get_ref(&shorter_key).unwrap();
+        assert!(shorter.perp_positions[0].is_available());
+        assert_eq!(shorter.spot_positions[0].scaled_balance, 93_990_000_000);
+        drop(shorter);
+
+        let first_longer = user_map.get_ref(&first_longer_key).unwrap();
+        assert!(first_longer.perp_positions[0].is_available());
+        assert_eq!(
+            first_longer.spot_positions[0].scaled_balance,
+            105_990_000_000
+        );
+        drop(first_longer);
+
+        let second_longer = user_map.get_ref(&second_longer_key).unwrap();
+        assert_eq!(
+            second_longer.perp_positions[0].base_asset_amount,
+            10 * BASE_PRECISION_I64
+        );
+        assert_eq!(
+            second_longer.perp_positions[0].quote_asset_amount,
+            -4_000_000
+        );
+        assert_eq!(
+            second_longer.spot_positions[0].scaled_balance,
+            100 * SPOT_BALANCE_PRECISION_U64
+        );
+        drop(second_longer);
+
+        let market = market_map.get_ref(&0).unwrap();
+        assert_eq!(market.pnl_pool.scaled_balance, 20_000_000);
+        assert_eq!(
+            market.amm.base_asset_amount_long,
+            10 * BASE_PRECISION_I64 as i128
+        );
+        assert_eq!(market.amm.base_asset_amount_short, 0);
+        assert_eq!(
+            market.amm.base_asset_amount_with_amm,
+            10 * AMM_RESERVE_PRECISION as i128
+        );
+    }
+
+    #[test]
+    fn batch_settle_skips_bankrupt_user_without_canceling_orders() {
+        let slot = 0_u64;
+        let clock = Clock {
+            slot: 6893025720,
+            epoch_start_timestamp: 1662065595 - 1000,
+            epoch: 2424,
+            leader_schedule_epoch: 1662065595 - 1,
+            unix_timestamp: 1662065595,
+        };
+
+        let mut oracle_price = get_hardcoded_pyth_price(600_000, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+        let mut market = PerpMarket {
+            amm: AMM {
+                base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+                base_asset_amount_with_amm: 10 * AMM_RESERVE_PRECISION as i128,
+                base_asset_amount_long: 10 * AMM_RESERVE_PRECISION as i128,
+                quote_asset_amount: -4_000_000,
+                quote_entry_amount_long: -4_000_000,
+                quote_break_even_amount_long: -4_000_000,
+                sqrt_k: 100 * AMM_RESERVE_PRECISION,
+                peg_multiplier: 600_000,
+                order_step_size: 10000000,
+                oracle: oracle_price_key,
+                historical_oracle_data: HistoricalOracleData {
+                    last_oracle_price_twap: 600_000,
+                    ..HistoricalOracleData::default()
+                },
+                ..AMM::default()
+            },
+            contract_type: ContractType::Prediction,
+            number_of_users_with_base: 1,
+            number_of_users: 1,
+            margin_ratio_initial: 1000,
+            margin_ratio_maintenance: 500,
+            status: MarketStatus::Settlement,
+            expiry_price: PRICE_PRECISION_I64,
+            expiry_ts: clock.unix_timestamp - 10,
+            ..PerpMarket::default_test()
+        };
+        market.amm.max_base_asset_reserve = u128::MAX;
+        market.amm.min_base_asset_reserve = 0;
+
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        let mut spot_market = SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            initial_asset_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+            deposit_balance: 10000 * SPOT_BALANCE_PRECISION,
+            historical_oracle_data: HistoricalOracleData::default_price(QUOTE_PRECISION_I64),
+            ..SpotMarket::default()
+        };
+        create_anchor_account_info!(spot_market, SpotMarket, spot_market_account_info);
+        let spot_market_map = SpotMarketMap::load_one(&spot_market_account_info, true).unwrap();
+
+        let mut user = User {
+            orders: get_orders(Order {
+                market_index: 0,
+                market_type: MarketType::Perp,
+                status: OrderStatus::Open,
+                order_type: OrderType::Limit,
+                direction: PositionDirection::Long,
+                base_asset_amount: BASE_PRECISION_U64,
+                price: 500_000,
+                ..Order::default()
+            }),
+            perp_positions: get_positions(PerpPosition {
+                market_index: 0,
+                base_asset_amount: 10 * BASE_PRECISION_I64,
+                quote_asset_amount: -4_000_000,
+                quote_entry_amount: -4_000_000,
+                quote_break_even_amount: -4_000_000,
+                open_orders: 1,
+                open_bids: BASE_PRECISION_I64,
+                ..PerpPosition::default()
+            }),
+            spot_positions: get_spot_positions(SpotPosition {
+                market_index: 0,
+                balance_type: SpotBalanceType::Deposit,
+                scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+                open_orders: 0,
+                ..SpotPosition::default()
+            }),
+            ..User::default()
+        };
+        user.enter_bankruptcy();
+        let user_key = Pubkey::new_unique();
+        create_anchor_account_info!(user, &user_key, User, user_account_info);
+        let user_map = UserMap::load_one(&user_account_info).unwrap();
+
+        let state = State::default();
+
+        let record = settle_expired_positions(
+            0,
+            &user_map,
+            &market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            &clock,
+            &state,
+        )
+        .unwrap();
+
+        assert_eq!(record.users_settled, 0);
+        assert_eq!(record.users_skipped, 1);
+
+        let user = user_map.get_ref(&user_key).unwrap();
+        assert_eq!(user.orders[0].status, OrderStatus::Open);
+        assert_eq!(user.perp_positions[0].open_orders, 1);
+        assert_eq!(user.perp_positions[0].open_bids, BASE_PRECISION_I64);
+        assert_eq!(
+            user.perp_positions[0].base_asset_amount,
+            10 * BASE_PRECISION_I64
+        );
+    }
 }
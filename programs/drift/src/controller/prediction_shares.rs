@@ -0,0 +1,425 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::controller;
+use crate::controller::amm::update_pnl_pool_and_user_balance;
+use crate::controller::position::{
+    get_position_index, update_position_and_market, update_quote_asset_amount, update_settled_pnl,
+    PositionDelta, PositionDirection,
+};
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::{PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO, QUOTE_SPOT_MARKET_INDEX};
+use crate::math::orders::standardize_base_asset_amount;
+use crate::math::safe_math::SafeMath;
+use crate::math::spot_balance::get_token_amount;
+use crate::state::paused_operations::PerpOperation;
+use crate::state::perp_market::{MarketStatus, PerpMarket};
+use crate::state::spot_market::{SpotBalance, SpotMarket};
+use crate::state::user::User;
+use crate::validate;
+use solana_program::msg;
+
+#[cfg(test)]
+mod tests;
+
+pub const PREDICTION_YES_MINT_SEED: &[u8] = b"prediction_yes_mint";
+pub const PREDICTION_NO_MINT_SEED: &[u8] = b"prediction_no_mint";
+pub const PREDICTION_SHARE_MINT_DECIMALS: u8 = 9;
+
+/// YES shares are tokenized longs, NO shares are tokenized shorts
+pub fn get_prediction_share_mint_seed(direction: PositionDirection) -> &'static [u8] {
+    match direction {
+        PositionDirection::Long => PREDICTION_YES_MINT_SEED,
+        PositionDirection::Short => PREDICTION_NO_MINT_SEED,
+    }
+}
+
+/// The price a share is backed at when it leaves a user's position. Longs are backed down to the
+/// lower bound and shorts up to the upper bound so a share can never owe more than it is worth.
+/// precision: PRICE_PRECISION
+pub fn get_prediction_share_backing_price(
+    market: &PerpMarket,
+    direction: PositionDirection,
+) -> DriftResult<u64> {
+    let price_bounds = market.get_prediction_market_price_bounds().ok_or_else(|| {
+        msg!(
+            "perp market {} is not a prediction market",
+            market.market_index
+        );
+        ErrorCode::InvalidPredictionShares
+    })?;
+
+    Ok(match direction {
+        PositionDirection::Long => price_bounds.lower,
+        PositionDirection::Short => price_bounds.upper,
+    })
+}
+
+/// The quote amount backing `base_asset_amount` shares
+/// precision: QUOTE_PRECISION
+pub fn calculate_prediction_share_backing_amount(
+    market: &PerpMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> DriftResult<u64> {
+    base_asset_amount
+        .cast::<u128>()?
+        .safe_mul(get_prediction_share_backing_price(market, direction)?.cast()?)?
+        .safe_div(PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO)?
+        .cast()
+}
+
+/// Signed backing amount as a position quote delta, positive for shorts and negative for longs
+/// precision: QUOTE_PRECISION
+fn calculate_backing_quote_asset_amount(
+    market: &PerpMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> DriftResult<i64> {
+    let quote_asset_amount =
+        calculate_prediction_share_backing_amount(market, direction, base_asset_amount)?
+            .cast::<i64>()?;
+
+    Ok(match direction {
+        PositionDirection::Long => -quote_asset_amount,
+        PositionDirection::Short => quote_asset_amount,
+    })
+}
+
+/// What a share redeems for once the market is settled
+/// precision: QUOTE_PRECISION
+pub fn calculate_prediction_share_redemption_amount(
+    market: &PerpMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> DriftResult<u64> {
+    let price_bounds = market.get_prediction_market_price_bounds().ok_or_else(|| {
+        msg!(
+            "perp market {} is not a prediction market",
+            market.market_index
+        );
+        ErrorCode::InvalidPredictionShares
+    })?;
+
+    // voided markets also set expiry_price, shares redeem at it since they carry no cost basis
+    let expiry_price = price_bounds
+        .clamp_price_i64(market.expiry_price)?
+        .unsigned_abs();
+
+    let payout_per_share = match direction {
+        PositionDirection::Long => expiry_price.safe_sub(price_bounds.lower)?,
+        PositionDirection::Short => price_bounds.upper.safe_sub(expiry_price)?,
+    };
+
+    base_asset_amount
+        .cast::<u128>()?
+        .safe_mul(payout_per_share.cast()?)?
+        .safe_div(PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO)?
+        .cast()
+}
+
+fn validate_prediction_share_amount(market: &PerpMarket, base_asset_amount: u64) -> DriftResult {
+    validate!(
+        market.is_prediction_market(),
+        ErrorCode::InvalidPredictionShares,
+        "perp market {} is not a prediction market",
+        market.market_index
+    )?;
+
+    validate!(
+        base_asset_amount > 0 && base_asset_amount % market.amm.order_step_size == 0,
+        ErrorCode::InvalidPredictionShares,
+        "share amount {} must be a positive multiple of step size {}",
+        base_asset_amount,
+        market.amm.order_step_size
+    )?;
+
+    Ok(())
+}
+
+/// Tokenized shares stay in the market's long/short totals as a position without a user so the
+/// market keeps owing them at settlement. The position is entered at the share backing price.
+fn update_market_for_prediction_shares(
+    market: &mut PerpMarket,
+    direction: PositionDirection,
+    base_asset_amount_delta: i64,
+    quote_asset_amount_delta: i64,
+) -> DriftResult {
+    match direction {
+        PositionDirection::Long => {
+            market.amm.base_asset_amount_long = market
+                .amm
+                .base_asset_amount_long
+                .safe_add(base_asset_amount_delta.cast()?)?;
+            market.amm.quote_entry_amount_long = market
+                .amm
+                .quote_entry_amount_long
+                .safe_add(quote_asset_amount_delta.cast()?)?;
+            market.amm.quote_break_even_amount_long = market
+                .amm
+                .quote_break_even_amount_long
+                .safe_add(quote_asset_amount_delta.cast()?)?;
+        }
+        PositionDirection::Short => {
+            market.amm.base_asset_amount_short = market
+                .amm
+                .base_asset_amount_short
+                .safe_sub(base_asset_amount_delta.cast()?)?;
+            market.amm.quote_entry_amount_short = market
+                .amm
+                .quote_entry_amount_short
+                .safe_add(quote_asset_amount_delta.cast()?)?;
+            market.amm.quote_break_even_amount_short = market
+                .amm
+                .quote_break_even_amount_short
+                .safe_add(quote_asset_amount_delta.cast()?)?;
+        }
+    }
+
+    market.amm.quote_asset_amount = market
+        .amm
+        .quote_asset_amount
+        .safe_add(quote_asset_amount_delta.cast()?)?;
+
+    Ok(())
+}
+
+/// Moves `base_asset_amount` of the user's long (YES) or short (NO) position into shares.
+/// The user closes the amount at the share backing price and settles the realized pnl with the
+/// pnl pool, so the shares are fully collateralized by the time they are minted.
+/// Returns the pnl settled
+pub fn wrap_prediction_shares(
+    user: &mut User,
+    user_key: &Pubkey,
+    market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<i64> {
+    validate_prediction_share_amount(market, base_asset_amount)?;
+
+    validate!(
+        matches!(
+            market.status,
+            MarketStatus::Active | MarketStatus::ReduceOnly
+        ),
+        ErrorCode::MarketActionPaused,
+        "perp market {} must be active to wrap shares",
+        market.market_index
+    )?;
+
+    validate!(
+        !market.is_trading_halted(now),
+        ErrorCode::MarketActionPaused,
+        "perp market {} trading is halted",
+        market.market_index
+    )?;
+
+    // shares don't pay or receive funding
+    validate!(
+        market.is_operation_paused(PerpOperation::UpdateFunding),
+        ErrorCode::InvalidPredictionShares,
+        "perp market {} must have funding paused to wrap shares",
+        market.market_index
+    )?;
+
+    controller::funding::settle_funding_payment(user, user_key, market, now)?;
+
+    let position_index = get_position_index(&user.perp_positions, market.market_index)?;
+    let position = &mut user.perp_positions[position_index];
+
+    let signed_base_asset_amount = match direction {
+        PositionDirection::Long => base_asset_amount.cast::<i64>()?,
+        PositionDirection::Short => -base_asset_amount.cast::<i64>()?,
+    };
+
+    validate!(
+        position.get_direction() == direction
+            && position.base_asset_amount.unsigned_abs() >= base_asset_amount,
+        ErrorCode::InvalidPredictionShares,
+        "user position {} can't wrap {:?} shares {}",
+        position.base_asset_amount,
+        direction,
+        base_asset_amount
+    )?;
+
+    let backing_quote_asset_amount =
+        calculate_backing_quote_asset_amount(market, direction, base_asset_amount)?;
+
+    let delta = PositionDelta {
+        base_asset_amount: -signed_base_asset_amount,
+        quote_asset_amount: -backing_quote_asset_amount,
+        remainder_base_asset_amount: None,
+    };
+
+    let pnl = update_position_and_market(position, market, &delta)?;
+
+    update_market_for_prediction_shares(
+        market,
+        direction,
+        signed_base_asset_amount,
+        backing_quote_asset_amount,
+    )?;
+
+    user.force_get_spot_position_index(QUOTE_SPOT_MARKET_INDEX)?;
+    let pnl_to_settle_with_user =
+        update_pnl_pool_and_user_balance(market, quote_spot_market, user, pnl.cast()?)?;
+
+    update_quote_asset_amount(
+        &mut user.perp_positions[position_index],
+        market,
+        -pnl_to_settle_with_user.cast()?,
+    )?;
+
+    update_settled_pnl(user, position_index, pnl_to_settle_with_user.cast()?)?;
+
+    Ok(pnl)
+}
+
+/// Moves `base_asset_amount` of YES (long) or NO (short) shares back into the user's position,
+/// entered at the share backing price. Returns the pnl realized on the user's position
+pub fn unwrap_prediction_shares(
+    user: &mut User,
+    user_key: &Pubkey,
+    market: &mut PerpMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+    now: i64,
+) -> DriftResult<i64> {
+    validate_prediction_share_amount(market, base_asset_amount)?;
+
+    validate!(
+        !matches!(
+            market.status,
+            MarketStatus::Settlement | MarketStatus::Delisted
+        ),
+        ErrorCode::MarketActionPaused,
+        "perp market {} already settled, redeem shares instead",
+        market.market_index
+    )?;
+
+    controller::funding::settle_funding_payment(user, user_key, market, now)?;
+
+    let signed_base_asset_amount = match direction {
+        PositionDirection::Long => base_asset_amount.cast::<i64>()?,
+        PositionDirection::Short => -base_asset_amount.cast::<i64>()?,
+    };
+
+    let backing_quote_asset_amount =
+        calculate_backing_quote_asset_amount(market, direction, base_asset_amount)?;
+
+    update_market_for_prediction_shares(
+        market,
+        direction,
+        -signed_base_asset_amount,
+        -backing_quote_asset_amount,
+    )?;
+
+    let delta = PositionDelta {
+        base_asset_amount: signed_base_asset_amount,
+        quote_asset_amount: backing_quote_asset_amount,
+        remainder_base_asset_amount: None,
+    };
+
+    update_position_and_market(
+        user.force_get_perp_position_mut(market.market_index)?,
+        market,
+        &delta,
+    )
+}
+
+/// Pays out up to `base_asset_amount` of YES (long) or NO (short) shares from the pnl pool into the
+/// user's quote balance once the market is settled. If the pnl pool can't cover every share only as
+/// many shares as it can pay for are redeemed. Returns the shares redeemed and the quote paid out
+pub fn redeem_prediction_shares(
+    user: &mut User,
+    market: &mut PerpMarket,
+    quote_spot_market: &mut SpotMarket,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> DriftResult<(u64, u64)> {
+    validate_prediction_share_amount(market, base_asset_amount)?;
+
+    validate!(
+        market.status == MarketStatus::Settlement,
+        ErrorCode::PerpMarketNotInSettlement,
+        "perp market {} isn't in settlement",
+        market.market_index
+    )?;
+
+    let redemption_amount =
+        calculate_prediction_share_redemption_amount(market, direction, base_asset_amount)?;
+
+    let pnl_pool_token_amount = get_token_amount(
+        market.pnl_pool.scaled_balance,
+        quote_spot_market,
+        market.pnl_pool.balance_type(),
+    )?
+    .cast::<u64>()?;
+
+    // the pnl pool fills up as losing positions settle, redeem what it can pay for now
+    let base_asset_amount = if redemption_amount > pnl_pool_token_amount {
+        let redeemable_base_asset_amount = standardize_base_asset_amount(
+            base_asset_amount
+                .cast::<u128>()?
+                .safe_mul(pnl_pool_token_amount.cast()?)?
+                .safe_div(redemption_amount.cast()?)?
+                .cast()?,
+            market.amm.order_step_size,
+        )?;
+
+        validate!(
+            redeemable_base_asset_amount > 0,
+            ErrorCode::InsufficientPerpPnlPool,
+            "pnl pool {} can not pay for any shares",
+            pnl_pool_token_amount
+        )?;
+
+        msg!(
+            "pnl pool {} can only pay for {} of {} shares",
+            pnl_pool_token_amount,
+            redeemable_base_asset_amount,
+            base_asset_amount
+        );
+
+        redeemable_base_asset_amount
+    } else {
+        base_asset_amount
+    };
+
+    let signed_base_asset_amount = match direction {
+        PositionDirection::Long => base_asset_amount.cast::<i64>()?,
+        PositionDirection::Short => -base_asset_amount.cast::<i64>()?,
+    };
+
+    let backing_quote_asset_amount =
+        calculate_backing_quote_asset_amount(market, direction, base_asset_amount)?;
+
+    update_market_for_prediction_shares(
+        market,
+        direction,
+        -signed_base_asset_amount,
+        -backing_quote_asset_amount,
+    )?;
+
+    // same as closing an expired position, the amm takes the other side
+    market.amm.base_asset_amount_with_amm = market
+        .amm
+        .base_asset_amount_with_amm
+        .safe_sub(signed_base_asset_amount.cast()?)?;
+
+    let redemption_amount =
+        calculate_prediction_share_redemption_amount(market, direction, base_asset_amount)?;
+
+    user.force_get_spot_position_index(QUOTE_SPOT_MARKET_INDEX)?;
+    let redeemed_quote_asset_amount = update_pnl_pool_and_user_balance(
+        market,
+        quote_spot_market,
+        user,
+        redemption_amount.cast()?,
+    )?
+    .cast()?;
+
+    Ok((base_asset_amount, redeemed_quote_asset_amount))
+}
@@ -0,0 +1,382 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::controller::position::{update_position_and_market, PositionDelta, PositionDirection};
+use crate::controller::prediction_shares::{
+    calculate_prediction_share_redemption_amount, redeem_prediction_shares,
+    unwrap_prediction_shares, wrap_prediction_shares,
+};
+use crate::error::ErrorCode;
+use crate::math::constants::{
+    AMM_RESERVE_PRECISION, BASE_PRECISION_I128, BASE_PRECISION_I64, BASE_PRECISION_U64,
+    PEG_PRECISION, PRICE_PRECISION_I64, QUOTE_PRECISION_I128, QUOTE_PRECISION_I64,
+    QUOTE_PRECISION_U64, QUOTE_SPOT_MARKET_INDEX, SPOT_BALANCE_PRECISION,
+    SPOT_BALANCE_PRECISION_U64, SPOT_CUMULATIVE_INTEREST_PRECISION,
+};
+use crate::state::oracle::OracleSource;
+use crate::state::paused_operations::PerpOperation;
+use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket, PoolBalance, AMM};
+use crate::state::spot_market::{SpotBalanceType, SpotMarket};
+use crate::state::user::{SpotPosition, User};
+use crate::test_utils::get_spot_positions;
+
+fn prediction_market() -> PerpMarket {
+    PerpMarket {
+        market_index: 0,
+        contract_type: ContractType::Prediction,
+        status: MarketStatus::Active,
+        paused_operations: PerpOperation::UpdateFunding as u8,
+        pnl_pool: PoolBalance {
+            scaled_balance: 50 * SPOT_BALANCE_PRECISION,
+            market_index: QUOTE_SPOT_MARKET_INDEX,
+            ..PoolBalance::default()
+        },
+        amm: AMM {
+            base_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+            quote_asset_reserve: 100 * AMM_RESERVE_PRECISION,
+            sqrt_k: 100 * AMM_RESERVE_PRECISION,
+            peg_multiplier: PEG_PRECISION / 2,
+            order_step_size: BASE_PRECISION_U64 / 10,
+            ..AMM::default()
+        },
+        ..PerpMarket::default()
+    }
+}
+
+fn quote_spot_market() -> SpotMarket {
+    SpotMarket {
+        market_index: QUOTE_SPOT_MARKET_INDEX,
+        oracle_source: OracleSource::QuoteAsset,
+        cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+        cumulative_borrow_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+        decimals: 6,
+        deposit_balance: 200 * SPOT_BALANCE_PRECISION,
+        ..SpotMarket::default()
+    }
+}
+
+fn user_with_position(market: &mut PerpMarket, base_asset_amount: i64) -> User {
+    let mut user = User {
+        spot_positions: get_spot_positions(SpotPosition {
+            market_index: QUOTE_SPOT_MARKET_INDEX,
+            balance_type: SpotBalanceType::Deposit,
+            scaled_balance: 100 * SPOT_BALANCE_PRECISION_U64,
+            ..SpotPosition::default()
+        }),
+        ..User::default()
+    };
+
+    // entered at 60c
+    let quote_asset_amount = -base_asset_amount / BASE_PRECISION_I64 * QUOTE_PRECISION_I64 * 6 / 10;
+    let position = user.force_get_perp_position_mut(0).unwrap();
+    update_position_and_market(
+        position,
+        market,
+        &PositionDelta {
+            base_asset_amount,
+            quote_asset_amount,
+            remainder_base_asset_amount: None,
+        },
+    )
+    .unwrap();
+    market.amm.base_asset_amount_with_amm = base_asset_amount.into();
+
+    user
+}
+
+#[test]
+fn wrap_and_unwrap_yes_shares() {
+    let mut market = prediction_market();
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, 10 * BASE_PRECISION_I64);
+    let user_key = Pubkey::default();
+
+    // cant wrap more than the position
+    assert!(wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        11 * BASE_PRECISION_U64,
+        0,
+    )
+    .is_err());
+
+    // cant wrap the wrong side
+    assert!(wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Short,
+        BASE_PRECISION_U64,
+        0,
+    )
+    .is_err());
+
+    // shares are backed down to the lower bound, user pays the 60c entry on 4 shares
+    let pnl = wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+        0,
+    )
+    .unwrap();
+    assert_eq!(pnl, -24 * QUOTE_PRECISION_I64 / 10);
+
+    let position = user.get_perp_position(0).unwrap();
+    assert_eq!(position.base_asset_amount, 6 * BASE_PRECISION_I64);
+    assert_eq!(position.quote_asset_amount, -36 * QUOTE_PRECISION_I64 / 10);
+    assert_eq!(position.quote_entry_amount, -36 * QUOTE_PRECISION_I64 / 10);
+    assert_eq!(position.settled_pnl, -24 * QUOTE_PRECISION_I64 / 10);
+
+    assert_eq!(
+        user.get_quote_spot_position().scaled_balance,
+        976 * SPOT_BALANCE_PRECISION_U64 / 10
+    );
+    assert_eq!(
+        market.pnl_pool.scaled_balance,
+        524 * SPOT_BALANCE_PRECISION / 10
+    );
+
+    // shares are still counted in the market
+    assert_eq!(market.amm.base_asset_amount_long, 10 * BASE_PRECISION_I128);
+    assert_eq!(
+        market.amm.quote_entry_amount_long,
+        -36 * QUOTE_PRECISION_I128 / 10
+    );
+    assert_eq!(
+        market.amm.quote_asset_amount,
+        -36 * QUOTE_PRECISION_I128 / 10
+    );
+
+    let pnl = unwrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+        0,
+    )
+    .unwrap();
+    assert_eq!(pnl, 0);
+
+    let position = user.get_perp_position(0).unwrap();
+    assert_eq!(position.base_asset_amount, 10 * BASE_PRECISION_I64);
+    assert_eq!(position.quote_asset_amount, -36 * QUOTE_PRECISION_I64 / 10);
+    assert_eq!(position.quote_entry_amount, -36 * QUOTE_PRECISION_I64 / 10);
+
+    assert_eq!(market.amm.base_asset_amount_long, 10 * BASE_PRECISION_I128);
+    assert_eq!(
+        market.amm.quote_entry_amount_long,
+        -36 * QUOTE_PRECISION_I128 / 10
+    );
+    assert_eq!(
+        market.amm.quote_asset_amount,
+        -36 * QUOTE_PRECISION_I128 / 10
+    );
+}
+
+#[test]
+fn wrap_no_shares() {
+    let mut market = prediction_market();
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, -10 * BASE_PRECISION_I64);
+    let user_key = Pubkey::default();
+
+    // shares are backed up to the upper bound, user posts the $1 payout less the 60c received
+    let pnl = wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Short,
+        4 * BASE_PRECISION_U64,
+        0,
+    )
+    .unwrap();
+    assert_eq!(pnl, -16 * QUOTE_PRECISION_I64 / 10);
+
+    let position = user.get_perp_position(0).unwrap();
+    assert_eq!(position.base_asset_amount, -6 * BASE_PRECISION_I64);
+    assert_eq!(position.quote_entry_amount, 36 * QUOTE_PRECISION_I64 / 10);
+
+    assert_eq!(
+        market.amm.base_asset_amount_short,
+        -10 * BASE_PRECISION_I128
+    );
+    assert_eq!(
+        market.amm.quote_entry_amount_short,
+        76 * QUOTE_PRECISION_I128 / 10
+    );
+    assert_eq!(
+        user.get_quote_spot_position().scaled_balance,
+        984 * SPOT_BALANCE_PRECISION_U64 / 10
+    );
+}
+
+#[test]
+fn wrap_requires_funding_paused() {
+    let mut market = prediction_market();
+    market.paused_operations = 0;
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, 10 * BASE_PRECISION_I64);
+
+    assert!(wrap_prediction_shares(
+        &mut user,
+        &Pubkey::default(),
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        BASE_PRECISION_U64,
+        0,
+    )
+    .is_err());
+}
+
+#[test]
+fn wrap_blocked_once_trading_halt_ts_reached() {
+    let mut market = prediction_market();
+    market.trading_halt_ts = 100;
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, 10 * BASE_PRECISION_I64);
+
+    assert!(wrap_prediction_shares(
+        &mut user,
+        &Pubkey::default(),
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        BASE_PRECISION_U64,
+        100,
+    )
+    .is_err());
+}
+
+#[test]
+fn redeem_after_settlement() {
+    let mut market = prediction_market();
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, 10 * BASE_PRECISION_I64);
+    let user_key = Pubkey::default();
+
+    wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+        0,
+    )
+    .unwrap();
+
+    // cant redeem before settlement
+    assert!(redeem_prediction_shares(
+        &mut user,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+    )
+    .is_err());
+
+    market.status = MarketStatus::Settlement;
+    market.expiry_price = PRICE_PRECISION_I64;
+
+    assert_eq!(
+        calculate_prediction_share_redemption_amount(
+            &market,
+            PositionDirection::Short,
+            4 * BASE_PRECISION_U64
+        )
+        .unwrap(),
+        0
+    );
+
+    let (base_asset_amount, redemption_amount) = redeem_prediction_shares(
+        &mut user,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+    )
+    .unwrap();
+    assert_eq!(base_asset_amount, 4 * BASE_PRECISION_U64);
+    assert_eq!(redemption_amount, 4 * QUOTE_PRECISION_U64);
+
+    assert_eq!(
+        user.get_quote_spot_position().scaled_balance,
+        1016 * SPOT_BALANCE_PRECISION_U64 / 10
+    );
+    assert_eq!(
+        market.pnl_pool.scaled_balance,
+        484 * SPOT_BALANCE_PRECISION / 10
+    );
+
+    // only the user's remaining position is left in the market
+    assert_eq!(market.amm.base_asset_amount_long, 6 * BASE_PRECISION_I128);
+    assert_eq!(
+        market.amm.base_asset_amount_with_amm,
+        6 * BASE_PRECISION_I128
+    );
+    assert_eq!(
+        market.amm.quote_entry_amount_long,
+        -36 * QUOTE_PRECISION_I128 / 10
+    );
+}
+
+#[test]
+fn partial_redemption_from_short_pnl_pool() {
+    let mut market = prediction_market();
+    let mut spot_market = quote_spot_market();
+    let mut user = user_with_position(&mut market, 10 * BASE_PRECISION_I64);
+    let user_key = Pubkey::default();
+
+    wrap_prediction_shares(
+        &mut user,
+        &user_key,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+        0,
+    )
+    .unwrap();
+
+    market.status = MarketStatus::Settlement;
+    market.expiry_price = PRICE_PRECISION_I64;
+    market.pnl_pool.scaled_balance = 15 * SPOT_BALANCE_PRECISION / 10;
+
+    // pnl pool only has $1.5 for $4 of shares
+    let (base_asset_amount, redemption_amount) = redeem_prediction_shares(
+        &mut user,
+        &mut market,
+        &mut spot_market,
+        PositionDirection::Long,
+        4 * BASE_PRECISION_U64,
+    )
+    .unwrap();
+    assert_eq!(base_asset_amount, 15 * BASE_PRECISION_U64 / 10);
+    assert_eq!(redemption_amount, 15 * QUOTE_PRECISION_U64 / 10);
+    assert_eq!(market.pnl_pool.scaled_balance, 0);
+    assert_eq!(
+        market.amm.base_asset_amount_long,
+        85 * BASE_PRECISION_I128 / 10
+    );
+
+    // nothing left to pay the remaining shares with until more pnl settles
+    assert_eq!(
+        redeem_prediction_shares(
+            &mut user,
+            &mut market,
+            &mut spot_market,
+            PositionDirection::Long,
+            25 * BASE_PRECISION_U64 / 10,
+        ),
+        Err(ErrorCode::InsufficientPerpPnlPool)
+    );
+}
@@ -19,19 +19,22 @@ use crate::math::cp_curve;
 use crate::math::cp_curve::get_update_k_result;
 use crate::math::cp_curve::UpdateKResult;
 use crate::math::oracle;
-use crate::math::oracle::{is_oracle_valid_for_action, oracle_validity, DriftAction};
+use crate::math::oracle::{
+    is_oracle_valid_for_action, oracle_validity, DriftAction, OracleValidity,
+};
 use crate::math::repeg;
 use crate::math::safe_math::SafeMath;
 use crate::math::spot_balance::get_token_amount;
 
 use crate::state::oracle::{OraclePriceData, OracleSource};
 use crate::state::oracle_map::OracleMap;
-use crate::state::perp_market::{MarketStatus, PerpMarket};
+use crate::state::perp_market::{MarketStatus, MarketVoidStatus, PerpMarket};
 use crate::state::perp_market_map::PerpMarketMap;
 use crate::state::spot_market::SpotBalance;
 use crate::state::spot_market::SpotBalanceType;
+use crate::state::spot_market::SpotMarket;
 use crate::state::spot_market_map::SpotMarketMap;
-use crate::state::state::{OracleGuardRails, State};
+use crate::state::state::{OracleGuardRails, State, ValidityGuardRails};
 use crate::state::user::MarketType;
 use crate::validate;
 
@@ -110,8 +113,18 @@ pub fn update_amms(
     let updated = true; // todo
     for (_key, market_account_loader) in perp_market_map.0.iter_mut() {
         let market = &mut load_mut!(market_account_loader)?;
-        let oracle_price_data = &oracle_map.get_price_data(&market.oracle_id())?;
-        _update_amm(market, oracle_price_data, state, now, clock_slot)?;
+        let (oracle_price_data, oracle_validity) =
+            get_oracle_price_data_and_validity(market, oracle_map)?;
+        _update_amm(market, &oracle_price_data, state, now, clock_slot)?;
+
+        oracle_map.record_price_history(
+            MarketType::Perp,
+            market.market_index,
+            now,
+            oracle_price_data.price,
+            oracle_validity,
+            market.amm.reserve_price()?.cast()?,
+        )?;
     }
 
     Ok(updated)
@@ -125,19 +138,46 @@ pub fn update_amm(
     clock: &Clock,
 ) -> DriftResult<i128> {
     let market = &mut perp_market_map.get_ref_mut(&market_index)?;
-    let oracle_price_data = oracle_map.get_price_data(&market.oracle_id())?;
+    let (oracle_price_data, oracle_validity) =
+        get_oracle_price_data_and_validity(market, oracle_map)?;
 
     let cost_of_update = _update_amm(
         market,
-        oracle_price_data,
+        &oracle_price_data,
         state,
         clock.unix_timestamp,
         clock.slot,
     )?;
 
+    oracle_map.record_price_history(
+        MarketType::Perp,
+        market.market_index,
+        clock.unix_timestamp,
+        oracle_price_data.price,
+        oracle_validity,
+        market.amm.reserve_price()?.cast()?,
+    )?;
+
     Ok(cost_of_update)
 }
 
+/// The market's oracle price and its validity from before the amm update, which moves the twap
+/// the validity is measured against
+fn get_oracle_price_data_and_validity(
+    market: &PerpMarket,
+    oracle_map: &mut OracleMap,
+) -> DriftResult<(OraclePriceData, OracleValidity)> {
+    let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
+        MarketType::Perp,
+        market.market_index,
+        &market.oracle_id(),
+        market.amm.historical_oracle_data.last_oracle_price_twap,
+        market.get_max_confidence_interval_multiplier()?,
+    )?;
+
+    Ok((*oracle_price_data, oracle_validity))
+}
+
 pub fn _update_amm(
     market: &mut PerpMarket,
     oracle_price_data: &OraclePriceData,
@@ -169,7 +209,8 @@ pub fn _update_amm(
         let curve_update_intensity =
             min(market.amm.curve_update_intensity, 100_u8).cast::<i128>()?;
 
-        if curve_update_intensity > 0 {
+        // the lmsr prices off its own cost function, not the oracle
+        if curve_update_intensity > 0 && !market.amm.is_lmsr() {
             let (optimal_peg, fee_budget, check_lower_bound) =
                 repeg::calculate_optimal_peg_and_budget(market, oracle_price_data)?;
 
@@ -231,6 +272,7 @@ pub fn update_amm_and_check_validity(
     market: &mut PerpMarket,
     oracle_price_data: &OraclePriceData,
     state: &State,
+    validity_guard_rails: &ValidityGuardRails,
     now: i64,
     clock_slot: u64,
     action: Option<DriftAction>,
@@ -245,7 +287,7 @@ pub fn update_amm_and_check_validity(
         market.market_index,
         risk_ema_price,
         oracle_price_data,
-        &state.oracle_guard_rails.validity,
+        validity_guard_rails,
         market.get_max_confidence_interval_multiplier()?,
         &market.amm.oracle_source,
         false,
@@ -312,10 +354,195 @@ pub fn settle_expired_market(
     spot_market_map: &SpotMarketMap,
     _state: &State,
     clock: &Clock,
+    resolved_expiry_price: Option<i64>,
 ) -> DriftResult {
     let now = clock.unix_timestamp;
     let market = &mut market_map.get_ref_mut(&market_index)?;
 
+    validate_market_can_settle(market, now)?;
+
+    let spot_market = &mut spot_market_map.get_ref_mut(&QUOTE_SPOT_MARKET_INDEX)?;
+    let budget = transfer_fee_pool_budget_to_pnl_pool(market, spot_market)?;
+
+    if budget > 0 {
+        let (k_scale_numerator, k_scale_denominator) = cp_curve::calculate_budgeted_k_scale(
+            market,
+            budget.cast()?,
+            K_BPS_UPDATE_SCALE * 100,
+            K_BPS_UPDATE_SCALE,
+        )?;
+
+        let new_sqrt_k = bn::U192::from(market.amm.sqrt_k)
+            .safe_mul(bn::U192::from(k_scale_numerator))?
+            .safe_div(bn::U192::from(k_scale_denominator))?
+            .min(bn::U192::from(MAX_SQRT_K));
+
+        let update_k_result = get_update_k_result(market, new_sqrt_k, true)?;
+
+        let adjustment_cost = cp_curve::adjust_k_cost(market, &update_k_result)?;
+
+        let cost_applied = apply_cost_to_market(market, adjustment_cost, true)?;
+
+        validate!(
+            cost_applied,
+            ErrorCode::InvalidUpdateK,
+            "Issue applying k increase on market"
+        )?;
+
+        if cost_applied {
+            cp_curve::update_k(market, &update_k_result)?;
+        }
+    }
+
+    validate!(
+        10_u128.pow(spot_market.decimals) == QUOTE_PRECISION,
+        ErrorCode::UnsupportedSpotMarket,
+        "Only support bank.decimals == QUOTE_PRECISION"
+    )?;
+
+    let expiry_price = if let Some(resolved_expiry_price) = resolved_expiry_price {
+        let prediction_price_bounds = market.get_prediction_market_price_bounds();
+        validate!(
+            resolved_expiry_price >= 0
+                && prediction_price_bounds.map_or(false, |price_bounds| {
+                    price_bounds.contains(resolved_expiry_price.unsigned_abs())
+                }),
+            ErrorCode::MarketSettlementTargetPriceInvalid,
+            "resolved expiry price {} invalid for market {}",
+            resolved_expiry_price,
+            market.market_index
+        )?;
+
+        resolved_expiry_price
+    } else {
+        let target_expiry_price = if market.amm.oracle_source == OracleSource::Prelaunch {
+            market.amm.historical_oracle_data.last_oracle_price
+        } else {
+            market.amm.historical_oracle_data.last_oracle_price_twap
+        };
+
+        crate::dlog!(target_expiry_price);
+
+        validate!(
+            target_expiry_price > 0,
+            ErrorCode::MarketSettlementTargetPriceInvalid,
+            "target_expiry_price <= 0 {}",
+            target_expiry_price
+        )?;
+
+        let pnl_pool_token_amount = get_token_amount(
+            market.pnl_pool.scaled_balance,
+            spot_market,
+            market.pnl_pool.balance_type(),
+        )?;
+
+        let fee_pool_token_amount = get_token_amount(
+            market.amm.fee_pool.scaled_balance,
+            spot_market,
+            market.amm.fee_pool.balance_type(),
+        )?;
+
+        let total_excess_balance: i128 = pnl_pool_token_amount
+            .safe_add(fee_pool_token_amount)?
+            .cast()?;
+
+        crate::dlog!(market.market_index);
+        crate::dlog!(total_excess_balance);
+
+        let expiry_price =
+            amm::calculate_expiry_price(&market.amm, target_expiry_price, total_excess_balance)?;
+
+        // prediction markets can only pay out within their band
+        match market.get_prediction_market_price_bounds() {
+            Some(prediction_price_bounds) => {
+                prediction_price_bounds.clamp_price_i64(expiry_price)?
+            }
+            None => expiry_price,
+        }
+    };
+
+    market.expiry_price = expiry_price;
+    market.status = MarketStatus::Settlement;
+
+    crate::dlog!(market.expiry_price);
+
+    Ok(())
+}
+
+/// Settles an expired prediction market without resolving it to a price. Each position
+/// later settles at its own cost basis, optionally refunding the fees it paid
+pub fn void_expired_market(
+    market_index: u16,
+    market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+    clock: &Clock,
+    refund_fees: bool,
+) -> DriftResult {
+    let now = clock.unix_timestamp;
+    let market = &mut market_map.get_ref_mut(&market_index)?;
+
+    validate_market_can_settle(market, now)?;
+
+    validate!(
+        market.is_prediction_market() && !market.is_prediction_event_outcome(),
+        ErrorCode::MarketCantBeVoided,
+        "only standalone prediction markets can be voided (market {})",
+        market.market_index
+    )?;
+
+    let spot_market = &mut spot_market_map.get_ref_mut(&QUOTE_SPOT_MARKET_INDEX)?;
+    transfer_fee_pool_budget_to_pnl_pool(market, spot_market)?;
+
+    if refund_fees {
+        // fees being refunded are no longer reserved for the protocol
+        let fee_pool_token_amount = get_token_amount(
+            market.amm.fee_pool.scaled_balance,
+            spot_market,
+            &SpotBalanceType::Deposit,
+        )?;
+
+        update_spot_balances(
+            fee_pool_token_amount,
+            &SpotBalanceType::Borrow,
+            spot_market,
+            &mut market.amm.fee_pool,
+            false,
+        )?;
+
+        update_spot_balances(
+            fee_pool_token_amount,
+            &SpotBalanceType::Deposit,
+            spot_market,
+            &mut market.pnl_pool,
+            false,
+        )?;
+    }
+
+    // only used to value positions that haven't settled yet, settlement uses cost basis
+    let expiry_price = match market.get_prediction_market_price_bounds() {
+        Some(prediction_price_bounds) => prediction_price_bounds
+            .clamp_price_i64(market.amm.historical_oracle_data.last_oracle_price_twap)?,
+        None => market.amm.historical_oracle_data.last_oracle_price_twap,
+    };
+
+    market.void_status = if refund_fees {
+        MarketVoidStatus::VoidedWithFeeRefund
+    } else {
+        MarketVoidStatus::Voided
+    };
+    market.expiry_price = expiry_price;
+    market.status = MarketStatus::Settlement;
+
+    msg!(
+        "market {} voided: {:?}",
+        market.market_index,
+        market.void_status
+    );
+
+    Ok(())
+}
+
+fn validate_market_can_settle(market: &PerpMarket, now: i64) -> DriftResult {
     validate!(
         market.expiry_ts != 0,
         ErrorCode::MarketSettlementAttemptOnActiveMarket,
@@ -336,7 +563,15 @@ pub fn settle_expired_market(
         "Outstanding LP in market"
     )?;
 
-    let spot_market = &mut spot_market_map.get_ref_mut(&QUOTE_SPOT_MARKET_INDEX)?;
+    Ok(())
+}
+
+/// Moves the fee pool in excess of what's reserved for the protocol into the pnl pool
+/// Returns the amm's fee budget
+fn transfer_fee_pool_budget_to_pnl_pool(
+    market: &mut PerpMarket,
+    spot_market: &mut SpotMarket,
+) -> DriftResult<i128> {
     let fee_reserved_for_protocol = repeg::get_total_fee_lower_bound(market)?
         .safe_add(market.amm.total_liquidation_fee)?
         .safe_sub(market.amm.total_fee_withdrawn)?
@@ -374,83 +609,5 @@ pub fn settle_expired_market(
         false,
     )?;
 
-    if budget > 0 {
-        let (k_scale_numerator, k_scale_denominator) = cp_curve::calculate_budgeted_k_scale(
-            market,
-            budget.cast()?,
-            K_BPS_UPDATE_SCALE * 100,
-            K_BPS_UPDATE_SCALE,
-        )?;
-
-        let new_sqrt_k = bn::U192::from(market.amm.sqrt_k)
-            .safe_mul(bn::U192::from(k_scale_numerator))?
-            .safe_div(bn::U192::from(k_scale_denominator))?
-            .min(bn::U192::from(MAX_SQRT_K));
-
-        let update_k_result = get_update_k_result(market, new_sqrt_k, true)?;
-
-        let adjustment_cost = cp_curve::adjust_k_cost(market, &update_k_result)?;
-
-        let cost_applied = apply_cost_to_market(market, adjustment_cost, true)?;
-
-        validate!(
-            cost_applied,
-            ErrorCode::InvalidUpdateK,
-            "Issue applying k increase on market"
-        )?;
-
-        if cost_applied {
-            cp_curve::update_k(market, &update_k_result)?;
-        }
-    }
-
-    validate!(
-        10_u128.pow(spot_market.decimals) == QUOTE_PRECISION,
-        ErrorCode::UnsupportedSpotMarket,
-        "Only support bank.decimals == QUOTE_PRECISION"
-    )?;
-
-    let target_expiry_price = if market.amm.oracle_source == OracleSource::Prelaunch {
-        market.amm.historical_oracle_data.last_oracle_price
-    } else {
-        market.amm.historical_oracle_data.last_oracle_price_twap
-    };
-
-    crate::dlog!(target_expiry_price);
-
-    validate!(
-        target_expiry_price > 0,
-        ErrorCode::MarketSettlementTargetPriceInvalid,
-        "target_expiry_price <= 0 {}",
-        target_expiry_price
-    )?;
-
-    let pnl_pool_token_amount = get_token_amount(
-        market.pnl_pool.scaled_balance,
-        spot_market,
-        market.pnl_pool.balance_type(),
-    )?;
-
-    let fee_pool_token_amount = get_token_amount(
-        market.amm.fee_pool.scaled_balance,
-        spot_market,
-        market.amm.fee_pool.balance_type(),
-    )?;
-
-    let total_excess_balance: i128 = pnl_pool_token_amount
-        .safe_add(fee_pool_token_amount)?
-        .cast()?;
-
-    crate::dlog!(market.market_index);
-    crate::dlog!(total_excess_balance);
-
-    let expiry_price =
-        amm::calculate_expiry_price(&market.amm, target_expiry_price, total_excess_balance)?;
-
-    market.expiry_price = expiry_price;
-    market.status = MarketStatus::Settlement;
-
-    crate::dlog!(market.expiry_price);
-
-    Ok(())
+    Ok(budget)
 }
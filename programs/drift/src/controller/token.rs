@@ -8,7 +8,7 @@ use anchor_spl::token_2022::spl_token_2022::extension::{
 };
 use anchor_spl::token_2022::spl_token_2022::state::Mint as MintInner;
 use anchor_spl::token_interface::{
-    self, CloseAccount, Mint, TokenAccount, TokenInterface, Transfer, TransferChecked,
+    self, Burn, CloseAccount, Mint, MintTo, TokenAccount, TokenInterface, Transfer, TransferChecked,
 };
 
 pub fn send_from_program_vault<'info>(
@@ -87,6 +87,43 @@ pub fn receive<'info>(
     }
 }
 
+pub fn mint_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    nonce: u8,
+    amount: u64,
+) -> Result<()> {
+    let signature_seeds = get_signer_seeds(&nonce);
+    let signers = &[&signature_seeds[..]];
+    let cpi_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signers);
+    token_interface::mint_to(cpi_context, amount)
+}
+
+pub fn burn_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Burn {
+        mint: mint.to_account_info(),
+        from: from.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+    token_interface::burn(cpi_context, amount)
+}
+
 pub fn close_vault<'info>(
     token_program: &Interface<'info, TokenInterface>,
     account: &InterfaceAccount<'info, TokenAccount>,
@@ -613,6 +613,62 @@ pub enum ErrorCode {
     InvalidPoolId,
     #[msg("Invalid Protected Maker Mode Config")]
     InvalidProtectedMakerModeConfig,
+    #[msg("Invalid prediction event")]
+    InvalidPredictionEvent,
+    #[msg("Prediction event not resolved")]
+    PredictionEventNotResolved,
+    #[msg("Invalid complete set")]
+    InvalidCompleteSet,
+    #[msg("Invalid market resolution")]
+    InvalidMarketResolution,
+    #[msg("Market resolution not finalized")]
+    MarketResolutionNotFinalized,
+    #[msg("Invalid prediction market price bounds")]
+    InvalidPredictionMarketPriceBounds,
+    #[msg("Invalid lmsr curve")]
+    InvalidLmsrCurve,
+    #[msg("Market can not be voided")]
+    MarketCantBeVoided,
+    #[msg("Invalid trading halt")]
+    InvalidTradingHalt,
+    #[msg("Invalid prediction fee curve")]
+    InvalidPredictionFeeCurve,
+    #[msg("Invalid prediction shares")]
+    InvalidPredictionShares,
+    #[msg("Invalid prediction market creation config")]
+    InvalidPredictionMarketCreationConfig,
+    #[msg("Prediction market creation paused")]
+    PredictionMarketCreationPaused,
+    #[msg("Invalid prediction market creator")]
+    InvalidPredictionMarketCreator,
+    #[msg("Invalid order trigger market")]
+    InvalidOrderTriggerMarket,
+    #[msg("Invalid fallback oracle")]
+    InvalidFallbackOracle,
+    #[msg("Invalid oracle guard rails override")]
+    InvalidOracleGuardRailsOverride,
+    #[msg("Invalid price history")]
+    InvalidPriceHistory,
+    #[msg("Invalid pyth lazer message")]
+    InvalidPythLazerMessage,
+    #[msg("Pyth lazer message older than oracle")]
+    StalePythLazerMessage,
+    #[msg("Invalid order link")]
+    InvalidOrderLink,
+    #[msg("Invalid trailing stop order")]
+    InvalidTrailingStopOrder,
+    #[msg("UserTrailingStops account has too many trailing stops")]
+    UserTrailingStopsAccountFull,
+    #[msg("Invalid parent order")]
+    InvalidParentOrder,
+    #[msg("UserParentOrders account has too many parent orders")]
+    UserParentOrdersAccountFull,
+    #[msg("Parent order slice not ready to be released")]
+    ParentOrderSliceNotReady,
+    #[msg("User heartbeat has not expired")]
+    HeartbeatNotExpired,
+    #[msg("User heartbeat has expired")]
+    HeartbeatExpired,
 }
 
 #[macro_export]
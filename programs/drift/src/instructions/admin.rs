@@ -11,19 +11,24 @@ use pyth_solana_receiver_sdk::program::PythSolanaReceiver;
 use serum_dex::state::ToAlignedBytes;
 use solana_program::msg;
 
+use crate::controller::prediction_shares::{
+    PREDICTION_NO_MINT_SEED, PREDICTION_SHARE_MINT_DECIMALS, PREDICTION_YES_MINT_SEED,
+};
 use crate::controller::token::close_vault;
-use crate::error::ErrorCode;
+use crate::error::{DriftResult, ErrorCode};
 use crate::ids::admin_hot_wallet;
 use crate::instructions::constraints::*;
-use crate::instructions::optional_accounts::{load_maps, AccountMaps};
+use crate::instructions::optional_accounts::{
+    get_market_resolution, get_prediction_event, load_maps, AccountMaps,
+};
 use crate::math::casting::Cast;
 use crate::math::constants::{
     DEFAULT_LIQUIDATION_MARGIN_BUFFER_RATIO, FEE_POOL_TO_REVENUE_POOL_THRESHOLD, FUEL_START_TS,
     IF_FACTOR_PRECISION, INSURANCE_A_MAX, INSURANCE_B_MAX, INSURANCE_C_MAX,
     INSURANCE_SPECULATIVE_MAX, LIQUIDATION_FEE_PRECISION, MAX_CONCENTRATION_COEFFICIENT,
-    MAX_SQRT_K, MAX_UPDATE_K_PRICE_CHANGE, PERCENTAGE_PRECISION, QUOTE_SPOT_MARKET_INDEX,
-    SPOT_CUMULATIVE_INTEREST_PRECISION, SPOT_IMF_PRECISION, SPOT_WEIGHT_PRECISION, THIRTEEN_DAY,
-    TWENTY_FOUR_HOUR,
+    MAX_CONFIDENCE_MARGIN_MULTIPLIER, MAX_SQRT_K, MAX_UPDATE_K_PRICE_CHANGE, PERCENTAGE_PRECISION,
+    PREDICTION_EVENT_PENDING_OUTCOMES, QUOTE_SPOT_MARKET_INDEX, SPOT_CUMULATIVE_INTEREST_PRECISION,
+    SPOT_IMF_PRECISION, SPOT_WEIGHT_PRECISION, THIRTEEN_DAY, TWENTY_FOUR_HOUR,
 };
 use crate::math::cp_curve::get_update_k_result;
 use crate::math::orders::is_multiple_of_step_size;
@@ -33,7 +38,13 @@ use crate::math::spot_balance::get_token_amount;
 use crate::math::spot_withdraw::validate_spot_market_vault_amount;
 use crate::math::{amm, bn};
 use crate::optional_accounts::get_token_mint;
-use crate::state::events::{CurveRecord, SpotMarketVaultDepositRecord};
+use crate::state::events::{
+    CurveRecord, MarketResolutionAction, MarketResolutionRecord, PredictionMarketCreatorAction,
+    PredictionMarketCreatorRecord, SpotMarketVaultDepositRecord,
+};
+use crate::state::fallback_oracle::{
+    FallbackOracle, PERP_FALLBACK_ORACLE_SEED, SPOT_FALLBACK_ORACLE_SEED,
+};
 use crate::state::fulfillment_params::openbook_v2::{
     OpenbookV2Context, OpenbookV2FulfillmentConfig,
 };
@@ -43,26 +54,42 @@ use crate::state::fulfillment_params::serum::SerumContext;
 use crate::state::fulfillment_params::serum::SerumV3FulfillmentConfig;
 use crate::state::high_leverage_mode_config::HighLeverageModeConfig;
 use crate::state::insurance_fund_stake::ProtocolIfSharesTransferConfig;
+use crate::state::market_resolution::{MarketResolution, MarketResolutionStatus, ResolutionSource};
 use crate::state::oracle::get_sb_on_demand_price;
 use crate::state::oracle::{
-    get_oracle_price, get_prelaunch_price, get_pyth_price, get_switchboard_price,
-    HistoricalIndexData, HistoricalOracleData, OraclePriceData, OracleSource, PrelaunchOracle,
-    PrelaunchOracleParams,
+    get_median_oracle_price, get_oracle_price, get_prelaunch_price, get_pyth_lazer_price,
+    get_pyth_price, get_switchboard_price, HistoricalIndexData, HistoricalOracleData,
+    MarketOracleAccount, MedianOracle, MedianOracleParams, OraclePriceData, OracleSource,
+    PrelaunchOracle, PrelaunchOracleParams,
 };
-use crate::state::oracle_map::OracleMap;
+use crate::state::oracle_guard_rails_override::{
+    OracleGuardRailsOverride, OracleGuardRailsOverrideParams,
+    PERP_ORACLE_GUARD_RAILS_OVERRIDE_SEED, SPOT_ORACLE_GUARD_RAILS_OVERRIDE_SEED,
+};
+use crate::state::oracle_map::{OracleIdentifier, OracleMap};
 use crate::state::paused_operations::{InsuranceFundOperation, PerpOperation, SpotOperation};
 use crate::state::perp_market::{
-    ContractTier, ContractType, InsuranceClaim, MarketStatus, PerpMarket, PoolBalance, AMM,
+    AMMCurve, ContractTier, ContractType, InsuranceClaim, MarketStatus, MarketVoidStatus,
+    PerpMarket, PoolBalance, PredictionFeeCurve, AMM,
+};
+use crate::state::perp_market_map::{
+    get_writable_perp_market_set, get_writable_perp_market_set_from_vec, MarketSet, PerpMarketMap,
 };
-use crate::state::perp_market_map::get_writable_perp_market_set;
+use crate::state::prediction_event::{PredictionEvent, PredictionEventStatus};
+use crate::state::prediction_market_creator::{
+    PredictionMarketCreationConfig, PredictionMarketCreator, PredictionMarketCreatorStatus,
+    PREDICTION_MARKET_CREATION_CONFIG_SEED, PREDICTION_MARKET_CREATOR_SEED,
+};
+use crate::state::price_history::{PriceHistory, PERP_PRICE_HISTORY_SEED, SPOT_PRICE_HISTORY_SEED};
 use crate::state::protected_maker_mode_config::ProtectedMakerModeConfig;
+use crate::state::pyth_lazer_oracle::{PythLazerOracle, PYTH_LAZER_ORACLE_SEED};
 use crate::state::spot_market::{
     AssetTier, InsuranceFund, SpotBalanceType, SpotFulfillmentConfigStatus, SpotMarket,
 };
 use crate::state::spot_market_map::get_writable_spot_market_set;
 use crate::state::state::{ExchangeStatus, FeeStructure, OracleGuardRails, State};
 use crate::state::traits::Size;
-use crate::state::user::{User, UserStats};
+use crate::state::user::{MarketType, User, UserStats};
 use crate::validate;
 use crate::validation::fee_structure::validate_fee_structure;
 use crate::validation::margin::{validate_margin, validate_margin_weights};
@@ -104,7 +131,8 @@ pub fn handle_initialize(ctx: Context<Initialize>) -> Result<()> {
         initial_pct_to_liquidate: 0,
         max_number_of_sub_accounts: 0,
         max_initialize_user_fee: 0,
-        padding: [0; 10],
+        number_of_prediction_events: 0,
+        padding: [0; 8],
     };
 
     Ok(())
@@ -310,7 +338,9 @@ pub fn handle_initialize_spot_market(
         fuel_boost_insurance: 0,
         token_program,
         pool_id: 0,
-        padding: [0; 40],
+        oracle_accounts: 0,
+        confidence_margin_multiplier: 0,
+        padding: [0; 38],
         insurance_fund: InsuranceFund {
             vault: *ctx.accounts.insurance_fund_vault.to_account_info().key,
             unstaking_period: THIRTEEN_DAY,
@@ -635,88 +665,22 @@ pub fn handle_update_phoenix_fulfillment_config_status(
     Ok(())
 }
 
-pub fn handle_initialize_perp_market(
-    ctx: Context<InitializePerpMarket>,
-    market_index: u16,
-    amm_base_asset_reserve: u128,
-    amm_quote_asset_reserve: u128,
-    amm_periodicity: i64,
-    amm_peg_multiplier: u128,
+/// Reads the oracle a new perp market is initialized with
+/// Returns (oracle_price, oracle_delay, last_oracle_price_twap)
+pub fn get_initial_perp_market_oracle_data(
+    oracle: &AccountInfo,
     oracle_source: OracleSource,
-    contract_tier: ContractTier,
-    margin_ratio_initial: u32,
-    margin_ratio_maintenance: u32,
-    liquidator_fee: u32,
-    if_liquidation_fee: u32,
-    imf_factor: u32,
-    active_status: bool,
-    base_spread: u32,
-    max_spread: u32,
-    max_open_interest: u128,
-    max_revenue_withdraw_per_period: u64,
-    quote_max_insurance: u64,
-    order_step_size: u64,
-    order_tick_size: u64,
-    min_order_size: u64,
-    concentration_coef_scale: u128,
-    curve_update_intensity: u8,
-    amm_jit_intensity: u8,
-    name: [u8; 32],
-) -> Result<()> {
-    msg!("perp market {}", market_index);
-    let perp_market_pubkey = ctx.accounts.perp_market.to_account_info().key;
-    let perp_market = &mut ctx.accounts.perp_market.load_init()?;
-    let clock = Clock::get()?;
-    let now = clock.unix_timestamp;
-    let clock_slot = clock.slot;
-
-    if amm_base_asset_reserve != amm_quote_asset_reserve {
-        return Err(ErrorCode::InvalidInitialPeg.into());
-    }
-
-    validate!(
-        (0..=200).contains(&curve_update_intensity),
-        ErrorCode::DefaultError,
-        "invalid curve_update_intensity",
-    )?;
-
-    validate!(
-        (0..=200).contains(&amm_jit_intensity),
-        ErrorCode::DefaultError,
-        "invalid amm_jit_intensity",
-    )?;
-
-    let init_reserve_price = amm::calculate_price(
-        amm_quote_asset_reserve,
-        amm_base_asset_reserve,
-        amm_peg_multiplier,
-    )?;
-
-    assert_eq!(amm_peg_multiplier, init_reserve_price.cast::<u128>()?);
-
-    let concentration_coef = MAX_CONCENTRATION_COEFFICIENT;
-
-    // Verify there's no overflow
-    let _k =
-        bn::U192::from(amm_base_asset_reserve).safe_mul(bn::U192::from(amm_quote_asset_reserve))?;
-
-    let (min_base_asset_reserve, max_base_asset_reserve) =
-        amm::calculate_bid_ask_bounds(concentration_coef, amm_base_asset_reserve)?;
-
-    OracleMap::validate_oracle_account_info(&ctx.accounts.oracle)?;
-
-    // Verify oracle is readable
-    let (oracle_price, oracle_delay, last_oracle_price_twap) = match oracle_source {
+    amm: &AMM,
+    clock_slot: u64,
+) -> DriftResult<(i64, i64, i64)> {
+    Ok(match oracle_source {
         OracleSource::Pyth => {
             let OraclePriceData {
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1, false)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1, false)?;
+            } = get_pyth_price(oracle, clock_slot, 1, false)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1, false)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::Pyth1K => {
@@ -724,11 +688,8 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1000, false)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1000, false)?;
+            } = get_pyth_price(oracle, clock_slot, 1000, false)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1000, false)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::Pyth1M => {
@@ -736,11 +697,8 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1000000, false)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1000000, false)?;
+            } = get_pyth_price(oracle, clock_slot, 1000000, false)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1000000, false)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::PythStableCoin => {
@@ -748,7 +706,7 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1, false)?;
+            } = get_pyth_price(oracle, clock_slot, 1, false)?;
             (oracle_price, oracle_delay, QUOTE_PRECISION_I64)
         }
         OracleSource::Switchboard => {
@@ -756,20 +714,20 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_switchboard_price(&ctx.accounts.oracle, clock_slot)?;
+            } = get_switchboard_price(oracle, clock_slot)?;
 
             (oracle_price, oracle_delay, oracle_price)
         }
         OracleSource::QuoteAsset => {
             msg!("Quote asset oracle cant be used for perp market");
-            return Err(ErrorCode::InvalidOracle.into());
+            return Err(ErrorCode::InvalidOracle);
         }
         OracleSource::Prelaunch => {
             let OraclePriceData {
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_prelaunch_price(&ctx.accounts.oracle, clock_slot)?;
+            } = get_prelaunch_price(oracle, clock_slot)?;
             (oracle_price, oracle_delay, oracle_price)
         }
         OracleSource::PythPull => {
@@ -777,11 +735,8 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1, true)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1, true)?;
+            } = get_pyth_price(oracle, clock_slot, 1, true)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1, true)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::Pyth1KPull => {
@@ -789,11 +744,8 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1000, true)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1000, true)?;
+            } = get_pyth_price(oracle, clock_slot, 1000, true)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1000, true)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::Pyth1MPull => {
@@ -801,11 +753,8 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1000000, true)?;
-            let last_oracle_price_twap =
-                perp_market
-                    .amm
-                    .get_pyth_twap(&ctx.accounts.oracle, 1000000, true)?;
+            } = get_pyth_price(oracle, clock_slot, 1000000, true)?;
+            let last_oracle_price_twap = amm.get_pyth_twap(oracle, 1000000, true)?;
             (oracle_price, oracle_delay, last_oracle_price_twap)
         }
         OracleSource::PythStableCoinPull => {
@@ -813,7 +762,7 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_pyth_price(&ctx.accounts.oracle, clock_slot, 1, true)?;
+            } = get_pyth_price(oracle, clock_slot, 1, true)?;
             (oracle_price, oracle_delay, QUOTE_PRECISION_I64)
         }
         OracleSource::SwitchboardOnDemand => {
@@ -821,11 +770,108 @@ pub fn handle_initialize_perp_market(
                 price: oracle_price,
                 delay: oracle_delay,
                 ..
-            } = get_sb_on_demand_price(&ctx.accounts.oracle, clock_slot)?;
+            } = get_sb_on_demand_price(oracle, clock_slot)?;
 
             (oracle_price, oracle_delay, oracle_price)
         }
-    };
+        OracleSource::Median => {
+            let OraclePriceData {
+                price: oracle_price,
+                delay: oracle_delay,
+                ..
+            } = get_median_oracle_price(oracle, clock_slot)?;
+
+            (oracle_price, oracle_delay, oracle_price)
+        }
+        OracleSource::PythLazer => {
+            let OraclePriceData {
+                price: oracle_price,
+                delay: oracle_delay,
+                ..
+            } = get_pyth_lazer_price(oracle, clock_slot)?;
+
+            (oracle_price, oracle_delay, oracle_price)
+        }
+    })
+}
+
+pub fn handle_initialize_perp_market(
+    ctx: Context<InitializePerpMarket>,
+    market_index: u16,
+    amm_base_asset_reserve: u128,
+    amm_quote_asset_reserve: u128,
+    amm_periodicity: i64,
+    amm_peg_multiplier: u128,
+    oracle_source: OracleSource,
+    contract_tier: ContractTier,
+    margin_ratio_initial: u32,
+    margin_ratio_maintenance: u32,
+    liquidator_fee: u32,
+    if_liquidation_fee: u32,
+    imf_factor: u32,
+    active_status: bool,
+    base_spread: u32,
+    max_spread: u32,
+    max_open_interest: u128,
+    max_revenue_withdraw_per_period: u64,
+    quote_max_insurance: u64,
+    order_step_size: u64,
+    order_tick_size: u64,
+    min_order_size: u64,
+    concentration_coef_scale: u128,
+    curve_update_intensity: u8,
+    amm_jit_intensity: u8,
+    name: [u8; 32],
+) -> Result<()> {
+    msg!("perp market {}", market_index);
+    let perp_market_pubkey = ctx.accounts.perp_market.to_account_info().key;
+    let perp_market = &mut ctx.accounts.perp_market.load_init()?;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let clock_slot = clock.slot;
+
+    if amm_base_asset_reserve != amm_quote_asset_reserve {
+        return Err(ErrorCode::InvalidInitialPeg.into());
+    }
+
+    validate!(
+        (0..=200).contains(&curve_update_intensity),
+        ErrorCode::DefaultError,
+        "invalid curve_update_intensity",
+    )?;
+
+    validate!(
+        (0..=200).contains(&amm_jit_intensity),
+        ErrorCode::DefaultError,
+        "invalid amm_jit_intensity",
+    )?;
+
+    let init_reserve_price = amm::calculate_price(
+        amm_quote_asset_reserve,
+        amm_base_asset_reserve,
+        amm_peg_multiplier,
+    )?;
+
+    assert_eq!(amm_peg_multiplier, init_reserve_price.cast::<u128>()?);
+
+    let concentration_coef = MAX_CONCENTRATION_COEFFICIENT;
+
+    // Verify there's no overflow
+    let _k =
+        bn::U192::from(amm_base_asset_reserve).safe_mul(bn::U192::from(amm_quote_asset_reserve))?;
+
+    let (min_base_asset_reserve, max_base_asset_reserve) =
+        amm::calculate_bid_ask_bounds(concentration_coef, amm_base_asset_reserve)?;
+
+    OracleMap::validate_oracle_account_info(&ctx.accounts.oracle)?;
+
+    // Verify oracle is readable
+    let (oracle_price, oracle_delay, last_oracle_price_twap) = get_initial_perp_market_oracle_data(
+        &ctx.accounts.oracle,
+        oracle_source,
+        &perp_market.amm,
+        clock_slot,
+    )?;
 
     validate_margin(
         margin_ratio_initial,
@@ -887,7 +933,18 @@ pub fn handle_initialize_perp_market(
         pool_id: 0,
         high_leverage_margin_ratio_initial: 0,
         high_leverage_margin_ratio_maintenance: 0,
-        padding: [0; 38],
+        prediction_event_index: 0,
+        prediction_event_outcomes: 0,
+        resolution_source: ResolutionSource::Oracle,
+        void_status: MarketVoidStatus::NotVoided,
+        prediction_fee_curve: PredictionFeeCurve::Flat,
+        scalar_lower_bound: 0,
+        scalar_upper_bound: 0,
+        prediction_min_ask: 0,
+        prediction_max_bid: 0,
+        prediction_fee_floor: 0,
+        prediction_fee_cap: 0,
+        trading_halt_ts: 0,
         amm: AMM {
             oracle: *ctx.accounts.oracle.key,
             oracle_source,
@@ -976,13 +1033,15 @@ pub fn handle_initialize_perp_market(
             last_oracle_valid: false,
             target_base_asset_amount_per_lp: 0,
             per_lp_base: 0,
-            padding1: 0,
-            padding2: 0,
+            curve: AMMCurve::ConstantProduct,
+            oracle_accounts: 0,
+            confidence_margin_multiplier: 0,
             total_fee_earned_per_lp: 0,
             net_unsettled_funding_pnl: 0,
             quote_asset_amount_with_unsettled_lp: 0,
             reference_price_offset: 0,
-            padding: [0; 12],
+            lmsr_liquidity: 0,
+            base_asset_amount_with_complete_sets: 0,
         },
     };
 
@@ -1015,78 +1074,656 @@ pub fn handle_initialize_prediction_market(ctx: Context<AdminUpdatePerpMarket>)
     Ok(())
 }
 
-pub fn handle_delete_initialized_perp_market(
-    ctx: Context<DeleteInitializedPerpMarket>,
-    market_index: u16,
+pub fn handle_update_perp_market_scalar_bounds(
+    ctx: Context<AdminUpdatePerpMarket>,
+    scalar_lower_bound: u64,
+    scalar_upper_bound: u64,
 ) -> Result<()> {
-    let perp_market = &mut ctx.accounts.perp_market.load()?;
-    msg!("perp market {}", perp_market.market_index);
-    let state = &mut ctx.accounts.state;
-
-    // to preserve all protocol invariants, can only remove the last market if it hasn't been "activated"
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    msg!(
+        "updating perp market {} scalar bounds",
+        perp_market.market_index
+    );
 
     validate!(
-        state.number_of_markets - 1 == market_index,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "state.number_of_markets={} != market_index={}",
-        state.number_of_markets,
-        market_index
-    )?;
-    validate!(
-        perp_market.status == MarketStatus::Initialized,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "perp_market.status != Initialized",
+        perp_market.status == MarketStatus::Initialized && perp_market.is_prediction_market(),
+        ErrorCode::InvalidPredictionMarketPriceBounds,
+        "Market must be just initialized prediction market to update scalar bounds"
     )?;
+
     validate!(
-        perp_market.number_of_users == 0,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "perp_market.number_of_users={} != 0",
-        perp_market.number_of_users,
+        !perp_market.is_prediction_event_outcome(),
+        ErrorCode::InvalidPredictionMarketPriceBounds,
+        "prediction event outcomes must be binary markets"
     )?;
+
     validate!(
-        perp_market.market_index == market_index,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "market_index={} != perp_market.market_index={}",
-        market_index,
-        perp_market.market_index
+        scalar_lower_bound < scalar_upper_bound,
+        ErrorCode::InvalidPredictionMarketPriceBounds,
+        "scalar lower bound {} must be less than upper bound {}",
+        scalar_lower_bound,
+        scalar_upper_bound
     )?;
 
-    safe_decrement!(state.number_of_markets, 1);
+    msg!(
+        "perp_market.scalar_lower_bound: {:?} -> {:?}",
+        perp_market.scalar_lower_bound,
+        scalar_lower_bound
+    );
+
+    msg!(
+        "perp_market.scalar_upper_bound: {:?} -> {:?}",
+        perp_market.scalar_upper_bound,
+        scalar_upper_bound
+    );
+
+    perp_market.scalar_lower_bound = scalar_lower_bound;
+    perp_market.scalar_upper_bound = scalar_upper_bound;
 
     Ok(())
 }
 
-pub fn handle_delete_initialized_spot_market(
-    ctx: Context<DeleteInitializedSpotMarket>,
-    market_index: u16,
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_update_perp_market_lmsr_liquidity(
+    ctx: Context<AdminUpdatePerpMarketCurve>,
+    lmsr_liquidity: u32,
 ) -> Result<()> {
-    let spot_market = ctx.accounts.spot_market.load()?;
-    msg!("spot market {}", spot_market.market_index);
-    let state = &mut ctx.accounts.state;
-
-    // to preserve all protocol invariants, can only remove the last market if it hasn't been "activated"
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    let quote_spot_market = &load!(ctx.accounts.quote_spot_market)?;
+    msg!("perp market {}", perp_market.market_index);
 
     validate!(
-        state.number_of_spot_markets - 1 == market_index,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "state.number_of_spot_markets={} != market_index={}",
-        state.number_of_markets,
-        market_index
-    )?;
-    validate!(
-        spot_market.status == MarketStatus::Initialized,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "spot_market.status != Initialized",
+        perp_market.is_prediction_market() && !perp_market.is_scalar_market(),
+        ErrorCode::InvalidLmsrCurve,
+        "lmsr curve is only available for binary prediction markets"
     )?;
+
     validate!(
-        spot_market.deposit_balance == 0,
-        ErrorCode::InvalidMarketAccountforDeletion,
-        "spot_market.number_of_users={} != 0",
-        spot_market.deposit_balance,
+        perp_market.amm.base_asset_amount_with_amm == 0
+            && perp_market.amm.user_lp_shares == 0,
+        ErrorCode::InvalidLmsrCurve,
+        "amm must have no open position or lp shares to change curve (base_asset_amount_with_amm={}, user_lp_shares={})",
+        perp_market.amm.base_asset_amount_with_amm,
+        perp_market.amm.user_lp_shares
     )?;
-    validate!(
-        spot_market.borrow_balance == 0,
-        ErrorCode::InvalidMarketAccountforDeletion,
+
+    let curve = if lmsr_liquidity > 0 {
+        let worst_case_loss = math::lmsr::calculate_lmsr_worst_case_loss(lmsr_liquidity)?;
+
+        let fee_pool_token_amount = get_token_amount(
+            perp_market.amm.fee_pool.scaled_balance,
+            quote_spot_market,
+            &SpotBalanceType::Deposit,
+        )?;
+
+        validate!(
+            fee_pool_token_amount >= worst_case_loss,
+            ErrorCode::InvalidLmsrCurve,
+            "fee pool {} can not fund lmsr worst case loss {}",
+            fee_pool_token_amount,
+            worst_case_loss
+        )?;
+
+        AMMCurve::Lmsr
+    } else {
+        AMMCurve::ConstantProduct
+    };
+
+    msg!(
+        "perp_market.amm.curve: {:?} -> {:?}",
+        perp_market.amm.curve,
+        curve
+    );
+
+    msg!(
+        "perp_market.amm.lmsr_liquidity: {:?} -> {:?}",
+        perp_market.amm.lmsr_liquidity,
+        lmsr_liquidity
+    );
+
+    perp_market.amm.curve = curve;
+    perp_market.amm.lmsr_liquidity = lmsr_liquidity;
+
+    if perp_market.amm.is_lmsr() {
+        // the lmsr is the only liquidity the amm provides
+        perp_market.amm.amm_jit_intensity = 0;
+        controller::amm::update_reserves_to_lmsr_price(
+            perp_market,
+            perp_market.amm.base_asset_amount_with_amm,
+        )?;
+        controller::amm::update_spread_reserves(perp_market)?;
+    }
+
+    validate_perp_market(perp_market)?;
+
+    Ok(())
+}
+
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_initialize_prediction_share_mints(
+    ctx: Context<InitializePredictionShareMints>,
+    market_index: u16,
+) -> Result<()> {
+    let perp_market = load!(ctx.accounts.perp_market)?;
+
+    validate!(
+        perp_market.is_prediction_market(),
+        ErrorCode::InvalidPredictionShares,
+        "perp market {} is not a prediction market",
+        market_index
+    )?;
+
+    msg!(
+        "initializing prediction share mints for perp market {}: yes {} no {}",
+        market_index,
+        ctx.accounts.yes_mint.key(),
+        ctx.accounts.no_mint.key()
+    );
+
+    Ok(())
+}
+
+pub fn handle_initialize_prediction_event(
+    ctx: Context<InitializePredictionEvent>,
+    name: [u8; 32],
+) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let mut prediction_event = ctx.accounts.prediction_event.load_init()?;
+
+    let event_index = state.number_of_prediction_events;
+    msg!("initializing prediction event {}", event_index);
+
+    *prediction_event = PredictionEvent {
+        name,
+        event_index,
+        status: PredictionEventStatus::Initialized,
+        ..PredictionEvent::default()
+    };
+
+    safe_increment!(state.number_of_prediction_events, 1);
+
+    Ok(())
+}
+
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_add_prediction_event_outcome(ctx: Context<AddPredictionEventOutcome>) -> Result<()> {
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+
+    msg!(
+        "adding perp market {} as outcome {} of prediction event {}",
+        perp_market.market_index,
+        prediction_event.number_of_outcomes,
+        prediction_event.event_index
+    );
+
+    prediction_event.add_outcome(perp_market)?;
+
+    // the number of outcomes is only final once the event is activated
+    perp_market.prediction_event_index = prediction_event.event_index;
+    perp_market.prediction_event_outcomes = PREDICTION_EVENT_PENDING_OUTCOMES;
+
+    Ok(())
+}
+
+pub fn handle_activate_prediction_event<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+) -> Result<()> {
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+
+    msg!(
+        "activating prediction event {} with {} outcomes",
+        prediction_event.event_index,
+        prediction_event.number_of_outcomes
+    );
+
+    prediction_event.activate()?;
+
+    let perp_market_map = PerpMarketMap::load(
+        &get_writable_perp_market_set_from_vec(prediction_event.outcome_market_indexes()),
+        &mut ctx.remaining_accounts.iter().peekable(),
+    )?;
+
+    // every outcome market needs the final number of outcomes for event level margin and settlement
+    for market_index in prediction_event.outcome_market_indexes() {
+        let perp_market = &mut perp_market_map.get_ref_mut(market_index)?;
+
+        validate!(
+            perp_market.is_prediction_event_outcome()
+                && perp_market.prediction_event_index == prediction_event.event_index,
+            ErrorCode::InvalidPredictionEvent,
+            "perp market {} not linked to prediction event {}",
+            market_index,
+            prediction_event.event_index
+        )?;
+
+        perp_market.prediction_event_outcomes = prediction_event.number_of_outcomes;
+    }
+
+    Ok(())
+}
+
+pub fn handle_resolve_prediction_event<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+    winning_outcome_index: u8,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let state = &ctx.accounts.state;
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+
+    prediction_event.resolve(winning_outcome_index)?;
+
+    msg!(
+        "prediction event {} resolved to outcome {} (perp market {})",
+        prediction_event.event_index,
+        winning_outcome_index,
+        prediction_event.get_winning_market_index()?
+    );
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        oracle_map: _,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set_from_vec(prediction_event.outcome_market_indexes()),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+    controller::spot_balance::update_spot_market_cumulative_interest(
+        quote_spot_market,
+        None,
+        clock.unix_timestamp,
+    )?;
+
+    // winning outcome's pnl pool pays out $1 per outstanding complete set
+    controller::complete_set::transfer_complete_set_collateral_to_winning_outcome(
+        prediction_event,
+        &perp_market_map,
+        quote_spot_market,
+    )?;
+
+    Ok(())
+}
+
+pub fn handle_settle_prediction_event<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+) -> Result<()> {
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+
+    let perp_market_map = PerpMarketMap::load(
+        &MarketSet::new(),
+        &mut ctx.remaining_accounts.iter().peekable(),
+    )?;
+
+    for market_index in prediction_event.outcome_market_indexes() {
+        let perp_market = perp_market_map.get_ref(market_index)?;
+        prediction_event.validate_outcome_settled(&perp_market)?;
+    }
+
+    prediction_event.settle()?;
+
+    msg!("prediction event {} settled", prediction_event.event_index);
+
+    Ok(())
+}
+
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+    spot_market_valid(&ctx.accounts.bond_spot_market)
+)]
+pub fn handle_initialize_market_resolution(
+    ctx: Context<InitializeMarketResolution>,
+    bond_amount: u64,
+    dispute_window: i64,
+    guardian: Pubkey,
+) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    let bond_spot_market = load!(ctx.accounts.bond_spot_market)?;
+    let mut market_resolution = ctx.accounts.market_resolution.load_init()?;
+
+    msg!(
+        "initializing optimistic resolution for perp market {}",
+        perp_market.market_index
+    );
+
+    validate!(
+        perp_market.is_prediction_market() && !perp_market.is_prediction_event_outcome(),
+        ErrorCode::InvalidMarketResolution,
+        "perp market {} must be a prediction market that isnt resolved by a prediction event",
+        perp_market.market_index
+    )?;
+
+    validate!(
+        bond_amount > 0 && dispute_window > 0,
+        ErrorCode::InvalidMarketResolution,
+        "bond amount ({}) and dispute window ({}) must be positive",
+        bond_amount,
+        dispute_window
+    )?;
+
+    *market_resolution = MarketResolution {
+        market_index: perp_market.market_index,
+        bond_spot_market_index: bond_spot_market.market_index,
+        bond_amount,
+        dispute_window,
+        guardian,
+        status: MarketResolutionStatus::Open,
+        ..MarketResolution::default()
+    };
+
+    perp_market.resolution_source = ResolutionSource::Optimistic;
+
+    Ok(())
+}
+
+pub fn handle_finalize_disputed_market_resolution<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, FinalizeDisputedMarketResolution<'info>>,
+    finalized_price: i64,
+) -> Result<()> {
+    let market_resolution = &mut load_mut!(ctx.accounts.market_resolution)?;
+    let proposer = &mut load_mut!(ctx.accounts.proposer)?;
+    let disputer = &mut load_mut!(ctx.accounts.disputer)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let proposer_was_right = {
+        let perp_market = load!(ctx.accounts.perp_market)?;
+        market_resolution.finalize_disputed(finalized_price, &perp_market)?
+    };
+
+    msg!(
+        "perp market {} resolution finalized at {} (proposed {})",
+        market_resolution.market_index,
+        finalized_price,
+        market_resolution.proposed_price
+    );
+
+    let AccountMaps {
+        perp_market_map: _,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(market_resolution.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    // the winner gets their bond back plus the loser's slashed bond
+    let (winner, winner_key) = if proposer_was_right {
+        (proposer, market_resolution.proposer)
+    } else {
+        (disputer, market_resolution.disputer)
+    };
+
+    let payout = market_resolution.bond_amount.safe_mul(2)?;
+
+    controller::market_resolution::pay_resolution_bond(
+        winner,
+        &spot_market_map,
+        &mut oracle_map,
+        market_resolution.bond_spot_market_index,
+        payout,
+        now,
+    )?;
+
+    emit!(MarketResolutionRecord {
+        ts: now,
+        action: MarketResolutionAction::Finalize,
+        market_index: market_resolution.market_index,
+        user: Some(winner_key),
+        price: finalized_price,
+        bond_amount: payout,
+    });
+
+    Ok(())
+}
+
+pub fn handle_initialize_prediction_market_creation_config(
+    ctx: Context<InitializePredictionMarketCreationConfig>,
+) -> Result<()> {
+    let mut config = ctx.accounts.prediction_market_creation_config.load_init()?;
+
+    // stays paused until admin sets the template
+    config.paused = 1;
+
+    Ok(())
+}
+
+pub fn handle_update_prediction_market_creation_config(
+    ctx: Context<UpdatePredictionMarketCreationConfig>,
+    max_open_interest: u128,
+    amm_base_asset_reserve: u128,
+    bond_amount: u64,
+    bond_spot_market_index: u16,
+    order_step_size: u64,
+    order_tick_size: u64,
+    min_order_size: u64,
+    margin_ratio_initial: u32,
+    margin_ratio_maintenance: u32,
+    liquidator_fee: u32,
+    max_spread: u32,
+    creator_fee_share: u32,
+    contract_tier: ContractTier,
+    paused: bool,
+) -> Result<()> {
+    let mut config = load_mut!(ctx.accounts.prediction_market_creation_config)?;
+
+    msg!(
+        "config.creator_fee_share {} -> {}",
+        config.creator_fee_share,
+        creator_fee_share
+    );
+    msg!(
+        "config.bond_amount {} -> {} (spot market {} -> {})",
+        config.bond_amount,
+        bond_amount,
+        config.bond_spot_market_index,
+        bond_spot_market_index
+    );
+    msg!("config.paused {} -> {}", config.paused, paused);
+
+    config.max_open_interest = max_open_interest;
+    config.amm_base_asset_reserve = amm_base_asset_reserve;
+    config.bond_amount = bond_amount;
+    config.bond_spot_market_index = bond_spot_market_index;
+    config.order_step_size = order_step_size;
+    config.order_tick_size = order_tick_size;
+    config.min_order_size = min_order_size;
+    config.margin_ratio_initial = margin_ratio_initial;
+    config.margin_ratio_maintenance = margin_ratio_maintenance;
+    config.liquidator_fee = liquidator_fee;
+    config.max_spread = max_spread;
+    config.creator_fee_share = creator_fee_share;
+    config.contract_tier = contract_tier;
+    config.paused = paused as u8;
+
+    config.validate()?;
+
+    Ok(())
+}
+
+/// Ends a permissionlessly created market early and slashes the creator's bond into the bond
+/// spot market's revenue pool. The market then settles through the regular expiry flow.
+pub fn handle_delist_created_prediction_market<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, DelistCreatedPredictionMarket<'info>>,
+    market_index: u16,
+) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    let creator = &mut load_mut!(ctx.accounts.prediction_market_creator)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate!(
+        creator.status == PredictionMarketCreatorStatus::Active,
+        ErrorCode::InvalidPredictionMarketCreator,
+        "creator of perp market {} is {:?}",
+        market_index,
+        creator.status
+    )?;
+
+    validate!(
+        !matches!(
+            perp_market.status,
+            MarketStatus::Settlement | MarketStatus::Delisted
+        ),
+        ErrorCode::InvalidPredictionMarketCreator,
+        "perp market {} already settled",
+        market_index
+    )?;
+
+    msg!(
+        "delisting perp market {} and slashing creator {} bond {}",
+        market_index,
+        creator.creator,
+        creator.bond_amount
+    );
+
+    if perp_market.expiry_ts == 0 || perp_market.expiry_ts > now {
+        msg!("perp_market.expiry_ts {} -> {}", perp_market.expiry_ts, now);
+        perp_market.expiry_ts = now;
+    }
+
+    if perp_market.trading_halt_ts > perp_market.expiry_ts {
+        msg!(
+            "perp_market.trading_halt_ts {} -> {}",
+            perp_market.trading_halt_ts,
+            perp_market.expiry_ts
+        );
+        perp_market.trading_halt_ts = perp_market.expiry_ts;
+    }
+
+    msg!(
+        "perp_market.status {:?} -> {:?}",
+        perp_market.status,
+        MarketStatus::ReduceOnly
+    );
+    perp_market.status = MarketStatus::ReduceOnly;
+
+    let AccountMaps {
+        perp_market_map: _,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(creator.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let spot_market = &mut spot_market_map.get_ref_mut(&creator.bond_spot_market_index)?;
+    let oracle_price_data = oracle_map.get_price_data(&spot_market.oracle_id())?;
+    controller::spot_balance::update_spot_market_cumulative_interest(
+        spot_market,
+        Some(oracle_price_data),
+        now,
+    )?;
+
+    controller::spot_balance::update_revenue_pool_balances(
+        creator.bond_amount.cast()?,
+        &SpotBalanceType::Deposit,
+        spot_market,
+    )?;
+
+    creator.status = PredictionMarketCreatorStatus::Slashed;
+
+    emit!(PredictionMarketCreatorRecord {
+        ts: now,
+        action: PredictionMarketCreatorAction::Slash,
+        market_index,
+        user: creator.creator,
+        amount: creator.bond_amount,
+    });
+
+    Ok(())
+}
+
+pub fn handle_delete_initialized_perp_market(
+    ctx: Context<DeleteInitializedPerpMarket>,
+    market_index: u16,
+) -> Result<()> {
+    let perp_market = &mut ctx.accounts.perp_market.load()?;
+    msg!("perp market {}", perp_market.market_index);
+    let state = &mut ctx.accounts.state;
+
+    // to preserve all protocol invariants, can only remove the last market if it hasn't been "activated"
+
+    validate!(
+        state.number_of_markets - 1 == market_index,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "state.number_of_markets={} != market_index={}",
+        state.number_of_markets,
+        market_index
+    )?;
+    validate!(
+        perp_market.status == MarketStatus::Initialized,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "perp_market.status != Initialized",
+    )?;
+    validate!(
+        perp_market.number_of_users == 0,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "perp_market.number_of_users={} != 0",
+        perp_market.number_of_users,
+    )?;
+    validate!(
+        perp_market.market_index == market_index,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "market_index={} != perp_market.market_index={}",
+        market_index,
+        perp_market.market_index
+    )?;
+
+    safe_decrement!(state.number_of_markets, 1);
+
+    Ok(())
+}
+
+pub fn handle_delete_initialized_spot_market(
+    ctx: Context<DeleteInitializedSpotMarket>,
+    market_index: u16,
+) -> Result<()> {
+    let spot_market = ctx.accounts.spot_market.load()?;
+    msg!("spot market {}", spot_market.market_index);
+    let state = &mut ctx.accounts.state;
+
+    // to preserve all protocol invariants, can only remove the last market if it hasn't been "activated"
+
+    validate!(
+        state.number_of_spot_markets - 1 == market_index,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "state.number_of_spot_markets={} != market_index={}",
+        state.number_of_markets,
+        market_index
+    )?;
+    validate!(
+        spot_market.status == MarketStatus::Initialized,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "spot_market.status != Initialized",
+    )?;
+    validate!(
+        spot_market.deposit_balance == 0,
+        ErrorCode::InvalidMarketAccountforDeletion,
+        "spot_market.number_of_users={} != 0",
+        spot_market.deposit_balance,
+    )?;
+    validate!(
+        spot_market.borrow_balance == 0,
+        ErrorCode::InvalidMarketAccountforDeletion,
         "spot_market.borrow_balance={} != 0",
         spot_market.borrow_balance,
     )?;
@@ -1294,32 +1931,93 @@ pub fn handle_init_user_fuel(
 )]
 pub fn handle_update_perp_market_expiry(
     ctx: Context<AdminUpdatePerpMarket>,
-    expiry_ts: i64,
+    expiry_ts: i64,
+) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    msg!("updating perp market {} expiry", perp_market.market_index);
+
+    validate!(
+        clock.unix_timestamp < expiry_ts,
+        ErrorCode::DefaultError,
+        "Market expiry ts must later than current clock timestamp"
+    )?;
+
+    validate!(
+        perp_market.trading_halt_ts <= expiry_ts,
+        ErrorCode::InvalidTradingHalt,
+        "Market expiry ts must be at or after trading halt ts {}",
+        perp_market.trading_halt_ts
+    )?;
+
+    msg!(
+        "perp_market.expiry_ts {} -> {}",
+        perp_market.expiry_ts,
+        expiry_ts
+    );
+
+    perp_market.expiry_ts = expiry_ts;
+
+    // markets with a trading halt keep trading until it, then await resolution until expiry
+    if perp_market.trading_halt_ts != 0 {
+        return Ok(());
+    }
+
+    msg!(
+        "perp_market.status {:?} -> {:?}",
+        perp_market.status,
+        MarketStatus::ReduceOnly
+    );
+
+    // automatically enter reduce only
+    perp_market.status = MarketStatus::ReduceOnly;
+
+    Ok(())
+}
+
+pub fn handle_update_perp_market_trading_halt_ts(
+    ctx: Context<AdminUpdatePerpMarket>,
+    trading_halt_ts: i64,
 ) -> Result<()> {
     let clock: Clock = Clock::get()?;
     let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
-    msg!("updating perp market {} expiry", perp_market.market_index);
+    msg!(
+        "updating perp market {} trading halt ts",
+        perp_market.market_index
+    );
 
     validate!(
-        clock.unix_timestamp < expiry_ts,
-        ErrorCode::DefaultError,
-        "Market expiry ts must later than current clock timestamp"
+        perp_market.is_prediction_market(),
+        ErrorCode::InvalidTradingHalt,
+        "Only prediction markets can halt trading ahead of expiry"
+    )?;
+
+    validate!(
+        !perp_market.is_trading_halted(clock.unix_timestamp),
+        ErrorCode::InvalidTradingHalt,
+        "Market trading is already halted"
+    )?;
+
+    validate!(
+        trading_halt_ts == 0 || clock.unix_timestamp < trading_halt_ts,
+        ErrorCode::InvalidTradingHalt,
+        "Market trading halt ts must later than current clock timestamp"
+    )?;
+
+    validate!(
+        perp_market.expiry_ts == 0 || trading_halt_ts <= perp_market.expiry_ts,
+        ErrorCode::InvalidTradingHalt,
+        "Market trading halt ts must be at or before expiry ts {}",
+        perp_market.expiry_ts
     )?;
 
     msg!(
-        "perp_market.status {:?} -> {:?}",
-        perp_market.status,
-        MarketStatus::ReduceOnly
-    );
-    msg!(
-        "perp_market.expiry_ts {} -> {}",
-        perp_market.expiry_ts,
-        expiry_ts
+        "perp_market.trading_halt_ts {} -> {}",
+        perp_market.trading_halt_ts,
+        trading_halt_ts
     );
 
-    // automatically enter reduce only
-    perp_market.status = MarketStatus::ReduceOnly;
-    perp_market.expiry_ts = expiry_ts;
+    perp_market.trading_halt_ts = trading_halt_ts;
 
     Ok(())
 }
@@ -2660,6 +3358,13 @@ pub fn handle_update_spot_market_status(
     status: MarketStatus,
 ) -> Result<()> {
     status.validate_not_deprecated()?;
+
+    validate!(
+        status != MarketStatus::TradingHalted,
+        ErrorCode::InvalidTradingHalt,
+        "spot markets can not halt trading ahead of resolution",
+    )?;
+
     let spot_market = &mut load_mut!(ctx.accounts.spot_market)?;
     msg!("spot market {}", spot_market.market_index);
 
@@ -2718,6 +3423,33 @@ pub fn handle_update_spot_market_asset_tier(
     Ok(())
 }
 
+#[access_control(
+    spot_market_valid(&ctx.accounts.spot_market)
+)]
+pub fn handle_update_spot_market_confidence_margin_multiplier(
+    ctx: Context<AdminUpdateSpotMarket>,
+    confidence_margin_multiplier: u8,
+) -> Result<()> {
+    let spot_market = &mut load_mut!(ctx.accounts.spot_market)?;
+    msg!("spot market {}", spot_market.market_index);
+
+    validate!(
+        confidence_margin_multiplier <= MAX_CONFIDENCE_MARGIN_MULTIPLIER,
+        ErrorCode::DefaultError,
+        "confidence_margin_multiplier must be <= {}",
+        MAX_CONFIDENCE_MARGIN_MULTIPLIER
+    )?;
+
+    msg!(
+        "spot_market.confidence_margin_multiplier: {:?} -> {:?}",
+        spot_market.confidence_margin_multiplier,
+        confidence_margin_multiplier
+    );
+
+    spot_market.confidence_margin_multiplier = confidence_margin_multiplier;
+    Ok(())
+}
+
 #[access_control(
     spot_market_valid(&ctx.accounts.spot_market)
 )]
@@ -3019,6 +3751,33 @@ pub fn handle_update_perp_market_contract_tier(
     Ok(())
 }
 
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_update_perp_market_confidence_margin_multiplier(
+    ctx: Context<AdminUpdatePerpMarket>,
+    confidence_margin_multiplier: u8,
+) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    msg!("perp market {}", perp_market.market_index);
+
+    validate!(
+        confidence_margin_multiplier <= MAX_CONFIDENCE_MARGIN_MULTIPLIER,
+        ErrorCode::DefaultError,
+        "confidence_margin_multiplier must be <= {}",
+        MAX_CONFIDENCE_MARGIN_MULTIPLIER
+    )?;
+
+    msg!(
+        "perp_market.amm.confidence_margin_multiplier: {:?} -> {:?}",
+        perp_market.amm.confidence_margin_multiplier,
+        confidence_margin_multiplier
+    );
+
+    perp_market.amm.confidence_margin_multiplier = confidence_margin_multiplier;
+    Ok(())
+}
+
 #[access_control(
     perp_market_valid(&ctx.accounts.perp_market)
 )]
@@ -3401,6 +4160,323 @@ pub fn handle_update_perp_market_oracle(
     Ok(())
 }
 
+pub fn handle_initialize_perp_fallback_oracle(
+    ctx: Context<InitializePerpFallbackOracle>,
+    oracle_source: OracleSource,
+    max_divergence: u64,
+) -> Result<()> {
+    let perp_market = load!(ctx.accounts.perp_market)?;
+    let mut fallback_oracle = ctx.accounts.fallback_oracle.load_init()?;
+    msg!(
+        "initializing perp market {} fallback oracle",
+        perp_market.market_index
+    );
+
+    fallback_oracle.market_index = perp_market.market_index;
+    fallback_oracle.market_type = MarketType::Perp;
+
+    set_fallback_oracle(
+        &mut fallback_oracle,
+        &ctx.accounts.oracle,
+        oracle_source,
+        max_divergence,
+        &perp_market.oracle_id(),
+    )
+}
+
+pub fn handle_initialize_spot_fallback_oracle(
+    ctx: Context<InitializeSpotFallbackOracle>,
+    oracle_source: OracleSource,
+    max_divergence: u64,
+) -> Result<()> {
+    let spot_market = load!(ctx.accounts.spot_market)?;
+    let mut fallback_oracle = ctx.accounts.fallback_oracle.load_init()?;
+    msg!(
+        "initializing spot market {} fallback oracle",
+        spot_market.market_index
+    );
+
+    fallback_oracle.market_index = spot_market.market_index;
+    fallback_oracle.market_type = MarketType::Spot;
+
+    set_fallback_oracle(
+        &mut fallback_oracle,
+        &ctx.accounts.oracle,
+        oracle_source,
+        max_divergence,
+        &spot_market.oracle_id(),
+    )
+}
+
+pub fn handle_update_perp_fallback_oracle(
+    ctx: Context<UpdatePerpFallbackOracle>,
+    oracle_source: OracleSource,
+    max_divergence: u64,
+) -> Result<()> {
+    let perp_market = load!(ctx.accounts.perp_market)?;
+    let mut fallback_oracle = load_mut!(ctx.accounts.fallback_oracle)?;
+    msg!(
+        "updating perp market {} fallback oracle",
+        perp_market.market_index
+    );
+
+    set_fallback_oracle(
+        &mut fallback_oracle,
+        &ctx.accounts.oracle,
+        oracle_source,
+        max_divergence,
+        &perp_market.oracle_id(),
+    )
+}
+
+pub fn handle_update_spot_fallback_oracle(
+    ctx: Context<UpdateSpotFallbackOracle>,
+    oracle_source: OracleSource,
+    max_divergence: u64,
+) -> Result<()> {
+    let spot_market = load!(ctx.accounts.spot_market)?;
+    let mut fallback_oracle = load_mut!(ctx.accounts.fallback_oracle)?;
+    msg!(
+        "updating spot market {} fallback oracle",
+        spot_market.market_index
+    );
+
+    set_fallback_oracle(
+        &mut fallback_oracle,
+        &ctx.accounts.oracle,
+        oracle_source,
+        max_divergence,
+        &spot_market.oracle_id(),
+    )
+}
+
+fn set_fallback_oracle(
+    fallback_oracle: &mut FallbackOracle,
+    oracle: &AccountInfo,
+    oracle_source: OracleSource,
+    max_divergence: u64,
+    primary_oracle_id: &OracleIdentifier,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    OracleMap::validate_oracle_account_info(oracle)?;
+
+    // Verify oracle is readable
+    let OraclePriceData {
+        price: _oracle_price,
+        delay: _oracle_delay,
+        ..
+    } = get_oracle_price(&oracle_source, oracle, clock.slot)?;
+
+    msg!(
+        "fallback_oracle.oracle {:?} -> {:?}",
+        fallback_oracle.oracle,
+        oracle.key
+    );
+
+    msg!(
+        "fallback_oracle.oracle_source {:?} -> {:?}",
+        fallback_oracle.oracle_source,
+        oracle_source
+    );
+
+    msg!(
+        "fallback_oracle.max_divergence {} -> {}",
+        fallback_oracle.max_divergence,
+        max_divergence
+    );
+
+    if fallback_oracle.oracle_id() != (*oracle.key, oracle_source) {
+        // a new feed has to earn the switch again
+        fallback_oracle.is_active = false;
+    }
+
+    fallback_oracle.oracle = *oracle.key;
+    fallback_oracle.oracle_source = oracle_source;
+    fallback_oracle.max_divergence = max_divergence;
+
+    fallback_oracle.validate(primary_oracle_id)?;
+
+    Ok(())
+}
+
+pub fn handle_initialize_perp_oracle_guard_rails_override(
+    ctx: Context<InitializePerpOracleGuardRailsOverride>,
+    params: OracleGuardRailsOverrideParams,
+) -> Result<()> {
+    let mut perp_market = load_mut!(ctx.accounts.perp_market)?;
+    let mut oracle_guard_rails_override = ctx.accounts.oracle_guard_rails_override.load_init()?;
+    msg!(
+        "initializing perp market {} oracle guard rails override",
+        perp_market.market_index
+    );
+
+    oracle_guard_rails_override.market_index = perp_market.market_index;
+    oracle_guard_rails_override.market_type = MarketType::Perp;
+    oracle_guard_rails_override.update(&params);
+    oracle_guard_rails_override.validate(&ctx.accounts.state.oracle_guard_rails)?;
+
+    perp_market.amm.oracle_accounts |= MarketOracleAccount::GuardRailsOverride as u8;
+
+    Ok(())
+}
+
+pub fn handle_initialize_spot_oracle_guard_rails_override(
+    ctx: Context<InitializeSpotOracleGuardRailsOverride>,
+    params: OracleGuardRailsOverrideParams,
+) -> Result<()> {
+    let mut spot_market = load_mut!(ctx.accounts.spot_market)?;
+    let mut oracle_guard_rails_override = ctx.accounts.oracle_guard_rails_override.load_init()?;
+    msg!(
+        "initializing spot market {} oracle guard rails override",
+        spot_market.market_index
+    );
+
+    oracle_guard_rails_override.market_index = spot_market.market_index;
+    oracle_guard_rails_override.market_type = MarketType::Spot;
+    oracle_guard_rails_override.update(&params);
+    oracle_guard_rails_override.validate(&ctx.accounts.state.oracle_guard_rails)?;
+
+    spot_market.oracle_accounts |= MarketOracleAccount::GuardRailsOverride as u8;
+
+    Ok(())
+}
+
+pub fn handle_update_oracle_guard_rails_override(
+    ctx: Context<UpdateOracleGuardRailsOverride>,
+    params: OracleGuardRailsOverrideParams,
+) -> Result<()> {
+    let mut oracle_guard_rails_override = load_mut!(ctx.accounts.oracle_guard_rails_override)?;
+    msg!(
+        "updating {} market {} oracle guard rails override",
+        oracle_guard_rails_override.market_type,
+        oracle_guard_rails_override.market_index
+    );
+
+    msg!(
+        "oracle_guard_rails_override: {:?} -> {:?}",
+        *oracle_guard_rails_override,
+        params
+    );
+
+    oracle_guard_rails_override.update(&params);
+    oracle_guard_rails_override.validate(&ctx.accounts.state.oracle_guard_rails)?;
+
+    Ok(())
+}
+
+pub fn handle_initialize_pyth_lazer_oracle(
+    ctx: Context<InitializePythLazerOracle>,
+    feed_id: u32,
+    signer: Pubkey,
+) -> Result<()> {
+    let mut oracle = ctx.accounts.pyth_lazer_oracle.load_init()?;
+    msg!("initializing pyth lazer oracle for feed {}", feed_id);
+
+    validate!(
+        signer != Pubkey::default(),
+        ErrorCode::InvalidOracle,
+        "signer must be set"
+    )?;
+
+    oracle.feed_id = feed_id;
+    oracle.signer = signer;
+
+    Ok(())
+}
+
+pub fn handle_update_pyth_lazer_oracle_signer(
+    ctx: Context<UpdatePythLazerOracleSigner>,
+    signer: Pubkey,
+) -> Result<()> {
+    let mut oracle = load_mut!(ctx.accounts.pyth_lazer_oracle)?;
+    msg!("updating pyth lazer oracle for feed {}", oracle.feed_id);
+
+    validate!(
+        signer != Pubkey::default(),
+        ErrorCode::InvalidOracle,
+        "signer must be set"
+    )?;
+
+    msg!(
+        "pyth_lazer_oracle.signer: {:?} -> {:?}",
+        oracle.signer,
+        signer
+    );
+
+    oracle.signer = signer;
+
+    Ok(())
+}
+
+pub fn handle_initialize_perp_price_history(
+    ctx: Context<InitializePerpPriceHistory>,
+    sample_interval: i64,
+) -> Result<()> {
+    let mut perp_market = load_mut!(ctx.accounts.perp_market)?;
+    let mut price_history = ctx.accounts.price_history.load_init()?;
+    msg!(
+        "initializing perp market {} price history",
+        perp_market.market_index
+    );
+
+    price_history.market_index = perp_market.market_index;
+    price_history.market_type = MarketType::Perp;
+    price_history.sample_interval = sample_interval;
+
+    price_history.validate()?;
+
+    perp_market.amm.oracle_accounts |= MarketOracleAccount::PriceHistory as u8;
+
+    Ok(())
+}
+
+pub fn handle_initialize_spot_price_history(
+    ctx: Context<InitializeSpotPriceHistory>,
+    sample_interval: i64,
+) -> Result<()> {
+    let mut spot_market = load_mut!(ctx.accounts.spot_market)?;
+    let mut price_history = ctx.accounts.price_history.load_init()?;
+    msg!(
+        "initializing spot market {} price history",
+        spot_market.market_index
+    );
+
+    price_history.market_index = spot_market.market_index;
+    price_history.market_type = MarketType::Spot;
+    price_history.sample_interval = sample_interval;
+
+    price_history.validate()?;
+
+    spot_market.oracle_accounts |= MarketOracleAccount::PriceHistory as u8;
+
+    Ok(())
+}
+
+pub fn handle_update_price_history_sample_interval(
+    ctx: Context<UpdatePriceHistory>,
+    sample_interval: i64,
+) -> Result<()> {
+    let mut price_history = load_mut!(ctx.accounts.price_history)?;
+    msg!(
+        "updating {} market {} price history",
+        price_history.market_type,
+        price_history.market_index
+    );
+
+    msg!(
+        "price_history.sample_interval: {:?} -> {:?}",
+        price_history.sample_interval,
+        sample_interval
+    );
+
+    price_history.sample_interval = sample_interval;
+
+    price_history.validate()?;
+
+    Ok(())
+}
+
 #[access_control(
     perp_market_valid(&ctx.accounts.perp_market)
 )]
@@ -3451,6 +4527,12 @@ pub fn handle_update_amm_jit_intensity(
     let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
     msg!("perp market {}", perp_market.market_index);
 
+    validate!(
+        !perp_market.amm.is_lmsr() || amm_jit_intensity == 0,
+        ErrorCode::InvalidLmsrCurve,
+        "lmsr markets can not use amm jit",
+    )?;
+
     msg!(
         "perp_market.amm.amm_jit_intensity: {} -> {}",
         perp_market.amm.amm_jit_intensity,
@@ -3668,12 +4750,50 @@ pub fn handle_update_perp_market_max_open_interest(
     )?;
 
     msg!(
-        "perp_market.amm.max_open_interest: {:?} -> {:?}",
-        perp_market.amm.max_open_interest,
-        max_open_interest
+        "perp_market.amm.max_open_interest: {:?} -> {:?}",
+        perp_market.amm.max_open_interest,
+        max_open_interest
+    );
+
+    perp_market.amm.max_open_interest = max_open_interest;
+    Ok(())
+}
+
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_update_perp_market_prediction_price_band(
+    ctx: Context<AdminUpdatePerpMarket>,
+    prediction_min_ask: u16,
+    prediction_max_bid: u16,
+) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    msg!("perp market {}", perp_market.market_index);
+
+    validate!(
+        perp_market.is_prediction_market(),
+        ErrorCode::InvalidPredictionMarketPriceBounds,
+        "perp market {} is not a prediction market",
+        perp_market.market_index
+    )?;
+
+    msg!(
+        "perp_market.prediction_min_ask: {:?} -> {:?}",
+        perp_market.prediction_min_ask,
+        prediction_min_ask
+    );
+
+    msg!(
+        "perp_market.prediction_max_bid: {:?} -> {:?}",
+        perp_market.prediction_max_bid,
+        prediction_max_bid
     );
 
-    perp_market.amm.max_open_interest = max_open_interest;
+    perp_market.prediction_min_ask = prediction_min_ask;
+    perp_market.prediction_max_bid = prediction_max_bid;
+
+    validate_perp_market(perp_market)?;
+
     Ok(())
 }
 
@@ -3705,6 +4825,45 @@ pub fn handle_update_perp_market_fee_adjustment(
     Ok(())
 }
 
+#[access_control(
+    perp_market_valid(&ctx.accounts.perp_market)
+)]
+pub fn handle_update_perp_market_prediction_fee_curve(
+    ctx: Context<AdminUpdatePerpMarket>,
+    prediction_fee_curve: PredictionFeeCurve,
+    prediction_fee_floor: u16,
+    prediction_fee_cap: u16,
+) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    msg!("perp market {}", perp_market.market_index);
+
+    msg!(
+        "perp_market.prediction_fee_curve: {:?} -> {:?}",
+        perp_market.prediction_fee_curve,
+        prediction_fee_curve
+    );
+
+    msg!(
+        "perp_market.prediction_fee_floor: {:?} -> {:?}",
+        perp_market.prediction_fee_floor,
+        prediction_fee_floor
+    );
+
+    msg!(
+        "perp_market.prediction_fee_cap: {:?} -> {:?}",
+        perp_market.prediction_fee_cap,
+        prediction_fee_cap
+    );
+
+    perp_market.prediction_fee_curve = prediction_fee_curve;
+    perp_market.prediction_fee_floor = prediction_fee_floor;
+    perp_market.prediction_fee_cap = prediction_fee_cap;
+
+    validate_perp_market(perp_market)?;
+
+    Ok(())
+}
+
 pub fn handle_update_perp_market_number_of_users(
     ctx: Context<AdminUpdatePerpMarket>,
     number_of_users: Option<u32>,
@@ -4105,6 +5264,68 @@ pub fn handle_delete_prelaunch_oracle(
     Ok(())
 }
 
+pub fn handle_initialize_median_oracle<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, InitializeMedianOracle<'info>>,
+    params: MedianOracleParams,
+) -> Result<()> {
+    let mut oracle = ctx.accounts.median_oracle.load_init()?;
+    msg!("median oracle {}", params.id);
+
+    oracle.id = params.id;
+    set_median_oracle_params(&mut oracle, &params, ctx.remaining_accounts)?;
+
+    Ok(())
+}
+
+pub fn handle_update_median_oracle_params<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdateMedianOracleParams<'info>>,
+    params: MedianOracleParams,
+) -> Result<()> {
+    let mut oracle = ctx.accounts.median_oracle.load_mut()?;
+    msg!("median oracle {}", params.id);
+
+    msg!(
+        "min quorum: {:?} -> {:?}",
+        oracle.min_quorum,
+        params.min_quorum
+    );
+    msg!(
+        "max member delay: {:?} -> {:?}",
+        oracle.max_member_delay,
+        params.max_member_delay
+    );
+
+    set_median_oracle_params(&mut oracle, &params, ctx.remaining_accounts)?;
+
+    Ok(())
+}
+
+fn set_median_oracle_params(
+    oracle: &mut MedianOracle,
+    params: &MedianOracleParams,
+    member_account_infos: &[AccountInfo],
+) -> Result<()> {
+    let slot = Clock::get()?.slot;
+
+    oracle.set_members(&params.members)?;
+    oracle.min_quorum = params.min_quorum;
+    oracle.max_member_delay = params.max_member_delay;
+
+    oracle.validate()?;
+
+    // every member has to be readable to start with
+    let members_updated = oracle.update_members(member_account_infos, slot)?;
+    validate!(
+        members_updated == oracle.num_members,
+        ErrorCode::InvalidOracle,
+        "only {} of {} median oracle members could be read",
+        members_updated,
+        oracle.num_members
+    )?;
+
+    Ok(())
+}
+
 pub fn handle_initialize_pyth_pull_oracle(
     ctx: Context<InitPythPullPriceFeed>,
     feed_id: [u8; 32],
@@ -4138,18 +5359,34 @@ pub fn handle_settle_expired_market<'c: 'info, 'info>(
     let _now = clock.unix_timestamp;
     let state = &ctx.accounts.state;
 
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
     let AccountMaps {
         perp_market_map,
         spot_market_map,
         mut oracle_map,
     } = load_maps(
-        &mut ctx.remaining_accounts.iter().peekable(),
+        remaining_accounts_iter,
         &get_writable_perp_market_set(market_index),
         &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
         clock.slot,
         Some(state.oracle_guard_rails),
     )?;
 
+    let resolved_expiry_price = {
+        let perp_market = perp_market_map.get_ref(&market_index)?;
+        if perp_market.is_prediction_event_outcome() {
+            let prediction_event = get_prediction_event(remaining_accounts_iter, &perp_market)?;
+            let expiry_price = load!(prediction_event)?.get_outcome_expiry_price(market_index)?;
+            Some(expiry_price)
+        } else if perp_market.has_optimistic_resolution() {
+            let market_resolution = get_market_resolution(remaining_accounts_iter, &perp_market)?;
+            let expiry_price = load!(market_resolution)?.get_finalized_price()?;
+            Some(expiry_price)
+        } else {
+            None
+        }
+    };
+
     controller::repeg::update_amm(
         market_index,
         &perp_market_map,
@@ -4165,6 +5402,39 @@ pub fn handle_settle_expired_market<'c: 'info, 'info>(
         &spot_market_map,
         state,
         &clock,
+        resolved_expiry_price,
+    )?;
+
+    Ok(())
+}
+
+pub fn handle_void_expired_market<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, AdminUpdatePerpMarket<'info>>,
+    market_index: u16,
+    refund_fees: bool,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        oracle_map: _,
+    } = load_maps(
+        remaining_accounts_iter,
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    controller::repeg::void_expired_market(
+        market_index,
+        &perp_market_map,
+        &spot_market_map,
+        &clock,
+        refund_fees,
     )?;
 
     Ok(())
@@ -4438,64 +5708,278 @@ pub struct UpdatePhoenixFulfillmentConfig<'info> {
 #[derive(Accounts)]
 pub struct UpdateSerumVault<'info> {
     #[account(
-        mut,
+        mut,
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub srm_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePerpMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        init,
+        seeds = [b"perp_market", state.number_of_markets.to_le_bytes().as_ref()],
+        space = PerpMarket::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    /// CHECK: checked in `initialize_perp_market`
+    pub oracle: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteInitializedPerpMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut, close = admin)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+}
+
+#[derive(Accounts)]
+pub struct AdminUpdatePerpMarket<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_index: u16)]
+pub struct InitializePredictionShareMints<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        seeds = [b"perp_market", market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        init,
+        seeds = [PREDICTION_YES_MINT_SEED, market_index.to_le_bytes().as_ref()],
+        bump,
+        payer = admin,
+        mint::decimals = PREDICTION_SHARE_MINT_DECIMALS,
+        mint::authority = drift_signer
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        seeds = [PREDICTION_NO_MINT_SEED, market_index.to_le_bytes().as_ref()],
+        bump,
+        payer = admin,
+        mint::decimals = PREDICTION_SHARE_MINT_DECIMALS,
+        mint::authority = drift_signer
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        constraint = state.signer.eq(&drift_signer.key())
+    )]
+    /// CHECK: program signer
+    pub drift_signer: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePredictionMarketCreationConfig<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == admin_hot_wallet::id() || admin.key() == state.admin
+    )]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        seeds = [PREDICTION_MARKET_CREATION_CONFIG_SEED],
+        space = PredictionMarketCreationConfig::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub prediction_market_creation_config: AccountLoader<'info, PredictionMarketCreationConfig>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePredictionMarketCreationConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PREDICTION_MARKET_CREATION_CONFIG_SEED],
+        bump,
+    )]
+    pub prediction_market_creation_config: AccountLoader<'info, PredictionMarketCreationConfig>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_index: u16)]
+pub struct DelistCreatedPredictionMarket<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        seeds = [b"perp_market", market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        mut,
+        seeds = [PREDICTION_MARKET_CREATOR_SEED, market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub prediction_market_creator: AccountLoader<'info, PredictionMarketCreator>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePredictionEvent<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        init,
+        seeds = [b"prediction_event", state.number_of_prediction_events.to_le_bytes().as_ref()],
+        space = PredictionEvent::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub prediction_event: AccountLoader<'info, PredictionEvent>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminUpdatePredictionEvent<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub prediction_event: AccountLoader<'info, PredictionEvent>,
+}
+
+#[derive(Accounts)]
+pub struct AddPredictionEventOutcome<'info> {
+    pub admin: Signer<'info>,
+    #[account(
         has_one = admin
     )]
     pub state: Box<Account<'info, State>>,
     #[account(mut)]
-    pub admin: Signer<'info>,
-    pub srm_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub prediction_event: AccountLoader<'info, PredictionEvent>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
 }
 
 #[derive(Accounts)]
-pub struct InitializePerpMarket<'info> {
+pub struct InitializeMarketResolution<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
     #[account(
-        mut,
         has_one = admin
     )]
     pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    pub bond_spot_market: AccountLoader<'info, SpotMarket>,
     #[account(
         init,
-        seeds = [b"perp_market", state.number_of_markets.to_le_bytes().as_ref()],
-        space = PerpMarket::SIZE,
+        seeds = [b"market_resolution", perp_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = MarketResolution::SIZE,
         bump,
         payer = admin
     )]
-    pub perp_market: AccountLoader<'info, PerpMarket>,
-    /// CHECK: checked in `initialize_perp_market`
-    pub oracle: AccountInfo<'info>,
+    pub market_resolution: AccountLoader<'info, MarketResolution>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct DeleteInitializedPerpMarket<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+pub struct FinalizeDisputedMarketResolution<'info> {
     #[account(
-        mut,
-        has_one = admin
+        constraint = admin.key() == state.admin || admin.key() == market_resolution.load()?.guardian
     )]
+    pub admin: Signer<'info>,
     pub state: Box<Account<'info, State>>,
-    #[account(mut, close = admin)]
+    #[account(
+        constraint = perp_market.load()?.market_index == market_resolution.load()?.market_index
+    )]
     pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(mut)]
+    pub market_resolution: AccountLoader<'info, MarketResolution>,
+    #[account(
+        mut,
+        constraint = proposer.key() == market_resolution.load()?.proposer
+    )]
+    pub proposer: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        constraint = disputer.key() == market_resolution.load()?.disputer
+    )]
+    pub disputer: AccountLoader<'info, User>,
 }
 
 #[derive(Accounts)]
-pub struct AdminUpdatePerpMarket<'info> {
-    pub admin: Signer<'info>,
+pub struct AdminUpdatePerpMarketAmmSummaryStats<'info> {
     #[account(
-        has_one = admin
+        constraint = admin.key() == admin_hot_wallet::id() || admin.key() == state.admin
     )]
+    pub admin: Signer<'info>,
     pub state: Box<Account<'info, State>>,
     #[account(mut)]
     pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        seeds = [b"spot_market", perp_market.load()?.quote_spot_market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub spot_market: AccountLoader<'info, SpotMarket>,
+    /// CHECK: checked in `admin_update_perp_market_summary_stats` ix constraint
+    pub oracle: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct AdminUpdatePerpMarketAmmSummaryStats<'info> {
+pub struct AdminUpdatePerpMarketCurve<'info> {
     #[account(
         constraint = admin.key() == admin_hot_wallet::id() || admin.key() == state.admin
     )]
@@ -4507,9 +5991,7 @@ pub struct AdminUpdatePerpMarketAmmSummaryStats<'info> {
         seeds = [b"spot_market", perp_market.load()?.quote_spot_market_index.to_le_bytes().as_ref()],
         bump,
     )]
-    pub spot_market: AccountLoader<'info, SpotMarket>,
-    /// CHECK: checked in `admin_update_perp_market_summary_stats` ix constraint
-    pub oracle: AccountInfo<'info>,
+    pub quote_spot_market: AccountLoader<'info, SpotMarket>,
 }
 
 #[derive(Accounts)]
@@ -4646,6 +6128,230 @@ pub struct AdminUpdateSpotMarketOracle<'info> {
     pub oracle: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct InitializePerpFallbackOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        init,
+        seeds = [PERP_FALLBACK_ORACLE_SEED, perp_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = FallbackOracle::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub fallback_oracle: AccountLoader<'info, FallbackOracle>,
+    /// CHECK: checked in `initialize_perp_fallback_oracle`
+    pub oracle: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSpotFallbackOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub spot_market: AccountLoader<'info, SpotMarket>,
+    #[account(
+        init,
+        seeds = [SPOT_FALLBACK_ORACLE_SEED, spot_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = FallbackOracle::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub fallback_oracle: AccountLoader<'info, FallbackOracle>,
+    /// CHECK: checked in `initialize_spot_fallback_oracle`
+    pub oracle: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePerpFallbackOracle<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        mut,
+        seeds = [PERP_FALLBACK_ORACLE_SEED, perp_market.load()?.market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub fallback_oracle: AccountLoader<'info, FallbackOracle>,
+    /// CHECK: checked in `update_perp_fallback_oracle`
+    pub oracle: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSpotFallbackOracle<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub spot_market: AccountLoader<'info, SpotMarket>,
+    #[account(
+        mut,
+        seeds = [SPOT_FALLBACK_ORACLE_SEED, spot_market.load()?.market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub fallback_oracle: AccountLoader<'info, FallbackOracle>,
+    /// CHECK: checked in `update_spot_fallback_oracle`
+    pub oracle: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePerpOracleGuardRailsOverride<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        init,
+        seeds = [PERP_ORACLE_GUARD_RAILS_OVERRIDE_SEED, perp_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = OracleGuardRailsOverride::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub oracle_guard_rails_override: AccountLoader<'info, OracleGuardRailsOverride>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSpotOracleGuardRailsOverride<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub spot_market: AccountLoader<'info, SpotMarket>,
+    #[account(
+        init,
+        seeds = [SPOT_ORACLE_GUARD_RAILS_OVERRIDE_SEED, spot_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = OracleGuardRailsOverride::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub oracle_guard_rails_override: AccountLoader<'info, OracleGuardRailsOverride>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracleGuardRailsOverride<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub oracle_guard_rails_override: AccountLoader<'info, OracleGuardRailsOverride>,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: u32)]
+pub struct InitializePythLazerOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        init,
+        seeds = [PYTH_LAZER_ORACLE_SEED, feed_id.to_le_bytes().as_ref()],
+        space = PythLazerOracle::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub pyth_lazer_oracle: AccountLoader<'info, PythLazerOracle>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePythLazerOracleSigner<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub pyth_lazer_oracle: AccountLoader<'info, PythLazerOracle>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePerpPriceHistory<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        init,
+        seeds = [PERP_PRICE_HISTORY_SEED, perp_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = PriceHistory::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub price_history: AccountLoader<'info, PriceHistory>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSpotPriceHistory<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub spot_market: AccountLoader<'info, SpotMarket>,
+    #[account(
+        init,
+        seeds = [SPOT_PRICE_HISTORY_SEED, spot_market.load()?.market_index.to_le_bytes().as_ref()],
+        space = PriceHistory::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub price_history: AccountLoader<'info, PriceHistory>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceHistory<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub price_history: AccountLoader<'info, PriceHistory>,
+}
+
 #[derive(Accounts)]
 pub struct AdminDisableBidAskTwapUpdate<'info> {
     pub admin: Signer<'info>,
@@ -4771,6 +6477,43 @@ pub struct DeletePrelaunchOracle<'info> {
     pub state: Box<Account<'info, State>>,
 }
 
+#[derive(Accounts)]
+#[instruction(params: MedianOracleParams,)]
+pub struct InitializeMedianOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        seeds = [b"median_oracle".as_ref(), params.id.to_le_bytes().as_ref()],
+        space = MedianOracle::SIZE,
+        bump,
+        payer = admin
+    )]
+    pub median_oracle: AccountLoader<'info, MedianOracle>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: MedianOracleParams,)]
+pub struct UpdateMedianOracleParams<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"median_oracle".as_ref(), params.id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub median_oracle: AccountLoader<'info, MedianOracle>,
+    #[account(
+        has_one = admin
+    )]
+    pub state: Box<Account<'info, State>>,
+}
+
 #[derive(Accounts)]
 #[instruction(market_index: u16)]
 pub struct InitializeOpenbookV2FulfillmentConfig<'info> {
@@ -21,17 +21,24 @@ use crate::controller::token::{receive, send_from_program_vault};
 use crate::error::ErrorCode;
 use crate::ids::{admin_hot_wallet, swift_server};
 use crate::instructions::constraints::*;
-use crate::instructions::optional_accounts::{load_maps, AccountMaps};
+use crate::instructions::optional_accounts::{
+    get_user_parent_orders, get_user_trailing_stops, load_maps, load_maps_for_action, AccountMaps,
+};
 use crate::math::casting::Cast;
 use crate::math::constants::QUOTE_SPOT_MARKET_INDEX;
 use crate::math::margin::{calculate_user_equity, meets_settle_pnl_maintenance_margin_requirement};
+use crate::math::oracle::DriftAction;
 use crate::math::orders::{estimate_price_from_side, find_bids_and_asks_from_users};
 use crate::math::position::calculate_base_asset_value_and_pnl_with_oracle_price;
 use crate::math::safe_math::SafeMath;
+use crate::math::safe_unwrap::SafeUnwrap;
 use crate::math::spot_withdraw::validate_spot_market_vault_amount;
 use crate::math_error;
 use crate::optional_accounts::{get_token_mint, update_prelaunch_oracle};
-use crate::state::events::{DeleteUserRecord, OrderActionExplanation, SwiftOrderRecord};
+use crate::state::events::{
+    DeleteUserRecord, MarketResolutionAction, MarketResolutionRecord, OrderActionExplanation,
+    SwiftOrderRecord,
+};
 use crate::state::fill_mode::FillMode;
 use crate::state::fulfillment_params::drift::MatchFulfillmentParams;
 use crate::state::fulfillment_params::openbook_v2::OpenbookV2FulfillmentParams;
@@ -39,16 +46,20 @@ use crate::state::fulfillment_params::phoenix::PhoenixFulfillmentParams;
 use crate::state::fulfillment_params::serum::SerumFulfillmentParams;
 use crate::state::high_leverage_mode_config::HighLeverageModeConfig;
 use crate::state::insurance_fund_stake::InsuranceFundStake;
+use crate::state::market_resolution::MarketResolution;
+use crate::state::oracle::MedianOracle;
 use crate::state::oracle_map::OracleMap;
 use crate::state::order_params::{
-    OrderParams, PlaceOrderOptions, SwiftOrderParamsMessage, SwiftServerMessage,
+    OrderLinkType, OrderParams, PlaceOrderOptions, SwiftOrderParamsMessage, SwiftServerMessage,
 };
+use crate::state::parent_order::{UserParentOrders, PARENT_ORDERS_PDA_SEED};
 use crate::state::paused_operations::PerpOperation;
 use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket};
 use crate::state::perp_market_map::{
     get_market_set_for_spot_positions, get_market_set_for_user_positions, get_market_set_from_list,
     get_writable_perp_market_set, get_writable_perp_market_set_from_vec, MarketSet, PerpMarketMap,
 };
+use crate::state::pyth_lazer_oracle::{PythLazerOracle, PythLazerPayload};
 use crate::state::settle_pnl_mode::SettlePnlMode;
 use crate::state::spot_fulfillment_params::SpotFulfillmentParams;
 use crate::state::spot_market::{SpotBalanceType, SpotMarket};
@@ -59,11 +70,14 @@ use crate::state::state::State;
 use crate::state::swift_user::{
     SwiftOrderId, SwiftUserOrdersLoader, SwiftUserOrdersZeroCopyMut, SWIFT_PDA_SEED,
 };
+use crate::state::trailing_stop::{UserTrailingStops, TRAILING_STOPS_PDA_SEED};
 use crate::state::user::{
     MarginMode, MarketType, OrderStatus, OrderTriggerCondition, OrderType, User, UserStats,
 };
 use crate::state::user_map::{load_user_map, load_user_maps, UserMap, UserStatsMap};
-use crate::validation::sig_verification::{extract_ed25519_ix_signature, verify_ed25519_msg};
+use crate::validation::sig_verification::{
+    extract_ed25519_ix_pubkey, extract_ed25519_ix_signature, verify_ed25519_ix, verify_ed25519_msg,
+};
 use crate::validation::user::{validate_user_deletion, validate_user_is_idle};
 use crate::{
     controller, digest_struct, digest_struct_hex, load, math, print_error, safe_decrement,
@@ -124,14 +138,18 @@ fn fill_order<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         remaining_accounts_iter,
         &get_writable_perp_market_set(market_index),
         &MarketSet::new(),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::FillOrderAmm),
     )?;
 
+    let user_trailing_stops =
+        get_user_trailing_stops(remaining_accounts_iter, &ctx.accounts.user.key())?;
+
     let (makers_and_referrer, makers_and_referrer_stats) =
         load_user_maps(remaining_accounts_iter, true)?;
 
@@ -160,6 +178,16 @@ fn fill_order<'c: 'info, 'info>(
         FillMode::Fill,
     )?;
 
+    if let Some(user_trailing_stops) = user_trailing_stops {
+        controller::orders::update_trailing_stop_orders_after_fill(
+            &ctx.accounts.user,
+            &user_trailing_stops,
+            market_index,
+            &perp_market_map,
+            &mut oracle_map,
+        )?;
+    }
+
     Ok(())
 }
 
@@ -347,19 +375,29 @@ pub fn handle_trigger_order<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &writeable_perp_markets,
         &writeable_spot_markets,
         Clock::get()?.slot,
         None,
+        Some(DriftAction::TriggerOrder),
     )?;
 
+    // the trailing stops account is only initialized once the user places a trailing stop
+    let user_trailing_stops: Option<AccountLoader<UserTrailingStops>> =
+        if ctx.accounts.user_trailing_stops.data_is_empty() {
+            None
+        } else {
+            Some(AccountLoader::try_from(&ctx.accounts.user_trailing_stops)?)
+        };
+
     match market_type {
         MarketType::Perp => controller::orders::trigger_order(
             order_id,
             &ctx.accounts.state,
             &ctx.accounts.user,
+            user_trailing_stops.as_ref(),
             &spot_market_map,
             &perp_market_map,
             &mut oracle_map,
@@ -381,6 +419,43 @@ pub fn handle_trigger_order<'c: 'info, 'info>(
     Ok(())
 }
 
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_release_parent_order_slice<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ReleaseParentOrderSlice<'info>>,
+    parent_order_id: u32,
+) -> Result<()> {
+    let clock = &Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    controller::orders::release_parent_order_slice(
+        parent_order_id,
+        state,
+        &ctx.accounts.user,
+        &ctx.accounts.user_parent_orders,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        &ctx.accounts.filler,
+        clock,
+    )?;
+
+    Ok(())
+}
+
 #[access_control(
     exchange_not_paused(&ctx.accounts.state)
 )]
@@ -412,6 +487,42 @@ pub fn handle_force_cancel_orders<'c: 'info, 'info>(
     Ok(())
 }
 
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_cancel_orders_on_heartbeat_expiry<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ForceCancelOrder>,
+) -> Result<()> {
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        remaining_accounts_iter,
+        &MarketSet::new(),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        Clock::get()?.slot,
+        None,
+    )?;
+
+    let user_parent_orders =
+        get_user_parent_orders(remaining_accounts_iter, &ctx.accounts.user.key())?;
+
+    controller::orders::cancel_orders_on_heartbeat_expiry(
+        &ctx.accounts.state,
+        &ctx.accounts.user,
+        user_parent_orders.as_ref(),
+        &spot_market_map,
+        &perp_market_map,
+        &mut oracle_map,
+        &ctx.accounts.filler,
+        &Clock::get()?,
+    )?;
+
+    Ok(())
+}
+
 #[access_control(
     exchange_not_paused(&ctx.accounts.state)
 )]
@@ -742,6 +853,7 @@ pub fn place_swift_taker_order<'c: 'info, 'info>(
     }
     swift_account.add_swift_order_id(swift_order_id)?;
 
+    let swift_taker_order_id = taker.next_order_id;
     controller::orders::place_perp_order(
         state,
         taker,
@@ -757,6 +869,8 @@ pub fn place_swift_taker_order<'c: 'info, 'info>(
         },
     )?;
 
+    let swift_taker_order_placed = taker.get_order_index(swift_taker_order_id).is_ok();
+
     let order_params_hash = base64::encode(
         solana_program::hash::hash(&swift_message.swift_order_signature.try_to_vec().unwrap())
             .as_ref(),
@@ -837,6 +951,17 @@ pub fn place_swift_taker_order<'c: 'info, 'info>(
         )?;
     }
 
+    // stop loss and take profit wait on the swift order to fill and then cancel each other
+    let last_order_id = taker.get_last_order_id();
+    if swift_taker_order_placed && last_order_id > swift_taker_order_id {
+        controller::orders::link_orders(
+            taker,
+            swift_taker_order_id.safe_add(1)?,
+            last_order_id,
+            OrderLinkType::Bracket,
+        )?;
+    }
+
     Ok(())
 }
 
@@ -857,12 +982,13 @@ pub fn handle_settle_pnl<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &get_writable_perp_market_set(market_index),
         &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::SettlePnl),
     )?;
 
     let market_in_settlement =
@@ -935,12 +1061,13 @@ pub fn handle_settle_multiple_pnls<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &get_writable_perp_market_set_from_vec(&market_indexes),
         &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::SettlePnl),
     )?;
 
     let meets_margin_requirement = meets_settle_pnl_maintenance_margin_requirement(
@@ -1004,6 +1131,50 @@ pub fn handle_settle_multiple_pnls<'c: 'info, 'info>(
     Ok(())
 }
 
+#[access_control(
+    settle_pnl_not_paused(&ctx.accounts.state)
+    amm_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_settle_expired_positions<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, SettleExpiredPositions<'info>>,
+    market_index: u16,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        remaining_accounts_iter,
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let user_map = load_user_map(remaining_accounts_iter, true)?;
+
+    let record = controller::pnl::settle_expired_positions(
+        market_index,
+        &user_map,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        &clock,
+        state,
+    )?;
+
+    emit!(record);
+
+    let spot_market = spot_market_map.get_quote_spot_market()?;
+    validate_spot_market_vault_amount(&spot_market, ctx.accounts.spot_market_vault.amount)?;
+
+    Ok(())
+}
+
 #[access_control(
     funding_not_paused(&ctx.accounts.state)
 )]
@@ -1093,12 +1264,13 @@ pub fn handle_liquidate_perp<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &get_writable_perp_market_set(market_index),
         &MarketSet::new(),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::Liquidate),
     )?;
 
     controller::liquidation::liquidate_perp(
@@ -1145,12 +1317,13 @@ pub fn handle_liquidate_perp_with_fill<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         remaining_accounts_iter,
         &get_writable_perp_market_set(market_index),
         &MarketSet::new(),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::Liquidate),
     )?;
 
     let (makers_and_referrer, makers_and_referrer_stats) =
@@ -1207,12 +1380,13 @@ pub fn handle_liquidate_spot<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &MarketSet::new(),
         &get_writable_spot_market_set_from_many(vec![asset_market_index, liability_market_index]),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::Liquidate),
     )?;
 
     controller::liquidation::liquidate_spot(
@@ -1266,12 +1440,13 @@ pub fn handle_liquidate_borrow_for_perp_pnl<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &MarketSet::new(),
         &get_writable_spot_market_set(spot_market_index),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::Liquidate),
     )?;
 
     controller::liquidation::liquidate_borrow_for_perp_pnl(
@@ -1325,12 +1500,13 @@ pub fn handle_liquidate_perp_pnl_for_deposit<'c: 'info, 'info>(
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-    } = load_maps(
+    } = load_maps_for_action(
         &mut ctx.remaining_accounts.iter().peekable(),
         &MarketSet::new(),
         &get_writable_spot_market_set(spot_market_index),
         clock.slot,
         Some(state.oracle_guard_rails),
+        Some(DriftAction::Liquidate),
     )?;
 
     controller::liquidation::liquidate_perp_pnl_for_deposit(
@@ -1757,9 +1933,34 @@ pub fn handle_update_funding_rate(
         clock_slot,
         Some(state.oracle_guard_rails),
     )?;
+    oracle_map.load_market_oracle_accounts(ctx.remaining_accounts)?;
+    oracle_map.validate_price_history(
+        MarketType::Perp,
+        perp_market_index,
+        perp_market.has_price_history(),
+    )?;
 
-    let oracle_price_data = &oracle_map.get_price_data(&perp_market.oracle_id())?;
-    controller::repeg::_update_amm(perp_market, oracle_price_data, state, now, clock_slot)?;
+    let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
+        MarketType::Perp,
+        perp_market_index,
+        &perp_market.oracle_id(),
+        perp_market
+            .amm
+            .historical_oracle_data
+            .last_oracle_price_twap,
+        perp_market.get_max_confidence_interval_multiplier()?,
+    )?;
+    let oracle_price_data = *oracle_price_data;
+    controller::repeg::_update_amm(perp_market, &oracle_price_data, state, now, clock_slot)?;
+
+    oracle_map.record_price_history(
+        MarketType::Perp,
+        perp_market_index,
+        now,
+        oracle_price_data.price,
+        oracle_validity,
+        perp_market.amm.reserve_price()?.cast()?,
+    )?;
 
     validate!(
         matches!(
@@ -1821,6 +2022,166 @@ pub fn handle_update_prelaunch_oracle(ctx: Context<UpdatePrelaunchOracle>) -> Re
     Ok(())
 }
 
+pub fn handle_update_median_oracle<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdateMedianOracle<'info>>,
+) -> Result<()> {
+    let clock_slot = Clock::get()?.slot;
+    let mut oracle = load_mut!(ctx.accounts.median_oracle)?;
+
+    let members_updated = oracle.update_members(ctx.remaining_accounts, clock_slot)?;
+
+    let price_data = oracle.get_price_data(clock_slot)?;
+    msg!(
+        "median oracle {} updated {} members, price = {} confidence = {}",
+        oracle.id,
+        members_updated,
+        price_data.price,
+        price_data.confidence
+    );
+
+    Ok(())
+}
+
+pub fn handle_update_pyth_lazer_oracle<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdatePythLazerOracle<'info>>,
+    pyth_message: Vec<u8>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    // The message must be verified by the ed25519 ix right before this one
+    let ix_sysvar = &ctx.accounts.ix_sysvar.to_account_info();
+    let ix_idx = load_current_index_checked(ix_sysvar)?;
+    validate!(
+        ix_idx > 0,
+        ErrorCode::InvalidVerificationIxIndex,
+        "instruction index must be greater than 0 for one sig verify"
+    )?;
+
+    let ix: Instruction = load_instruction_at_checked(ix_idx as usize - 1, ix_sysvar)?;
+    validate!(
+        ix.data.len() > 112,
+        ErrorCode::SigVerificationFailed,
+        "ed25519 ix data too short"
+    )?;
+    let signer = extract_ed25519_ix_pubkey(&ix.data)?;
+    let signature = extract_ed25519_ix_signature(&ix.data)?;
+    verify_ed25519_ix(&ix, &signer, &pyth_message, &signature)?;
+    let signer = Pubkey::new_from_array(signer);
+
+    let payload = PythLazerPayload::deserialize(&pyth_message)?;
+    validate!(
+        payload.feeds.len() == ctx.remaining_accounts.len(),
+        ErrorCode::InvalidPythLazerMessage,
+        "message has {} feeds but {} oracles passed",
+        payload.feeds.len(),
+        ctx.remaining_accounts.len()
+    )?;
+
+    for (feed, account_info) in payload.feeds.iter().zip(ctx.remaining_accounts.iter()) {
+        let oracle_loader: AccountLoader<PythLazerOracle> = AccountLoader::try_from(account_info)?;
+        let mut oracle = load_mut!(oracle_loader)?;
+
+        validate!(
+            oracle.signer == signer,
+            ErrorCode::SigVerificationFailed,
+            "pyth lazer oracle {} signer {} != message signer {}",
+            account_info.key(),
+            oracle.signer,
+            signer
+        )?;
+
+        oracle.update(feed, payload.timestamp, clock.slot, clock.unix_timestamp)?;
+
+        msg!(
+            "pyth lazer feed {} price = {} conf = {} exponent = {}",
+            oracle.feed_id,
+            oracle.price,
+            oracle.conf,
+            oracle.exponent
+        );
+    }
+
+    Ok(())
+}
+
+pub fn handle_halt_perp_market_trading(ctx: Context<HaltPerpMarketTrading>) -> Result<()> {
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    validate!(
+        perp_market.trading_halt_ts != 0 && now >= perp_market.trading_halt_ts,
+        ErrorCode::InvalidTradingHalt,
+        "perp market {} trading halt ts not reached",
+        perp_market.market_index
+    )?;
+
+    validate!(
+        matches!(
+            perp_market.status,
+            MarketStatus::Active | MarketStatus::ReduceOnly
+        ),
+        ErrorCode::InvalidTradingHalt,
+        "perp market {} status {:?} can not be halted",
+        perp_market.market_index,
+        perp_market.status
+    )?;
+
+    msg!(
+        "perp_market.status {:?} -> {:?}",
+        perp_market.status,
+        MarketStatus::TradingHalted
+    );
+
+    perp_market.status = MarketStatus::TradingHalted;
+
+    Ok(())
+}
+
+pub fn handle_finalize_market_resolution<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, FinalizeMarketResolution<'info>>,
+) -> Result<()> {
+    let market_resolution = &mut load_mut!(ctx.accounts.market_resolution)?;
+    let proposer = &mut load_mut!(ctx.accounts.proposer)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    market_resolution.finalize_undisputed(now)?;
+
+    let AccountMaps {
+        perp_market_map: _,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(market_resolution.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    // undisputed proposer gets their bond back
+    controller::market_resolution::pay_resolution_bond(
+        proposer,
+        &spot_market_map,
+        &mut oracle_map,
+        market_resolution.bond_spot_market_index,
+        market_resolution.bond_amount,
+        now,
+    )?;
+
+    emit!(MarketResolutionRecord {
+        ts: now,
+        action: MarketResolutionAction::Finalize,
+        market_index: market_resolution.market_index,
+        user: Some(market_resolution.proposer),
+        price: market_resolution.finalized_price,
+        bond_amount: market_resolution.bond_amount,
+    });
+
+    Ok(())
+}
+
 #[access_control(
     perp_market_valid(&ctx.accounts.perp_market)
     funding_not_paused(&ctx.accounts.state)
@@ -1837,6 +2198,13 @@ pub fn handle_update_perp_bid_ask_twap<'c: 'info, 'info>(
     let mut oracle_map =
         OracleMap::load_one(&ctx.accounts.oracle, slot, Some(state.oracle_guard_rails))?;
 
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    if perp_market.has_oracle_guard_rails_override() {
+        let oracle_guard_rails_override = remaining_accounts_iter.next().safe_unwrap()?;
+        oracle_map
+            .load_oracle_guard_rails_overrides(std::slice::from_ref(oracle_guard_rails_override))?;
+    }
+
     let keeper_stats = load!(ctx.accounts.keeper_stats)?;
     validate!(
         !keeper_stats.disable_update_perp_bid_ask_twap,
@@ -1856,7 +2224,6 @@ pub fn handle_update_perp_bid_ask_twap<'c: 'info, 'info>(
     let oracle_price_data = oracle_map.get_price_data(&perp_market.oracle_id())?;
     controller::repeg::_update_amm(perp_market, oracle_price_data, state, now, slot)?;
 
-    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
     let makers = load_user_map(remaining_accounts_iter, false)?;
 
     let depth = perp_market.get_market_depth_for_funding_rate()?;
@@ -2014,24 +2381,46 @@ pub fn handle_update_spot_market_cumulative_interest(
         clock_slot,
         Some(state.oracle_guard_rails),
     )?;
+    oracle_map.load_market_oracle_accounts(ctx.remaining_accounts)?;
+    oracle_map.validate_price_history(
+        MarketType::Spot,
+        spot_market.market_index,
+        spot_market.has_price_history(),
+    )?;
 
-    let oracle_price_data = oracle_map.get_price_data(&spot_market.oracle_id())?;
+    let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
+        MarketType::Spot,
+        spot_market.market_index,
+        &spot_market.oracle_id(),
+        spot_market.historical_oracle_data.last_oracle_price_twap,
+        spot_market.get_max_confidence_interval_multiplier()?,
+    )?;
+    let oracle_price_data = *oracle_price_data;
 
     if !state.funding_paused()? {
         controller::spot_balance::update_spot_market_cumulative_interest(
             spot_market,
-            Some(oracle_price_data),
+            Some(&oracle_price_data),
             now,
         )?;
     } else {
         // even if funding is paused still update twap stats
         controller::spot_balance::update_spot_market_twap_stats(
             spot_market,
-            Some(oracle_price_data),
+            Some(&oracle_price_data),
             now,
         )?;
     }
 
+    oracle_map.record_price_history(
+        MarketType::Spot,
+        spot_market.market_index,
+        now,
+        oracle_price_data.price,
+        oracle_validity,
+        0,
+    )?;
+
     math::spot_withdraw::validate_spot_market_vault_amount(
         spot_market,
         ctx.accounts.spot_market_vault.amount,
@@ -2459,6 +2848,32 @@ pub struct TriggerOrder<'info> {
     pub filler: AccountLoader<'info, User>,
     #[account(mut)]
     pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        seeds = [TRAILING_STOPS_PDA_SEED.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    /// CHECK: the user's trailing stops, uninitialized until the user places a trailing stop
+    pub user_trailing_stops: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseParentOrderSlice<'info> {
+    pub state: Box<Account<'info, State>>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&filler, &authority)?
+    )]
+    pub filler: AccountLoader<'info, User>,
+    #[account(mut)]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        seeds = [PARENT_ORDERS_PDA_SEED.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_parent_orders: AccountLoader<'info, UserParentOrders>,
 }
 
 #[derive(Accounts)]
@@ -2529,6 +2944,17 @@ pub struct SettlePNL<'info> {
     pub spot_market_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 }
 
+#[derive(Accounts)]
+pub struct SettleExpiredPositions<'info> {
+    pub state: Box<Account<'info, State>>,
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"spot_market_vault".as_ref(), 0_u16.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub spot_market_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
 #[derive(Accounts)]
 pub struct PlaceSwiftTakerOrder<'info> {
     pub state: Box<Account<'info, State>>,
@@ -2795,6 +3221,27 @@ pub struct UpdateFundingRate<'info> {
     pub oracle: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct HaltPerpMarketTrading<'info> {
+    pub state: Box<Account<'info, State>>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeMarketResolution<'info> {
+    pub state: Box<Account<'info, State>>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub market_resolution: AccountLoader<'info, MarketResolution>,
+    #[account(
+        mut,
+        constraint = proposer.key() == market_resolution.load()?.proposer
+    )]
+    pub proposer: AccountLoader<'info, User>,
+}
+
 #[derive(Accounts)]
 pub struct UpdatePerpBidAskTwap<'info> {
     pub state: Box<Account<'info, State>>,
@@ -2872,6 +3319,24 @@ pub struct UpdatePrelaunchOracle<'info> {
     pub oracle: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateMedianOracle<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(mut)]
+    pub median_oracle: AccountLoader<'info, MedianOracle>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePythLazerOracle<'info> {
+    pub keeper: Signer<'info>,
+    /// CHECK: The address check is needed because otherwise
+    /// the supplied Sysvar could be anything else.
+    /// The Instruction Sysvar has not been implemented
+    /// in the Anchor framework yet, so this is the safe approach.
+    #[account(address = IX_ID)]
+    pub ix_sysvar: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 pub struct DisableUserHighLeverageMode<'info> {
     pub state: Box<Account<'info, State>>,
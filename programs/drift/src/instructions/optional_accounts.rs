@@ -3,19 +3,24 @@ use std::cell::RefMut;
 use std::convert::TryFrom;
 
 use crate::error::ErrorCode::UnableToLoadOracle;
+use crate::math::oracle::DriftAction;
 use crate::math::safe_unwrap::SafeUnwrap;
 use crate::state::load_ref::load_ref_mut;
+use crate::state::market_resolution::MarketResolution;
 use crate::state::oracle::PrelaunchOracle;
 use crate::state::oracle_map::OracleMap;
+use crate::state::parent_order::UserParentOrders;
 use crate::state::perp_market::PerpMarket;
 use crate::state::perp_market_map::{MarketSet, PerpMarketMap};
+use crate::state::prediction_event::PredictionEvent;
 use crate::state::spot_market_map::SpotMarketMap;
 use crate::state::state::OracleGuardRails;
+use crate::state::trailing_stop::UserTrailingStops;
 use crate::state::traits::Size;
 use crate::state::user::{User, UserStats};
-use crate::{validate, OracleSource};
+use crate::{load, validate, OracleSource};
 use anchor_lang::accounts::account::Account;
-use anchor_lang::prelude::{AccountInfo, Interface};
+use anchor_lang::prelude::{AccountInfo, Interface, Pubkey};
 use anchor_lang::prelude::{AccountLoader, InterfaceAccount};
 use anchor_lang::Discriminator;
 use anchor_spl::token::TokenAccount;
@@ -40,7 +45,27 @@ pub fn load_maps<'a, 'b>(
     slot: u64,
     oracle_guard_rails: Option<OracleGuardRails>,
 ) -> DriftResult<AccountMaps<'a>> {
-    let oracle_map = OracleMap::load(account_info_iter, slot, oracle_guard_rails)?;
+    load_maps_for_action(
+        account_info_iter,
+        writable_perp_markets,
+        writable_spot_markets,
+        slot,
+        oracle_guard_rails,
+        None,
+    )
+}
+
+/// Like load_maps, but markets fail over to their fallback oracle when their primary oracle is
+/// invalid for the given action rather than whenever it isn't fully valid
+pub fn load_maps_for_action<'a, 'b>(
+    account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
+    writable_perp_markets: &'b MarketSet,
+    writable_spot_markets: &'b MarketSet,
+    slot: u64,
+    oracle_guard_rails: Option<OracleGuardRails>,
+    action: Option<DriftAction>,
+) -> DriftResult<AccountMaps<'a>> {
+    let mut oracle_map = OracleMap::load(account_info_iter, slot, oracle_guard_rails)?;
     let spot_market_map = SpotMarketMap::load(writable_spot_markets, account_info_iter)?;
     let perp_market_map = PerpMarketMap::load(writable_perp_markets, account_info_iter)?;
 
@@ -52,6 +77,9 @@ pub fn load_maps<'a, 'b>(
         )?;
     }
 
+    oracle_map.validate_market_oracle_accounts(&perp_market_map, &spot_market_map)?;
+    oracle_map.update_fallback_oracles(&perp_market_map, &spot_market_map, action)?;
+
     Ok(AccountMaps {
         perp_market_map,
         spot_market_map,
@@ -78,6 +106,53 @@ pub fn update_prelaunch_oracle(
     Ok(())
 }
 
+pub fn get_prediction_event<'a>(
+    account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
+    perp_market: &PerpMarket,
+) -> DriftResult<AccountLoader<'a, PredictionEvent>> {
+    let prediction_event_account_info =
+        next_account_info(account_info_iter).or(Err(ErrorCode::InvalidPredictionEvent))?;
+
+    let prediction_event: AccountLoader<PredictionEvent> =
+        AccountLoader::try_from(prediction_event_account_info)
+            .or(Err(ErrorCode::InvalidPredictionEvent))?;
+
+    let event_index = load!(prediction_event)?.event_index;
+    validate!(
+        event_index == perp_market.prediction_event_index,
+        ErrorCode::InvalidPredictionEvent,
+        "perp market {} is an outcome of prediction event {} not {}",
+        perp_market.market_index,
+        perp_market.prediction_event_index,
+        event_index
+    )?;
+
+    Ok(prediction_event)
+}
+
+pub fn get_market_resolution<'a>(
+    account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
+    perp_market: &PerpMarket,
+) -> DriftResult<AccountLoader<'a, MarketResolution>> {
+    let market_resolution_account_info =
+        next_account_info(account_info_iter).or(Err(ErrorCode::InvalidMarketResolution))?;
+
+    let market_resolution: AccountLoader<MarketResolution> =
+        AccountLoader::try_from(market_resolution_account_info)
+            .or(Err(ErrorCode::InvalidMarketResolution))?;
+
+    let market_index = load!(market_resolution)?.market_index;
+    validate!(
+        market_index == perp_market.market_index,
+        ErrorCode::InvalidMarketResolution,
+        "market resolution is for perp market {} not {}",
+        market_index,
+        perp_market.market_index
+    )?;
+
+    Ok(market_resolution)
+}
+
 pub fn get_maker_and_maker_stats<'a>(
     account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
 ) -> DriftResult<(AccountLoader<'a, User>, AccountLoader<'a, UserStats>)> {
@@ -181,6 +256,110 @@ pub fn get_referrer_and_referrer_stats<'a>(
     Ok((Some(referrer), Some(referrer_stats)))
 }
 
+pub fn get_user_parent_orders<'a>(
+    account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
+    user_key: &Pubkey,
+) -> DriftResult<Option<AccountLoader<'a, UserParentOrders>>> {
+    let user_parent_orders_account_info = account_info_iter.peek();
+
+    if user_parent_orders_account_info.is_none() {
+        return Ok(None);
+    }
+
+    let user_parent_orders_account_info = user_parent_orders_account_info.safe_unwrap()?;
+    let data = user_parent_orders_account_info
+        .try_borrow_data()
+        .map_err(|e| {
+            msg!("{:?}", e);
+            ErrorCode::InvalidParentOrder
+        })?;
+
+    if data.len() < UserParentOrders::SIZE {
+        return Ok(None);
+    }
+
+    let user_parent_orders_discriminator: [u8; 8] = UserParentOrders::discriminator();
+    let account_discriminator = array_ref![data, 0, 8];
+    if account_discriminator != &user_parent_orders_discriminator {
+        return Ok(None);
+    }
+
+    let user_parent_orders_account_info = next_account_info(account_info_iter).safe_unwrap()?;
+
+    validate!(
+        user_parent_orders_account_info.is_writable,
+        ErrorCode::InvalidParentOrder,
+        "user parent orders must be writable"
+    )?;
+
+    let user_parent_orders: AccountLoader<UserParentOrders> =
+        AccountLoader::try_from(user_parent_orders_account_info)
+            .or(Err(ErrorCode::InvalidParentOrder))?;
+
+    let user_pubkey = load!(user_parent_orders)?.user_pubkey;
+    validate!(
+        user_pubkey == *user_key,
+        ErrorCode::InvalidParentOrder,
+        "parent orders are for user {} not {}",
+        user_pubkey,
+        user_key
+    )?;
+
+    Ok(Some(user_parent_orders))
+}
+
+pub fn get_user_trailing_stops<'a>(
+    account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
+    user_key: &Pubkey,
+) -> DriftResult<Option<AccountLoader<'a, UserTrailingStops>>> {
+    let user_trailing_stops_account_info = account_info_iter.peek();
+
+    if user_trailing_stops_account_info.is_none() {
+        return Ok(None);
+    }
+
+    let user_trailing_stops_account_info = user_trailing_stops_account_info.safe_unwrap()?;
+    let data = user_trailing_stops_account_info
+        .try_borrow_data()
+        .map_err(|e| {
+            msg!("{:?}", e);
+            ErrorCode::InvalidTrailingStopOrder
+        })?;
+
+    if data.len() < UserTrailingStops::SIZE {
+        return Ok(None);
+    }
+
+    let user_trailing_stops_discriminator: [u8; 8] = UserTrailingStops::discriminator();
+    let account_discriminator = array_ref![data, 0, 8];
+    if account_discriminator != &user_trailing_stops_discriminator {
+        return Ok(None);
+    }
+
+    let user_trailing_stops_account_info = next_account_info(account_info_iter).safe_unwrap()?;
+
+    validate!(
+        user_trailing_stops_account_info.is_writable,
+        ErrorCode::InvalidTrailingStopOrder,
+        "user trailing stops must be writable"
+    )?;
+
+    let user_trailing_stops: AccountLoader<UserTrailingStops> =
+        AccountLoader::try_from(user_trailing_stops_account_info)
+            .or(Err(ErrorCode::InvalidTrailingStopOrder))?;
+
+    let user_pubkey = load!(user_trailing_stops)?.user_pubkey;
+    validate!(
+        user_pubkey == *user_key,
+        ErrorCode::InvalidTrailingStopOrder,
+        "trailing stops are for user {} not {}",
+        user_pubkey,
+        user_key
+    )?;
+
+    Ok(Some(user_trailing_stops))
+}
+
 pub fn get_whitelist_token<'a>(
     account_info_iter: &mut Peekable<Iter<'a, AccountInfo<'a>>>,
 ) -> DriftResult<Account<'a, TokenAccount>> {
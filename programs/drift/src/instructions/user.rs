@@ -3,7 +3,7 @@ use anchor_lang::Discriminator;
 use anchor_spl::{
     token::Token,
     token_2022::Token2022,
-    token_interface::{TokenAccount, TokenInterface},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 use solana_program::instruction::Instruction;
 use solana_program::program::invoke;
@@ -12,6 +12,7 @@ use solana_program::system_instruction::transfer;
 use crate::controller::orders::place_and_match_rfq_orders;
 use crate::controller::orders::{cancel_orders, ModifyOrderId};
 use crate::controller::position::PositionDirection;
+use crate::controller::prediction_shares::get_prediction_share_mint_seed;
 use crate::controller::spot_balance::update_revenue_pool_balances;
 use crate::controller::spot_position::{
     update_spot_balances_and_cumulative_deposits,
@@ -27,6 +28,10 @@ use crate::instructions::optional_accounts::{
 };
 use crate::instructions::SpotFulfillmentType;
 use crate::math::casting::Cast;
+use crate::math::constants::{
+    MAX_CONCENTRATION_COEFFICIENT, MAX_PREDICTION_MARKET_PRICE_I64,
+    MAX_PREDICTION_MARKET_PRICE_U128, ONE_HOUR, SPOT_WEIGHT_PRECISION,
+};
 use crate::math::liquidation::is_user_being_liquidated;
 use crate::math::margin::meets_initial_margin_requirement;
 use crate::math::margin::{
@@ -35,7 +40,7 @@ use crate::math::margin::{
     validate_spot_margin_trading, MarginRequirementType,
 };
 use crate::math::safe_math::SafeMath;
-use crate::math::spot_balance::get_token_value;
+use crate::math::spot_balance::{get_token_amount, get_token_value};
 use crate::math::spot_swap;
 use crate::math::spot_swap::{calculate_swap_price, validate_price_bands_for_swap};
 use crate::math_error;
@@ -44,8 +49,10 @@ use crate::print_error;
 use crate::safe_decrement;
 use crate::safe_increment;
 use crate::state::events::{
-    DepositDirection, DepositExplanation, DepositRecord, LPAction, LPRecord, NewUserRecord,
-    OrderActionExplanation, SwapRecord,
+    CompleteSetAction, CompleteSetRecord, DepositDirection, DepositExplanation, DepositRecord,
+    LPAction, LPRecord, MarketResolutionAction, MarketResolutionRecord, NewUserRecord,
+    OrderActionExplanation, PredictionMarketCreatorAction, PredictionMarketCreatorRecord,
+    PredictionShareAction, PredictionShareRecord, SwapRecord,
 };
 use crate::state::fill_mode::FillMode;
 use crate::state::fulfillment_params::drift::MatchFulfillmentParams;
@@ -53,21 +60,33 @@ use crate::state::fulfillment_params::openbook_v2::OpenbookV2FulfillmentParams;
 use crate::state::fulfillment_params::phoenix::PhoenixFulfillmentParams;
 use crate::state::fulfillment_params::serum::SerumFulfillmentParams;
 use crate::state::high_leverage_mode_config::HighLeverageModeConfig;
-use crate::state::oracle::StrictOraclePrice;
+use crate::state::market_resolution::MarketResolution;
+use crate::state::oracle::{
+    HistoricalOracleData, OracleSource, PrelaunchOracle, StrictOraclePrice,
+};
 use crate::state::order_params::RFQMatch;
 use crate::state::order_params::{
-    parse_optional_params, ModifyOrderParams, OrderParams, PlaceAndTakeOrderSuccessCondition,
-    PlaceOrderOptions, PostOnlyParam,
+    parse_optional_params, ModifyOrderParams, OrderLinkType, OrderParams, ParentOrderParams,
+    PlaceAndTakeOrderSuccessCondition, PlaceOrderOptions, PostOnlyParam, TrailingStopDistance,
 };
+use crate::state::parent_order::{UserParentOrders, PARENT_ORDERS_PDA_SEED};
 use crate::state::paused_operations::{PerpOperation, SpotOperation};
 use crate::state::perp_market::ContractType;
 use crate::state::perp_market::MarketStatus;
-use crate::state::perp_market_map::{get_writable_perp_market_set, MarketSet};
+use crate::state::perp_market::{PerpMarket, AMM};
+use crate::state::perp_market_map::{
+    get_writable_perp_market_set, get_writable_perp_market_set_from_vec, MarketSet,
+};
+use crate::state::prediction_event::PredictionEvent;
+use crate::state::prediction_market_creator::{
+    PredictionMarketCreationConfig, PredictionMarketCreator, PredictionMarketCreatorStatus,
+    PREDICTION_MARKET_CREATION_CONFIG_SEED, PREDICTION_MARKET_CREATOR_SEED,
+};
 use crate::state::protected_maker_mode_config::ProtectedMakerModeConfig;
 use crate::state::rfq_user::{load_rfq_user_account_map, RFQUser, RFQ_PDA_SEED};
 use crate::state::spot_fulfillment_params::SpotFulfillmentParams;
-use crate::state::spot_market::SpotBalanceType;
 use crate::state::spot_market::SpotMarket;
+use crate::state::spot_market::{SpotBalance, SpotBalanceType};
 use crate::state::spot_market_map::{
     get_writable_spot_market_set, get_writable_spot_market_set_from_many,
 };
@@ -75,11 +94,16 @@ use crate::state::state::State;
 use crate::state::swift_user::SwiftOrderId;
 use crate::state::swift_user::SwiftUserOrdersLoader;
 use crate::state::swift_user::{SwiftUserOrders, SWIFT_PDA_SEED};
+use crate::state::trailing_stop::{UserTrailingStops, TRAILING_STOPS_PDA_SEED};
 use crate::state::traits::Size;
 use crate::state::user::ReferrerStatus;
-use crate::state::user::{MarginMode, MarketType, OrderType, ReferrerName, User, UserStats};
+use crate::state::user::{
+    MarginMode, MarketType, OrderTriggerSource, OrderType, ReferrerName, SelfTradePreventionMode,
+    User, UserStats,
+};
 use crate::state::user_map::{load_user_maps, UserMap, UserStatsMap};
 use crate::validate;
+use crate::validation::perp_market::validate_perp_market;
 use crate::validation::sig_verification::verify_ed25519_ix;
 use crate::validation::user::validate_user_deletion;
 use crate::validation::whitelist::validate_whitelist_token;
@@ -288,6 +312,30 @@ pub fn handle_initialize_rfq_user<'c: 'info, 'info>(
     Ok(())
 }
 
+pub fn handle_initialize_user_trailing_stops<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, InitializeUserTrailingStops<'info>>,
+) -> Result<()> {
+    let mut user_trailing_stops = ctx
+        .accounts
+        .user_trailing_stops
+        .load_init()
+        .or(Err(ErrorCode::UnableToLoadAccountLoader))?;
+    user_trailing_stops.user_pubkey = ctx.accounts.user.key();
+    Ok(())
+}
+
+pub fn handle_initialize_user_parent_orders<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, InitializeUserParentOrders<'info>>,
+) -> Result<()> {
+    let mut user_parent_orders = ctx
+        .accounts
+        .user_parent_orders
+        .load_init()
+        .or(Err(ErrorCode::UnableToLoadAccountLoader))?;
+    user_parent_orders.user_pubkey = ctx.accounts.user.key();
+    Ok(())
+}
+
 pub fn handle_initialize_swift_user_orders<'c: 'info, 'info>(
     ctx: Context<'_, '_, 'c, 'info, InitializeSwiftUserOrders<'info>>,
     num_orders: u16,
@@ -932,6 +980,175 @@ pub fn handle_place_perp_order<'c: 'info, 'info>(
     Ok(())
 }
 
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_place_perp_order_with_trigger_market<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
+    params: OrderParams,
+    trigger_source: OrderTriggerSource,
+    trigger_market_index: u16,
+) -> Result<()> {
+    let clock = &Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    if params.immediate_or_cancel {
+        msg!("immediate_or_cancel order must be in place_and_make or place_and_take");
+        return Err(print_error!(ErrorCode::InvalidOrderIOC)().into());
+    }
+
+    let user_key = ctx.accounts.user.key();
+    let mut user = load_mut!(ctx.accounts.user)?;
+
+    controller::orders::place_perp_order(
+        &ctx.accounts.state,
+        &mut user,
+        user_key,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        clock,
+        params,
+        PlaceOrderOptions::default().trigger_market(trigger_source, trigger_market_index),
+    )?;
+
+    Ok(())
+}
+
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_place_perp_order_with_trailing_stop<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PlaceTrailingStopOrder<'info>>,
+    params: OrderParams,
+    trailing_stop_distance: TrailingStopDistance,
+) -> Result<()> {
+    let clock = &Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    if params.immediate_or_cancel {
+        msg!("immediate_or_cancel order must be in place_and_make or place_and_take");
+        return Err(print_error!(ErrorCode::InvalidOrderIOC)().into());
+    }
+
+    let user_key = ctx.accounts.user.key();
+    let mut user = load_mut!(ctx.accounts.user)?;
+    let mut user_trailing_stops = load_mut!(ctx.accounts.user_trailing_stops)?;
+
+    controller::orders::place_perp_order_with_trailing_stop(
+        &ctx.accounts.state,
+        &mut user,
+        user_key,
+        &mut user_trailing_stops,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        clock,
+        params,
+        trailing_stop_distance,
+    )?;
+
+    Ok(())
+}
+
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_place_parent_order<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PlaceParentOrder<'info>>,
+    params: ParentOrderParams,
+) -> Result<()> {
+    let clock = &Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let AccountMaps {
+        perp_market_map, ..
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let mut user = load_mut!(ctx.accounts.user)?;
+    let mut user_parent_orders = load_mut!(ctx.accounts.user_parent_orders)?;
+
+    controller::orders::place_parent_order(
+        &mut user,
+        &mut user_parent_orders,
+        &perp_market_map,
+        params,
+    )?;
+
+    Ok(())
+}
+
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_cancel_parent_order<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CancelParentOrder<'info>>,
+    parent_order_id: u32,
+) -> Result<()> {
+    let clock = &Clock::get()?;
+    let state = &ctx.accounts.state;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let user_key = ctx.accounts.user.key();
+    let mut user = load_mut!(ctx.accounts.user)?;
+    let mut user_parent_orders = load_mut!(ctx.accounts.user_parent_orders)?;
+
+    controller::orders::cancel_parent_order(
+        parent_order_id,
+        &mut user,
+        &user_key,
+        &mut user_parent_orders,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        clock.unix_timestamp,
+        clock.slot,
+    )?;
+
+    Ok(())
+}
+
 pub fn handle_place_and_match_rfq_orders<'c: 'info, 'info>(
     ctx: Context<'_, '_, 'c, 'info, PlaceAndMatchRFQOrders<'info>>,
     rfq_matches: Vec<RFQMatch>,
@@ -1240,6 +1457,25 @@ pub fn handle_modify_order_by_user_order_id<'c: 'info, 'info>(
 pub fn handle_place_orders<'c: 'info, 'info>(
     ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
     params: Vec<OrderParams>,
+) -> Result<()> {
+    place_orders(ctx, params, None)
+}
+
+#[access_control(
+    exchange_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_place_linked_orders<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
+    params: Vec<OrderParams>,
+    order_link_type: OrderLinkType,
+) -> Result<()> {
+    place_orders(ctx, params, Some(order_link_type))
+}
+
+fn place_orders<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
+    params: Vec<OrderParams>,
+    order_link_type: Option<OrderLinkType>,
 ) -> Result<()> {
     let clock = &Clock::get()?;
     let state = &ctx.accounts.state;
@@ -1265,6 +1501,12 @@ pub fn handle_place_orders<'c: 'info, 'info>(
     let user_key = ctx.accounts.user.key();
     let mut user = load_mut!(ctx.accounts.user)?;
 
+    if order_link_type == Some(OrderLinkType::OneCancelsOther) {
+        // leave an unused order id between the group and any earlier orders
+        get_then_update_id!(user, next_order_id);
+    }
+
+    let first_order_id = user.next_order_id;
     let num_orders = params.len();
     for (i, params) in params.iter().enumerate() {
         validate!(
@@ -1310,6 +1552,22 @@ pub fn handle_place_orders<'c: 'info, 'info>(
         }
     }
 
+    if let Some(order_link_type) = order_link_type {
+        // the entry order of a bracket is placed first and isn't part of the group
+        let first_linked_order_id = match order_link_type {
+            OrderLinkType::OneCancelsOther => first_order_id,
+            OrderLinkType::Bracket => first_order_id.safe_add(1)?,
+        };
+        let last_linked_order_id = first_order_id.safe_add(num_orders.cast()?)?.safe_sub(1)?;
+
+        controller::orders::link_orders(
+            &mut user,
+            first_linked_order_id,
+            last_linked_order_id,
+            order_link_type,
+        )?;
+    }
+
     Ok(())
 }
 
@@ -2015,6 +2273,12 @@ pub fn handle_add_perp_lp_shares<'c: 'info, 'info>(
             "Market Status doesn't allow for new LP liquidity"
         )?;
 
+        validate!(
+            !market.is_trading_halted(now),
+            ErrorCode::MarketStatusInvalidForNewLP,
+            "Market trading is halted"
+        )?;
+
         validate!(
             !matches!(market.contract_type, ContractType::Prediction),
             ErrorCode::MarketStatusInvalidForNewLP,
@@ -2169,61 +2433,1107 @@ pub fn handle_remove_perp_lp_shares<'c: 'info, 'info>(
     Ok(())
 }
 
-pub fn handle_update_user_name(
-    ctx: Context<UpdateUser>,
-    _sub_account_id: u16,
-    name: [u8; 32],
-) -> Result<()> {
-    let mut user = load_mut!(ctx.accounts.user)?;
-    user.name = name;
-    Ok(())
-}
-
-pub fn handle_update_user_custom_margin_ratio(
-    ctx: Context<UpdateUser>,
-    _sub_account_id: u16,
-    margin_ratio: u32,
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_mint_complete_set<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CompleteSet<'info>>,
+    base_asset_amount: u64,
 ) -> Result<()> {
-    let mut user = load_mut!(ctx.accounts.user)?;
-    user.max_margin_ratio = margin_ratio;
-    Ok(())
-}
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
 
-pub fn handle_update_user_margin_trading_enabled<'c: 'info, 'info>(
-    ctx: Context<'_, '_, 'c, 'info, UpdateUser<'info>>,
-    _sub_account_id: u16,
-    margin_trading_enabled: bool,
-) -> Result<()> {
-    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
     let AccountMaps {
         perp_market_map,
         spot_market_map,
         mut oracle_map,
-        ..
     } = load_maps(
-        remaining_accounts_iter,
-        &MarketSet::new(),
-        &MarketSet::new(),
-        Clock::get()?.slot,
-        None,
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set_from_vec(prediction_event.outcome_market_indexes()),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
     )?;
 
-    let mut user = load_mut!(ctx.accounts.user)?;
-    user.is_margin_trading_enabled = margin_trading_enabled;
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+    math::liquidation::validate_user_not_being_liquidated(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        state.liquidation_margin_buffer_ratio,
+    )?;
 
-    validate_spot_margin_trading(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(|_| ErrorCode::MarginOrdersOpen)?;
+    let outcome_prices = controller::complete_set::get_outcome_prices(
+        prediction_event,
+        &perp_market_map,
+        &mut oracle_map,
+    )?;
 
-    Ok(())
-}
+    let quote_asset_amount = {
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
 
-pub fn handle_update_user_pool_id<'c: 'info, 'info>(
-    ctx: Context<'_, '_, 'c, 'info, UpdateUser<'info>>,
-    _sub_account_id: u16,
-    pool_id: u8,
-) -> Result<()> {
-    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
-    let AccountMaps {
+        controller::complete_set::mint_complete_set(
+            user,
+            &user_key,
+            prediction_event,
+            &perp_market_map,
+            quote_spot_market,
+            &outcome_prices,
+            base_asset_amount,
+            now,
+        )?
+    };
+
+    meets_place_order_margin_requirement(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        true,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(CompleteSetRecord {
+        ts: now,
+        user: user_key,
+        action: CompleteSetAction::Mint,
+        prediction_event_index: prediction_event.event_index,
+        market_index: None,
+        base_asset_amount,
+        quote_asset_amount,
+        pnl: 0,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_redeem_complete_set<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CompleteSet<'info>>,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let prediction_event = &mut load_mut!(ctx.accounts.prediction_event)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map: _,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set_from_vec(prediction_event.outcome_market_indexes()),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+
+    let (quote_asset_amount, pnl) = {
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
+
+        controller::complete_set::redeem_complete_set(
+            user,
+            &user_key,
+            prediction_event,
+            &perp_market_map,
+            quote_spot_market,
+            base_asset_amount,
+            now,
+        )?
+    };
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(CompleteSetRecord {
+        ts: now,
+        user: user_key,
+        action: CompleteSetAction::Redeem,
+        prediction_event_index: prediction_event.event_index,
+        market_index: None,
+        base_asset_amount,
+        quote_asset_amount,
+        pnl,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_mint_binary_complete_set<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, BinaryCompleteSet<'info>>,
+    market_index: u16,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let long_user_key = ctx.accounts.user.key();
+    let short_user_key = ctx.accounts.short_user.key();
+    let long_user = &mut load_mut!(ctx.accounts.user)?;
+    let short_user = &mut load_mut!(ctx.accounts.short_user)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate_binary_complete_set_users(&long_user_key, long_user, &short_user_key, short_user)?;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    for user in [&mut **long_user, &mut **short_user] {
+        math::liquidation::validate_user_not_being_liquidated(
+            user,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            state.liquidation_margin_buffer_ratio,
+        )?;
+    }
+
+    let quote_asset_amount = {
+        let perp_market = &mut perp_market_map.get_ref_mut(&market_index)?;
+        let oracle_price = oracle_map.get_price_data(&perp_market.oracle_id())?.price;
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
+
+        controller::complete_set::mint_binary_complete_set(
+            long_user,
+            &long_user_key,
+            short_user,
+            &short_user_key,
+            perp_market,
+            quote_spot_market,
+            oracle_price,
+            base_asset_amount,
+            now,
+        )?
+    };
+
+    for user in [&mut **long_user, &mut **short_user] {
+        meets_place_order_margin_requirement(
+            user,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            true,
+        )?;
+
+        user.update_last_active_slot(clock.slot);
+    }
+
+    for user_key in [long_user_key, short_user_key] {
+        emit!(CompleteSetRecord {
+            ts: now,
+            user: user_key,
+            action: CompleteSetAction::Mint,
+            prediction_event_index: 0,
+            market_index: Some(market_index),
+            base_asset_amount,
+            quote_asset_amount,
+            pnl: 0,
+        });
+    }
+
+    Ok(())
+}
+
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_redeem_binary_complete_set<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, BinaryCompleteSet<'info>>,
+    market_index: u16,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let long_user_key = ctx.accounts.user.key();
+    let short_user_key = ctx.accounts.short_user.key();
+    let long_user = &mut load_mut!(ctx.accounts.user)?;
+    let short_user = &mut load_mut!(ctx.accounts.short_user)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate_binary_complete_set_users(&long_user_key, long_user, &short_user_key, short_user)?;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let (quote_asset_amount, long_pnl, short_pnl) = {
+        let perp_market = &mut perp_market_map.get_ref_mut(&market_index)?;
+        let oracle_price = oracle_map.get_price_data(&perp_market.oracle_id())?.price;
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
+
+        controller::complete_set::redeem_binary_complete_set(
+            long_user,
+            &long_user_key,
+            short_user,
+            &short_user_key,
+            perp_market,
+            quote_spot_market,
+            oracle_price,
+            base_asset_amount,
+            now,
+        )?
+    };
+
+    long_user.update_last_active_slot(clock.slot);
+    short_user.update_last_active_slot(clock.slot);
+
+    for (user_key, pnl) in [(long_user_key, long_pnl), (short_user_key, short_pnl)] {
+        emit!(CompleteSetRecord {
+            ts: now,
+            user: user_key,
+            action: CompleteSetAction::Redeem,
+            prediction_event_index: 0,
+            market_index: Some(market_index),
+            base_asset_amount,
+            quote_asset_amount,
+            pnl,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_binary_complete_set_users(
+    long_user_key: &Pubkey,
+    long_user: &User,
+    short_user_key: &Pubkey,
+    short_user: &User,
+) -> Result<()> {
+    validate!(
+        long_user_key != short_user_key,
+        ErrorCode::InvalidCompleteSet,
+        "binary complete set needs separate long and short users"
+    )?;
+
+    validate!(
+        long_user.authority == short_user.authority,
+        ErrorCode::InvalidCompleteSet,
+        "binary complete set users must have the same authority"
+    )?;
+
+    validate!(
+        !long_user.is_bankrupt() && !short_user.is_bankrupt(),
+        ErrorCode::UserBankrupt
+    )?;
+
+    Ok(())
+}
+
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_wrap_prediction_shares<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+    market_index: u16,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+    math::liquidation::validate_user_not_being_liquidated(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        state.liquidation_margin_buffer_ratio,
+    )?;
+
+    let (pnl, quote_asset_amount) = {
+        let perp_market = &mut perp_market_map.get_ref_mut(&market_index)?;
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
+
+        let pnl = controller::prediction_shares::wrap_prediction_shares(
+            user,
+            &user_key,
+            perp_market,
+            quote_spot_market,
+            direction,
+            base_asset_amount,
+            now,
+        )?;
+
+        // every share in circulation must still be counted in the market's open interest
+        let tokenized_base_asset_amount = ctx
+            .accounts
+            .prediction_share_mint
+            .supply
+            .safe_add(base_asset_amount)?;
+        let market_base_asset_amount = match direction {
+            PositionDirection::Long => perp_market.amm.base_asset_amount_long,
+            PositionDirection::Short => perp_market.amm.base_asset_amount_short,
+        }
+        .unsigned_abs();
+
+        validate!(
+            tokenized_base_asset_amount.cast::<u128>()? <= market_base_asset_amount,
+            ErrorCode::InvalidPredictionShares,
+            "share supply {} exceeds market base asset amount {}",
+            tokenized_base_asset_amount,
+            market_base_asset_amount
+        )?;
+
+        let quote_asset_amount =
+            controller::prediction_shares::calculate_prediction_share_backing_amount(
+                perp_market,
+                direction,
+                base_asset_amount,
+            )?;
+
+        (pnl, quote_asset_amount)
+    };
+
+    meets_place_order_margin_requirement(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        false,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    controller::token::mint_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.prediction_share_mint,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.drift_signer,
+        state.signer_nonce,
+        base_asset_amount,
+    )?;
+
+    emit!(PredictionShareRecord {
+        ts: now,
+        user: user_key,
+        action: PredictionShareAction::Wrap,
+        market_index,
+        direction,
+        base_asset_amount,
+        quote_asset_amount,
+        pnl,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    fill_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_unwrap_prediction_shares<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+    market_index: u16,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set(market_index),
+        &MarketSet::new(),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+    math::liquidation::validate_user_not_being_liquidated(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        state.liquidation_margin_buffer_ratio,
+    )?;
+
+    controller::token::burn_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.prediction_share_mint,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.authority,
+        base_asset_amount,
+    )?;
+
+    let pnl = controller::prediction_shares::unwrap_prediction_shares(
+        user,
+        &user_key,
+        &mut perp_market_map.get_ref_mut(&market_index)?,
+        direction,
+        base_asset_amount,
+        now,
+    )?;
+
+    meets_place_order_margin_requirement(
+        user,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        true,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    let quote_asset_amount =
+        controller::prediction_shares::calculate_prediction_share_backing_amount(
+            &perp_market_map.get_ref(&market_index)?,
+            direction,
+            base_asset_amount,
+        )?;
+
+    emit!(PredictionShareRecord {
+        ts: now,
+        user: user_key,
+        action: PredictionShareAction::Unwrap,
+        market_index,
+        direction,
+        base_asset_amount,
+        quote_asset_amount,
+        pnl,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    settle_pnl_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_redeem_prediction_shares<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+    market_index: u16,
+    direction: PositionDirection,
+    base_asset_amount: u64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        oracle_map: _,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &get_writable_perp_market_set(market_index),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+
+    let (base_asset_amount, quote_asset_amount) = {
+        let perp_market = &mut perp_market_map.get_ref_mut(&market_index)?;
+        let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+        controller::spot_balance::update_spot_market_cumulative_interest(
+            quote_spot_market,
+            None,
+            now,
+        )?;
+
+        controller::prediction_shares::redeem_prediction_shares(
+            user,
+            perp_market,
+            quote_spot_market,
+            direction,
+            base_asset_amount,
+        )?
+    };
+
+    // only burn the shares the pnl pool paid for, the rest can be redeemed later
+    controller::token::burn_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.prediction_share_mint,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.authority,
+        base_asset_amount,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(PredictionShareRecord {
+        ts: now,
+        user: user_key,
+        action: PredictionShareAction::Redeem,
+        market_index,
+        direction,
+        base_asset_amount,
+        quote_asset_amount,
+        pnl: quote_asset_amount.cast()?,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    withdraw_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_create_prediction_market<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CreatePredictionMarket<'info>>,
+    name: [u8; 32],
+    amm_peg_multiplier: u128,
+    expiry_ts: i64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let user_stats = &mut load_mut!(ctx.accounts.user_stats)?;
+    let config = load!(ctx.accounts.prediction_market_creation_config)?;
+    let perp_market_pubkey = ctx.accounts.perp_market.key();
+    let perp_market = &mut ctx.accounts.perp_market.load_init()?;
+    let creator = &mut ctx.accounts.prediction_market_creator.load_init()?;
+    let state = &mut ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate!(
+        !config.is_paused(),
+        ErrorCode::PredictionMarketCreationPaused
+    )?;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+
+    validate!(
+        expiry_ts > now,
+        ErrorCode::DefaultError,
+        "Market expiry ts must later than current clock timestamp"
+    )?;
+
+    // initial price is the market's implied probability, the prelaunch oracle starts at it
+    validate!(
+        amm_peg_multiplier > 0 && amm_peg_multiplier < MAX_PREDICTION_MARKET_PRICE_U128,
+        ErrorCode::InvalidPredictionMarketPriceBounds,
+        "initial price {} must be inside (0, {})",
+        amm_peg_multiplier,
+        MAX_PREDICTION_MARKET_PRICE_U128
+    )?;
+
+    let market_index = state.number_of_markets;
+    msg!("creating prediction market {}", market_index);
+
+    let amm_reserve = config.amm_base_asset_reserve;
+    let init_reserve_price =
+        math::amm::calculate_price(amm_reserve, amm_reserve, amm_peg_multiplier)?;
+    let concentration_coef = MAX_CONCENTRATION_COEFFICIENT;
+    let (min_base_asset_reserve, max_base_asset_reserve) =
+        math::amm::calculate_bid_ask_bounds(concentration_coef, amm_reserve)?;
+
+    // created markets are priced by a prelaunch oracle that tracks the market's own mark price
+    let oracle_price = {
+        let prelaunch_oracle = &mut ctx.accounts.prelaunch_oracle.load_init()?;
+        prelaunch_oracle.perp_market_index = market_index;
+        prelaunch_oracle.price = amm_peg_multiplier.cast()?;
+        prelaunch_oracle.max_price = MAX_PREDICTION_MARKET_PRICE_I64;
+        prelaunch_oracle.last_update_slot = clock.slot;
+        prelaunch_oracle.validate()?;
+        prelaunch_oracle.price
+    };
+
+    **perp_market = PerpMarket {
+        contract_type: ContractType::Prediction,
+        contract_tier: config.contract_tier,
+        status: MarketStatus::Active,
+        name,
+        expiry_ts,
+        pubkey: perp_market_pubkey,
+        market_index,
+        margin_ratio_initial: config.margin_ratio_initial,
+        margin_ratio_maintenance: config.margin_ratio_maintenance,
+        next_fill_record_id: 1,
+        next_funding_rate_record_id: 1,
+        next_curve_record_id: 1,
+        unrealized_pnl_maintenance_asset_weight: SPOT_WEIGHT_PRECISION.cast()?,
+        liquidator_fee: config.liquidator_fee,
+        paused_operations: PerpOperation::UpdateFunding as u8,
+        quote_spot_market_index: QUOTE_SPOT_MARKET_INDEX,
+        amm: AMM {
+            oracle: ctx.accounts.prelaunch_oracle.key(),
+            oracle_source: OracleSource::Prelaunch,
+            base_asset_reserve: amm_reserve,
+            quote_asset_reserve: amm_reserve,
+            terminal_quote_asset_reserve: amm_reserve,
+            ask_base_asset_reserve: amm_reserve,
+            ask_quote_asset_reserve: amm_reserve,
+            bid_base_asset_reserve: amm_reserve,
+            bid_quote_asset_reserve: amm_reserve,
+            last_funding_rate_ts: now,
+            funding_period: ONE_HOUR,
+            last_mark_price_twap: init_reserve_price,
+            last_mark_price_twap_5min: init_reserve_price,
+            last_mark_price_twap_ts: now,
+            sqrt_k: amm_reserve,
+            concentration_coef,
+            min_base_asset_reserve,
+            max_base_asset_reserve,
+            peg_multiplier: amm_peg_multiplier,
+            historical_oracle_data: HistoricalOracleData {
+                last_oracle_price: oracle_price,
+                last_oracle_price_twap: oracle_price,
+                last_oracle_price_twap_5min: oracle_price,
+                last_oracle_price_twap_ts: now,
+                ..HistoricalOracleData::default()
+            },
+            last_oracle_normalised_price: oracle_price,
+            order_step_size: config.order_step_size,
+            order_tick_size: config.order_tick_size,
+            min_order_size: config.min_order_size,
+            max_slippage_ratio: 50,         // ~2%
+            max_fill_reserve_fraction: 100, // moves price ~2%
+            max_spread: config.max_spread,
+            last_bid_price_twap: init_reserve_price,
+            last_ask_price_twap: init_reserve_price,
+            max_open_interest: config.max_open_interest,
+            last_trade_ts: now,
+            last_update_slot: clock.slot,
+            ..AMM::default()
+        },
+        ..PerpMarket::default()
+    };
+
+    validate_perp_market(perp_market)?;
+
+    **creator = PredictionMarketCreator {
+        creator: user_key,
+        authority: ctx.accounts.authority.key(),
+        bond_amount: config.bond_amount,
+        creator_fee_share: config.creator_fee_share,
+        market_index,
+        bond_spot_market_index: config.bond_spot_market_index,
+        status: PredictionMarketCreatorStatus::Active,
+        ..PredictionMarketCreator::default()
+    };
+
+    safe_increment!(state.number_of_markets, 1);
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(config.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    // the bond is held the same way as a resolution bond until the market settles or is slashed
+    controller::market_resolution::post_resolution_bond(
+        user,
+        user_stats,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        config.bond_spot_market_index,
+        config.bond_amount,
+        now,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(PredictionMarketCreatorRecord {
+        ts: now,
+        action: PredictionMarketCreatorAction::Create,
+        market_index,
+        user: user_key,
+        amount: config.bond_amount,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    withdraw_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_claim_prediction_market_creator_fee<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdatePredictionMarketCreator<'info>>,
+    market_index: u16,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let perp_market = &mut load_mut!(ctx.accounts.perp_market)?;
+    let creator = &mut load_mut!(ctx.accounts.prediction_market_creator)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    let AccountMaps {
+        perp_market_map: _,
+        spot_market_map,
+        oracle_map: _,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(QUOTE_SPOT_MARKET_INDEX),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    let quote_spot_market = &mut spot_market_map.get_quote_spot_market_mut()?;
+    controller::spot_balance::update_spot_market_cumulative_interest(quote_spot_market, None, now)?;
+
+    let fee_pool_token_amount = get_token_amount(
+        perp_market.amm.fee_pool.scaled_balance,
+        quote_spot_market,
+        perp_market.amm.fee_pool.balance_type(),
+    )?
+    .cast::<u64>()?;
+
+    let creator_fee = creator
+        .calculate_unclaimed_creator_fee(perp_market)?
+        .min(fee_pool_token_amount);
+
+    validate!(
+        creator_fee > 0,
+        ErrorCode::InvalidPredictionMarketCreator,
+        "no creator fee to claim for perp market {}",
+        market_index
+    )?;
+
+    let spot_position_index = user.force_get_spot_position_index(QUOTE_SPOT_MARKET_INDEX)?;
+    controller::spot_balance::transfer_spot_balances(
+        creator_fee.cast()?,
+        quote_spot_market,
+        &mut perp_market.amm.fee_pool,
+        &mut user.spot_positions[spot_position_index],
+    )?;
+
+    perp_market.amm.total_fee_minus_distributions = perp_market
+        .amm
+        .total_fee_minus_distributions
+        .safe_sub(creator_fee.cast()?)?;
+
+    creator.record_creator_fee_paid(creator_fee)?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(PredictionMarketCreatorRecord {
+        ts: now,
+        action: PredictionMarketCreatorAction::ClaimFee,
+        market_index,
+        user: user_key,
+        amount: creator_fee,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    withdraw_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_return_prediction_market_creator_bond<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdatePredictionMarketCreator<'info>>,
+    market_index: u16,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let perp_market = load!(ctx.accounts.perp_market)?;
+    let creator = &mut load_mut!(ctx.accounts.prediction_market_creator)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate!(
+        creator.status == PredictionMarketCreatorStatus::Active,
+        ErrorCode::InvalidPredictionMarketCreator,
+        "creator of perp market {} is {:?}",
+        market_index,
+        creator.status
+    )?;
+
+    validate!(
+        matches!(
+            perp_market.status,
+            MarketStatus::Settlement | MarketStatus::Delisted
+        ),
+        ErrorCode::InvalidPredictionMarketCreator,
+        "perp market {} must be settled to return the bond",
+        market_index
+    )?;
+
+    let AccountMaps {
+        perp_market_map: _,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(creator.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    controller::market_resolution::pay_resolution_bond(
+        user,
+        &spot_market_map,
+        &mut oracle_map,
+        creator.bond_spot_market_index,
+        creator.bond_amount,
+        now,
+    )?;
+
+    creator.status = PredictionMarketCreatorStatus::BondReturned;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(PredictionMarketCreatorRecord {
+        ts: now,
+        action: PredictionMarketCreatorAction::ReturnBond,
+        market_index,
+        user: user_key,
+        amount: creator.bond_amount,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    withdraw_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_propose_market_resolution<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ProposeMarketResolution<'info>>,
+    proposed_price: i64,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let user_stats = &mut load_mut!(ctx.accounts.user_stats)?;
+    let market_resolution = &mut load_mut!(ctx.accounts.market_resolution)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+
+    {
+        let perp_market = load!(ctx.accounts.perp_market)?;
+        market_resolution.propose(user_key, proposed_price, &perp_market, now)?;
+    }
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(market_resolution.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    controller::market_resolution::post_resolution_bond(
+        user,
+        user_stats,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        market_resolution.bond_spot_market_index,
+        market_resolution.bond_amount,
+        now,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(MarketResolutionRecord {
+        ts: now,
+        action: MarketResolutionAction::Propose,
+        market_index: market_resolution.market_index,
+        user: Some(user_key),
+        price: proposed_price,
+        bond_amount: market_resolution.bond_amount,
+    });
+
+    Ok(())
+}
+
+#[access_control(
+    withdraw_not_paused(&ctx.accounts.state)
+)]
+pub fn handle_dispute_market_resolution<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, DisputeMarketResolution<'info>>,
+) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+    let user = &mut load_mut!(ctx.accounts.user)?;
+    let user_stats = &mut load_mut!(ctx.accounts.user_stats)?;
+    let market_resolution = &mut load_mut!(ctx.accounts.market_resolution)?;
+    let state = &ctx.accounts.state;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    validate!(!user.is_bankrupt(), ErrorCode::UserBankrupt)?;
+
+    market_resolution.dispute(user_key, now)?;
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_maps(
+        &mut ctx.remaining_accounts.iter().peekable(),
+        &MarketSet::new(),
+        &get_writable_spot_market_set(market_resolution.bond_spot_market_index),
+        clock.slot,
+        Some(state.oracle_guard_rails),
+    )?;
+
+    controller::market_resolution::post_resolution_bond(
+        user,
+        user_stats,
+        &perp_market_map,
+        &spot_market_map,
+        &mut oracle_map,
+        market_resolution.bond_spot_market_index,
+        market_resolution.bond_amount,
+        now,
+    )?;
+
+    user.update_last_active_slot(clock.slot);
+
+    emit!(MarketResolutionRecord {
+        ts: now,
+        action: MarketResolutionAction::Dispute,
+        market_index: market_resolution.market_index,
+        user: Some(user_key),
+        price: market_resolution.proposed_price,
+        bond_amount: market_resolution.bond_amount,
+    });
+
+    Ok(())
+}
+
+pub fn handle_update_user_name(
+    ctx: Context<UpdateUser>,
+    _sub_account_id: u16,
+    name: [u8; 32],
+) -> Result<()> {
+    let mut user = load_mut!(ctx.accounts.user)?;
+    user.name = name;
+    Ok(())
+}
+
+pub fn handle_update_user_custom_margin_ratio(
+    ctx: Context<UpdateUser>,
+    _sub_account_id: u16,
+    margin_ratio: u32,
+) -> Result<()> {
+    let mut user = load_mut!(ctx.accounts.user)?;
+    user.max_margin_ratio = margin_ratio;
+    Ok(())
+}
+
+pub fn handle_update_user_margin_trading_enabled<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdateUser<'info>>,
+    _sub_account_id: u16,
+    margin_trading_enabled: bool,
+) -> Result<()> {
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+        ..
+    } = load_maps(
+        remaining_accounts_iter,
+        &MarketSet::new(),
+        &MarketSet::new(),
+        Clock::get()?.slot,
+        None,
+    )?;
+
+    let mut user = load_mut!(ctx.accounts.user)?;
+    user.is_margin_trading_enabled = margin_trading_enabled;
+
+    validate_spot_margin_trading(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
+        .map_err(|_| ErrorCode::MarginOrdersOpen)?;
+
+    Ok(())
+}
+
+pub fn handle_update_user_pool_id<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, UpdateUser<'info>>,
+    _sub_account_id: u16,
+    pool_id: u8,
+) -> Result<()> {
+    let remaining_accounts_iter = &mut ctx.remaining_accounts.iter().peekable();
+    let AccountMaps {
         perp_market_map,
         spot_market_map,
         mut oracle_map,
@@ -2268,6 +3578,30 @@ pub fn handle_update_user_reduce_only(
     Ok(())
 }
 
+pub fn handle_update_user_self_trade_prevention_mode(
+    ctx: Context<UpdateUser>,
+    _sub_account_id: u16,
+    self_trade_prevention_mode: SelfTradePreventionMode,
+) -> Result<()> {
+    let mut user = load_mut!(ctx.accounts.user)?;
+
+    validate!(!user.is_being_liquidated(), ErrorCode::LiquidationsOngoing)?;
+
+    user.self_trade_prevention_mode = self_trade_prevention_mode;
+    Ok(())
+}
+
+pub fn handle_update_user_heartbeat(
+    ctx: Context<UpdateUserHeartbeat>,
+    heartbeat_timeout: u32,
+) -> Result<()> {
+    let mut user = load_mut!(ctx.accounts.user)?;
+    let clock = Clock::get()?;
+
+    user.update_heartbeat(clock.unix_timestamp, heartbeat_timeout)?;
+    Ok(())
+}
+
 pub fn handle_update_user_advanced_lp(
     ctx: Context<UpdateUser>,
     _sub_account_id: u16,
@@ -2540,6 +3874,50 @@ pub struct InitializeRFQUser<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeUserTrailingStops<'info> {
+    #[account(
+        init,
+        seeds = [TRAILING_STOPS_PDA_SEED.as_ref(), user.key().as_ref()],
+        space = UserTrailingStops::SIZE,
+        bump,
+        payer = payer
+    )]
+    pub user_trailing_stops: AccountLoader<'info, UserTrailingStops>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserParentOrders<'info> {
+    #[account(
+        init,
+        seeds = [PARENT_ORDERS_PDA_SEED.as_ref(), user.key().as_ref()],
+        space = UserParentOrders::SIZE,
+        bump,
+        payer = payer
+    )]
+    pub user_parent_orders: AccountLoader<'info, UserParentOrders>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(num_orders: u16)]
 pub struct InitializeSwiftUserOrders<'info> {
@@ -2748,6 +4126,67 @@ pub struct CancelOrder<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct PlaceTrailingStopOrder<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        seeds = [TRAILING_STOPS_PDA_SEED.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trailing_stops: AccountLoader<'info, UserTrailingStops>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateUserHeartbeat<'info> {
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceParentOrder<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        seeds = [PARENT_ORDERS_PDA_SEED.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_parent_orders: AccountLoader<'info, UserParentOrders>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelParentOrder<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        seeds = [PARENT_ORDERS_PDA_SEED.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_parent_orders: AccountLoader<'info, UserParentOrders>,
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct PlaceAndTake<'info> {
     pub state: Box<Account<'info, State>>,
@@ -2846,6 +4285,179 @@ pub struct AddRemoveLiquidity<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CompleteSet<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?,
+    )]
+    pub user: AccountLoader<'info, User>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub prediction_event: AccountLoader<'info, PredictionEvent>,
+}
+
+#[derive(Accounts)]
+pub struct BinaryCompleteSet<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&user, &authority)?,
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        constraint = can_sign_for_user(&short_user, &authority)?,
+    )]
+    pub short_user: AccountLoader<'info, User>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_index: u16, direction: PositionDirection,)]
+pub struct PredictionShares<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub user: AccountLoader<'info, User>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [get_prediction_share_mint_seed(direction), market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub prediction_share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = &prediction_share_mint.key().eq(&user_token_account.mint),
+        constraint = &user_token_account.owner.eq(authority.key)
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        constraint = state.signer.eq(&drift_signer.key())
+    )]
+    /// CHECK: forced drift_signer
+    pub drift_signer: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePredictionMarket<'info> {
+    #[account(mut)]
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        constraint = is_stats_for_user(&user, &user_stats)?
+    )]
+    pub user_stats: AccountLoader<'info, UserStats>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        seeds = [b"perp_market", state.number_of_markets.to_le_bytes().as_ref()],
+        space = PerpMarket::SIZE,
+        bump,
+        payer = authority
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        init,
+        seeds = [PREDICTION_MARKET_CREATOR_SEED, state.number_of_markets.to_le_bytes().as_ref()],
+        space = PredictionMarketCreator::SIZE,
+        bump,
+        payer = authority
+    )]
+    pub prediction_market_creator: AccountLoader<'info, PredictionMarketCreator>,
+    #[account(
+        seeds = [PREDICTION_MARKET_CREATION_CONFIG_SEED],
+        bump,
+    )]
+    pub prediction_market_creation_config: AccountLoader<'info, PredictionMarketCreationConfig>,
+    #[account(
+        init,
+        seeds = [b"prelaunch_oracle".as_ref(), state.number_of_markets.to_le_bytes().as_ref()],
+        space = PrelaunchOracle::SIZE,
+        bump,
+        payer = authority
+    )]
+    pub prelaunch_oracle: AccountLoader<'info, PrelaunchOracle>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_index: u16)]
+pub struct UpdatePredictionMarketCreator<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub user: AccountLoader<'info, User>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"perp_market", market_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(
+        mut,
+        seeds = [PREDICTION_MARKET_CREATOR_SEED, market_index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction_market_creator.load()?.creator.eq(&user.key()),
+    )]
+    pub prediction_market_creator: AccountLoader<'info, PredictionMarketCreator>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeMarketResolution<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        constraint = is_stats_for_user(&user, &user_stats)?
+    )]
+    pub user_stats: AccountLoader<'info, UserStats>,
+    pub authority: Signer<'info>,
+    #[account(
+        constraint = perp_market.load()?.market_index == market_resolution.load()?.market_index
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+    #[account(mut)]
+    pub market_resolution: AccountLoader<'info, MarketResolution>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeMarketResolution<'info> {
+    pub state: Box<Account<'info, State>>,
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub user: AccountLoader<'info, User>,
+    #[account(
+        mut,
+        constraint = is_stats_for_user(&user, &user_stats)?
+    )]
+    pub user_stats: AccountLoader<'info, UserStats>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub market_resolution: AccountLoader<'info, MarketResolution>,
+}
+
 #[derive(Accounts)]
 pub struct RemoveLiquidityInExpiredMarket<'info> {
     pub state: Box<Account<'info, State>>,
@@ -11,15 +11,19 @@ use math::{bn, constants::*};
 use state::oracle::OracleSource;
 
 use crate::controller::position::PositionDirection;
-use crate::state::oracle::PrelaunchOracleParams;
-use crate::state::order_params::{ModifyOrderParams, OrderParams, RFQMatch};
-use crate::state::perp_market::{ContractTier, MarketStatus};
+use crate::state::oracle::{MedianOracleParams, PrelaunchOracleParams};
+use crate::state::oracle_guard_rails_override::OracleGuardRailsOverrideParams;
+use crate::state::order_params::{
+    ModifyOrderParams, OrderLinkType, OrderParams, ParentOrderParams, RFQMatch,
+    TrailingStopDistance,
+};
+use crate::state::perp_market::{ContractTier, MarketStatus, PredictionFeeCurve};
 use crate::state::settle_pnl_mode::SettlePnlMode;
 use crate::state::spot_market::AssetTier;
 use crate::state::spot_market::SpotFulfillmentConfigStatus;
 use crate::state::state::FeeStructure;
 use crate::state::state::*;
-use crate::state::user::MarketType;
+use crate::state::user::{MarketType, OrderTriggerSource, SelfTradePreventionMode};
 
 pub mod controller;
 pub mod error;
@@ -65,6 +69,18 @@ pub mod drift {
         handle_initialize_rfq_user(ctx)
     }
 
+    pub fn initialize_user_trailing_stops<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, InitializeUserTrailingStops<'info>>,
+    ) -> Result<()> {
+        handle_initialize_user_trailing_stops(ctx)
+    }
+
+    pub fn initialize_user_parent_orders<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, InitializeUserParentOrders<'info>>,
+    ) -> Result<()> {
+        handle_initialize_user_parent_orders(ctx)
+    }
+
     pub fn initialize_swift_user_orders<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, InitializeSwiftUserOrders<'info>>,
         num_orders: u16,
@@ -119,6 +135,28 @@ pub mod drift {
         handle_place_perp_order(ctx, params)
     }
 
+    pub fn place_perp_order_with_trigger_market<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
+        params: OrderParams,
+        trigger_source: OrderTriggerSource,
+        trigger_market_index: u16,
+    ) -> Result<()> {
+        handle_place_perp_order_with_trigger_market(
+            ctx,
+            params,
+            trigger_source,
+            trigger_market_index,
+        )
+    }
+
+    pub fn place_perp_order_with_trailing_stop<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PlaceTrailingStopOrder<'info>>,
+        params: OrderParams,
+        trailing_stop_distance: TrailingStopDistance,
+    ) -> Result<()> {
+        handle_place_perp_order_with_trailing_stop(ctx, params, trailing_stop_distance)
+    }
+
     pub fn cancel_order<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, CancelOrder>,
         order_id: Option<u32>,
@@ -197,6 +235,20 @@ pub mod drift {
         handle_place_swift_taker_order(ctx, swift_message_bytes, swift_order_params_message_bytes)
     }
 
+    pub fn place_parent_order<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PlaceParentOrder<'info>>,
+        params: ParentOrderParams,
+    ) -> Result<()> {
+        handle_place_parent_order(ctx, params)
+    }
+
+    pub fn cancel_parent_order<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CancelParentOrder<'info>>,
+        parent_order_id: u32,
+    ) -> Result<()> {
+        handle_cancel_parent_order(ctx, parent_order_id)
+    }
+
     pub fn place_and_match_rfq_orders<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, PlaceAndMatchRFQOrders<'info>>,
         rfq_matches: Vec<RFQMatch>,
@@ -246,6 +298,14 @@ pub mod drift {
         handle_place_orders(ctx, params)
     }
 
+    pub fn place_linked_orders<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PlaceOrder>,
+        params: Vec<OrderParams>,
+        order_link_type: OrderLinkType,
+    ) -> Result<()> {
+        handle_place_linked_orders(ctx, params, order_link_type)
+    }
+
     pub fn begin_swap<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, Swap<'info>>,
         in_market_index: u16,
@@ -295,6 +355,99 @@ pub mod drift {
         handle_remove_perp_lp_shares_in_expiring_market(ctx, shares_to_burn, market_index)
     }
 
+    pub fn mint_complete_set<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CompleteSet<'info>>,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_mint_complete_set(ctx, base_asset_amount)
+    }
+
+    pub fn redeem_complete_set<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CompleteSet<'info>>,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_redeem_complete_set(ctx, base_asset_amount)
+    }
+
+    pub fn mint_binary_complete_set<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, BinaryCompleteSet<'info>>,
+        market_index: u16,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_mint_binary_complete_set(ctx, market_index, base_asset_amount)
+    }
+
+    pub fn redeem_binary_complete_set<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, BinaryCompleteSet<'info>>,
+        market_index: u16,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_redeem_binary_complete_set(ctx, market_index, base_asset_amount)
+    }
+
+    pub fn wrap_prediction_shares<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+        market_index: u16,
+        direction: PositionDirection,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_wrap_prediction_shares(ctx, market_index, direction, base_asset_amount)
+    }
+
+    pub fn unwrap_prediction_shares<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+        market_index: u16,
+        direction: PositionDirection,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_unwrap_prediction_shares(ctx, market_index, direction, base_asset_amount)
+    }
+
+    pub fn redeem_prediction_shares<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, PredictionShares<'info>>,
+        market_index: u16,
+        direction: PositionDirection,
+        base_asset_amount: u64,
+    ) -> Result<()> {
+        handle_redeem_prediction_shares(ctx, market_index, direction, base_asset_amount)
+    }
+
+    pub fn create_prediction_market<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CreatePredictionMarket<'info>>,
+        name: [u8; 32],
+        amm_peg_multiplier: u128,
+        expiry_ts: i64,
+    ) -> Result<()> {
+        handle_create_prediction_market(ctx, name, amm_peg_multiplier, expiry_ts)
+    }
+
+    pub fn claim_prediction_market_creator_fee<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, UpdatePredictionMarketCreator<'info>>,
+        market_index: u16,
+    ) -> Result<()> {
+        handle_claim_prediction_market_creator_fee(ctx, market_index)
+    }
+
+    pub fn return_prediction_market_creator_bond<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, UpdatePredictionMarketCreator<'info>>,
+        market_index: u16,
+    ) -> Result<()> {
+        handle_return_prediction_market_creator_bond(ctx, market_index)
+    }
+
+    pub fn propose_market_resolution<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, ProposeMarketResolution<'info>>,
+        proposed_price: i64,
+    ) -> Result<()> {
+        handle_propose_market_resolution(ctx, proposed_price)
+    }
+
+    pub fn dispute_market_resolution<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, DisputeMarketResolution<'info>>,
+    ) -> Result<()> {
+        handle_dispute_market_resolution(ctx)
+    }
+
     pub fn update_user_name(
         ctx: Context<UpdateUser>,
         _sub_account_id: u16,
@@ -343,6 +496,25 @@ pub mod drift {
         handle_update_user_reduce_only(ctx, _sub_account_id, reduce_only)
     }
 
+    pub fn update_user_self_trade_prevention_mode(
+        ctx: Context<UpdateUser>,
+        _sub_account_id: u16,
+        self_trade_prevention_mode: SelfTradePreventionMode,
+    ) -> Result<()> {
+        handle_update_user_self_trade_prevention_mode(
+            ctx,
+            _sub_account_id,
+            self_trade_prevention_mode,
+        )
+    }
+
+    pub fn update_user_heartbeat(
+        ctx: Context<UpdateUserHeartbeat>,
+        heartbeat_timeout: u32,
+    ) -> Result<()> {
+        handle_update_user_heartbeat(ctx, heartbeat_timeout)
+    }
+
     pub fn update_user_advanced_lp(
         ctx: Context<UpdateUser>,
         _sub_account_id: u16,
@@ -418,6 +590,19 @@ pub mod drift {
         handle_trigger_order(ctx, order_id)
     }
 
+    pub fn release_parent_order_slice<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, ReleaseParentOrderSlice<'info>>,
+        parent_order_id: u32,
+    ) -> Result<()> {
+        handle_release_parent_order_slice(ctx, parent_order_id)
+    }
+
+    pub fn cancel_orders_on_heartbeat_expiry<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, ForceCancelOrder>,
+    ) -> Result<()> {
+        handle_cancel_orders_on_heartbeat_expiry(ctx)
+    }
+
     pub fn force_cancel_orders<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, ForceCancelOrder<'info>>,
     ) -> Result<()> {
@@ -480,6 +665,13 @@ pub mod drift {
         handle_settle_multiple_pnls(ctx, market_indexes, mode)
     }
 
+    pub fn settle_expired_positions<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, SettleExpiredPositions<'info>>,
+        market_index: u16,
+    ) -> Result<()> {
+        handle_settle_expired_positions(ctx, market_index)
+    }
+
     pub fn settle_funding_payment<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, SettleFunding>,
     ) -> Result<()> {
@@ -500,6 +692,14 @@ pub mod drift {
         handle_settle_expired_market(ctx, market_index)
     }
 
+    pub fn void_expired_market<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, AdminUpdatePerpMarket<'info>>,
+        market_index: u16,
+        refund_fees: bool,
+    ) -> Result<()> {
+        handle_void_expired_market(ctx, market_index, refund_fees)
+    }
+
     pub fn liquidate_perp<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, LiquidatePerp<'info>>,
         market_index: u16,
@@ -613,12 +813,35 @@ pub mod drift {
         handle_update_prelaunch_oracle(ctx)
     }
 
+    pub fn update_pyth_lazer_oracle<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, UpdatePythLazerOracle<'info>>,
+        pyth_message: Vec<u8>,
+    ) -> Result<()> {
+        handle_update_pyth_lazer_oracle(ctx, pyth_message)
+    }
+
+    pub fn update_median_oracle<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, UpdateMedianOracle<'info>>,
+    ) -> Result<()> {
+        handle_update_median_oracle(ctx)
+    }
+
     pub fn update_perp_bid_ask_twap<'c: 'info, 'info>(
         ctx: Context<'_, '_, 'c, 'info, UpdatePerpBidAskTwap<'info>>,
     ) -> Result<()> {
         handle_update_perp_bid_ask_twap(ctx)
     }
 
+    pub fn finalize_market_resolution<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, FinalizeMarketResolution<'info>>,
+    ) -> Result<()> {
+        handle_finalize_market_resolution(ctx)
+    }
+
+    pub fn halt_perp_market_trading(ctx: Context<HaltPerpMarketTrading>) -> Result<()> {
+        handle_halt_perp_market_trading(ctx)
+    }
+
     pub fn update_spot_market_cumulative_interest(
         ctx: Context<UpdateSpotMarketCumulativeInterest>,
     ) -> Result<()> {
@@ -896,6 +1119,107 @@ pub mod drift {
         handle_initialize_prediction_market(ctx)
     }
 
+    pub fn update_perp_market_scalar_bounds(
+        ctx: Context<AdminUpdatePerpMarket>,
+        scalar_lower_bound: u64,
+        scalar_upper_bound: u64,
+    ) -> Result<()> {
+        handle_update_perp_market_scalar_bounds(ctx, scalar_lower_bound, scalar_upper_bound)
+    }
+
+    pub fn update_perp_market_lmsr_liquidity(
+        ctx: Context<AdminUpdatePerpMarketCurve>,
+        lmsr_liquidity: u32,
+    ) -> Result<()> {
+        handle_update_perp_market_lmsr_liquidity(ctx, lmsr_liquidity)
+    }
+
+    pub fn initialize_prediction_market_creation_config(
+        ctx: Context<InitializePredictionMarketCreationConfig>,
+    ) -> Result<()> {
+        handle_initialize_prediction_market_creation_config(ctx)
+    }
+
+    pub fn update_prediction_market_creation_config(
+        ctx: Context<UpdatePredictionMarketCreationConfig>,
+        max_open_interest: u128,
+        amm_base_asset_reserve: u128,
+        bond_amount: u64,
+        bond_spot_market_index: u16,
+        order_step_size: u64,
+        order_tick_size: u64,
+        min_order_size: u64,
+        margin_ratio_initial: u32,
+        margin_ratio_maintenance: u32,
+        liquidator_fee: u32,
+        max_spread: u32,
+        creator_fee_share: u32,
+        contract_tier: ContractTier,
+        paused: bool,
+    ) -> Result<()> {
+        handle_update_prediction_market_creation_config(
+            ctx,
+            max_open_interest,
+            amm_base_asset_reserve,
+            bond_amount,
+            bond_spot_market_index,
+            order_step_size,
+            order_tick_size,
+            min_order_size,
+            margin_ratio_initial,
+            margin_ratio_maintenance,
+            liquidator_fee,
+            max_spread,
+            creator_fee_share,
+            contract_tier,
+            paused,
+        )
+    }
+
+    pub fn delist_created_prediction_market<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, DelistCreatedPredictionMarket<'info>>,
+        market_index: u16,
+    ) -> Result<()> {
+        handle_delist_created_prediction_market(ctx, market_index)
+    }
+
+    pub fn initialize_prediction_share_mints(
+        ctx: Context<InitializePredictionShareMints>,
+        market_index: u16,
+    ) -> Result<()> {
+        handle_initialize_prediction_share_mints(ctx, market_index)
+    }
+
+    pub fn initialize_prediction_event(
+        ctx: Context<InitializePredictionEvent>,
+        name: [u8; 32],
+    ) -> Result<()> {
+        handle_initialize_prediction_event(ctx, name)
+    }
+
+    pub fn add_prediction_event_outcome(ctx: Context<AddPredictionEventOutcome>) -> Result<()> {
+        handle_add_prediction_event_outcome(ctx)
+    }
+
+    pub fn activate_prediction_event<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+    ) -> Result<()> {
+        handle_activate_prediction_event(ctx)
+    }
+
+    pub fn resolve_prediction_event<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+        winning_outcome_index: u8,
+    ) -> Result<()> {
+        handle_resolve_prediction_event(ctx, winning_outcome_index)
+    }
+
+    pub fn settle_prediction_event<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, AdminUpdatePredictionEvent<'info>>,
+    ) -> Result<()> {
+        handle_settle_prediction_event(ctx)
+    }
+
     pub fn delete_initialized_perp_market(
         ctx: Context<DeleteInitializedPerpMarket>,
         market_index: u16,
@@ -934,6 +1258,29 @@ pub mod drift {
         handle_update_perp_market_expiry(ctx, expiry_ts)
     }
 
+    pub fn update_perp_market_trading_halt_ts(
+        ctx: Context<AdminUpdatePerpMarket>,
+        trading_halt_ts: i64,
+    ) -> Result<()> {
+        handle_update_perp_market_trading_halt_ts(ctx, trading_halt_ts)
+    }
+
+    pub fn initialize_market_resolution(
+        ctx: Context<InitializeMarketResolution>,
+        bond_amount: u64,
+        dispute_window: i64,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        handle_initialize_market_resolution(ctx, bond_amount, dispute_window, guardian)
+    }
+
+    pub fn finalize_disputed_market_resolution<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, FinalizeDisputedMarketResolution<'info>>,
+        finalized_price: i64,
+    ) -> Result<()> {
+        handle_finalize_disputed_market_resolution(ctx, finalized_price)
+    }
+
     pub fn settle_expired_market_pools_to_revenue_pool(
         ctx: Context<SettleExpiredMarketPoolsToRevenuePool>,
     ) -> Result<()> {
@@ -1092,6 +1439,13 @@ pub mod drift {
         handle_update_spot_market_asset_tier(ctx, asset_tier)
     }
 
+    pub fn update_spot_market_confidence_margin_multiplier(
+        ctx: Context<AdminUpdateSpotMarket>,
+        confidence_margin_multiplier: u8,
+    ) -> Result<()> {
+        handle_update_spot_market_confidence_margin_multiplier(ctx, confidence_margin_multiplier)
+    }
+
     pub fn update_spot_market_margin_weights(
         ctx: Context<AdminUpdateSpotMarket>,
         initial_asset_weight: u32,
@@ -1215,6 +1569,13 @@ pub mod drift {
         handle_update_perp_market_contract_tier(ctx, contract_tier)
     }
 
+    pub fn update_perp_market_confidence_margin_multiplier(
+        ctx: Context<AdminUpdatePerpMarket>,
+        confidence_margin_multiplier: u8,
+    ) -> Result<()> {
+        handle_update_perp_market_confidence_margin_multiplier(ctx, confidence_margin_multiplier)
+    }
+
     pub fn update_perp_market_imf_factor(
         ctx: Context<AdminUpdatePerpMarket>,
         imf_factor: u32,
@@ -1344,6 +1705,95 @@ pub mod drift {
         handle_update_perp_market_oracle(ctx, oracle, oracle_source)
     }
 
+    pub fn initialize_perp_fallback_oracle(
+        ctx: Context<InitializePerpFallbackOracle>,
+        oracle_source: OracleSource,
+        max_divergence: u64,
+    ) -> Result<()> {
+        handle_initialize_perp_fallback_oracle(ctx, oracle_source, max_divergence)
+    }
+
+    pub fn initialize_spot_fallback_oracle(
+        ctx: Context<InitializeSpotFallbackOracle>,
+        oracle_source: OracleSource,
+        max_divergence: u64,
+    ) -> Result<()> {
+        handle_initialize_spot_fallback_oracle(ctx, oracle_source, max_divergence)
+    }
+
+    pub fn update_perp_fallback_oracle(
+        ctx: Context<UpdatePerpFallbackOracle>,
+        oracle_source: OracleSource,
+        max_divergence: u64,
+    ) -> Result<()> {
+        handle_update_perp_fallback_oracle(ctx, oracle_source, max_divergence)
+    }
+
+    pub fn update_spot_fallback_oracle(
+        ctx: Context<UpdateSpotFallbackOracle>,
+        oracle_source: OracleSource,
+        max_divergence: u64,
+    ) -> Result<()> {
+        handle_update_spot_fallback_oracle(ctx, oracle_source, max_divergence)
+    }
+
+    pub fn initialize_perp_oracle_guard_rails_override(
+        ctx: Context<InitializePerpOracleGuardRailsOverride>,
+        params: OracleGuardRailsOverrideParams,
+    ) -> Result<()> {
+        handle_initialize_perp_oracle_guard_rails_override(ctx, params)
+    }
+
+    pub fn initialize_spot_oracle_guard_rails_override(
+        ctx: Context<InitializeSpotOracleGuardRailsOverride>,
+        params: OracleGuardRailsOverrideParams,
+    ) -> Result<()> {
+        handle_initialize_spot_oracle_guard_rails_override(ctx, params)
+    }
+
+    pub fn update_oracle_guard_rails_override(
+        ctx: Context<UpdateOracleGuardRailsOverride>,
+        params: OracleGuardRailsOverrideParams,
+    ) -> Result<()> {
+        handle_update_oracle_guard_rails_override(ctx, params)
+    }
+
+    pub fn initialize_pyth_lazer_oracle(
+        ctx: Context<InitializePythLazerOracle>,
+        feed_id: u32,
+        signer: Pubkey,
+    ) -> Result<()> {
+        handle_initialize_pyth_lazer_oracle(ctx, feed_id, signer)
+    }
+
+    pub fn update_pyth_lazer_oracle_signer(
+        ctx: Context<UpdatePythLazerOracleSigner>,
+        signer: Pubkey,
+    ) -> Result<()> {
+        handle_update_pyth_lazer_oracle_signer(ctx, signer)
+    }
+
+    pub fn initialize_perp_price_history(
+        ctx: Context<InitializePerpPriceHistory>,
+        sample_interval: i64,
+    ) -> Result<()> {
+        handle_initialize_perp_price_history(ctx, sample_interval)
+    }
+
+    pub fn initialize_spot_price_history(
+        ctx: Context<InitializeSpotPriceHistory>,
+        sample_interval: i64,
+    ) -> Result<()> {
+        handle_initialize_spot_price_history(ctx, sample_interval)
+    }
+
+    pub fn update_price_history_sample_interval(
+        ctx: Context<UpdatePriceHistory>,
+        sample_interval: i64,
+    ) -> Result<()> {
+        handle_update_price_history_sample_interval(ctx, sample_interval)
+    }
+
     pub fn update_perp_market_base_spread(
         ctx: Context<AdminUpdatePerpMarket>,
         base_spread: u32,
@@ -1416,6 +1866,14 @@ pub mod drift {
         handle_update_perp_market_number_of_users(ctx, number_of_users, number_of_users_with_base)
     }
 
+    pub fn update_perp_market_prediction_price_band(
+        ctx: Context<AdminUpdatePerpMarket>,
+        prediction_min_ask: u16,
+        prediction_max_bid: u16,
+    ) -> Result<()> {
+        handle_update_perp_market_prediction_price_band(ctx, prediction_min_ask, prediction_max_bid)
+    }
+
     pub fn update_perp_market_fee_adjustment(
         ctx: Context<AdminUpdatePerpMarket>,
         fee_adjustment: i16,
@@ -1423,6 +1881,20 @@ pub mod drift {
         handle_update_perp_market_fee_adjustment(ctx, fee_adjustment)
     }
 
+    pub fn update_perp_market_prediction_fee_curve(
+        ctx: Context<AdminUpdatePerpMarket>,
+        prediction_fee_curve: PredictionFeeCurve,
+        prediction_fee_floor: u16,
+        prediction_fee_cap: u16,
+    ) -> Result<()> {
+        handle_update_perp_market_prediction_fee_curve(
+            ctx,
+            prediction_fee_curve,
+            prediction_fee_floor,
+            prediction_fee_cap,
+        )
+    }
+
     pub fn update_spot_market_fee_adjustment(
         ctx: Context<AdminUpdateSpotMarket>,
         fee_adjustment: i16,
@@ -1546,6 +2018,20 @@ pub mod drift {
         handle_update_prelaunch_oracle_params(ctx, params)
     }
 
+    pub fn initialize_median_oracle<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, InitializeMedianOracle<'info>>,
+        params: MedianOracleParams,
+    ) -> Result<()> {
+        handle_initialize_median_oracle(ctx, params)
+    }
+
+    pub fn update_median_oracle_params<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, UpdateMedianOracleParams<'info>>,
+        params: MedianOracleParams,
+    ) -> Result<()> {
+        handle_update_median_oracle_params(ctx, params)
+    }
+
     pub fn delete_prelaunch_oracle(
         ctx: Context<DeletePrelaunchOracle>,
         perp_market_index: u16,
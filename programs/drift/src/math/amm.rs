@@ -835,6 +835,7 @@ pub fn calculate_net_user_pnl(amm: &AMM, oracle_price: i64) -> DriftResult<i128>
     let net_user_base_asset_value = amm
         .base_asset_amount_with_amm
         .safe_add(amm.base_asset_amount_with_unsettled_lp)?
+        .safe_add(amm.base_asset_amount_with_complete_sets.cast()?)?
         .safe_mul(oracle_price.cast()?)?
         .safe_div(PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO.cast()?)?;
 
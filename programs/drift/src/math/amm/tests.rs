@@ -1,7 +1,7 @@
 use crate::math::amm::*;
 use crate::math::constants::{
-    AMM_RESERVE_PRECISION, PEG_PRECISION, PRICE_PRECISION, PRICE_PRECISION_I64,
-    PRICE_PRECISION_U64, QUOTE_PRECISION,
+    AMM_RESERVE_PRECISION, PEG_PRECISION, PERCENTAGE_PRECISION_U64, PRICE_PRECISION,
+    PRICE_PRECISION_I64, PRICE_PRECISION_U64, QUOTE_PRECISION,
 };
 use crate::state::oracle::HistoricalOracleData;
 use crate::state::perp_market::PerpMarket;
@@ -67,6 +67,24 @@ fn calculate_amm_available_guards() {
     market.amm.total_fee_minus_distributions = -9279797219;
 
     assert_eq!(!market.has_too_much_drawdown().unwrap(), false); // too small net_revenue_since_last_funding drawdown
+
+    // 3% drawdown only breaches the limit in a calm market
+    market.amm.net_revenue_since_last_funding = -6_000_000_000;
+    market.amm.total_fee_minus_distributions = 200_000_000_000;
+
+    assert_eq!(!market.has_too_much_drawdown().unwrap(), true);
+    assert_eq!(
+        !market
+            .has_too_much_drawdown_for_oracle_volatility(Some(PERCENTAGE_PRECISION_U64 / 10))
+            .unwrap(),
+        true
+    );
+    assert_eq!(
+        !market
+            .has_too_much_drawdown_for_oracle_volatility(Some(PERCENTAGE_PRECISION_U64 / 1000))
+            .unwrap(),
+        false
+    );
 }
 
 #[test]
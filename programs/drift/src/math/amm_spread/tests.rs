@@ -1614,4 +1614,67 @@ mod test {
             949981 // under .95
         );
     }
+
+    #[test]
+    fn calculate_prediction_market_spread_tests_configured_price_band() {
+        let amm = AMM {
+            base_asset_reserve: 2 * AMM_RESERVE_PRECISION,
+            quote_asset_reserve: 2 * AMM_RESERVE_PRECISION,
+            sqrt_k: 2 * AMM_RESERVE_PRECISION,
+            peg_multiplier: PEG_PRECISION - PEG_PRECISION / 1000, // .999
+            long_spread: 10000,
+            short_spread: 10000,
+            base_spread: 10000,
+            max_spread: 100000,
+            curve_update_intensity: 100,
+            ..AMM::default()
+        };
+
+        // amm can bid up to .99
+        let market = PerpMarket {
+            amm,
+            contract_type: ContractType::Prediction,
+            prediction_max_bid: 9900,
+            ..PerpMarket::default()
+        };
+
+        let (new_bid_base_asset_reserve, new_bid_quote_asset_reserve) =
+            crate::math::amm_spread::calculate_spread_reserves(&market, PositionDirection::Short)
+                .unwrap();
+
+        assert_eq!(
+            crate::math::amm::calculate_price(
+                new_bid_quote_asset_reserve,
+                new_bid_base_asset_reserve,
+                market.amm.peg_multiplier,
+            )
+            .unwrap(),
+            989034 // no longer capped at .95
+        );
+
+        // amm can ask down to .01
+        let market = PerpMarket {
+            amm: AMM {
+                peg_multiplier: PEG_PRECISION / 100, // .01
+                ..amm
+            },
+            contract_type: ContractType::Prediction,
+            prediction_min_ask: 100,
+            ..PerpMarket::default()
+        };
+
+        let (new_ask_base_asset_reserve, new_ask_quote_asset_reserve) =
+            crate::math::amm_spread::calculate_spread_reserves(&market, PositionDirection::Long)
+                .unwrap();
+
+        assert_eq!(
+            crate::math::amm::calculate_price(
+                new_ask_quote_asset_reserve,
+                new_ask_base_asset_reserve,
+                market.amm.peg_multiplier,
+            )
+            .unwrap(),
+            10100 // no longer floored at .05
+        );
+    }
 }
@@ -9,8 +9,8 @@ use crate::state::user::{Order, OrderType};
 use solana_program::msg;
 
 use crate::state::fill_mode::FillMode;
-use crate::state::perp_market::{AMMAvailability, PerpMarket};
-use crate::{OrderParams, MAX_PREDICTION_MARKET_PRICE};
+use crate::state::perp_market::{AMMAvailability, PerpMarket, PredictionMarketPriceBounds};
+use crate::OrderParams;
 use std::cmp::min;
 
 #[cfg(test)]
@@ -86,7 +86,7 @@ pub fn calculate_auction_price(
     slot: u64,
     tick_size: u64,
     valid_oracle_price: Option<i64>,
-    is_prediction_market: bool,
+    prediction_price_bounds: Option<PredictionMarketPriceBounds>,
 ) -> DriftResult<u64> {
     match order.order_type {
         OrderType::Market | OrderType::TriggerMarket | OrderType::TriggerLimit => {
@@ -99,7 +99,7 @@ pub fn calculate_auction_price(
                     slot,
                     tick_size,
                     valid_oracle_price,
-                    is_prediction_market,
+                    prediction_price_bounds,
                 )
             } else {
                 calculate_auction_price_for_fixed_auction(order, slot, tick_size)
@@ -110,7 +110,7 @@ pub fn calculate_auction_price(
             slot,
             tick_size,
             valid_oracle_price,
-            is_prediction_market,
+            prediction_price_bounds,
         ),
     }
 }
@@ -156,7 +156,7 @@ fn calculate_auction_price_for_oracle_offset_auction(
     slot: u64,
     tick_size: u64,
     valid_oracle_price: Option<i64>,
-    is_prediction_market: bool,
+    prediction_price_bounds: Option<PredictionMarketPriceBounds>,
 ) -> DriftResult<u64> {
     let oracle_price = valid_oracle_price.ok_or_else(|| {
         msg!("Could not find oracle too calculate oracle offset auction price");
@@ -177,8 +177,8 @@ fn calculate_auction_price_for_oracle_offset_auction(
             .max(tick_size.cast()?)
             .cast::<u64>()?;
 
-        if is_prediction_market {
-            price = price.min(MAX_PREDICTION_MARKET_PRICE);
+        if let Some(prediction_price_bounds) = prediction_price_bounds {
+            price = prediction_price_bounds.clamp_price(price);
         }
 
         return standardize_price(price, tick_size, order.direction);
@@ -205,8 +205,8 @@ fn calculate_auction_price_for_oracle_offset_auction(
         .max(tick_size.cast()?)
         .cast::<u64>()?;
 
-    if is_prediction_market {
-        price = price.min(MAX_PREDICTION_MARKET_PRICE);
+    if let Some(prediction_price_bounds) = prediction_price_bounds {
+        price = prediction_price_bounds.clamp_price(price);
     }
 
     standardize_price(price, tick_size, order.direction)
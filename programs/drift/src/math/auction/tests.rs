@@ -187,17 +187,17 @@ mod calculate_auction_price {
         let oracle_price = Some(PRICE_PRECISION_I64);
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 9 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, PRICE_PRECISION_U64);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 11 * PRICE_PRECISION_U64 / 10);
 
@@ -212,17 +212,17 @@ mod calculate_auction_price {
         };
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 8 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 85 * PRICE_PRECISION_U64 / 100);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 9 * PRICE_PRECISION_U64 / 10);
 
@@ -237,17 +237,17 @@ mod calculate_auction_price {
         };
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 11 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 115 * PRICE_PRECISION_U64 / 100);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 12 * PRICE_PRECISION_U64 / 10);
     }
@@ -267,17 +267,17 @@ mod calculate_auction_price {
         let oracle_price = Some(PRICE_PRECISION_I64);
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 11 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, PRICE_PRECISION_U64);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 9 * PRICE_PRECISION_U64 / 10);
 
@@ -292,17 +292,17 @@ mod calculate_auction_price {
         };
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 12 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 115 * PRICE_PRECISION_U64 / 100);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 11 * PRICE_PRECISION_U64 / 10);
 
@@ -317,17 +317,17 @@ mod calculate_auction_price {
         };
 
         let slot = 0;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 9 * PRICE_PRECISION_U64 / 10);
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 85 * PRICE_PRECISION_U64 / 100);
 
         let slot = 10;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
 
         assert_eq!(price, 8 * PRICE_PRECISION_U64 / 10);
     }
@@ -346,11 +346,11 @@ mod calculate_auction_price {
         };
 
         let slot = 5;
-        let price = calculate_auction_price(&order, slot, tick_size, None, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, None, None).unwrap();
         assert_eq!(price, PRICE_PRECISION_U64);
 
         order.direction = PositionDirection::Short;
-        let price = calculate_auction_price(&order, slot, tick_size, None, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, None, None).unwrap();
         assert_eq!(price, PRICE_PRECISION_U64);
 
         let mut order = Order {
@@ -363,11 +363,11 @@ mod calculate_auction_price {
             ..Order::default()
         };
         let oracle_price = Some(PRICE_PRECISION_I64);
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 3 * PRICE_PRECISION_U64 / 2);
 
         order.direction = PositionDirection::Short;
-        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, slot, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 3 * PRICE_PRECISION_U64 / 2);
     }
 
@@ -388,15 +388,15 @@ mod calculate_auction_price {
         let oracle_price = Some(100 * PRICE_PRECISION_I64);
 
         // At start of auction
-        let price = calculate_auction_price(&order, 0, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 0, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 105 * PRICE_PRECISION_U64);
 
         // Midway through auction
-        let price = calculate_auction_price(&order, 5, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 5, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 107_5 * PRICE_PRECISION_U64 / 10);
 
         // End of auction
-        let price = calculate_auction_price(&order, 10, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 10, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 110 * PRICE_PRECISION_U64);
     }
 
@@ -417,15 +417,15 @@ mod calculate_auction_price {
         let oracle_price = Some(100 * PRICE_PRECISION_I64);
 
         // At start of auction
-        let price = calculate_auction_price(&order, 0, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 0, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 95 * PRICE_PRECISION_U64);
 
         // Midway through auction
-        let price = calculate_auction_price(&order, 5, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 5, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 92_5 * PRICE_PRECISION_U64 / 10);
 
         // End of auction
-        let price = calculate_auction_price(&order, 10, tick_size, oracle_price, false).unwrap();
+        let price = calculate_auction_price(&order, 10, tick_size, oracle_price, None).unwrap();
         assert_eq!(price, 90 * PRICE_PRECISION_U64);
     }
 }
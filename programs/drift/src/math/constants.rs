@@ -72,6 +72,8 @@ pub const LIQUIDATION_FEE_PRECISION: u32 = PERCENTAGE_PRECISION as u32; // expo
 pub const LIQUIDATION_FEE_PRECISION_U128: u128 = LIQUIDATION_FEE_PRECISION as u128; // expo = -6
 pub const SPOT_IMF_PRECISION: u32 = PERCENTAGE_PRECISION as u32; // expo = -6
 pub const SPOT_IMF_PRECISION_U128: u128 = SPOT_IMF_PRECISION as u128; // expo = -6
+pub const CONFIDENCE_MARGIN_MULTIPLIER_PRECISION: u64 = 10; // expo = -1
+pub const MAX_CONFIDENCE_MARGIN_MULTIPLIER: u8 = 100; // 10x
 
 // FORMULAIC REPEG / K
 pub const K_BPS_UPDATE_SCALE: i128 = PERCENTAGE_PRECISION_I128;
@@ -192,6 +194,7 @@ pub const DEFAULT_MAX_TWAP_UPDATE_PRICE_BAND_DENOMINATOR: i64 = 3; // '3' here m
 
 // DEFAULTS
 pub const DEFAULT_REVENUE_SINCE_LAST_FUNDING_SPREAD_RETREAT: i64 = -25 * QUOTE_PRECISION_I64; //$25 loss
+pub const CALM_MARKET_ORACLE_VOLATILITY: u64 = PERCENTAGE_PRECISION_U64 / 200; // .5%
 pub const DEFAULT_LARGE_BID_ASK_FACTOR: u64 = 10 * BID_ASK_SPREAD_PRECISION;
 pub const DEFAULT_LIQUIDATION_MARGIN_BUFFER_RATIO: u32 = MARGIN_PRECISION / 50; // 2%
 pub const DEFAULT_BASE_ASSET_AMOUNT_STEP_SIZE: u64 = BASE_PRECISION_U64 / 10000; // 1e-4;
@@ -215,3 +218,11 @@ pub const FUEL_START_TS: i64 = 1723147200_i64; // August 8 2024 UTC
 pub const MAX_PREDICTION_MARKET_PRICE: u64 = PRICE_PRECISION_U64;
 pub const MAX_PREDICTION_MARKET_PRICE_I64: i64 = PRICE_PRECISION_U64 as i64;
 pub const MAX_PREDICTION_MARKET_PRICE_U128: u128 = PRICE_PRECISION_U64 as u128;
+pub const MAX_PREDICTION_EVENT_OUTCOMES: usize = 16;
+// outcome count of a market linked to a prediction event that hasnt been activated
+pub const PREDICTION_EVENT_PENDING_OUTCOMES: u8 = u8::MAX;
+pub const PREDICTION_MARKET_PRICE_BAND_PRECISION: u64 = 10_000; // expo = -4
+pub const DEFAULT_PREDICTION_MARKET_MIN_ASK: u16 = 500; // 5% of payout range
+pub const DEFAULT_PREDICTION_MARKET_MAX_BID: u16 = 9_500; // 95% of payout range
+pub const LMSR_PRECISION_I128: i128 = 1_000_000_000_000; // expo = -12
+pub const LMSR_LN_2: i128 = 693_147_180_560; // ln(2), expo = -12
@@ -6,13 +6,15 @@ use crate::error::DriftResult;
 use crate::math::casting::Cast;
 
 use crate::math::constants::{
-    FIFTY_MILLION_QUOTE, FIVE_MILLION_QUOTE, ONE_HUNDRED_MILLION_QUOTE, ONE_HUNDRED_THOUSAND_QUOTE,
-    ONE_MILLION_QUOTE, ONE_THOUSAND_QUOTE, TEN_BPS, TEN_MILLION_QUOTE, TEN_THOUSAND_QUOTE,
+    BASE_PRECISION, FIFTY_MILLION_QUOTE, FIVE_MILLION_QUOTE, ONE_HUNDRED_MILLION_QUOTE,
+    ONE_HUNDRED_THOUSAND_QUOTE, ONE_MILLION_QUOTE, ONE_THOUSAND_QUOTE,
+    PREDICTION_MARKET_PRICE_BAND_PRECISION, TEN_BPS, TEN_MILLION_QUOTE, TEN_THOUSAND_QUOTE,
     TWENTY_FIVE_THOUSAND_QUOTE,
 };
 use crate::math::helpers::get_proportion_u128;
 use crate::math::safe_math::SafeMath;
 
+use crate::state::perp_market::{PerpMarket, PredictionFeeCurve};
 use crate::state::state::{FeeStructure, FeeTier, OrderFillerRewardStructure};
 use crate::state::user::{MarketType, UserStats};
 
@@ -144,6 +146,68 @@ pub fn calculate_fee_for_fulfillment_with_amm(
     }
 }
 
+/// The notional a perp fill's fees are charged on. Prediction markets with a fee curve charge on
+/// a per share price derived from the fill price, clamped to the market's fee floor and cap
+/// precision: QUOTE_PRECISION
+pub fn calculate_perp_fee_notional(
+    market: &PerpMarket,
+    base_asset_amount: u64,
+    quote_asset_amount: u64,
+) -> DriftResult<u64> {
+    let price_bounds = match market.get_prediction_market_price_bounds() {
+        Some(price_bounds) if market.prediction_fee_curve != PredictionFeeCurve::Flat => {
+            price_bounds
+        }
+        _ => return Ok(quote_asset_amount),
+    };
+
+    if base_asset_amount == 0 {
+        return Ok(quote_asset_amount);
+    }
+
+    let fill_price = quote_asset_amount
+        .cast::<u128>()?
+        .safe_mul(BASE_PRECISION)?
+        .safe_div(base_asset_amount.cast()?)?
+        .cast::<u64>()?;
+
+    let payout_range = price_bounds.upper.safe_sub(price_bounds.lower)?;
+    let long_payout_at_risk = fill_price
+        .clamp(price_bounds.lower, price_bounds.upper)
+        .safe_sub(price_bounds.lower)?;
+    let short_payout_at_risk = payout_range.safe_sub(long_payout_at_risk)?;
+
+    let fee_price = match market.prediction_fee_curve {
+        PredictionFeeCurve::Flat => return Ok(quote_asset_amount),
+        PredictionFeeCurve::Variance => long_payout_at_risk
+            .cast::<u128>()?
+            .safe_mul(short_payout_at_risk.cast()?)?
+            .safe_div(payout_range.cast()?)?
+            .cast::<u64>()?,
+        PredictionFeeCurve::PayoutAtRisk => long_payout_at_risk.min(short_payout_at_risk),
+    };
+
+    let fee_price_floor = payout_range
+        .safe_mul(market.prediction_fee_floor.cast()?)?
+        .safe_div(PREDICTION_MARKET_PRICE_BAND_PRECISION)?;
+
+    let fee_price_cap = if market.prediction_fee_cap == 0 {
+        payout_range
+    } else {
+        payout_range
+            .safe_mul(market.prediction_fee_cap.cast()?)?
+            .safe_div(PREDICTION_MARKET_PRICE_BAND_PRECISION)?
+    };
+
+    let fee_price = fee_price.max(fee_price_floor).min(fee_price_cap);
+
+    base_asset_amount
+        .cast::<u128>()?
+        .safe_mul(fee_price.cast()?)?
+        .safe_div(BASE_PRECISION)?
+        .cast()
+}
+
 fn calculate_taker_fee(
     quote_asset_amount: u64,
     fee_tier: &FeeTier,
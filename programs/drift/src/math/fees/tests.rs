@@ -752,3 +752,168 @@ mod calculate_fee_for_fulfillment_with_serum {
         assert_eq!(filler_reward, 2000);
     }
 }
+
+mod calculate_perp_fee_notional {
+    use crate::math::constants::{BASE_PRECISION_U64, PRICE_PRECISION_U64, QUOTE_PRECISION_U64};
+    use crate::math::fees::{
+        calculate_fee_for_fulfillment_with_match, calculate_perp_fee_notional, FillFees,
+    };
+    use crate::state::perp_market::{ContractType, PerpMarket, PredictionFeeCurve};
+    use crate::state::state::FeeStructure;
+    use crate::state::user::{MarketType, UserStats};
+
+    #[test]
+    fn flat() {
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            ..PerpMarket::default()
+        };
+
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 100 * BASE_PRECISION_U64, 2 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(fee_notional, 2 * QUOTE_PRECISION_U64);
+
+        // fee curve is ignored outside prediction markets
+        let market = PerpMarket {
+            prediction_fee_curve: PredictionFeeCurve::Variance,
+            ..PerpMarket::default()
+        };
+
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 100 * BASE_PRECISION_U64, 2 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(fee_notional, 2 * QUOTE_PRECISION_U64);
+    }
+
+    #[test]
+    fn variance() {
+        let mut market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            prediction_fee_curve: PredictionFeeCurve::Variance,
+            ..PerpMarket::default()
+        };
+
+        // 100 shares at 50c charged on 100 * .5 * .5
+        let fee_notional = calculate_perp_fee_notional(
+            &market,
+            100 * BASE_PRECISION_U64,
+            50 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(fee_notional, 25 * QUOTE_PRECISION_U64);
+
+        // 100 shares at 2c and 98c are charged the same
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 100 * BASE_PRECISION_U64, 2 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(fee_notional, 1960000);
+
+        let fee_notional = calculate_perp_fee_notional(
+            &market,
+            100 * BASE_PRECISION_U64,
+            98 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(fee_notional, 1960000);
+
+        // floor of 5c per share
+        market.prediction_fee_floor = 500;
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 100 * BASE_PRECISION_U64, 2 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(fee_notional, 5 * QUOTE_PRECISION_U64);
+
+        // cap of 20c per share
+        market.prediction_fee_cap = 2000;
+        let fee_notional = calculate_perp_fee_notional(
+            &market,
+            100 * BASE_PRECISION_U64,
+            50 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(fee_notional, 20 * QUOTE_PRECISION_U64);
+    }
+
+    #[test]
+    fn payout_at_risk() {
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            prediction_fee_curve: PredictionFeeCurve::PayoutAtRisk,
+            prediction_fee_cap: 100,
+            ..PerpMarket::default()
+        };
+
+        let fee_notional = calculate_perp_fee_notional(
+            &market,
+            100 * BASE_PRECISION_U64,
+            98 * QUOTE_PRECISION_U64,
+        )
+        .unwrap();
+        assert_eq!(fee_notional, QUOTE_PRECISION_U64);
+
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 100 * BASE_PRECISION_U64, QUOTE_PRECISION_U64 / 2)
+                .unwrap();
+        assert_eq!(fee_notional, QUOTE_PRECISION_U64 / 2);
+    }
+
+    #[test]
+    fn scalar() {
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            prediction_fee_curve: PredictionFeeCurve::Variance,
+            scalar_lower_bound: PRICE_PRECISION_U64,
+            scalar_upper_bound: 3 * PRICE_PRECISION_U64,
+            ..PerpMarket::default()
+        };
+
+        // $2 in a [$1, $3] range is charged on (2 - 1) * (3 - 2) / (3 - 1)
+        let fee_notional =
+            calculate_perp_fee_notional(&market, 10 * BASE_PRECISION_U64, 20 * QUOTE_PRECISION_U64)
+                .unwrap();
+        assert_eq!(fee_notional, 5 * QUOTE_PRECISION_U64);
+    }
+
+    #[test]
+    fn fill_fees() {
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            prediction_fee_curve: PredictionFeeCurve::Variance,
+            ..PerpMarket::default()
+        };
+
+        let taker_stats = UserStats::default();
+        let mut maker_stats = UserStats::default();
+
+        let FillFees {
+            user_fee: taker_fee,
+            maker_rebate,
+            fee_to_market,
+            ..
+        } = calculate_fee_for_fulfillment_with_match(
+            &taker_stats,
+            &Some(&mut maker_stats),
+            calculate_perp_fee_notional(
+                &market,
+                100 * BASE_PRECISION_U64,
+                50 * QUOTE_PRECISION_U64,
+            )
+            .unwrap(),
+            &FeeStructure::test_default(),
+            0,
+            0,
+            0,
+            false,
+            &None,
+            &MarketType::Perp,
+            market.fee_adjustment,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(taker_fee, 25000);
+        assert_eq!(maker_rebate, 15000);
+        assert_eq!(fee_to_market, 10000);
+    }
+}
@@ -30,7 +30,8 @@ pub fn calculate_funding_rate_long_short(
     let settled_net_market_position = market
         .amm
         .base_asset_amount_with_amm
-        .safe_add(market.amm.base_asset_amount_with_unsettled_lp)?;
+        .safe_add(market.amm.base_asset_amount_with_unsettled_lp)?
+        .safe_add(market.amm.base_asset_amount_with_complete_sets.cast()?)?;
 
     let net_market_position_funding_payment =
         calculate_funding_payment_in_quote_precision(funding_rate, settled_net_market_position)?;
@@ -0,0 +1,249 @@
+use crate::controller::amm::SwapDirection;
+use crate::controller::position::PositionDirection;
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::{
+    BASE_PRECISION_I128, LMSR_LN_2, LMSR_PRECISION_I128, MAX_PREDICTION_MARKET_PRICE,
+    PRICE_PRECISION_I128,
+};
+use crate::math::safe_math::SafeMath;
+use crate::state::perp_market::AMM;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+// e^-29 < 1 / LMSR_PRECISION
+const LMSR_MIN_EXP_EXPONENT: i128 = -29 * LMSR_PRECISION_I128;
+const LMSR_MAX_SERIES_TERMS: i128 = 32;
+// LMSR_PRECISION / QUOTE_PRECISION
+const LMSR_TO_QUOTE_PRECISION_RATIO: i128 = 1_000_000;
+
+/// e^x for x <= 0
+/// precision: LMSR_PRECISION
+pub fn exp_non_positive(x: i128) -> DriftResult<i128> {
+    validate!(
+        x <= 0,
+        ErrorCode::MathError,
+        "exp_non_positive called with positive exponent {}",
+        x
+    )?;
+
+    if x < LMSR_MIN_EXP_EXPONENT {
+        return Ok(0);
+    }
+
+    // x = -k * ln(2) + r with r in (-ln(2), 0]
+    let k = x.unsigned_abs().safe_div(LMSR_LN_2.unsigned_abs())?;
+    let r = x.safe_add(k.cast::<i128>()?.safe_mul(LMSR_LN_2)?)?;
+
+    let mut term = LMSR_PRECISION_I128;
+    let mut sum = LMSR_PRECISION_I128;
+    for i in 1..=LMSR_MAX_SERIES_TERMS {
+        term = term
+            .safe_mul(r)?
+            .safe_div(LMSR_PRECISION_I128.safe_mul(i)?)?;
+        if term == 0 {
+            break;
+        }
+        sum = sum.safe_add(term)?;
+    }
+
+    Ok(sum >> k)
+}
+
+/// natural log of x for x > 0
+/// precision: LMSR_PRECISION
+pub fn ln(x: i128) -> DriftResult<i128> {
+    validate!(
+        x > 0,
+        ErrorCode::MathError,
+        "ln called with non-positive value {}",
+        x
+    )?;
+
+    // x = m * 2^k with m in [1, 2)
+    let mut m = x;
+    let mut k: i128 = 0;
+    while m >= LMSR_PRECISION_I128.safe_mul(2)? {
+        m = m.safe_div(2)?;
+        k = k.safe_add(1)?;
+    }
+    while m < LMSR_PRECISION_I128 {
+        m = m.safe_mul(2)?;
+        k = k.safe_sub(1)?;
+    }
+
+    // ln(m) = 2 * atanh(s) = 2 * (s + s^3/3 + s^5/5 + ...) with s = (m - 1) / (m + 1) <= 1/3
+    let s = m
+        .safe_sub(LMSR_PRECISION_I128)?
+        .safe_mul(LMSR_PRECISION_I128)?
+        .safe_div(m.safe_add(LMSR_PRECISION_I128)?)?;
+    let s_squared = s.safe_mul(s)?.safe_div(LMSR_PRECISION_I128)?;
+
+    let mut power = s;
+    let mut sum = s;
+    for i in 1..=LMSR_MAX_SERIES_TERMS {
+        power = power.safe_mul(s_squared)?.safe_div(LMSR_PRECISION_I128)?;
+        let term = power.safe_div(i.safe_mul(2)?.safe_add(1)?)?;
+        if term == 0 {
+            break;
+        }
+        sum = sum.safe_add(term)?;
+    }
+
+    k.safe_mul(LMSR_LN_2)?.safe_add(sum.safe_mul(2)?)
+}
+
+/// ln(1 + e^z)
+/// precision: LMSR_PRECISION
+pub fn softplus(z: i128) -> DriftResult<i128> {
+    let exp = exp_non_positive(-z.abs())?;
+    z.max(0).safe_add(ln(LMSR_PRECISION_I128.safe_add(exp)?)?)
+}
+
+/// 1 / (1 + e^-z)
+/// precision: LMSR_PRECISION
+pub fn sigmoid(z: i128) -> DriftResult<i128> {
+    let exp = exp_non_positive(-z.abs())?;
+    let denominator = LMSR_PRECISION_I128.safe_add(exp)?;
+
+    if z >= 0 {
+        LMSR_PRECISION_I128
+            .safe_mul(LMSR_PRECISION_I128)?
+            .safe_div(denominator)
+    } else {
+        exp.safe_mul(LMSR_PRECISION_I128)?.safe_div(denominator)
+    }
+}
+
+/// ln(p / (1 - p)) for p in (0, 1)
+/// precision: LMSR_PRECISION
+pub fn logit(p: i128) -> DriftResult<i128> {
+    validate!(
+        p > 0 && p < LMSR_PRECISION_I128,
+        ErrorCode::MathError,
+        "logit called with p {} outside (0, 1)",
+        p
+    )?;
+
+    ln(p.safe_mul(LMSR_PRECISION_I128)?
+        .safe_div(LMSR_PRECISION_I128.safe_sub(p)?)?)
+}
+
+/// the lmsr exponent q / b for a net user position q
+/// precision: LMSR_PRECISION
+fn calculate_lmsr_exponent(base_asset_amount: i128, liquidity: u32) -> DriftResult<i128> {
+    validate!(
+        liquidity > 0,
+        ErrorCode::InvalidLmsrCurve,
+        "lmsr liquidity must be positive"
+    )?;
+
+    base_asset_amount
+        .safe_mul(LMSR_PRECISION_I128)?
+        .safe_div(liquidity.cast::<i128>()?.safe_mul(BASE_PRECISION_I128)?)
+}
+
+/// The lmsr cost function C(q) = b * ln(1 + e^(q / b)) divided by b
+/// precision: LMSR_PRECISION
+pub fn calculate_lmsr_cost(base_asset_amount: i128, liquidity: u32) -> DriftResult<i128> {
+    softplus(calculate_lmsr_exponent(base_asset_amount, liquidity)?)
+}
+
+/// The instantaneous lmsr price for the amm's net user position
+/// precision: PRICE_PRECISION
+pub fn calculate_lmsr_price(base_asset_amount: i128, liquidity: u32) -> DriftResult<u64> {
+    let price = sigmoid(calculate_lmsr_exponent(base_asset_amount, liquidity)?)?
+        .safe_mul(PRICE_PRECISION_I128)?
+        .safe_div(LMSR_PRECISION_I128)?
+        .cast::<u64>()?;
+
+    // keep the price strictly inside the payout range so the reserves stay valid
+    Ok(price.clamp(1, MAX_PREDICTION_MARKET_PRICE - 1))
+}
+
+/// Quote asset amount paid (SwapDirection::Remove) or received (SwapDirection::Add) by the user
+/// for swapping base_asset_swap_amount with the lmsr
+/// precision: QUOTE_PRECISION
+pub fn calculate_lmsr_quote_asset_amount(
+    amm: &AMM,
+    base_asset_swap_amount: u64,
+    direction: SwapDirection,
+) -> DriftResult<u64> {
+    let base_asset_amount_before = amm.base_asset_amount_with_amm;
+    let base_asset_amount_after = match direction {
+        SwapDirection::Remove => {
+            base_asset_amount_before.safe_add(base_asset_swap_amount.cast()?)?
+        }
+        SwapDirection::Add => base_asset_amount_before.safe_sub(base_asset_swap_amount.cast()?)?,
+    };
+
+    let cost_delta = calculate_lmsr_cost(base_asset_amount_after, amm.lmsr_liquidity)?
+        .safe_sub(calculate_lmsr_cost(
+            base_asset_amount_before,
+            amm.lmsr_liquidity,
+        )?)?
+        .abs()
+        .safe_mul(amm.lmsr_liquidity.cast()?)?;
+
+    // round in favor of the amm
+    let quote_asset_amount = match direction {
+        SwapDirection::Remove => cost_delta.safe_div_ceil(LMSR_TO_QUOTE_PRECISION_RATIO)?,
+        SwapDirection::Add => cost_delta.safe_div(LMSR_TO_QUOTE_PRECISION_RATIO)?,
+    };
+
+    quote_asset_amount.cast()
+}
+
+/// Base asset amount the lmsr can trade before its price reaches limit_price
+pub fn calculate_lmsr_base_asset_amount_to_trade_to_price(
+    amm: &AMM,
+    limit_price: u64,
+) -> DriftResult<(u64, PositionDirection)> {
+    validate!(
+        limit_price > 0,
+        ErrorCode::InvalidOrderLimitPrice,
+        "limit_price <= 0"
+    )?;
+
+    let limit_price = limit_price.clamp(1, MAX_PREDICTION_MARKET_PRICE - 1);
+
+    let target_base_asset_amount = logit(
+        limit_price
+            .cast::<i128>()?
+            .safe_mul(LMSR_PRECISION_I128)?
+            .safe_div(PRICE_PRECISION_I128)?,
+    )?
+    .safe_mul(
+        amm.lmsr_liquidity
+            .cast::<i128>()?
+            .safe_mul(BASE_PRECISION_I128)?,
+    )?
+    .safe_div(LMSR_PRECISION_I128)?;
+
+    let base_asset_amount_delta =
+        target_base_asset_amount.safe_sub(amm.base_asset_amount_with_amm)?;
+
+    if base_asset_amount_delta > 0 {
+        Ok((
+            base_asset_amount_delta.unsigned_abs().cast()?,
+            PositionDirection::Long,
+        ))
+    } else {
+        Ok((
+            base_asset_amount_delta.unsigned_abs().cast()?,
+            PositionDirection::Short,
+        ))
+    }
+}
+
+/// The most the lmsr can lose starting from a 50/50 price, b * ln(2)
+/// precision: QUOTE_PRECISION
+pub fn calculate_lmsr_worst_case_loss(liquidity: u32) -> DriftResult<u128> {
+    liquidity
+        .cast::<i128>()?
+        .safe_mul(LMSR_LN_2)?
+        .safe_div_ceil(LMSR_TO_QUOTE_PRECISION_RATIO)?
+        .cast()
+}
@@ -0,0 +1,108 @@
+use crate::controller::amm::SwapDirection;
+use crate::controller::position::PositionDirection;
+use crate::error::ErrorCode;
+use crate::math::constants::{BASE_PRECISION_I128, BASE_PRECISION_U64, LMSR_PRECISION_I128};
+use crate::math::lmsr::*;
+use crate::state::perp_market::{AMMCurve, AMM};
+
+#[test]
+fn exp_and_ln() {
+    assert_eq!(exp_non_positive(0).unwrap(), LMSR_PRECISION_I128);
+    assert_eq!(
+        exp_non_positive(-LMSR_PRECISION_I128).unwrap(),
+        367879441171
+    );
+    assert_eq!(
+        exp_non_positive(-5 * LMSR_PRECISION_I128).unwrap(),
+        6737946999
+    );
+    assert_eq!(exp_non_positive(-30 * LMSR_PRECISION_I128).unwrap(), 0);
+    assert!(exp_non_positive(1).is_err());
+
+    assert_eq!(ln(LMSR_PRECISION_I128).unwrap(), 0);
+    assert_eq!(ln(2 * LMSR_PRECISION_I128).unwrap(), 693147180560);
+    assert_eq!(ln(LMSR_PRECISION_I128 / 2).unwrap(), -693147180560);
+    assert_eq!(ln(1_000_000).unwrap(), -13815510557968);
+    assert!(ln(0).is_err());
+
+    assert_eq!(softplus(0).unwrap(), 693147180560);
+    assert_eq!(sigmoid(0).unwrap(), LMSR_PRECISION_I128 / 2);
+    assert_eq!(sigmoid(LMSR_PRECISION_I128).unwrap(), 731058578630);
+    assert_eq!(sigmoid(-LMSR_PRECISION_I128).unwrap(), 268941421369);
+    assert_eq!(logit(LMSR_PRECISION_I128 / 2).unwrap(), 0);
+    assert_eq!(logit(750_000_000_000).unwrap(), 1098612288662);
+}
+
+#[test]
+fn lmsr_price_and_cost() {
+    let mut amm = AMM {
+        curve: AMMCurve::Lmsr,
+        lmsr_liquidity: 1000,
+        ..AMM::default()
+    };
+
+    assert_eq!(calculate_lmsr_price(0, 1000).unwrap(), 500000);
+    assert_eq!(
+        calculate_lmsr_price(1000 * BASE_PRECISION_I128, 1000).unwrap(),
+        731058
+    );
+    assert_eq!(
+        calculate_lmsr_price(-1000 * BASE_PRECISION_I128, 1000).unwrap(),
+        268941
+    );
+    assert_eq!(calculate_lmsr_price(0, 0), Err(ErrorCode::InvalidLmsrCurve));
+
+    // buying 100 shares at 50% costs more than 50
+    let cost =
+        calculate_lmsr_quote_asset_amount(&amm, 100 * BASE_PRECISION_U64, SwapDirection::Remove)
+            .unwrap();
+    assert_eq!(cost, 51249480);
+
+    let proceeds =
+        calculate_lmsr_quote_asset_amount(&amm, 100 * BASE_PRECISION_U64, SwapDirection::Add)
+            .unwrap();
+    assert_eq!(proceeds, 48750520);
+
+    // selling back what was bought never pays out more than it cost
+    amm.base_asset_amount_with_amm = 100 * BASE_PRECISION_I128;
+    let proceeds =
+        calculate_lmsr_quote_asset_amount(&amm, 100 * BASE_PRECISION_U64, SwapDirection::Add)
+            .unwrap();
+    assert_eq!(proceeds, 51249479);
+    assert!(proceeds <= cost);
+
+    // amm loss is bounded by b * ln(2)
+    amm.base_asset_amount_with_amm = 0;
+    let cost = calculate_lmsr_quote_asset_amount(
+        &amm,
+        100_000 * BASE_PRECISION_U64,
+        SwapDirection::Remove,
+    )
+    .unwrap();
+    assert_eq!(cost, 99306852820);
+    assert_eq!(calculate_lmsr_worst_case_loss(1000).unwrap(), 693147181);
+    assert!(100_000_000_000 - cost < calculate_lmsr_worst_case_loss(1000).unwrap() as u64);
+}
+
+#[test]
+fn lmsr_base_asset_amount_to_trade_to_price() {
+    let amm = AMM {
+        curve: AMMCurve::Lmsr,
+        lmsr_liquidity: 1000,
+        ..AMM::default()
+    };
+
+    let (base_asset_amount, direction) =
+        calculate_lmsr_base_asset_amount_to_trade_to_price(&amm, 750_000).unwrap();
+    assert_eq!(base_asset_amount, 1098612288662);
+    assert_eq!(direction, PositionDirection::Long);
+
+    let (base_asset_amount, direction) =
+        calculate_lmsr_base_asset_amount_to_trade_to_price(&amm, 250_000).unwrap();
+    assert_eq!(base_asset_amount, 1098612288678);
+    assert_eq!(direction, PositionDirection::Short);
+
+    let (base_asset_amount, _) =
+        calculate_lmsr_base_asset_amount_to_trade_to_price(&amm, 500_000).unwrap();
+    assert_eq!(base_asset_amount, 0);
+}
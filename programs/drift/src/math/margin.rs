@@ -1,8 +1,9 @@
 use crate::error::DriftResult;
 use crate::error::ErrorCode;
 use crate::math::constants::{
-    MARGIN_PRECISION_U128, MAX_POSITIVE_UPNL_FOR_INITIAL_MARGIN, PRICE_PRECISION,
-    SPOT_IMF_PRECISION_U128, SPOT_WEIGHT_PRECISION, SPOT_WEIGHT_PRECISION_U128,
+    BASE_PRECISION_I128, CONFIDENCE_MARGIN_MULTIPLIER_PRECISION, MARGIN_PRECISION_U128,
+    MAX_POSITIVE_UPNL_FOR_INITIAL_MARGIN, PRICE_PRECISION, SPOT_IMF_PRECISION_U128,
+    SPOT_WEIGHT_PRECISION, SPOT_WEIGHT_PRECISION_U128,
 };
 use crate::math::position::calculate_base_asset_value_and_pnl_with_oracle_price;
 
@@ -19,7 +20,9 @@ use crate::math::safe_math::SafeMath;
 use crate::state::margin_calculation::{MarginCalculation, MarginContext, MarketIdentifier};
 use crate::state::oracle::{OraclePriceData, StrictOraclePrice};
 use crate::state::oracle_map::OracleMap;
-use crate::state::perp_market::{ContractTier, MarketStatus, PerpMarket};
+use crate::state::perp_market::{
+    ContractTier, MarketStatus, PerpMarket, PredictionMarketPriceBounds,
+};
 use crate::state::perp_market_map::PerpMarketMap;
 use crate::state::spot_market::{AssetTier, SpotBalanceType};
 use crate::state::spot_market_map::SpotMarketMap;
@@ -93,6 +96,23 @@ pub fn calculate_size_discount_asset_weight(
     Ok(min_asset_weight)
 }
 
+/// The k * confidence deposits are marked down and liabilities marked up by for margin, for markets
+/// that opt in with a confidence margin multiplier
+pub fn calculate_confidence_margin_haircut(
+    oracle_price_data: &OraclePriceData,
+    confidence_margin_multiplier: u8,
+) -> DriftResult<i64> {
+    if confidence_margin_multiplier == 0 {
+        return Ok(0);
+    }
+
+    oracle_price_data
+        .confidence
+        .safe_mul(confidence_margin_multiplier.cast()?)?
+        .safe_div(CONFIDENCE_MARGIN_MULTIPLIER_PRECISION)?
+        .cast()
+}
+
 pub fn calculate_perp_position_value_and_pnl(
     market_position: &PerpPosition,
     market: &PerpMarket,
@@ -102,9 +122,21 @@ pub fn calculate_perp_position_value_and_pnl(
     user_custom_margin_ratio: u32,
     user_high_leverage_mode: bool,
     track_open_order_fraction: bool,
+    prediction_event_liability: Option<&PredictionEventLiability>,
 ) -> DriftResult<(u128, i128, u128, u128, u128)> {
+    let confidence_haircut = calculate_confidence_margin_haircut(
+        oracle_price_data,
+        market.amm.confidence_margin_multiplier,
+    )?;
+
+    // with a confidence haircut, longs are valued at the low end of the confidence interval and
+    // shorts at the high end
     let valuation_price = if market.status == MarketStatus::Settlement {
-        market.expiry_price
+        market.get_settlement_price(market_position)?
+    } else if market_position.base_asset_amount > 0 {
+        oracle_price_data.price.safe_sub(confidence_haircut)?.max(0)
+    } else if market_position.base_asset_amount < 0 {
+        oracle_price_data.price.safe_add(confidence_haircut)?
     } else {
         oracle_price_data.price
     };
@@ -126,8 +158,24 @@ pub fn calculate_perp_position_value_and_pnl(
 
     let total_unrealized_pnl = unrealized_pnl.safe_add(unrealized_funding.cast()?)?;
 
+    // prediction market liabilities are already the max loss to the price bounds
+    let prediction_market_price_bounds = market.get_prediction_market_price_bounds();
+    let liability_price = if prediction_market_price_bounds.is_some() {
+        oracle_price_data.price
+    } else {
+        oracle_price_data.price.safe_add(confidence_haircut)?
+    };
+
     let (worst_case_base_asset_amount, worse_case_liability_value) = market_position
-        .worst_case_liability_value(oracle_price_data.price, market.contract_type)?;
+        .worst_case_liability_value(liability_price, prediction_market_price_bounds)?;
+
+    // outcomes in the same prediction event offset each other, only charge the event's worst-case loss
+    let worse_case_liability_value = match prediction_event_liability {
+        Some(prediction_event_liability) if market.status != MarketStatus::Settlement => {
+            prediction_event_liability.net_liability_value(worse_case_liability_value)?
+        }
+        _ => worse_case_liability_value,
+    };
 
     // for calculating the perps value, since it's a liability, use the large of twap and quote oracle price
     let worse_case_liability_value = worse_case_liability_value
@@ -205,6 +253,213 @@ pub fn calculate_perp_position_value_and_pnl(
     ))
 }
 
+/// A user's liability across its positions in the outcomes of a prediction event. Exactly one
+/// outcome resolves to the upper bound and the rest to the lower bound, so the most the user can
+/// lose is the worst case over which outcome wins rather than the sum of every leg's liability
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PredictionEventLiability {
+    pub event_index: u16,
+    pub number_of_outcomes: u8,
+    pub number_of_positions: u8,
+    /// sum of each leg's worst-case liability
+    /// precision: QUOTE_PRECISION
+    pub gross_liability_value: u128,
+    /// sum of each leg's worst-case loss if its outcome loses
+    /// precision: QUOTE_PRECISION
+    pub losing_loss_value: i128,
+    /// max over legs of the worst-case loss if its outcome wins less the loss if it loses
+    /// precision: QUOTE_PRECISION
+    pub max_winning_loss_adjustment: i128,
+}
+
+impl PredictionEventLiability {
+    pub fn new(event_index: u16, number_of_outcomes: u8) -> Self {
+        PredictionEventLiability {
+            event_index,
+            number_of_outcomes,
+            max_winning_loss_adjustment: i128::MIN,
+            ..PredictionEventLiability::default()
+        }
+    }
+
+    pub fn add_position(
+        &mut self,
+        market_position: &PerpPosition,
+        oracle_price: i64,
+        price_bounds: PredictionMarketPriceBounds,
+    ) -> DriftResult {
+        let (_, liability_value) =
+            market_position.worst_case_liability_value(oracle_price, Some(price_bounds))?;
+
+        let base_asset_amount_all_bids_fill = market_position
+            .base_asset_amount
+            .safe_add(market_position.open_bids)?
+            .cast::<i128>()?;
+        let base_asset_amount_all_asks_fill = market_position
+            .base_asset_amount
+            .safe_add(market_position.open_asks)?
+            .cast::<i128>()?;
+
+        let calculate_loss = |settle_price: u64| -> DriftResult<i128> {
+            let price_delta = oracle_price
+                .cast::<i128>()?
+                .safe_sub(settle_price.cast()?)?;
+            let loss_all_bids_fill = base_asset_amount_all_bids_fill
+                .safe_mul(price_delta)?
+                .safe_div(BASE_PRECISION_I128)?;
+            let loss_all_asks_fill = base_asset_amount_all_asks_fill
+                .safe_mul(price_delta)?
+                .safe_div(BASE_PRECISION_I128)?;
+            Ok(loss_all_bids_fill.max(loss_all_asks_fill))
+        };
+
+        let losing_loss = calculate_loss(price_bounds.lower)?;
+        let winning_loss = calculate_loss(price_bounds.upper)?;
+
+        self.number_of_positions = self.number_of_positions.safe_add(1)?;
+        self.gross_liability_value = self.gross_liability_value.safe_add(liability_value)?;
+        self.losing_loss_value = self.losing_loss_value.safe_add(losing_loss)?;
+        self.max_winning_loss_adjustment = self
+            .max_winning_loss_adjustment
+            .max(winning_loss.safe_sub(losing_loss)?);
+
+        Ok(())
+    }
+
+    /// The most the user can lose across the event
+    /// precision: QUOTE_PRECISION
+    pub fn netted_liability_value(&self) -> DriftResult<u128> {
+        if self.number_of_positions == 0 {
+            return Ok(0);
+        }
+
+        // event isnt activated, outcomes can still be added so no leg is known to pay out
+        if self.number_of_outcomes == 0 {
+            return Ok(self.gross_liability_value);
+        }
+
+        // if an outcome the user has no position in wins, every leg loses
+        let max_winning_loss_adjustment = if self.number_of_positions < self.number_of_outcomes {
+            self.max_winning_loss_adjustment.max(0)
+        } else {
+            self.max_winning_loss_adjustment
+        };
+
+        let worst_case_loss = self
+            .losing_loss_value
+            .safe_add(max_winning_loss_adjustment)?
+            .max(0)
+            .unsigned_abs();
+
+        Ok(worst_case_loss.min(self.gross_liability_value))
+    }
+
+    /// Scales a leg's liability by the event's netted liability over its gross liability
+    pub fn net_liability_value(&self, liability_value: u128) -> DriftResult<u128> {
+        if self.gross_liability_value == 0 {
+            return Ok(liability_value);
+        }
+
+        liability_value
+            .safe_mul(self.netted_liability_value()?)?
+            .safe_div(self.gross_liability_value)
+    }
+
+    /// Scales a leg's margin ratio by the event's netted liability over its gross liability
+    pub fn net_margin_ratio(&self, margin_ratio: u32) -> DriftResult<u32> {
+        self.net_liability_value(margin_ratio.cast()?)?.cast()
+    }
+}
+
+/// Aggregates the user's positions in prediction event outcome markets by event
+pub fn calculate_prediction_event_liabilities(
+    user: &User,
+    perp_market_map: &PerpMarketMap,
+    oracle_map: &mut OracleMap,
+) -> DriftResult<Vec<PredictionEventLiability>> {
+    let mut prediction_event_liabilities: Vec<PredictionEventLiability> = vec![];
+
+    for market_position in user.perp_positions.iter() {
+        if market_position.is_available() {
+            continue;
+        }
+
+        let market = &perp_market_map.get_ref(&market_position.market_index)?;
+
+        if !market.is_prediction_event_outcome() || market.status == MarketStatus::Settlement {
+            continue;
+        }
+
+        let price_bounds = match market.get_prediction_market_price_bounds() {
+            Some(price_bounds) => price_bounds,
+            None => continue,
+        };
+
+        let oracle_price = oracle_map.get_price_data(&market.oracle_id())?.price;
+
+        let market_position = market_position.simulate_settled_lp_position(market, oracle_price)?;
+
+        let index = match prediction_event_liabilities
+            .iter()
+            .position(|liability| liability.event_index == market.prediction_event_index)
+        {
+            Some(index) => index,
+            None => {
+                prediction_event_liabilities.push(PredictionEventLiability::new(
+                    market.prediction_event_index,
+                    market.get_prediction_event_outcomes(),
+                ));
+                prediction_event_liabilities.len() - 1
+            }
+        };
+
+        prediction_event_liabilities[index].add_position(
+            &market_position,
+            oracle_price,
+            price_bounds,
+        )?;
+    }
+
+    Ok(prediction_event_liabilities)
+}
+
+pub fn get_prediction_event_liability<'a>(
+    prediction_event_liabilities: &'a [PredictionEventLiability],
+    market: &PerpMarket,
+) -> Option<&'a PredictionEventLiability> {
+    if !market.is_prediction_event_outcome() {
+        return None;
+    }
+
+    prediction_event_liabilities
+        .iter()
+        .find(|liability| liability.event_index == market.prediction_event_index)
+}
+
+/// Scales the margin ratio of a prediction event outcome by the user's netted event liability
+pub fn calculate_prediction_event_netted_margin_ratio(
+    user: &User,
+    perp_market_map: &PerpMarketMap,
+    oracle_map: &mut OracleMap,
+    market_index: u16,
+    margin_ratio: u32,
+) -> DriftResult<u32> {
+    let market = perp_market_map.get_ref(&market_index)?;
+    if !market.is_prediction_event_outcome() {
+        return Ok(margin_ratio);
+    }
+
+    let prediction_event_liabilities =
+        calculate_prediction_event_liabilities(user, perp_market_map, oracle_map)?;
+
+    match get_prediction_event_liability(&prediction_event_liabilities, &market) {
+        Some(prediction_event_liability) => {
+            prediction_event_liability.net_margin_ratio(margin_ratio)
+        }
+        None => Ok(margin_ratio),
+    }
+}
+
 pub fn calculate_user_safest_position_tiers(
     user: &User,
     perp_market_map: &PerpMarketMap,
@@ -283,7 +538,11 @@ pub fn calculate_margin_requirement_and_total_collateral_and_liability_info(
                 .historical_oracle_data
                 .last_oracle_price_twap_5min,
             calculation.context.strict,
-        );
+        )
+        .with_confidence_haircut(calculate_confidence_margin_haircut(
+            oracle_price_data,
+            spot_market.confidence_margin_multiplier,
+        )?);
         strict_oracle_price.validate()?;
 
         if spot_market.market_index == 0 {
@@ -481,6 +740,9 @@ pub fn calculate_margin_requirement_and_total_collateral_and_liability_info(
         }
     }
 
+    let prediction_event_liabilities =
+        calculate_prediction_event_liabilities(user, perp_market_map, oracle_map)?;
+
     for market_position in user.perp_positions.iter() {
         if market_position.is_available() {
             continue;
@@ -514,7 +776,11 @@ pub fn calculate_margin_requirement_and_total_collateral_and_liability_info(
                 .historical_oracle_data
                 .last_oracle_price_twap_5min,
             calculation.context.strict,
-        );
+        )
+        .with_confidence_haircut(calculate_confidence_margin_haircut(
+            quote_oracle_price_data,
+            quote_spot_market.confidence_margin_multiplier,
+        )?);
         drop(quote_spot_market);
 
         let (oracle_price_data, oracle_validity) = oracle_map.get_price_data_and_validity(
@@ -540,6 +806,7 @@ pub fn calculate_margin_requirement_and_total_collateral_and_liability_info(
             user_custom_margin_ratio,
             user_high_leverage_mode,
             calculation.track_open_orders_fraction(),
+            get_prediction_event_liability(&prediction_event_liabilities, market),
         )?;
 
         calculation.update_fuel_perp_bonus(
@@ -950,7 +1217,7 @@ pub fn calculate_user_equity(
             is_oracle_valid_for_action(oracle_validity, Some(DriftAction::MarginCalc))?;
 
         let valuation_price = if market.status == MarketStatus::Settlement {
-            market.expiry_price
+            market.get_settlement_price(market_position)?
         } else {
             oracle_price_data.price
         };
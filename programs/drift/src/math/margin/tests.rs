@@ -294,6 +294,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -372,6 +373,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -437,6 +439,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -462,6 +465,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -511,6 +515,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -536,6 +541,7 @@ mod test {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -3943,6 +3949,7 @@ mod calculate_perp_position_value_and_pnl_prediction_market {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -3958,6 +3965,7 @@ mod calculate_perp_position_value_and_pnl_prediction_market {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -4006,6 +4014,7 @@ mod calculate_perp_position_value_and_pnl_prediction_market {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -4021,6 +4030,7 @@ mod calculate_perp_position_value_and_pnl_prediction_market {
             0,
             false,
             false,
+            None,
         )
         .unwrap();
 
@@ -4169,3 +4179,458 @@ mod pools {
         assert_eq!(result.unwrap_err(), ErrorCode::InvalidPoolId)
     }
 }
+
+#[cfg(test)]
+mod prediction_event_netting {
+    use std::str::FromStr;
+
+    use anchor_lang::Owner;
+    use solana_program::pubkey::Pubkey;
+
+    use crate::math::constants::{
+        BASE_PRECISION_I64, MAX_PREDICTION_MARKET_PRICE_I64, PREDICTION_EVENT_PENDING_OUTCOMES,
+        QUOTE_PRECISION_I64, SPOT_CUMULATIVE_INTEREST_PRECISION, SPOT_WEIGHT_PRECISION,
+    };
+    use crate::math::margin::{
+        calculate_margin_requirement_and_total_collateral_and_liability_info,
+        MarginRequirementType, PredictionEventLiability,
+    };
+    use crate::state::margin_calculation::MarginContext;
+    use crate::state::oracle::{HistoricalOracleData, OracleSource};
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::perp_market::{
+        ContractType, MarketStatus, PerpMarket, PredictionMarketPriceBounds, AMM,
+    };
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::spot_market::SpotMarket;
+    use crate::state::spot_market_map::SpotMarketMap;
+    use crate::state::user::{PerpPosition, User};
+    use crate::test_utils::get_hardcoded_pyth_price;
+    use crate::{create_account_info, create_anchor_account_info};
+
+    fn long_position(market_index: u16, base_asset_amount: i64) -> PerpPosition {
+        PerpPosition {
+            market_index,
+            base_asset_amount,
+            quote_asset_amount: -base_asset_amount / 2 * QUOTE_PRECISION_I64 / BASE_PRECISION_I64,
+            ..PerpPosition::default()
+        }
+    }
+
+    #[test]
+    fn every_outcome_covered() {
+        let oracle_price = MAX_PREDICTION_MARKET_PRICE_I64 / 2;
+        let mut prediction_event_liability = PredictionEventLiability::new(0, 2);
+
+        prediction_event_liability
+            .add_position(
+                &long_position(0, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+        prediction_event_liability
+            .add_position(
+                &long_position(1, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+
+        // one of the two outcomes always pays out $10
+        assert_eq!(prediction_event_liability.gross_liability_value, 10_000_000);
+        assert_eq!(
+            prediction_event_liability.netted_liability_value().unwrap(),
+            0
+        );
+        assert_eq!(
+            prediction_event_liability
+                .net_liability_value(5_000_000)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            prediction_event_liability.net_margin_ratio(10_000).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn outcome_not_covered() {
+        let oracle_price = MAX_PREDICTION_MARKET_PRICE_I64 / 3;
+        let mut prediction_event_liability = PredictionEventLiability::new(0, 3);
+
+        prediction_event_liability
+            .add_position(
+                &long_position(0, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+        prediction_event_liability
+            .add_position(
+                &long_position(1, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+
+        // if the third outcome wins both legs lose
+        assert_eq!(prediction_event_liability.gross_liability_value, 6_666_660);
+        assert_eq!(
+            prediction_event_liability.netted_liability_value().unwrap(),
+            6_666_660
+        );
+        assert_eq!(
+            prediction_event_liability
+                .net_liability_value(3_333_330)
+                .unwrap(),
+            3_333_330
+        );
+    }
+
+    #[test]
+    fn event_not_activated() {
+        let oracle_price = MAX_PREDICTION_MARKET_PRICE_I64 / 2;
+        let mut prediction_event_liability = PredictionEventLiability::new(0, 0);
+
+        prediction_event_liability
+            .add_position(
+                &long_position(0, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+
+        // a lone outcome isnt hedged until every outcome is known
+        assert_eq!(prediction_event_liability.gross_liability_value, 5_000_000);
+        assert_eq!(
+            prediction_event_liability.netted_liability_value().unwrap(),
+            5_000_000
+        );
+    }
+
+    #[test]
+    fn long_and_short_same_outcome() {
+        let oracle_price = MAX_PREDICTION_MARKET_PRICE_I64 / 2;
+        let mut prediction_event_liability = PredictionEventLiability::new(0, 2);
+
+        prediction_event_liability
+            .add_position(
+                &long_position(0, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+        prediction_event_liability
+            .add_position(
+                &PerpPosition {
+                    market_index: 1,
+                    base_asset_amount: -10 * BASE_PRECISION_I64,
+                    quote_asset_amount: 5 * QUOTE_PRECISION_I64,
+                    ..PerpPosition::default()
+                },
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+
+        // both legs lose $5 if the second outcome wins
+        assert_eq!(prediction_event_liability.gross_liability_value, 10_000_000);
+        assert_eq!(
+            prediction_event_liability.netted_liability_value().unwrap(),
+            10_000_000
+        );
+    }
+
+    #[test]
+    fn open_orders_use_worst_case_fill() {
+        let oracle_price = MAX_PREDICTION_MARKET_PRICE_I64 / 2;
+        let mut prediction_event_liability = PredictionEventLiability::new(0, 2);
+
+        prediction_event_liability
+            .add_position(
+                &long_position(0, 10 * BASE_PRECISION_I64),
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+        prediction_event_liability
+            .add_position(
+                &PerpPosition {
+                    market_index: 1,
+                    base_asset_amount: 10 * BASE_PRECISION_I64,
+                    quote_asset_amount: -5 * QUOTE_PRECISION_I64,
+                    open_asks: -10 * BASE_PRECISION_I64,
+                    open_orders: 1,
+                    ..PerpPosition::default()
+                },
+                oracle_price,
+                PredictionMarketPriceBounds::default(),
+            )
+            .unwrap();
+
+        // the ask filling removes the hedge, leaving the first leg's $5 at risk
+        assert_eq!(prediction_event_liability.gross_liability_value, 10_000_000);
+        assert_eq!(
+            prediction_event_liability.netted_liability_value().unwrap(),
+            5_000_000
+        );
+    }
+
+    #[test]
+    fn margin_calculation() {
+        let slot = 0_u64;
+
+        let mut oracle_price = get_hardcoded_pyth_price(MAX_PREDICTION_MARKET_PRICE_I64 / 2, 6);
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, slot, None).unwrap();
+
+        let mut first_outcome_market = PerpMarket {
+            market_index: 0,
+            amm: AMM {
+                oracle: oracle_price_key,
+                ..AMM::default()
+            },
+            margin_ratio_initial: 10_000,
+            margin_ratio_maintenance: 9_999,
+            contract_type: ContractType::Prediction,
+            prediction_event_index: 0,
+            prediction_event_outcomes: 2,
+            status: MarketStatus::Active,
+            ..PerpMarket::default()
+        };
+        create_anchor_account_info!(
+            first_outcome_market,
+            PerpMarket,
+            first_outcome_market_account_info
+        );
+        let mut second_outcome_market = PerpMarket {
+            market_index: 1,
+            ..first_outcome_market
+        };
+        create_anchor_account_info!(
+            second_outcome_market,
+            PerpMarket,
+            second_outcome_market_account_info
+        );
+        let perp_market_map = PerpMarketMap::load_multiple(
+            vec![
+                &first_outcome_market_account_info,
+                &second_outcome_market_account_info,
+            ],
+            true,
+        )
+        .unwrap();
+
+        let mut usdc_spot_market = SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            cumulative_deposit_interest: SPOT_CUMULATIVE_INTEREST_PRECISION,
+            decimals: 6,
+            initial_asset_weight: SPOT_WEIGHT_PRECISION,
+            maintenance_asset_weight: SPOT_WEIGHT_PRECISION,
+            historical_oracle_data: HistoricalOracleData::default_quote_oracle(),
+            ..SpotMarket::default()
+        };
+        create_anchor_account_info!(usdc_spot_market, SpotMarket, usdc_spot_market_account_info);
+        let spot_market_map =
+            SpotMarketMap::load_one(&usdc_spot_market_account_info, true).unwrap();
+
+        let mut user = User::default();
+        user.perp_positions[0] = long_position(0, 10 * BASE_PRECISION_I64);
+        user.perp_positions[1] = long_position(1, 5 * BASE_PRECISION_I64);
+
+        // legs charge $5 + $2.5, but the most the user can lose is $2.5 if the first outcome loses
+        for margin_requirement_type in [
+            MarginRequirementType::Initial,
+            MarginRequirementType::Maintenance,
+        ] {
+            let calculation = calculate_margin_requirement_and_total_collateral_and_liability_info(
+                &user,
+                &perp_market_map,
+                &spot_market_map,
+                &mut oracle_map,
+                MarginContext::standard(margin_requirement_type),
+            )
+            .unwrap();
+
+            let expected_margin_requirement = match margin_requirement_type {
+                MarginRequirementType::Initial => 2_499_999,
+                _ => 2_499_748,
+            };
+            assert_eq!(calculation.margin_requirement, expected_margin_requirement);
+        }
+
+        // trading before the event is activated, more outcomes can still be added
+        for market_index in [0, 1] {
+            let mut outcome_market = perp_market_map.get_ref_mut(&market_index).unwrap();
+            outcome_market.prediction_event_outcomes = PREDICTION_EVENT_PENDING_OUTCOMES;
+        }
+
+        let calculation = calculate_margin_requirement_and_total_collateral_and_liability_info(
+            &user,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            MarginContext::standard(MarginRequirementType::Initial),
+        )
+        .unwrap();
+
+        assert_eq!(calculation.margin_requirement, 7_500_000);
+
+        for market_index in [0, 1] {
+            let mut outcome_market = perp_market_map.get_ref_mut(&market_index).unwrap();
+            outcome_market.prediction_event_outcomes = 2;
+        }
+
+        // without a shared event each leg is charged in full
+        let mut first_outcome_market = perp_market_map.get_ref_mut(&0).unwrap();
+        first_outcome_market.prediction_event_index = 1;
+        drop(first_outcome_market);
+
+        let calculation = calculate_margin_requirement_and_total_collateral_and_liability_info(
+            &user,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+            MarginContext::standard(MarginRequirementType::Initial),
+        )
+        .unwrap();
+
+        assert_eq!(calculation.margin_requirement, 7_500_000);
+    }
+}
+
+mod confidence_margin_haircut {
+    use crate::math::constants::{
+        AMM_RESERVE_PRECISION, BASE_PRECISION_I64, PRICE_PRECISION_I64, QUOTE_PRECISION_I64,
+    };
+    use crate::math::margin::{
+        calculate_confidence_margin_haircut, calculate_perp_position_value_and_pnl,
+        MarginRequirementType,
+    };
+    use crate::math::spot_balance::get_strict_token_value;
+    use crate::state::oracle::{OraclePriceData, StrictOraclePrice};
+    use crate::state::perp_market::{PerpMarket, AMM};
+    use crate::state::user::PerpPosition;
+
+    #[test]
+    fn spot_deposits_and_borrows() {
+        let oracle_price_data = OraclePriceData {
+            price: 100 * PRICE_PRECISION_I64,
+            confidence: PRICE_PRECISION_I64 as u64, // 1%
+            delay: 0,
+            has_sufficient_number_of_data_points: true,
+        };
+
+        assert_eq!(
+            calculate_confidence_margin_haircut(&oracle_price_data, 0).unwrap(),
+            0
+        );
+
+        // 2x confidence
+        let confidence_haircut =
+            calculate_confidence_margin_haircut(&oracle_price_data, 20).unwrap();
+        assert_eq!(confidence_haircut, 2 * PRICE_PRECISION_I64);
+
+        let strict_oracle_price = StrictOraclePrice::new(oracle_price_data.price, 0, false)
+            .with_confidence_haircut(confidence_haircut);
+        assert_eq!(strict_oracle_price.min(), 98 * PRICE_PRECISION_I64);
+        assert_eq!(strict_oracle_price.max(), 102 * PRICE_PRECISION_I64);
+
+        let deposit_value = get_strict_token_value(1_000_000_000, 9, &strict_oracle_price).unwrap();
+        assert_eq!(deposit_value, 98 * QUOTE_PRECISION_I64 as i128);
+
+        let borrow_value = get_strict_token_value(-1_000_000_000, 9, &strict_oracle_price).unwrap();
+        assert_eq!(borrow_value, -102 * QUOTE_PRECISION_I64 as i128);
+
+        // applied on top of the twap
+        let strict_oracle_price =
+            StrictOraclePrice::new(oracle_price_data.price, 99 * PRICE_PRECISION_I64, true)
+                .with_confidence_haircut(confidence_haircut);
+        assert_eq!(strict_oracle_price.min(), 97 * PRICE_PRECISION_I64);
+        assert_eq!(strict_oracle_price.max(), 102 * PRICE_PRECISION_I64);
+    }
+
+    #[test]
+    fn perp_long() {
+        let mut market = PerpMarket {
+            market_index: 0,
+            amm: AMM {
+                base_asset_reserve: 5 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 5 * AMM_RESERVE_PRECISION,
+                sqrt_k: 5 * AMM_RESERVE_PRECISION,
+                ..AMM::default_test()
+            },
+            margin_ratio_initial: 1000,
+            margin_ratio_maintenance: 500,
+            unrealized_pnl_initial_asset_weight: 10000,
+            unrealized_pnl_maintenance_asset_weight: 10000,
+            ..PerpMarket::default()
+        };
+
+        let position = PerpPosition {
+            base_asset_amount: BASE_PRECISION_I64,
+            quote_asset_amount: -100 * QUOTE_PRECISION_I64,
+            ..PerpPosition::default()
+        };
+
+        let oracle_price_data = OraclePriceData {
+            price: 100 * PRICE_PRECISION_I64,
+            confidence: PRICE_PRECISION_I64 as u64, // 1%
+            delay: 0,
+            has_sufficient_number_of_data_points: true,
+        };
+
+        let strict_quote_price = StrictOraclePrice::test(QUOTE_PRECISION_I64);
+        let (_, weighted_pnl, worst_case_liability_value, _, _) =
+            calculate_perp_position_value_and_pnl(
+                &position,
+                &market,
+                &oracle_price_data,
+                &strict_quote_price,
+                MarginRequirementType::Initial,
+                0,
+                false,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(weighted_pnl, 0);
+        assert_eq!(
+            worst_case_liability_value,
+            100 * QUOTE_PRECISION_I64 as u128
+        );
+
+        // 2x confidence
+        market.amm.confidence_margin_multiplier = 20;
+
+        let (_, weighted_pnl, worst_case_liability_value, _, _) =
+            calculate_perp_position_value_and_pnl(
+                &position,
+                &market,
+                &oracle_price_data,
+                &strict_quote_price,
+                MarginRequirementType::Initial,
+                0,
+                false,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(weighted_pnl, -2 * QUOTE_PRECISION_I64 as i128);
+        assert_eq!(
+            worst_case_liability_value,
+            102 * QUOTE_PRECISION_I64 as u128
+        );
+    }
+}
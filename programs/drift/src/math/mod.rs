@@ -16,6 +16,7 @@ pub mod funding;
 pub mod helpers;
 pub mod insurance;
 pub mod liquidation;
+pub mod lmsr;
 pub mod lp;
 pub mod margin;
 pub mod matching;
@@ -6,7 +6,7 @@ use solana_program::msg;
 use crate::error::{DriftResult, ErrorCode};
 use crate::math::amm;
 use crate::math::casting::Cast;
-use crate::math::constants::BID_ASK_SPREAD_PRECISION;
+use crate::math::constants::{BID_ASK_SPREAD_PRECISION, PERCENTAGE_PRECISION};
 use crate::math::safe_math::SafeMath;
 
 use crate::state::oracle::{OraclePriceData, OracleSource};
@@ -140,6 +140,54 @@ pub fn is_oracle_valid_for_action(
     Ok(is_ok)
 }
 
+/// Whether a market should be priced off its fallback oracle instead of its primary oracle
+///
+/// The fallback takes over while the primary is invalid for the requested action and the fallback
+/// is valid for it, and the fallback's price is within max_divergence of the primary's last price
+pub fn should_use_fallback_oracle(
+    primary: (i64, OracleValidity),
+    fallback: Option<(i64, OracleValidity)>,
+    max_divergence: u64,
+    action: Option<DriftAction>,
+) -> DriftResult<bool> {
+    let (fallback_price, fallback_validity) = match fallback {
+        Some(fallback) => fallback,
+        None => return Ok(false),
+    };
+
+    if !is_oracle_valid_for_action(fallback_validity, action)? {
+        return Ok(false);
+    }
+
+    let (primary_price, primary_validity) = primary;
+
+    if is_oracle_valid_for_action(primary_validity, action)? {
+        return Ok(false);
+    }
+
+    if primary_price <= 0 {
+        return Ok(true);
+    }
+
+    let divergence = fallback_price
+        .safe_sub(primary_price)?
+        .unsigned_abs()
+        .cast::<u128>()?
+        .safe_mul(PERCENTAGE_PRECISION)?
+        .safe_div(primary_price.unsigned_abs().cast()?)?;
+
+    if divergence > max_divergence.cast()? {
+        msg!(
+            "Fallback oracle price {} too divergent from primary oracle price {}",
+            fallback_price,
+            primary_price
+        );
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
 pub fn block_operation(
     market: &PerpMarket,
     oracle_price_data: &OraclePriceData,
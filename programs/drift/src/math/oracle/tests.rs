@@ -1,6 +1,7 @@
 use crate::math::amm::update_oracle_price_twap;
 use crate::math::constants::{
-    AMM_RESERVE_PRECISION, PEG_PRECISION, PRICE_PRECISION, PRICE_PRECISION_U64,
+    AMM_RESERVE_PRECISION, PEG_PRECISION, PERCENTAGE_PRECISION_U64, PRICE_PRECISION,
+    PRICE_PRECISION_I64, PRICE_PRECISION_U64,
 };
 use crate::math::oracle::*;
 use crate::state::oracle::HistoricalOracleData;
@@ -131,3 +132,86 @@ fn calculate_oracle_valid() {
     assert!(oracle_status.mark_too_divergent);
     assert!(oracle_status.oracle_validity == OracleValidity::TooUncertain);
 }
+
+#[test]
+fn use_fallback_oracle() {
+    let max_divergence = PERCENTAGE_PRECISION_U64 / 100; // 1%
+    let price = 100 * PRICE_PRECISION_I64;
+
+    // healthy primary keeps the market on it
+    assert!(!should_use_fallback_oracle(
+        (price, OracleValidity::Valid),
+        Some((price, OracleValidity::Valid)),
+        max_divergence,
+        None,
+    )
+    .unwrap());
+
+    // no fallback price
+    assert!(!should_use_fallback_oracle(
+        (price, OracleValidity::StaleForAMM),
+        None,
+        max_divergence,
+        Some(DriftAction::FillOrderAmm),
+    )
+    .unwrap());
+
+    // primary invalid for the action, fallback valid and close enough
+    assert!(should_use_fallback_oracle(
+        (price, OracleValidity::StaleForAMM),
+        Some((price + PRICE_PRECISION_I64 / 2, OracleValidity::Valid)),
+        max_divergence,
+        Some(DriftAction::FillOrderAmm),
+    )
+    .unwrap());
+
+    // primary still valid for the action
+    assert!(!should_use_fallback_oracle(
+        (price, OracleValidity::StaleForAMM),
+        Some((price, OracleValidity::Valid)),
+        max_divergence,
+        Some(DriftAction::MarginCalc),
+    )
+    .unwrap());
+
+    // fallback less severe but not valid for the action either
+    assert!(!should_use_fallback_oracle(
+        (price, OracleValidity::StaleForMargin),
+        Some((price, OracleValidity::StaleForAMM)),
+        max_divergence,
+        Some(DriftAction::FillOrderAmm),
+    )
+    .unwrap());
+
+    // fallback valid but too divergent
+    assert!(!should_use_fallback_oracle(
+        (price, OracleValidity::TooUncertain),
+        Some((price - 2 * PRICE_PRECISION_I64, OracleValidity::Valid)),
+        max_divergence,
+        Some(DriftAction::MarginCalc),
+    )
+    .unwrap());
+
+    // primary non positive
+    assert!(should_use_fallback_oracle(
+        (0, OracleValidity::NonPositive),
+        Some((price, OracleValidity::StaleForAMM)),
+        max_divergence,
+        Some(DriftAction::MarginCalc),
+    )
+    .unwrap());
+    assert!(should_use_fallback_oracle(
+        (0, OracleValidity::NonPositive),
+        Some((price, OracleValidity::Valid)),
+        max_divergence,
+        None,
+    )
+    .unwrap());
+    assert!(!should_use_fallback_oracle(
+        (0, OracleValidity::NonPositive),
+        Some((0, OracleValidity::NonPositive)),
+        max_divergence,
+        Some(DriftAction::UpdateTwap),
+    )
+    .unwrap());
+}
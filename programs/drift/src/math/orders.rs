@@ -12,9 +12,8 @@ use crate::math::casting::Cast;
 use crate::state::fill_mode::FillMode;
 use crate::{
     load, math, FeeTier, State, BASE_PRECISION_I128, FEE_ADJUSTMENT_MAX,
-    MAX_PREDICTION_MARKET_PRICE, MAX_PREDICTION_MARKET_PRICE_I64, OPEN_ORDER_MARGIN_REQUIREMENT,
-    PERCENTAGE_PRECISION, PERCENTAGE_PRECISION_U64, PRICE_PRECISION_I128, PRICE_PRECISION_U64,
-    QUOTE_PRECISION_I128, SPOT_WEIGHT_PRECISION, SPOT_WEIGHT_PRECISION_I128,
+    OPEN_ORDER_MARGIN_REQUIREMENT, PERCENTAGE_PRECISION, PERCENTAGE_PRECISION_U64,
+    PRICE_PRECISION_I128, QUOTE_PRECISION_I128, SPOT_WEIGHT_PRECISION, SPOT_WEIGHT_PRECISION_I128,
 };
 
 use crate::math::constants::MARGIN_PRECISION_U128;
@@ -22,6 +21,7 @@ use crate::math::margin::{
     calculate_margin_requirement_and_total_collateral_and_liability_info, MarginRequirementType,
 };
 use crate::math::safe_math::SafeMath;
+use crate::math::safe_unwrap::SafeUnwrap;
 use crate::math::spot_balance::get_strict_token_value;
 use crate::math::spot_withdraw::get_max_withdraw_for_market_with_token_amount;
 use crate::math_error;
@@ -30,12 +30,14 @@ use crate::state::margin_calculation::{MarginCalculation, MarginContext};
 use crate::state::oracle::{OraclePriceData, StrictOraclePrice};
 use crate::state::oracle_map::OracleMap;
 use crate::state::order_params::PostOnlyParam;
-use crate::state::perp_market::{PerpMarket, AMM};
+use crate::state::perp_market::{MarketStatus, PerpMarket, PredictionMarketPriceBounds, AMM};
 use crate::state::perp_market_map::PerpMarketMap;
 use crate::state::spot_market::SpotMarket;
 use crate::state::spot_market_map::SpotMarketMap;
+use crate::state::trailing_stop::TrailingStop;
 use crate::state::user::{
-    MarketType, Order, OrderFillSimulation, OrderStatus, OrderTriggerCondition, PerpPosition, User,
+    MarketType, Order, OrderFillSimulation, OrderStatus, OrderTriggerCondition, OrderTriggerSource,
+    PerpPosition, User,
 };
 use crate::state::user_map::UserMap;
 use crate::validate;
@@ -138,11 +140,18 @@ pub fn calculate_base_asset_amount_to_fill_up_to_limit_price(
             PositionDirection::Short => limit_price.safe_add(market.amm.order_tick_size)?,
         };
 
-        math::amm_spread::calculate_base_asset_amount_to_trade_to_price(
-            &market.amm,
-            adjusted_limit_price,
-            order.direction,
-        )?
+        if market.amm.is_lmsr() {
+            math::lmsr::calculate_lmsr_base_asset_amount_to_trade_to_price(
+                &market.amm,
+                adjusted_limit_price,
+            )?
+        } else {
+            math::amm_spread::calculate_base_asset_amount_to_trade_to_price(
+                &market.amm,
+                adjusted_limit_price,
+                order.direction,
+            )?
+        }
     } else {
         (base_asset_amount_unfilled, order.direction)
     };
@@ -389,14 +398,14 @@ pub fn order_breaches_maker_oracle_price_bands(
     slot: u64,
     tick_size: u64,
     margin_ratio_initial: u32,
-    is_prediction_market: bool,
+    prediction_price_bounds: Option<PredictionMarketPriceBounds>,
 ) -> DriftResult<bool> {
     let order_limit_price = order.force_get_limit_price(
         Some(oracle_price),
         None,
         slot,
         tick_size,
-        is_prediction_market,
+        prediction_price_bounds,
     )?;
     limit_price_breaches_maker_oracle_price_bands(
         order_limit_price,
@@ -476,15 +485,16 @@ pub fn validate_fill_price_within_price_bands(
     oracle_twap_5min: i64,
     margin_ratio_initial: u32,
     oracle_twap_5min_percent_divergence: u64,
-    is_prediction_market: bool,
+    prediction_price_bounds: Option<PredictionMarketPriceBounds>,
 ) -> DriftResult {
-    if is_prediction_market {
+    if let Some(prediction_price_bounds) = prediction_price_bounds {
         validate!(
-            fill_price <= MAX_PREDICTION_MARKET_PRICE,
+            prediction_price_bounds.contains(fill_price),
             ErrorCode::PriceBandsBreached,
-            "Fill Price Breaches Prediction Market Price Bands: (fill: {} >= oracle: {})",
+            "Fill Price Breaches Prediction Market Price Bands: (fill: {} outside [{}, {}])",
             fill_price,
-            PRICE_PRECISION_U64
+            prediction_price_bounds.lower,
+            prediction_price_bounds.upper
         )?;
 
         return Ok(());
@@ -597,10 +607,68 @@ pub fn is_oracle_too_divergent_with_twap_5min(
     Ok(too_divergent)
 }
 
-pub fn order_satisfies_trigger_condition(order: &Order, oracle_price: u64) -> DriftResult<bool> {
+/// `oracle_price` is the oracle price of the market the trigger source points at: the order's own
+/// market, or the trigger market for linked market oracle triggers. Linked market outcome triggers
+/// only fire once `trigger_market` has resolved and compare against its expiry price
+pub fn order_satisfies_trigger_condition(
+    order: &Order,
+    oracle_price: u64,
+    trigger_market: Option<&PerpMarket>,
+) -> DriftResult<bool> {
+    let trigger_source_price = match order.trigger_source {
+        OrderTriggerSource::Oracle | OrderTriggerSource::LinkedMarketOracle => oracle_price,
+        OrderTriggerSource::LinkedMarketOutcome => {
+            let trigger_market = trigger_market.safe_unwrap()?;
+
+            validate!(
+                trigger_market.market_index == order.trigger_market_index,
+                ErrorCode::InvalidOrderTriggerMarket,
+                "trigger market {} != order trigger market {}",
+                trigger_market.market_index,
+                order.trigger_market_index
+            )?;
+
+            if trigger_market.status != MarketStatus::Settlement || trigger_market.is_voided() {
+                return Ok(false);
+            }
+
+            trigger_market.expiry_price.unsigned_abs()
+        }
+    };
+
     match order.trigger_condition {
-        OrderTriggerCondition::Above => Ok(oracle_price > order.trigger_price),
-        OrderTriggerCondition::Below => Ok(oracle_price < order.trigger_price),
+        OrderTriggerCondition::Above => Ok(trigger_source_price > order.trigger_price),
+        OrderTriggerCondition::Below => Ok(trigger_source_price < order.trigger_price),
+        _ => Err(print_error!(ErrorCode::InvalidTriggerOrderCondition)()),
+    }
+}
+
+/// Moves a trailing stop's water mark to `oracle_price` if it is more favorable and returns the
+/// new water mark and trigger price. Stops triggered below trail the highest oracle price, stops
+/// triggered above trail the lowest
+pub fn calculate_trailing_stop_trigger_price(
+    trailing_stop: &TrailingStop,
+    trigger_condition: OrderTriggerCondition,
+    oracle_price: i64,
+) -> DriftResult<(i64, u64)> {
+    let trailing_stop_distance = trailing_stop.get_distance();
+
+    match trigger_condition {
+        OrderTriggerCondition::Below => {
+            let water_mark = trailing_stop.water_mark.max(oracle_price);
+            let trigger_price = water_mark
+                .safe_sub(trailing_stop_distance.calculate_price_distance(water_mark)?)?
+                .max(0)
+                .unsigned_abs();
+            Ok((water_mark, trigger_price))
+        }
+        OrderTriggerCondition::Above => {
+            let water_mark = trailing_stop.water_mark.min(oracle_price);
+            let trigger_price = water_mark
+                .safe_add(trailing_stop_distance.calculate_price_distance(water_mark)?)?
+                .unsigned_abs();
+            Ok((water_mark, trigger_price))
+        }
         _ => Err(print_error!(ErrorCode::InvalidTriggerOrderCondition)()),
     }
 }
@@ -760,7 +828,7 @@ pub fn find_maker_orders(
     valid_oracle_price: Option<i64>,
     slot: u64,
     tick_size: u64,
-    is_prediction_market: bool,
+    prediction_price_bounds: Option<PredictionMarketPriceBounds>,
 ) -> DriftResult<Vec<(usize, u64)>> {
     let mut orders: Vec<(usize, u64)> = Vec::with_capacity(32);
 
@@ -787,7 +855,7 @@ pub fn find_maker_orders(
             None,
             slot,
             tick_size,
-            is_prediction_market,
+            prediction_price_bounds,
         )?;
 
         orders.push((order_index, limit_price));
@@ -840,7 +908,10 @@ pub fn calculate_max_perp_order_size(
 
     let perp_position: &PerpPosition = &user.perp_positions[position_index];
     let (worst_case_base_asset_amount, worst_case_liability_value) = perp_position
-        .worst_case_liability_value(oracle_price_data_price, perp_market.contract_type)?;
+        .worst_case_liability_value(
+            oracle_price_data_price,
+            perp_market.get_prediction_market_price_bounds(),
+        )?;
 
     let margin_ratio = perp_market
         .get_margin_ratio(
@@ -888,12 +959,17 @@ pub fn calculate_max_perp_order_size(
         );
     }
 
-    let oracle_price =
-        if !perp_market.is_prediction_market() || direction == PositionDirection::Long {
-            oracle_price_data_price
-        } else {
-            MAX_PREDICTION_MARKET_PRICE_I64.safe_sub(oracle_price_data_price)?
-        };
+    // prediction market liability is the distance to the bound the position loses towards
+    let oracle_price = match perp_market.get_prediction_market_price_bounds() {
+        Some(price_bounds) if direction == PositionDirection::Long => {
+            oracle_price_data_price.safe_sub(price_bounds.lower.cast()?)?
+        }
+        Some(price_bounds) => price_bounds
+            .upper
+            .cast::<i64>()?
+            .safe_sub(oracle_price_data_price)?,
+        None => oracle_price_data_price,
+    };
 
     let calculate_order_size_and_margin_ratio = |margin_ratio: u32| {
         let new_order_size = free_collateral_before
@@ -1283,7 +1359,7 @@ pub fn find_bids_and_asks_from_users(
                 None,
                 slot,
                 tick_size,
-                perp_market.is_prediction_market(),
+                perp_market.get_prediction_market_price_bounds(),
             )?;
 
             insert_order(base_amount, limit_price, order.direction);
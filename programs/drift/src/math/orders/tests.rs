@@ -160,7 +160,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -191,7 +191,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -224,7 +224,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -257,7 +257,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -290,7 +290,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -323,7 +323,7 @@ mod order_breaches_oracle_price_limits {
             slot,
             tick_size,
             margin_ratio_initial,
-            false,
+            None,
         )
         .unwrap();
 
@@ -734,7 +734,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -769,7 +769,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -805,7 +805,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -841,7 +841,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -877,7 +877,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -913,7 +913,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -952,7 +952,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -993,7 +993,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -1039,7 +1039,7 @@ mod find_maker_orders {
             Some(oracle_price),
             slot,
             tick_size,
-            false,
+            None,
         )
         .unwrap();
 
@@ -2927,7 +2927,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_ok())
     }
@@ -2947,7 +2947,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_ok())
     }
@@ -2968,7 +2968,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_err())
     }
@@ -2989,7 +2989,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_err())
     }
@@ -3010,7 +3010,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_err())
     }
@@ -3031,7 +3031,7 @@ pub mod validate_fill_price_within_price_bands {
             twap,
             margin_ratio_initial,
             (PERCENTAGE_PRECISION / 2) as u64,
-            false,
+            None,
         )
         .is_err())
     }
@@ -3882,3 +3882,218 @@ mod fallback_price_logic {
         assert_eq!(result, 99500000);
     }
 }
+
+mod order_satisfies_trigger_condition {
+    use crate::math::constants::{PRICE_PRECISION_I64, PRICE_PRECISION_U64};
+    use crate::math::orders::order_satisfies_trigger_condition;
+    use crate::state::perp_market::{MarketStatus, MarketVoidStatus, PerpMarket};
+    use crate::state::user::{Order, OrderTriggerCondition, OrderTriggerSource, OrderType};
+
+    #[test]
+    fn own_oracle() {
+        let order = Order {
+            order_type: OrderType::TriggerMarket,
+            trigger_condition: OrderTriggerCondition::Above,
+            trigger_price: 100 * PRICE_PRECISION_U64,
+            ..Order::default()
+        };
+
+        assert!(
+            order_satisfies_trigger_condition(&order, 101 * PRICE_PRECISION_U64, None).unwrap()
+        );
+        assert!(
+            !order_satisfies_trigger_condition(&order, 99 * PRICE_PRECISION_U64, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn linked_market_oracle() {
+        // sol long that goes live once the prediction market trades above 60%
+        let order = Order {
+            order_type: OrderType::TriggerMarket,
+            market_index: 0,
+            trigger_condition: OrderTriggerCondition::Above,
+            trigger_price: PRICE_PRECISION_U64 * 6 / 10,
+            trigger_source: OrderTriggerSource::LinkedMarketOracle,
+            trigger_market_index: 1,
+            ..Order::default()
+        };
+
+        assert!(
+            order_satisfies_trigger_condition(&order, PRICE_PRECISION_U64 * 7 / 10, None).unwrap()
+        );
+        assert!(!order_satisfies_trigger_condition(&order, PRICE_PRECISION_U64 / 2, None).unwrap());
+    }
+
+    #[test]
+    fn linked_market_outcome() {
+        // sol long that goes live once the prediction market resolves yes
+        let order = Order {
+            order_type: OrderType::TriggerMarket,
+            market_index: 0,
+            trigger_condition: OrderTriggerCondition::Above,
+            trigger_price: PRICE_PRECISION_U64 / 2,
+            trigger_source: OrderTriggerSource::LinkedMarketOutcome,
+            trigger_market_index: 1,
+            ..Order::default()
+        };
+
+        let mut trigger_market = PerpMarket {
+            market_index: 1,
+            status: MarketStatus::Active,
+            ..PerpMarket::default()
+        };
+
+        // the linked market's oracle is ignored and the market must be passed in
+        assert!(order_satisfies_trigger_condition(&order, PRICE_PRECISION_U64, None).is_err());
+
+        // trading at 100% isn't a resolution
+        assert!(!order_satisfies_trigger_condition(
+            &order,
+            PRICE_PRECISION_U64,
+            Some(&trigger_market)
+        )
+        .unwrap());
+
+        trigger_market.status = MarketStatus::Settlement;
+        trigger_market.expiry_price = 0;
+        assert!(!order_satisfies_trigger_condition(&order, 0, Some(&trigger_market)).unwrap());
+
+        trigger_market.expiry_price = PRICE_PRECISION_I64;
+        assert!(order_satisfies_trigger_condition(&order, 0, Some(&trigger_market)).unwrap());
+
+        // voided markets never trigger
+        trigger_market.void_status = MarketVoidStatus::Voided;
+        assert!(!order_satisfies_trigger_condition(&order, 0, Some(&trigger_market)).unwrap());
+
+        // wrong market passed in
+        let other_market = PerpMarket {
+            market_index: 2,
+            status: MarketStatus::Settlement,
+            expiry_price: PRICE_PRECISION_I64,
+            ..PerpMarket::default()
+        };
+        assert!(order_satisfies_trigger_condition(&order, 0, Some(&other_market)).is_err());
+    }
+}
+
+mod calculate_trailing_stop_trigger_price {
+    use crate::math::constants::{PERCENTAGE_PRECISION, PRICE_PRECISION_I64, PRICE_PRECISION_U64};
+    use crate::math::orders::{
+        calculate_trailing_stop_trigger_price, order_satisfies_trigger_condition,
+    };
+    use crate::state::order_params::TrailingStopDistance;
+    use crate::state::trailing_stop::TrailingStop;
+    use crate::state::user::{Order, OrderStatus, OrderTriggerCondition, OrderType};
+
+    fn get_trailing_stop(
+        trigger_condition: OrderTriggerCondition,
+        distance: TrailingStopDistance,
+    ) -> (TrailingStop, Order) {
+        let trailing_stop = TrailingStop::new(1, distance, 100 * PRICE_PRECISION_I64);
+        let order = Order {
+            status: OrderStatus::Open,
+            order_type: OrderType::TriggerMarket,
+            order_id: 1,
+            trigger_condition,
+            trigger_price: calculate_trailing_stop_trigger_price(
+                &trailing_stop,
+                trigger_condition,
+                100 * PRICE_PRECISION_I64,
+            )
+            .unwrap()
+            .1,
+            ..Order::default()
+        };
+        (trailing_stop, order)
+    }
+
+    #[test]
+    fn offset_below() {
+        let (mut trailing_stop, mut order) = get_trailing_stop(
+            OrderTriggerCondition::Below,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+        );
+        assert_eq!(order.trigger_price, 95 * PRICE_PRECISION_U64);
+
+        // price rises, trigger follows
+        let (water_mark, trigger_price) = calculate_trailing_stop_trigger_price(
+            &trailing_stop,
+            order.trigger_condition,
+            110 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(water_mark, 110 * PRICE_PRECISION_I64);
+        assert_eq!(trigger_price, 105 * PRICE_PRECISION_U64);
+        trailing_stop.water_mark = water_mark;
+        order.trigger_price = trigger_price;
+
+        // price falls, trigger stays
+        let (water_mark, trigger_price) = calculate_trailing_stop_trigger_price(
+            &trailing_stop,
+            order.trigger_condition,
+            106 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(water_mark, 110 * PRICE_PRECISION_I64);
+        assert_eq!(trigger_price, 105 * PRICE_PRECISION_U64);
+
+        assert!(
+            !order_satisfies_trigger_condition(&order, 106 * PRICE_PRECISION_U64, None).unwrap()
+        );
+        assert!(
+            order_satisfies_trigger_condition(&order, 104 * PRICE_PRECISION_U64, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn percentage_above() {
+        let (mut trailing_stop, mut order) = get_trailing_stop(
+            OrderTriggerCondition::Above,
+            TrailingStopDistance::Percentage(PERCENTAGE_PRECISION as u32 / 10),
+        );
+        assert_eq!(order.trigger_price, 110 * PRICE_PRECISION_U64);
+
+        // price falls, trigger follows
+        let (water_mark, trigger_price) = calculate_trailing_stop_trigger_price(
+            &trailing_stop,
+            order.trigger_condition,
+            80 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(water_mark, 80 * PRICE_PRECISION_I64);
+        assert_eq!(trigger_price, 88 * PRICE_PRECISION_U64);
+        trailing_stop.water_mark = water_mark;
+        order.trigger_price = trigger_price;
+
+        // price rises, trigger stays
+        let (water_mark, trigger_price) = calculate_trailing_stop_trigger_price(
+            &trailing_stop,
+            order.trigger_condition,
+            87 * PRICE_PRECISION_I64,
+        )
+        .unwrap();
+        assert_eq!(water_mark, 80 * PRICE_PRECISION_I64);
+        assert_eq!(trigger_price, 88 * PRICE_PRECISION_U64);
+
+        assert!(
+            !order_satisfies_trigger_condition(&order, 87 * PRICE_PRECISION_U64, None).unwrap()
+        );
+        assert!(order_satisfies_trigger_condition(&order, 89 * PRICE_PRECISION_U64, None).unwrap());
+    }
+
+    #[test]
+    fn invalid_trigger_condition() {
+        let trailing_stop = TrailingStop::new(
+            1,
+            TrailingStopDistance::Offset(5 * PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_I64,
+        );
+        assert!(calculate_trailing_stop_trigger_price(
+            &trailing_stop,
+            OrderTriggerCondition::TriggeredBelow,
+            100 * PRICE_PRECISION_I64,
+        )
+        .is_err());
+    }
+}
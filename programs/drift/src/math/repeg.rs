@@ -13,6 +13,7 @@ use crate::math::constants::{
     SHARE_OF_FEES_ALLOCATED_TO_DRIFT_NUMERATOR,
 };
 use crate::math::cp_curve;
+use crate::math::lmsr::calculate_lmsr_worst_case_loss;
 use crate::math::oracle;
 use crate::math::oracle::OracleValidity;
 use crate::math::position::calculate_base_asset_value_and_pnl;
@@ -430,5 +431,11 @@ pub fn get_total_fee_lower_bound(market: &PerpMarket) -> DriftResult<u128> {
         .safe_mul(SHARE_OF_FEES_ALLOCATED_TO_DRIFT_NUMERATOR)?
         .safe_div(SHARE_OF_FEES_ALLOCATED_TO_DRIFT_DENOMINATOR)?;
 
+    // lmsr markets also retain enough to cover the amm's worst case loss
+    if market.amm.is_lmsr() {
+        return total_fee_lower_bound
+            .safe_add(calculate_lmsr_worst_case_loss(market.amm.lmsr_liquidity)?);
+    }
+
     Ok(total_fee_lower_bound)
 }
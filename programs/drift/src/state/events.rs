@@ -4,6 +4,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use crate::controller::position::PositionDirection;
 use crate::error::{DriftResult, ErrorCode::InvalidOrder};
 use crate::math::casting::Cast;
+use crate::math::oracle::OracleValidity;
 use crate::math::safe_unwrap::SafeUnwrap;
 use crate::state::order_params::OrderParams;
 use crate::state::traits::Size;
@@ -345,6 +346,14 @@ pub enum OrderActionExplanation {
     OrderFilledWithLPJit,
     DeriskLp,
     OrderFilledWithOpenbookV2,
+    CanceledByLinkedOrder,
+    ParentOrderSliceReleased,
+    ParentOrderSliceReplaced,
+    SelfTradePreventionCancelMaker,
+    SelfTradePreventionCancelTaker,
+    SelfTradePreventionCancelBoth,
+    SelfTradePreventionDecrementAndCancel,
+    HeartbeatExpired,
 }
 
 #[event]
@@ -497,6 +506,28 @@ pub enum SettlePnlExplanation {
     #[default]
     None,
     ExpiredPosition,
+    VoidedPosition,
+}
+
+/// Summary of a keeper settling many users' expired positions at once
+#[event]
+#[derive(Default)]
+pub struct SettleExpiredPositionsRecord {
+    pub ts: i64,
+    pub market_index: u16,
+    /// precision: PRICE_PRECISION
+    pub settle_price: i64,
+    pub users_settled: u32,
+    /// users left to settle in a later batch or through settle_pnl
+    pub users_skipped: u32,
+    /// precision: BASE_PRECISION
+    pub base_asset_amount_settled: u128,
+    /// pnl paid from the pnl pool to winners
+    /// precision: QUOTE_PRECISION
+    pub pnl_paid: u128,
+    /// pnl collected into the pnl pool from losers
+    /// precision: QUOTE_PRECISION
+    pub pnl_collected: u128,
 }
 
 #[event]
@@ -592,6 +623,123 @@ pub struct DeleteUserRecord {
     pub keeper: Option<Pubkey>,
 }
 
+#[event]
+#[derive(Default)]
+pub struct CompleteSetRecord {
+    pub ts: i64,
+    pub user: Pubkey,
+    pub action: CompleteSetAction,
+    pub prediction_event_index: u16,
+    /// perp market of a binary complete set, None for prediction event complete sets
+    pub market_index: Option<u16>,
+    /// amount of each outcome minted or redeemed
+    /// precision: BASE_PRECISION
+    pub base_asset_amount: u64,
+    /// precision: QUOTE_PRECISION
+    pub quote_asset_amount: u64,
+    /// pnl realized across the outcome positions on redeem
+    /// precision: QUOTE_PRECISION
+    pub pnl: i64,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default)]
+pub enum CompleteSetAction {
+    #[default]
+    Mint,
+    Redeem,
+}
+
+#[event]
+#[derive(Default)]
+pub struct MarketResolutionRecord {
+    pub ts: i64,
+    pub action: MarketResolutionAction,
+    pub market_index: u16,
+    /// user account that posted or received the bond
+    pub user: Option<Pubkey>,
+    /// proposed price on Propose, finalized price on Finalize
+    /// precision: PRICE_PRECISION
+    pub price: i64,
+    /// precision: token mint precision
+    pub bond_amount: u64,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default)]
+pub enum MarketResolutionAction {
+    #[default]
+    Propose,
+    Dispute,
+    Finalize,
+}
+
+#[event]
+#[derive(Default)]
+pub struct PredictionShareRecord {
+    pub ts: i64,
+    pub user: Pubkey,
+    pub action: PredictionShareAction,
+    pub market_index: u16,
+    /// Long for YES shares, Short for NO shares
+    pub direction: PositionDirection,
+    /// amount of shares wrapped, unwrapped or redeemed
+    /// precision: BASE_PRECISION
+    pub base_asset_amount: u64,
+    /// quote backing the shares, or paid out on redeem
+    /// precision: QUOTE_PRECISION
+    pub quote_asset_amount: u64,
+    /// pnl realized on the user's position on wrap/unwrap
+    /// precision: QUOTE_PRECISION
+    pub pnl: i64,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default)]
+pub enum PredictionShareAction {
+    #[default]
+    Wrap,
+    Unwrap,
+    Redeem,
+}
+
+#[event]
+#[derive(Default)]
+pub struct PredictionMarketCreatorRecord {
+    pub ts: i64,
+    pub action: PredictionMarketCreatorAction,
+    pub market_index: u16,
+    /// creator user account
+    pub user: Pubkey,
+    /// bond posted, returned or slashed, or creator fee claimed
+    /// precision: token mint precision
+    pub amount: u64,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default)]
+pub enum PredictionMarketCreatorAction {
+    #[default]
+    Create,
+    ClaimFee,
+    ReturnBond,
+    Slash,
+}
+
+#[event]
+#[derive(Default)]
+pub struct OracleFailoverRecord {
+    pub slot: u64,
+    pub market_type: MarketType,
+    pub market_index: u16,
+    pub oracle: Pubkey,
+    pub fallback_oracle: Pubkey,
+    /// true when the market switched to the fallback oracle, false when it switched back
+    pub is_fallback_active: bool,
+    /// validity of the primary oracle at the switch
+    pub oracle_validity: OracleValidity,
+    /// precision: PRICE_PRECISION
+    pub oracle_price: i64,
+    /// precision: PRICE_PRECISION
+    pub fallback_oracle_price: i64,
+}
+
 pub fn emit_stack<T: AnchorSerialize + Discriminator, const N: usize>(event: T) -> DriftResult {
     let mut data_buf = [0u8; N];
     let mut out_buf = [0u8; N];
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::PERCENTAGE_PRECISION;
+use crate::state::oracle::OracleSource;
+use crate::state::oracle_map::OracleIdentifier;
+use crate::state::traits::Size;
+use crate::state::user::MarketType;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+pub const PERP_FALLBACK_ORACLE_SEED: &[u8] = b"perp_fallback_oracle";
+pub const SPOT_FALLBACK_ORACLE_SEED: &[u8] = b"spot_fallback_oracle";
+
+/// A second oracle a market is priced off while its primary oracle is invalid.
+/// Passed alongside the oracles in remaining accounts; see OracleMap::update_fallback_oracles
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct FallbackOracle {
+    pub oracle: Pubkey,
+    /// The max divergence between the primary and fallback oracle prices for the fallback to be used
+    /// precision: PERCENTAGE_PRECISION
+    pub max_divergence: u64,
+    /// The slot the market last switched to or back from the fallback oracle
+    pub last_switch_slot: u64,
+    pub market_index: u16,
+    pub market_type: MarketType,
+    pub oracle_source: OracleSource,
+    /// Whether the market is currently priced off the fallback oracle
+    pub is_active: bool,
+    pub padding: [u8; 11],
+}
+
+impl Size for FallbackOracle {
+    const SIZE: usize = 72;
+}
+
+impl FallbackOracle {
+    pub fn oracle_id(&self) -> OracleIdentifier {
+        (self.oracle, self.oracle_source)
+    }
+
+    pub fn validate(&self, primary_oracle_id: &OracleIdentifier) -> DriftResult {
+        validate!(
+            self.oracle != Pubkey::default(),
+            ErrorCode::InvalidFallbackOracle,
+            "fallback oracle must be set"
+        )?;
+
+        validate!(
+            self.oracle_id() != *primary_oracle_id,
+            ErrorCode::InvalidFallbackOracle,
+            "fallback oracle must differ from the primary oracle"
+        )?;
+
+        validate!(
+            self.oracle_source != OracleSource::Prelaunch,
+            ErrorCode::InvalidFallbackOracle,
+            "fallback oracle cant be a prelaunch oracle"
+        )?;
+
+        validate!(
+            self.max_divergence > 0 && self.max_divergence.cast::<u128>()? <= PERCENTAGE_PRECISION,
+            ErrorCode::InvalidFallbackOracle,
+            "max divergence must be in (0, {}], got {}",
+            PERCENTAGE_PRECISION,
+            self.max_divergence
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+mod update_fallback_oracles {
+    use std::str::FromStr;
+
+    use anchor_lang::prelude::{AccountInfo, Pubkey};
+    use anchor_lang::Owner;
+
+    use crate::error::ErrorCode;
+    use crate::math::constants::{PERCENTAGE_PRECISION_U64, PRICE_PRECISION_I64};
+    use crate::math::oracle::{DriftAction, OracleValidity};
+    use crate::state::fallback_oracle::FallbackOracle;
+    use crate::state::oracle::{HistoricalOracleData, OracleSource};
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::perp_market::{PerpMarket, AMM};
+    use crate::state::perp_market_map::PerpMarketMap;
+    use crate::state::spot_market_map::SpotMarketMap;
+    use crate::state::user::MarketType;
+    use crate::test_utils::*;
+    use crate::{create_account_info, create_anchor_account_info};
+
+    fn perp_market(market_index: u16, oracle: Pubkey) -> PerpMarket {
+        PerpMarket {
+            market_index,
+            amm: AMM {
+                oracle,
+                oracle_source: OracleSource::Pyth,
+                historical_oracle_data: HistoricalOracleData {
+                    last_oracle_price_twap: PRICE_PRECISION_I64,
+                    ..HistoricalOracleData::default()
+                },
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        }
+    }
+
+    fn fallback_oracle(market_index: u16, oracle: Pubkey) -> FallbackOracle {
+        FallbackOracle {
+            oracle,
+            max_divergence: PERCENTAGE_PRECISION_U64 / 10,
+            market_index,
+            market_type: MarketType::Perp,
+            oracle_source: OracleSource::Pyth,
+            ..FallbackOracle::default()
+        }
+    }
+
+    #[test]
+    fn primary_oracle_required() {
+        let primary_oracle_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let fallback_oracle_key =
+            Pubkey::from_str("GVXRSBjFk6e6J3NbVPXohDJetcTjaeeuykUpbQF8UoMU").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+
+        let mut fallback_oracle_price = get_hardcoded_pyth_price(PRICE_PRECISION_I64, 6);
+        create_account_info!(
+            fallback_oracle_price,
+            &fallback_oracle_key,
+            &pyth_program,
+            fallback_oracle_price_account_info
+        );
+        let mut fallback_oracle = fallback_oracle(0, fallback_oracle_key);
+        create_anchor_account_info!(
+            fallback_oracle,
+            FallbackOracle,
+            fallback_oracle_account_info
+        );
+
+        let mut market = perp_market(0, primary_oracle_key);
+        create_anchor_account_info!(market, PerpMarket, market_account_info);
+        let perp_market_map = PerpMarketMap::load_one(&market_account_info, true).unwrap();
+
+        // the primary oracle is left out so the fallback can't be forced
+        let account_infos: Vec<AccountInfo> = vec![
+            fallback_oracle_price_account_info,
+            fallback_oracle_account_info,
+        ];
+        let mut account_info_iter = account_infos.iter().peekable();
+        let mut oracle_map = OracleMap::load(&mut account_info_iter, 0, None).unwrap();
+
+        let result = oracle_map.update_fallback_oracles(
+            &perp_market_map,
+            &SpotMarketMap::empty(),
+            Some(DriftAction::MarginCalc),
+        );
+        assert_eq!(result, Err(ErrorCode::OracleNotFound));
+    }
+
+    #[test]
+    fn markets_sharing_primary_oracle_fail_over_together() {
+        let primary_oracle_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let fallback_oracle_key =
+            Pubkey::from_str("GVXRSBjFk6e6J3NbVPXohDJetcTjaeeuykUpbQF8UoMU").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        let primary_oracle_id = (primary_oracle_key, OracleSource::Pyth);
+
+        // 5% confidence is too uncertain for the primary to be used for margin
+        let mut primary_oracle_price = get_hardcoded_pyth_price(PRICE_PRECISION_I64, 6);
+        primary_oracle_price.agg.conf = 50_000;
+        create_account_info!(
+            primary_oracle_price,
+            &primary_oracle_key,
+            &pyth_program,
+            primary_oracle_price_account_info
+        );
+        let mut fallback_oracle_price =
+            get_hardcoded_pyth_price(PRICE_PRECISION_I64 + PRICE_PRECISION_I64 / 100, 6);
+        create_account_info!(
+            fallback_oracle_price,
+            &fallback_oracle_key,
+            &pyth_program,
+            fallback_oracle_price_account_info
+        );
+
+        let mut first_fallback_oracle = fallback_oracle(0, fallback_oracle_key);
+        create_anchor_account_info!(
+            first_fallback_oracle,
+            FallbackOracle,
+            first_fallback_oracle_account_info
+        );
+        let mut second_fallback_oracle = fallback_oracle(1, fallback_oracle_key);
+        create_anchor_account_info!(
+            second_fallback_oracle,
+            FallbackOracle,
+            second_fallback_oracle_account_info
+        );
+
+        let mut first_market = perp_market(0, primary_oracle_key);
+        create_anchor_account_info!(first_market, PerpMarket, first_market_account_info);
+        let mut second_market = perp_market(1, primary_oracle_key);
+        create_anchor_account_info!(second_market, PerpMarket, second_market_account_info);
+        let perp_market_map = PerpMarketMap::load_multiple(
+            vec![&first_market_account_info, &second_market_account_info],
+            true,
+        )
+        .unwrap();
+
+        // only the first market's fallback passed in, the second market keeps the primary price
+        let account_infos: Vec<AccountInfo> = vec![
+            primary_oracle_price_account_info.clone(),
+            fallback_oracle_price_account_info.clone(),
+            first_fallback_oracle_account_info.clone(),
+        ];
+        let mut account_info_iter = account_infos.iter().peekable();
+        let mut oracle_map = OracleMap::load(&mut account_info_iter, 0, None).unwrap();
+        oracle_map
+            .update_fallback_oracles(
+                &perp_market_map,
+                &SpotMarketMap::empty(),
+                Some(DriftAction::MarginCalc),
+            )
+            .unwrap();
+        let oracle_price_data = oracle_map.get_price_data(&primary_oracle_id).unwrap();
+        assert_eq!(oracle_price_data.price, PRICE_PRECISION_I64);
+
+        // both markets' fallbacks passed in, both fail over
+        let account_infos: Vec<AccountInfo> = vec![
+            primary_oracle_price_account_info,
+            fallback_oracle_price_account_info,
+            first_fallback_oracle_account_info,
+            second_fallback_oracle_account_info,
+        ];
+        let mut account_info_iter = account_infos.iter().peekable();
+        let mut oracle_map = OracleMap::load(&mut account_info_iter, 0, None).unwrap();
+        oracle_map
+            .update_fallback_oracles(
+                &perp_market_map,
+                &SpotMarketMap::empty(),
+                Some(DriftAction::MarginCalc),
+            )
+            .unwrap();
+        for market_index in [0, 1] {
+            let (oracle_price_data, oracle_validity) = oracle_map
+                .get_price_data_and_validity(
+                    MarketType::Perp,
+                    market_index,
+                    &primary_oracle_id,
+                    PRICE_PRECISION_I64,
+                    1,
+                )
+                .unwrap();
+            assert_eq!(
+                oracle_price_data.price,
+                PRICE_PRECISION_I64 + PRICE_PRECISION_I64 / 100
+            );
+            assert_eq!(oracle_validity, OracleValidity::Valid);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::error::DriftResult;
 use crate::math::auction::calculate_auction_price;
 use crate::math::casting::Cast;
 use crate::math::safe_math::SafeMath;
+use crate::state::perp_market::PredictionMarketPriceBounds;
 use crate::state::user::Order;
 
 #[cfg(test)]
@@ -23,7 +24,7 @@ impl FillMode {
         valid_oracle_price: Option<i64>,
         slot: u64,
         tick_size: u64,
-        is_prediction_market: bool,
+        prediction_price_bounds: Option<PredictionMarketPriceBounds>,
     ) -> DriftResult<Option<u64>> {
         match self {
             FillMode::Fill | FillMode::PlaceAndMake | FillMode::Liquidation | FillMode::RFQ => {
@@ -32,7 +33,7 @@ impl FillMode {
                     None,
                     slot,
                     tick_size,
-                    is_prediction_market,
+                    prediction_price_bounds,
                 )
             }
             FillMode::PlaceAndTake(_, auction_duration_percentage) => {
@@ -49,7 +50,7 @@ impl FillMode {
                         order.slot.safe_add(auction_duration)?,
                         tick_size,
                         valid_oracle_price,
-                        is_prediction_market,
+                        prediction_price_bounds,
                     )
                     .map(Some)
                 } else {
@@ -58,7 +59,7 @@ impl FillMode {
                         None,
                         slot,
                         tick_size,
-                        is_prediction_market,
+                        prediction_price_bounds,
                     )
                 }
             }
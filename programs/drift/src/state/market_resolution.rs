@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::safe_math::SafeMath;
+use crate::state::perp_market::PerpMarket;
+use crate::state::traits::Size;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum ResolutionSource {
+    /// expiry price is derived from the market's oracle
+    #[default]
+    Oracle,
+    /// expiry price is the finalized outcome of the market's MarketResolution
+    Optimistic,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum MarketResolutionStatus {
+    /// waiting for a proposal
+    #[default]
+    Open,
+    /// outcome proposed, can be disputed until the dispute window ends
+    Proposed,
+    /// proposal challenged, waiting for admin or guardian to finalize
+    Disputed,
+    /// outcome is final and can be used to settle the market
+    Finalized,
+}
+
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct MarketResolution {
+    /// The user account that proposed the outcome and posted the proposal bond
+    pub proposer: Pubkey,
+    /// The user account that disputed the proposal and posted the counter bond
+    pub disputer: Pubkey,
+    /// Can finalize a disputed proposal alongside the admin
+    pub guardian: Pubkey,
+    /// The proposed expiry price
+    /// precision: PRICE_PRECISION
+    pub proposed_price: i64,
+    /// The expiry price the market settles at once finalized
+    /// precision: PRICE_PRECISION
+    pub finalized_price: i64,
+    /// The ts when the outcome was proposed
+    pub proposal_ts: i64,
+    /// The ts when the proposal was disputed
+    pub dispute_ts: i64,
+    /// How long after a proposal it can be disputed
+    /// unit: seconds
+    pub dispute_window: i64,
+    /// The bond posted by the proposer and the disputer
+    /// precision: token mint precision
+    pub bond_amount: u64,
+    /// The perp market being resolved
+    pub market_index: u16,
+    /// The spot market the bonds are denominated in
+    pub bond_spot_market_index: u16,
+    pub status: MarketResolutionStatus,
+    pub padding: [u8; 27],
+}
+
+impl Size for MarketResolution {
+    const SIZE: usize = 184;
+}
+
+impl MarketResolution {
+    pub fn validate_price(price: i64, perp_market: &PerpMarket) -> DriftResult {
+        let price_bounds = perp_market
+            .get_prediction_market_price_bounds()
+            .ok_or(ErrorCode::InvalidMarketResolution)?;
+
+        validate!(
+            price >= 0 && price_bounds.contains(price.unsigned_abs()),
+            ErrorCode::InvalidMarketResolution,
+            "price {} outside of prediction market range [{}, {}]",
+            price,
+            price_bounds.lower,
+            price_bounds.upper
+        )
+    }
+
+    pub fn dispute_window_end_ts(&self) -> DriftResult<i64> {
+        self.proposal_ts.safe_add(self.dispute_window)
+    }
+
+    pub fn propose(
+        &mut self,
+        proposer: Pubkey,
+        proposed_price: i64,
+        perp_market: &PerpMarket,
+        now: i64,
+    ) -> DriftResult {
+        validate!(
+            self.status == MarketResolutionStatus::Open,
+            ErrorCode::InvalidMarketResolution,
+            "outcome already proposed for perp market {}",
+            self.market_index
+        )?;
+
+        validate!(
+            perp_market.expiry_ts != 0 && now >= perp_market.expiry_ts,
+            ErrorCode::InvalidMarketResolution,
+            "perp market {} has not expired (expiry_ts = {}, now = {})",
+            perp_market.market_index,
+            perp_market.expiry_ts,
+            now
+        )?;
+
+        Self::validate_price(proposed_price, perp_market)?;
+
+        self.proposer = proposer;
+        self.proposed_price = proposed_price;
+        self.proposal_ts = now;
+        self.status = MarketResolutionStatus::Proposed;
+
+        Ok(())
+    }
+
+    pub fn dispute(&mut self, disputer: Pubkey, now: i64) -> DriftResult {
+        validate!(
+            self.status == MarketResolutionStatus::Proposed,
+            ErrorCode::InvalidMarketResolution,
+            "no proposal to dispute for perp market {}",
+            self.market_index
+        )?;
+
+        validate!(
+            now < self.dispute_window_end_ts()?,
+            ErrorCode::InvalidMarketResolution,
+            "dispute window ended at {}",
+            self.dispute_window_end_ts()?
+        )?;
+
+        validate!(
+            disputer != self.proposer,
+            ErrorCode::InvalidMarketResolution,
+            "proposer cant dispute their own proposal"
+        )?;
+
+        self.disputer = disputer;
+        self.dispute_ts = now;
+        self.status = MarketResolutionStatus::Disputed;
+
+        Ok(())
+    }
+
+    pub fn finalize_undisputed(&mut self, now: i64) -> DriftResult {
+        validate!(
+            self.status == MarketResolutionStatus::Proposed,
+            ErrorCode::InvalidMarketResolution,
+            "only an undisputed proposal can be finalized without admin"
+        )?;
+
+        validate!(
+            now >= self.dispute_window_end_ts()?,
+            ErrorCode::InvalidMarketResolution,
+            "dispute window ends at {}",
+            self.dispute_window_end_ts()?
+        )?;
+
+        self.finalized_price = self.proposed_price;
+        self.status = MarketResolutionStatus::Finalized;
+
+        Ok(())
+    }
+
+    /// returns true if the proposer was right and wins the disputer's bond
+    pub fn finalize_disputed(
+        &mut self,
+        finalized_price: i64,
+        perp_market: &PerpMarket,
+    ) -> DriftResult<bool> {
+        validate!(
+            self.status == MarketResolutionStatus::Disputed,
+            ErrorCode::InvalidMarketResolution,
+            "proposal for perp market {} not disputed",
+            self.market_index
+        )?;
+
+        Self::validate_price(finalized_price, perp_market)?;
+
+        self.finalized_price = finalized_price;
+        self.status = MarketResolutionStatus::Finalized;
+
+        Ok(finalized_price == self.proposed_price)
+    }
+
+    pub fn get_finalized_price(&self) -> DriftResult<i64> {
+        validate!(
+            self.status == MarketResolutionStatus::Finalized,
+            ErrorCode::MarketResolutionNotFinalized,
+            "resolution for perp market {} not finalized",
+            self.market_index
+        )?;
+
+        Ok(self.finalized_price)
+    }
+}
@@ -0,0 +1,237 @@
+mod propose {
+    use crate::math::constants::{
+        MAX_PREDICTION_MARKET_PRICE_I64, PRICE_PRECISION_I64, PRICE_PRECISION_U64,
+    };
+    use crate::state::market_resolution::{MarketResolution, MarketResolutionStatus};
+    use crate::state::perp_market::{ContractType, PerpMarket};
+    use anchor_lang::prelude::Pubkey;
+
+    #[test]
+    fn propose_after_expiry() {
+        let perp_market = PerpMarket {
+            market_index: 1,
+            contract_type: ContractType::Prediction,
+            expiry_ts: 100,
+            ..PerpMarket::default()
+        };
+
+        let mut market_resolution = MarketResolution {
+            market_index: 1,
+            dispute_window: 60,
+            bond_amount: 100,
+            ..MarketResolution::default()
+        };
+
+        let proposer = Pubkey::new_unique();
+
+        // market hasnt expired
+        assert!(market_resolution
+            .propose(proposer, MAX_PREDICTION_MARKET_PRICE_I64, &perp_market, 99)
+            .is_err());
+
+        // price outside of prediction market range
+        assert!(market_resolution
+            .propose(
+                proposer,
+                MAX_PREDICTION_MARKET_PRICE_I64 + 1,
+                &perp_market,
+                100
+            )
+            .is_err());
+        assert!(market_resolution
+            .propose(proposer, -1, &perp_market, 100)
+            .is_err());
+
+        market_resolution
+            .propose(proposer, MAX_PREDICTION_MARKET_PRICE_I64, &perp_market, 100)
+            .unwrap();
+
+        assert_eq!(market_resolution.status, MarketResolutionStatus::Proposed);
+        assert_eq!(market_resolution.proposer, proposer);
+        assert_eq!(
+            market_resolution.proposed_price,
+            MAX_PREDICTION_MARKET_PRICE_I64
+        );
+        assert_eq!(market_resolution.dispute_window_end_ts().unwrap(), 160);
+
+        // only one proposal
+        assert!(market_resolution
+            .propose(Pubkey::new_unique(), 0, &perp_market, 101)
+            .is_err());
+    }
+
+    #[test]
+    fn scalar_market() {
+        let perp_market = PerpMarket {
+            market_index: 1,
+            contract_type: ContractType::Prediction,
+            expiry_ts: 100,
+            scalar_lower_bound: 2 * PRICE_PRECISION_U64,
+            scalar_upper_bound: 4 * PRICE_PRECISION_U64,
+            ..PerpMarket::default()
+        };
+
+        let mut market_resolution = MarketResolution {
+            market_index: 1,
+            dispute_window: 60,
+            ..MarketResolution::default()
+        };
+
+        // outside of the band
+        assert!(market_resolution
+            .propose(Pubkey::new_unique(), PRICE_PRECISION_I64, &perp_market, 100)
+            .is_err());
+        assert!(market_resolution
+            .propose(
+                Pubkey::new_unique(),
+                5 * PRICE_PRECISION_I64,
+                &perp_market,
+                100
+            )
+            .is_err());
+
+        market_resolution
+            .propose(
+                Pubkey::new_unique(),
+                3 * PRICE_PRECISION_I64,
+                &perp_market,
+                100,
+            )
+            .unwrap();
+        assert_eq!(market_resolution.proposed_price, 3 * PRICE_PRECISION_I64);
+    }
+
+    #[test]
+    fn cant_propose_without_expiry() {
+        let perp_market = PerpMarket {
+            market_index: 1,
+            contract_type: ContractType::Prediction,
+            ..PerpMarket::default()
+        };
+
+        let mut market_resolution = MarketResolution {
+            market_index: 1,
+            dispute_window: 60,
+            ..MarketResolution::default()
+        };
+
+        assert!(market_resolution
+            .propose(Pubkey::new_unique(), 0, &perp_market, 100)
+            .is_err());
+    }
+}
+
+mod finalize {
+    use crate::math::constants::MAX_PREDICTION_MARKET_PRICE_I64;
+    use crate::state::market_resolution::{MarketResolution, MarketResolutionStatus};
+    use crate::state::perp_market::{ContractType, PerpMarket};
+    use anchor_lang::prelude::Pubkey;
+
+    fn prediction_market() -> PerpMarket {
+        PerpMarket {
+            market_index: 1,
+            contract_type: ContractType::Prediction,
+            expiry_ts: 100,
+            ..PerpMarket::default()
+        }
+    }
+
+    fn proposed_market_resolution(proposer: Pubkey) -> MarketResolution {
+        let mut market_resolution = MarketResolution {
+            market_index: 1,
+            dispute_window: 60,
+            bond_amount: 100,
+            ..MarketResolution::default()
+        };
+
+        market_resolution
+            .propose(
+                proposer,
+                MAX_PREDICTION_MARKET_PRICE_I64,
+                &prediction_market(),
+                100,
+            )
+            .unwrap();
+
+        market_resolution
+    }
+
+    #[test]
+    fn undisputed() {
+        let mut market_resolution = proposed_market_resolution(Pubkey::new_unique());
+
+        // not finalized yet
+        assert!(market_resolution.get_finalized_price().is_err());
+
+        // dispute window still open
+        assert!(market_resolution.finalize_undisputed(159).is_err());
+
+        market_resolution.finalize_undisputed(160).unwrap();
+
+        assert_eq!(market_resolution.status, MarketResolutionStatus::Finalized);
+        assert_eq!(
+            market_resolution.get_finalized_price().unwrap(),
+            MAX_PREDICTION_MARKET_PRICE_I64
+        );
+
+        // cant dispute once finalized
+        assert!(market_resolution
+            .dispute(Pubkey::new_unique(), 161)
+            .is_err());
+    }
+
+    #[test]
+    fn disputed_proposer_wrong() {
+        let proposer = Pubkey::new_unique();
+        let mut market_resolution = proposed_market_resolution(proposer);
+
+        // proposer cant dispute themselves
+        assert!(market_resolution.dispute(proposer, 120).is_err());
+
+        // dispute window over
+        let disputer = Pubkey::new_unique();
+        assert!(market_resolution.dispute(disputer, 160).is_err());
+
+        market_resolution.dispute(disputer, 159).unwrap();
+        assert_eq!(market_resolution.status, MarketResolutionStatus::Disputed);
+        assert_eq!(market_resolution.disputer, disputer);
+
+        // disputed proposals need admin to finalize
+        assert!(market_resolution.finalize_undisputed(1000).is_err());
+        assert!(market_resolution.get_finalized_price().is_err());
+
+        let proposer_was_right = market_resolution
+            .finalize_disputed(0, &prediction_market())
+            .unwrap();
+        assert!(!proposer_was_right);
+        assert_eq!(market_resolution.get_finalized_price().unwrap(), 0);
+
+        // cant finalize twice
+        assert!(market_resolution
+            .finalize_disputed(0, &prediction_market())
+            .is_err());
+    }
+
+    #[test]
+    fn disputed_proposer_right() {
+        let mut market_resolution = proposed_market_resolution(Pubkey::new_unique());
+
+        market_resolution
+            .dispute(Pubkey::new_unique(), 130)
+            .unwrap();
+
+        // invalid price
+        assert!(market_resolution
+            .finalize_disputed(MAX_PREDICTION_MARKET_PRICE_I64 + 1, &prediction_market())
+            .is_err());
+
+        let proposer_was_right = market_resolution
+            .finalize_disputed(MAX_PREDICTION_MARKET_PRICE_I64, &prediction_market())
+            .unwrap();
+        assert!(proposer_was_right);
+        assert_eq!(
+            market_resolution.get_finalized_price().unwrap(),
+            MAX_PREDICTION_MARKET_PRICE_I64
+        );
+    }
+}
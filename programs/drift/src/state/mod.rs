@@ -1,4 +1,5 @@
 pub mod events;
+pub mod fallback_oracle;
 pub mod fill_mode;
 pub mod fulfillment;
 pub mod fulfillment_params;
@@ -6,13 +7,20 @@ pub mod high_leverage_mode_config;
 pub mod insurance_fund_stake;
 pub mod load_ref;
 pub mod margin_calculation;
+pub mod market_resolution;
 pub mod oracle;
+pub mod oracle_guard_rails_override;
 pub mod oracle_map;
 pub mod order_params;
+pub mod parent_order;
 pub mod paused_operations;
 pub mod perp_market;
 pub mod perp_market_map;
+pub mod prediction_event;
+pub mod prediction_market_creator;
+pub mod price_history;
 pub mod protected_maker_mode_config;
+pub mod pyth_lazer_oracle;
 pub mod rfq_user;
 pub mod settle_pnl_mode;
 pub mod spot_fulfillment_params;
@@ -21,6 +29,7 @@ pub mod spot_market_map;
 #[allow(clippy::module_inception)]
 pub mod state;
 pub mod swift_user;
+pub mod trailing_stop;
 pub mod traits;
 pub mod user;
 pub mod user_map;
@@ -11,7 +11,9 @@ use switchboard_on_demand::{PullFeedAccountData, SB_ON_DEMAND_PRECISION};
 use crate::error::ErrorCode::{InvalidOracle, UnableToLoadOracle};
 use crate::math::safe_unwrap::SafeUnwrap;
 use crate::state::load_ref::load_ref;
+use crate::state::oracle_map::OracleMap;
 use crate::state::perp_market::PerpMarket;
+use crate::state::pyth_lazer_oracle::PythLazerOracle;
 use crate::state::traits::Size;
 use crate::validate;
 
@@ -124,6 +126,8 @@ pub enum OracleSource {
     Pyth1MPull,
     PythStableCoinPull,
     SwitchboardOnDemand,
+    Median,
+    PythLazer,
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -170,6 +174,8 @@ pub fn get_oracle_price(
         OracleSource::PythStableCoinPull => {
             get_pyth_stable_coin_price(price_oracle, clock_slot, true)
         }
+        OracleSource::Median => get_median_oracle_price(price_oracle, clock_slot),
+        OracleSource::PythLazer => get_pyth_lazer_price(price_oracle, clock_slot),
     }
 }
 
@@ -255,6 +261,49 @@ pub fn get_pyth_price(
     })
 }
 
+pub fn get_pyth_lazer_price(
+    price_oracle: &AccountInfo,
+    clock_slot: u64,
+) -> DriftResult<OraclePriceData> {
+    let oracle: Ref<PythLazerOracle> = load_ref(price_oracle).or(Err(UnableToLoadOracle))?;
+
+    let oracle_precision = 10_u128.pow(oracle.exponent.unsigned_abs());
+
+    let mut oracle_scale_mult = 1;
+    let mut oracle_scale_div = 1;
+
+    if oracle_precision > PRICE_PRECISION {
+        oracle_scale_div = oracle_precision.safe_div(PRICE_PRECISION)?;
+    } else {
+        oracle_scale_mult = PRICE_PRECISION.safe_div(oracle_precision)?;
+    }
+
+    let oracle_price_scaled = oracle
+        .price
+        .cast::<i128>()?
+        .safe_mul(oracle_scale_mult.cast()?)?
+        .safe_div(oracle_scale_div.cast()?)?
+        .cast::<i64>()?;
+
+    let oracle_conf_scaled = oracle
+        .conf
+        .cast::<u128>()?
+        .safe_mul(oracle_scale_mult)?
+        .safe_div(oracle_scale_div)?
+        .cast::<u64>()?;
+
+    let oracle_delay: i64 = clock_slot
+        .cast::<i64>()?
+        .safe_sub(oracle.publish_slot.cast()?)?;
+
+    Ok(OraclePriceData {
+        price: oracle_price_scaled,
+        confidence: oracle_conf_scaled,
+        delay: oracle_delay,
+        has_sufficient_number_of_data_points: true,
+    })
+}
+
 pub fn get_pyth_stable_coin_price(
     price_oracle: &AccountInfo,
     clock_slot: u64,
@@ -394,6 +443,9 @@ pub fn get_prelaunch_price(price_oracle: &AccountInfo, slot: u64) -> DriftResult
 pub struct StrictOraclePrice {
     pub current: i64,
     pub twap_5min: Option<i64>,
+    /// k * confidence, added to max and taken off min for markets with a confidence margin
+    /// multiplier. Applies whether or not the twap is used
+    pub confidence_haircut: i64,
 }
 
 impl StrictOraclePrice {
@@ -401,21 +453,31 @@ impl StrictOraclePrice {
         Self {
             current: price,
             twap_5min: if enabled { Some(twap_5min) } else { None },
+            confidence_haircut: 0,
         }
     }
 
+    pub fn with_confidence_haircut(mut self, confidence_haircut: i64) -> Self {
+        self.confidence_haircut = confidence_haircut;
+        self
+    }
+
     pub fn max(&self) -> i64 {
-        match self.twap_5min {
+        let max = match self.twap_5min {
             Some(twap) => self.current.max(twap),
             None => self.current,
-        }
+        };
+
+        max.saturating_add(self.confidence_haircut)
     }
 
     pub fn min(&self) -> i64 {
-        match self.twap_5min {
+        let min = match self.twap_5min {
             Some(twap) => self.current.min(twap),
             None => self.current,
-        }
+        };
+
+        min.saturating_sub(self.confidence_haircut).max(0)
     }
 
     pub fn validate(&self) -> DriftResult {
@@ -445,6 +507,7 @@ impl StrictOraclePrice {
         Self {
             current: price,
             twap_5min: None,
+            confidence_haircut: 0,
         }
     }
 }
@@ -544,3 +607,273 @@ pub struct PrelaunchOracleParams {
     pub price: Option<i64>,
     pub max_price: Option<i64>,
 }
+
+/// Accounts a market must be loaded with alongside its oracle, stored as bitflags in the market's
+/// oracle_accounts
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub enum MarketOracleAccount {
+    GuardRailsOverride = 0b00000001,
+    PriceHistory = 0b00000010,
+}
+
+impl MarketOracleAccount {
+    pub fn is_required(current: u8, account: MarketOracleAccount) -> bool {
+        current & account as u8 != 0
+    }
+}
+
+pub const MEDIAN_ORACLE_MAX_MEMBERS: usize = 5;
+pub const MEDIAN_ORACLE_MIN_MEMBERS: usize = 3;
+
+#[zero_copy(unsafe)]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct MedianOracleMember {
+    pub oracle: Pubkey,
+    /// precision: PRICE_PRECISION
+    pub price: i64,
+    /// precision: PRICE_PRECISION
+    pub confidence: u64,
+    /// slot the member's price was published
+    pub published_slot: u64,
+    pub oracle_source: OracleSource,
+    pub padding: [u8; 7],
+}
+
+/// Aggregates 3-5 underlying oracles into one price, the median of the members that published
+/// within max_member_delay. Member prices are snapshotted by `update_median_oracle`
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct MedianOracle {
+    pub members: [MedianOracleMember; 5],
+    /// last slot member prices were snapshotted
+    pub last_update_slot: u64,
+    /// max slots since a member published for it to count towards the median
+    pub max_member_delay: u64,
+    pub id: u16,
+    pub num_members: u8,
+    /// min number of members the median must be taken over for the price to be valid
+    pub min_quorum: u8,
+    pub padding: [u8; 28],
+}
+
+impl Size for MedianOracle {
+    const SIZE: usize = 376;
+}
+
+impl MedianOracle {
+    pub fn members(&self) -> &[MedianOracleMember] {
+        &self.members[..(self.num_members as usize).min(MEDIAN_ORACLE_MAX_MEMBERS)]
+    }
+
+    pub fn set_members(&mut self, members: &[MedianOracleMemberParams]) -> DriftResult {
+        validate!(
+            (MEDIAN_ORACLE_MIN_MEMBERS..=MEDIAN_ORACLE_MAX_MEMBERS).contains(&members.len()),
+            InvalidOracle,
+            "median oracle needs {}-{} members, got {}",
+            MEDIAN_ORACLE_MIN_MEMBERS,
+            MEDIAN_ORACLE_MAX_MEMBERS,
+            members.len()
+        )?;
+
+        self.members = [MedianOracleMember::default(); MEDIAN_ORACLE_MAX_MEMBERS];
+        for (member, params) in self.members.iter_mut().zip(members.iter()) {
+            member.oracle = params.oracle;
+            member.oracle_source = params.oracle_source;
+        }
+        self.num_members = members.len().cast()?;
+
+        Ok(())
+    }
+
+    /// Snapshots the price of every member. Every member's account must be passed in, so a member
+    /// can't be left out to move the median. A member that can't be read keeps its last snapshot
+    /// and ages out of the median
+    pub fn update_members(&mut self, account_infos: &[AccountInfo], slot: u64) -> DriftResult<u8> {
+        let num_members = self.members().len();
+        validate!(
+            account_infos.len() == num_members,
+            InvalidOracle,
+            "median oracle needs all {} member accounts, got {}",
+            num_members,
+            account_infos.len()
+        )?;
+
+        let mut members_updated: u8 = 0;
+        let mut members_passed = [false; MEDIAN_ORACLE_MAX_MEMBERS];
+
+        for account_info in account_infos.iter() {
+            let member_index = self.members[..num_members]
+                .iter()
+                .position(|member| member.oracle == *account_info.key)
+                .ok_or_else(|| {
+                    msg!("{} is not a median oracle member", account_info.key);
+                    InvalidOracle
+                })?;
+
+            validate!(
+                !members_passed[member_index],
+                InvalidOracle,
+                "median oracle member {} passed twice",
+                account_info.key
+            )?;
+            members_passed[member_index] = true;
+
+            let member = &mut self.members[member_index];
+
+            OracleMap::validate_oracle_account_info(account_info)?;
+
+            let price_data = match get_oracle_price(&member.oracle_source, account_info, slot) {
+                Ok(price_data) => price_data,
+                Err(e) => {
+                    msg!(
+                        "Failed to read median oracle member {}: {:?}",
+                        member.oracle,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            member.price = price_data.price;
+            member.confidence = price_data.confidence;
+            member.published_slot = slot.saturating_sub(price_data.delay.max(0).unsigned_abs());
+
+            members_updated = members_updated.safe_add(1)?;
+        }
+
+        self.last_update_slot = slot;
+
+        Ok(members_updated)
+    }
+
+    /// The median price of the members that published within max_member_delay, with the max
+    /// deviation of those members from the median as confidence and the stalest member's delay.
+    /// Fewer members than min_quorum leave the price non positive so it's invalid for every action
+    pub fn get_price_data(&self, slot: u64) -> DriftResult<OraclePriceData> {
+        let mut members = self
+            .members()
+            .iter()
+            .filter(|member| {
+                member.price > 0
+                    && slot.saturating_sub(member.published_slot) <= self.max_member_delay
+            })
+            .collect::<Vec<&MedianOracleMember>>();
+
+        if members.is_empty() || members.len() < self.min_quorum as usize {
+            return Ok(OraclePriceData {
+                price: 0,
+                confidence: 0,
+                delay: slot.saturating_sub(self.last_update_slot).cast()?,
+                has_sufficient_number_of_data_points: false,
+            });
+        }
+
+        members.sort_by_key(|member| member.price);
+
+        let mid = members.len() / 2;
+        let price = if members.len() % 2 == 1 {
+            members[mid].price
+        } else {
+            members[mid - 1]
+                .price
+                .safe_add(members[mid].price)?
+                .safe_div(2)?
+        };
+
+        let mut confidence: u64 = 0;
+        let mut delay: u64 = 0;
+        for member in members.iter() {
+            confidence = confidence.max(member.price.safe_sub(price)?.unsigned_abs());
+            delay = delay.max(slot.saturating_sub(member.published_slot));
+        }
+
+        Ok(OraclePriceData {
+            price,
+            confidence,
+            delay: delay.cast()?,
+            has_sufficient_number_of_data_points: true,
+        })
+    }
+
+    pub fn validate(&self) -> DriftResult {
+        let members = self.members();
+
+        validate!(
+            (MEDIAN_ORACLE_MIN_MEMBERS..=MEDIAN_ORACLE_MAX_MEMBERS).contains(&members.len()),
+            InvalidOracle,
+            "median oracle needs {}-{} members, got {}",
+            MEDIAN_ORACLE_MIN_MEMBERS,
+            MEDIAN_ORACLE_MAX_MEMBERS,
+            members.len()
+        )?;
+
+        for (i, member) in members.iter().enumerate() {
+            validate!(
+                matches!(
+                    member.oracle_source,
+                    OracleSource::PythPull
+                        | OracleSource::Pyth1KPull
+                        | OracleSource::Pyth1MPull
+                        | OracleSource::PythStableCoinPull
+                        | OracleSource::SwitchboardOnDemand
+                        | OracleSource::Prelaunch
+                        | OracleSource::PythLazer
+                ),
+                InvalidOracle,
+                "median oracle member {} has unsupported source {:?}",
+                member.oracle,
+                member.oracle_source
+            )?;
+
+            validate!(
+                !members[..i]
+                    .iter()
+                    .any(|other| other.oracle == member.oracle),
+                InvalidOracle,
+                "median oracle member {} listed twice",
+                member.oracle
+            )?;
+        }
+
+        validate!(
+            self.min_quorum >= 2 && self.min_quorum as usize <= members.len(),
+            InvalidOracle,
+            "min quorum {} must be in [2, {}]",
+            self.min_quorum,
+            members.len()
+        )?;
+
+        validate!(
+            self.max_member_delay > 0,
+            InvalidOracle,
+            "max member delay must be positive"
+        )?;
+
+        Ok(())
+    }
+}
+
+pub fn get_median_oracle_price(
+    price_oracle: &AccountInfo,
+    slot: u64,
+) -> DriftResult<OraclePriceData> {
+    let oracle: Ref<MedianOracle> = load_ref(price_oracle).or(Err(UnableToLoadOracle))?;
+
+    oracle.get_price_data(slot)
+}
+
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct MedianOracleMemberParams {
+    pub oracle: Pubkey,
+    pub oracle_source: OracleSource,
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct MedianOracleParams {
+    pub id: u16,
+    pub members: Vec<MedianOracleMemberParams>,
+    pub min_quorum: u8,
+    pub max_member_delay: u64,
+}
@@ -157,3 +157,120 @@ fn oracle_map_diff_oracle_source() {
         .unwrap();
     assert_eq!(oracle_price_data.price, 34);
 }
+
+mod median_oracle {
+    use solana_program::pubkey::Pubkey;
+
+    use crate::math::constants::PRICE_PRECISION_I64;
+    use crate::state::oracle::{MedianOracle, MedianOracleMember, OracleSource};
+
+    fn member(price: i64, published_slot: u64) -> MedianOracleMember {
+        MedianOracleMember {
+            oracle: Pubkey::new_unique(),
+            price,
+            published_slot,
+            oracle_source: OracleSource::PythPull,
+            ..MedianOracleMember::default()
+        }
+    }
+
+    fn median_oracle(members: &[MedianOracleMember]) -> MedianOracle {
+        let mut oracle = MedianOracle {
+            num_members: members.len() as u8,
+            min_quorum: 2,
+            max_member_delay: 10,
+            ..MedianOracle::default()
+        };
+        oracle.members[..members.len()].copy_from_slice(members);
+        oracle
+    }
+
+    #[test]
+    fn median_of_fresh_members() {
+        let oracle = median_oracle(&[
+            member(101 * PRICE_PRECISION_I64, 95),
+            member(100 * PRICE_PRECISION_I64, 98),
+            member(250 * PRICE_PRECISION_I64, 99), // misreporting
+        ]);
+        assert!(oracle.validate().is_ok());
+
+        let price_data = oracle.get_price_data(100).unwrap();
+        assert_eq!(price_data.price, 101 * PRICE_PRECISION_I64);
+        assert_eq!(price_data.confidence, 149 * PRICE_PRECISION_I64 as u64);
+        assert_eq!(price_data.delay, 5);
+        assert!(price_data.has_sufficient_number_of_data_points);
+    }
+
+    #[test]
+    fn stale_members_drop_out() {
+        let oracle = median_oracle(&[
+            member(100 * PRICE_PRECISION_I64, 80), // stale
+            member(100 * PRICE_PRECISION_I64, 98),
+            member(102 * PRICE_PRECISION_I64, 99),
+            member(0, 99), // non positive
+        ]);
+
+        let price_data = oracle.get_price_data(100).unwrap();
+        assert_eq!(price_data.price, 101 * PRICE_PRECISION_I64);
+        assert_eq!(price_data.confidence, PRICE_PRECISION_I64 as u64);
+        assert_eq!(price_data.delay, 2);
+        assert!(price_data.has_sufficient_number_of_data_points);
+
+        // one fresh member is below quorum
+        let price_data = oracle.get_price_data(109).unwrap();
+        assert_eq!(price_data.price, 0);
+        assert!(!price_data.has_sufficient_number_of_data_points);
+
+        // nothing fresh
+        let price_data = oracle.get_price_data(200).unwrap();
+        assert_eq!(price_data.price, 0);
+        assert!(!price_data.has_sufficient_number_of_data_points);
+    }
+
+    #[test]
+    fn update_requires_every_member() {
+        let mut oracle = median_oracle(&[
+            member(100 * PRICE_PRECISION_I64, 98),
+            member(101 * PRICE_PRECISION_I64, 98),
+            member(102 * PRICE_PRECISION_I64, 98),
+        ]);
+
+        assert!(oracle.update_members(&[], 100).is_err());
+        assert_eq!(oracle.last_update_slot, 0);
+    }
+
+    #[test]
+    fn validate() {
+        let members = [
+            member(100 * PRICE_PRECISION_I64, 0),
+            member(100 * PRICE_PRECISION_I64, 0),
+        ];
+        // too few members
+        assert!(median_oracle(&members).validate().is_err());
+
+        let mut members = [
+            member(100 * PRICE_PRECISION_I64, 0),
+            member(100 * PRICE_PRECISION_I64, 0),
+            member(100 * PRICE_PRECISION_I64, 0),
+        ];
+        assert!(median_oracle(&members).validate().is_ok());
+
+        // quorum above member count
+        let oracle = MedianOracle {
+            min_quorum: 4,
+            ..median_oracle(&members)
+        };
+        assert!(oracle.validate().is_err());
+
+        // duplicate member
+        members[2].oracle = members[0].oracle;
+        assert!(median_oracle(&members).validate().is_err());
+
+        // nested median oracle
+        members[2] = MedianOracleMember {
+            oracle_source: OracleSource::Median,
+            ..member(100 * PRICE_PRECISION_I64, 0)
+        };
+        assert!(median_oracle(&members).validate().is_err());
+    }
+}
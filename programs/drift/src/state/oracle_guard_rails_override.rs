@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::state::state::OracleGuardRails;
+use crate::state::traits::Size;
+use crate::state::user::MarketType;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+pub const PERP_ORACLE_GUARD_RAILS_OVERRIDE_SEED: &[u8] = b"perp_oracle_guard_rails_override";
+pub const SPOT_ORACLE_GUARD_RAILS_OVERRIDE_SEED: &[u8] = b"spot_oracle_guard_rails_override";
+
+/// A market's overrides of the global oracle guard rails in State. Fields left at 0 use the
+/// global value. Markets with an override must be passed with it; see OracleMap::load
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct OracleGuardRailsOverride {
+    pub slots_before_stale_for_amm: i64,
+    pub slots_before_stale_for_margin: i64,
+    /// precision: BID_ASK_SPREAD_PRECISION
+    pub confidence_interval_max_size: u64,
+    pub too_volatile_ratio: i64,
+    /// precision: PERCENTAGE_PRECISION
+    pub mark_oracle_percent_divergence: u64,
+    /// precision: PERCENTAGE_PRECISION
+    pub oracle_twap_5min_percent_divergence: u64,
+    pub market_index: u16,
+    pub market_type: MarketType,
+    pub padding: [u8; 13],
+}
+
+impl Size for OracleGuardRailsOverride {
+    const SIZE: usize = 72;
+}
+
+impl OracleGuardRailsOverride {
+    pub fn is_for_market(&self, market_type: MarketType, market_index: u16) -> bool {
+        self.market_type == market_type && self.market_index == market_index
+    }
+
+    pub fn apply(&self, oracle_guard_rails: &OracleGuardRails) -> OracleGuardRails {
+        let mut oracle_guard_rails = *oracle_guard_rails;
+
+        let validity = &mut oracle_guard_rails.validity;
+        if self.slots_before_stale_for_amm != 0 {
+            validity.slots_before_stale_for_amm = self.slots_before_stale_for_amm;
+        }
+        if self.slots_before_stale_for_margin != 0 {
+            validity.slots_before_stale_for_margin = self.slots_before_stale_for_margin;
+        }
+        if self.confidence_interval_max_size != 0 {
+            validity.confidence_interval_max_size = self.confidence_interval_max_size;
+        }
+        if self.too_volatile_ratio != 0 {
+            validity.too_volatile_ratio = self.too_volatile_ratio;
+        }
+
+        let price_divergence = &mut oracle_guard_rails.price_divergence;
+        if self.mark_oracle_percent_divergence != 0 {
+            price_divergence.mark_oracle_percent_divergence = self.mark_oracle_percent_divergence;
+        }
+        if self.oracle_twap_5min_percent_divergence != 0 {
+            price_divergence.oracle_twap_5min_percent_divergence =
+                self.oracle_twap_5min_percent_divergence;
+        }
+
+        oracle_guard_rails
+    }
+
+    pub fn validate(&self, oracle_guard_rails: &OracleGuardRails) -> DriftResult {
+        validate!(
+            self.slots_before_stale_for_amm >= 0
+                && self.slots_before_stale_for_margin >= 0
+                && self.too_volatile_ratio >= 0,
+            ErrorCode::InvalidOracleGuardRailsOverride,
+            "oracle guard rail overrides cant be negative"
+        )?;
+
+        let validity = self.apply(oracle_guard_rails).validity;
+
+        validate!(
+            validity.slots_before_stale_for_margin >= validity.slots_before_stale_for_amm,
+            ErrorCode::InvalidOracleGuardRailsOverride,
+            "slots before stale for margin {} < slots before stale for amm {}",
+            validity.slots_before_stale_for_margin,
+            validity.slots_before_stale_for_amm
+        )?;
+
+        validate!(
+            validity.too_volatile_ratio > 1,
+            ErrorCode::InvalidOracleGuardRailsOverride,
+            "too volatile ratio {} must be > 1",
+            validity.too_volatile_ratio
+        )?;
+
+        Ok(())
+    }
+
+    pub fn update(&mut self, params: &OracleGuardRailsOverrideParams) {
+        self.slots_before_stale_for_amm = params.slots_before_stale_for_amm;
+        self.slots_before_stale_for_margin = params.slots_before_stale_for_margin;
+        self.confidence_interval_max_size = params.confidence_interval_max_size;
+        self.too_volatile_ratio = params.too_volatile_ratio;
+        self.mark_oracle_percent_divergence = params.mark_oracle_percent_divergence;
+        self.oracle_twap_5min_percent_divergence = params.oracle_twap_5min_percent_divergence;
+    }
+}
+
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct OracleGuardRailsOverrideParams {
+    pub slots_before_stale_for_amm: i64,
+    pub slots_before_stale_for_margin: i64,
+    pub confidence_interval_max_size: u64,
+    pub too_volatile_ratio: i64,
+    pub mark_oracle_percent_divergence: u64,
+    pub oracle_twap_5min_percent_divergence: u64,
+}
@@ -0,0 +1,138 @@
+mod apply {
+    use crate::math::constants::PERCENTAGE_PRECISION_U64;
+    use crate::state::oracle_guard_rails_override::OracleGuardRailsOverride;
+    use crate::state::state::OracleGuardRails;
+
+    #[test]
+    fn unset_fields_use_global() {
+        let global = OracleGuardRails::default();
+
+        let oracle_guard_rails_override = OracleGuardRailsOverride {
+            slots_before_stale_for_margin: 20,
+            confidence_interval_max_size: 100_000, // 10%
+            mark_oracle_percent_divergence: PERCENTAGE_PRECISION_U64 / 2,
+            ..OracleGuardRailsOverride::default()
+        };
+
+        let oracle_guard_rails = oracle_guard_rails_override.apply(&global);
+        assert_eq!(
+            oracle_guard_rails.validity.slots_before_stale_for_amm,
+            global.validity.slots_before_stale_for_amm
+        );
+        assert_eq!(
+            oracle_guard_rails.validity.slots_before_stale_for_margin,
+            20
+        );
+        assert_eq!(
+            oracle_guard_rails.validity.confidence_interval_max_size,
+            100_000
+        );
+        assert_eq!(
+            oracle_guard_rails.validity.too_volatile_ratio,
+            global.validity.too_volatile_ratio
+        );
+        assert_eq!(
+            oracle_guard_rails
+                .price_divergence
+                .mark_oracle_percent_divergence,
+            PERCENTAGE_PRECISION_U64 / 2
+        );
+        assert_eq!(
+            oracle_guard_rails
+                .price_divergence
+                .oracle_twap_5min_percent_divergence,
+            global.price_divergence.oracle_twap_5min_percent_divergence
+        );
+
+        assert!(oracle_guard_rails_override.validate(&global).is_ok());
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        let global = OracleGuardRails::default();
+
+        // stale for margin before stale for amm
+        let oracle_guard_rails_override = OracleGuardRailsOverride {
+            slots_before_stale_for_margin: 5,
+            ..OracleGuardRailsOverride::default()
+        };
+        assert!(oracle_guard_rails_override.validate(&global).is_err());
+
+        let oracle_guard_rails_override = OracleGuardRailsOverride {
+            slots_before_stale_for_amm: -1,
+            ..OracleGuardRailsOverride::default()
+        };
+        assert!(oracle_guard_rails_override.validate(&global).is_err());
+
+        let oracle_guard_rails_override = OracleGuardRailsOverride {
+            too_volatile_ratio: 1,
+            ..OracleGuardRailsOverride::default()
+        };
+        assert!(oracle_guard_rails_override.validate(&global).is_err());
+    }
+}
+
+mod oracle_map_validity {
+    use std::str::FromStr;
+
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Owner;
+
+    use crate::math::constants::PRICE_PRECISION_I64;
+    use crate::math::oracle::OracleValidity;
+    use crate::state::oracle::OracleSource;
+    use crate::state::oracle_guard_rails_override::OracleGuardRailsOverride;
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::user::MarketType;
+    use crate::test_utils::*;
+    use crate::{create_account_info, create_anchor_account_info};
+
+    #[test]
+    fn cached_per_market() {
+        // 5% confidence is too uncertain for the global guard rails but not the override's
+        let mut oracle_price = get_hardcoded_pyth_price(PRICE_PRECISION_I64, 6);
+        oracle_price.agg.conf = 50_000;
+        let oracle_price_key =
+            Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+        let pyth_program = crate::ids::pyth_program::id();
+        create_account_info!(
+            oracle_price,
+            &oracle_price_key,
+            &pyth_program,
+            oracle_account_info
+        );
+        let mut oracle_map = OracleMap::load_one(&oracle_account_info, 0, None).unwrap();
+
+        let mut oracle_guard_rails_override = OracleGuardRailsOverride {
+            confidence_interval_max_size: 100_000,
+            market_index: 0,
+            market_type: MarketType::Perp,
+            ..OracleGuardRailsOverride::default()
+        };
+        create_anchor_account_info!(
+            oracle_guard_rails_override,
+            OracleGuardRailsOverride,
+            oracle_guard_rails_override_account_info
+        );
+        oracle_map
+            .load_oracle_guard_rails_overrides(&[oracle_guard_rails_override_account_info])
+            .unwrap();
+
+        let oracle_id = (oracle_price_key, OracleSource::Pyth);
+
+        let (_, oracle_validity) = oracle_map
+            .get_price_data_and_validity(MarketType::Perp, 1, &oracle_id, PRICE_PRECISION_I64, 1)
+            .unwrap();
+        assert_eq!(oracle_validity, OracleValidity::TooUncertain);
+
+        let (_, oracle_validity) = oracle_map
+            .get_price_data_and_validity(MarketType::Perp, 0, &oracle_id, PRICE_PRECISION_I64, 1)
+            .unwrap();
+        assert_eq!(oracle_validity, OracleValidity::Valid);
+
+        let (_, oracle_validity) = oracle_map
+            .get_price_data_and_validity(MarketType::Spot, 0, &oracle_id, PRICE_PRECISION_I64, 1)
+            .unwrap();
+        assert_eq!(oracle_validity, OracleValidity::TooUncertain);
+    }
+}
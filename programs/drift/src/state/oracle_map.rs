@@ -4,15 +4,30 @@ use crate::ids::{
     drift_oracle_receiver_program, pyth_program, switchboard_on_demand, switchboard_program,
 };
 use crate::math::constants::PRICE_PRECISION_I64;
-use crate::math::oracle::{oracle_validity, OracleValidity};
-use crate::state::oracle::{get_oracle_price, OraclePriceData, OracleSource, PrelaunchOracle};
+use crate::math::oracle::{
+    is_oracle_valid_for_action, oracle_validity, should_use_fallback_oracle, DriftAction,
+    OracleValidity,
+};
+use crate::state::events::OracleFailoverRecord;
+use crate::state::fallback_oracle::FallbackOracle;
+use crate::state::load_ref::{load_ref, load_ref_mut};
+use crate::state::oracle::{
+    get_oracle_price, MedianOracle, OraclePriceData, OracleSource, PrelaunchOracle,
+};
+use crate::state::oracle_guard_rails_override::OracleGuardRailsOverride;
+use crate::state::perp_market_map::PerpMarketMap;
+use crate::state::price_history::{PriceHistory, PriceSampleType};
+use crate::state::pyth_lazer_oracle::PythLazerOracle;
+use crate::state::spot_market_map::SpotMarketMap;
 use crate::state::state::OracleGuardRails;
 use crate::state::user::MarketType;
+use anchor_lang::emit;
 use anchor_lang::prelude::{AccountInfo, Pubkey};
 use anchor_lang::Discriminator;
 use anchor_lang::Key;
 use arrayref::array_ref;
 use solana_program::msg;
+use std::cell::Ref;
 use std::collections::BTreeMap;
 use std::iter::Peekable;
 use std::slice::Iter;
@@ -33,8 +48,12 @@ const EXTERNAL_ORACLE_PROGRAM_IDS: [Pubkey; 4] = [
 
 pub struct OracleMap<'a> {
     oracles: BTreeMap<Pubkey, AccountInfo<'a>>,
+    fallback_oracles: Vec<AccountInfo<'a>>,
+    oracle_guard_rails_overrides: Vec<OracleGuardRailsOverride>,
+    price_histories: Vec<AccountInfo<'a>>,
     price_data: BTreeMap<OracleIdentifier, OraclePriceData>,
-    validity: BTreeMap<OracleIdentifier, OracleValidity>,
+    /// validity depends on the market's guard rails and twap, so it's cached per market
+    validity: BTreeMap<(OracleIdentifier, MarketType, u16), OracleValidity>,
     pub slot: u64,
     pub oracle_guard_rails: OracleGuardRails,
     pub quote_asset_price_data: OraclePriceData,
@@ -92,10 +111,15 @@ impl<'a> OracleMap<'a> {
             return Ok((&self.quote_asset_price_data, OracleValidity::Valid));
         }
 
+        let oracle_guard_rails =
+            self.get_market_oracle_guard_rails(market_type, market_index, &self.oracle_guard_rails);
+
+        let validity_key = (*oracle_id, market_type, market_index);
+
         if self.price_data.contains_key(oracle_id) {
             let oracle_price_data = self.price_data.get(oracle_id).safe_unwrap()?;
 
-            let oracle_validity = if let Some(oracle_validity) = self.validity.get(oracle_id) {
+            let oracle_validity = if let Some(oracle_validity) = self.validity.get(&validity_key) {
                 *oracle_validity
             } else {
                 let oracle_validity = oracle_validity(
@@ -103,12 +127,12 @@ impl<'a> OracleMap<'a> {
                     market_index,
                     last_oracle_price_twap,
                     oracle_price_data,
-                    &self.oracle_guard_rails.validity,
+                    &oracle_guard_rails.validity,
                     max_confidence_interval_multiplier,
                     &oracle_id.1,
                     true,
                 )?;
-                self.validity.insert(*oracle_id, oracle_validity);
+                self.validity.insert(validity_key, oracle_validity);
                 oracle_validity
             };
             return Ok((oracle_price_data, oracle_validity));
@@ -132,28 +156,32 @@ impl<'a> OracleMap<'a> {
             market_index,
             last_oracle_price_twap,
             oracle_price_data,
-            &self.oracle_guard_rails.validity,
+            &oracle_guard_rails.validity,
             max_confidence_interval_multiplier,
             &oracle_id.1,
             true,
         )?;
-        self.validity.insert(*oracle_id, oracle_validity);
+        self.validity.insert(validity_key, oracle_validity);
 
         Ok((oracle_price_data, oracle_validity))
     }
 
     pub fn get_price_data_and_guard_rails(
         &mut self,
+        market_type: MarketType,
+        market_index: u16,
         oracle_id: &OracleIdentifier,
-    ) -> DriftResult<(&OraclePriceData, &ValidityGuardRails)> {
+    ) -> DriftResult<(&OraclePriceData, ValidityGuardRails)> {
+        let validity_guard_rails = self
+            .get_market_oracle_guard_rails(market_type, market_index, &self.oracle_guard_rails)
+            .validity;
+
         if self.should_get_quote_asset_price_data(&oracle_id.0) {
-            let validity_guard_rails = &self.oracle_guard_rails.validity;
             return Ok((&self.quote_asset_price_data, validity_guard_rails));
         }
 
         if self.price_data.contains_key(oracle_id) {
             let oracle_price_data = self.price_data.get(oracle_id).safe_unwrap()?;
-            let validity_guard_rails = &self.oracle_guard_rails.validity;
 
             return Ok((oracle_price_data, validity_guard_rails));
         }
@@ -171,17 +199,203 @@ impl<'a> OracleMap<'a> {
         self.price_data.insert(*oracle_id, price_data);
 
         let oracle_price_data = self.price_data.get(oracle_id).safe_unwrap()?;
-        let validity_guard_rails = &self.oracle_guard_rails.validity;
 
         Ok((oracle_price_data, validity_guard_rails))
     }
 
+    /// The oracle guard rails with the market's override applied, if it was loaded
+    pub fn get_market_oracle_guard_rails(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        oracle_guard_rails: &OracleGuardRails,
+    ) -> OracleGuardRails {
+        match self
+            .oracle_guard_rails_overrides
+            .iter()
+            .find(|o| o.is_for_market(market_type, market_index))
+        {
+            Some(oracle_guard_rails_override) => {
+                oracle_guard_rails_override.apply(oracle_guard_rails)
+            }
+            None => *oracle_guard_rails,
+        }
+    }
+
+    /// Loads oracle guard rails overrides passed to instructions that load the oracle map with
+    /// load_one
+    pub fn load_oracle_guard_rails_overrides(
+        &mut self,
+        account_infos: &[AccountInfo],
+    ) -> DriftResult {
+        for account_info in account_infos.iter() {
+            validate!(
+                account_info.owner == &crate::id(),
+                ErrorCode::InvalidOracleGuardRailsOverride,
+                "oracle guard rails override not owned by program"
+            )?;
+
+            let oracle_guard_rails_override =
+                *load_ref::<OracleGuardRailsOverride>(account_info).or(Err(UnableToLoadOracle))?;
+            self.oracle_guard_rails_overrides
+                .push(oracle_guard_rails_override);
+        }
+
+        Ok(())
+    }
+
+    /// Markets with an oracle guard rails override can't be used without it, otherwise leaving it
+    /// out would loosen the market's guard rails
+    pub fn validate_oracle_guard_rails_override(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        has_oracle_guard_rails_override: bool,
+    ) -> DriftResult {
+        validate!(
+            !has_oracle_guard_rails_override
+                || self
+                    .oracle_guard_rails_overrides
+                    .iter()
+                    .any(|o| o.is_for_market(market_type, market_index)),
+            ErrorCode::InvalidOracleGuardRailsOverride,
+            "{} market {} oracle guard rails override not passed",
+            market_type,
+            market_index
+        )
+    }
+
+    /// Markets with a price history can't be used without it, otherwise twaps and volatility would
+    /// silently fall back to the amm's and samples would be skipped
+    pub fn validate_price_history(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        has_price_history: bool,
+    ) -> DriftResult {
+        validate!(
+            !has_price_history
+                || self
+                    .load_price_history(market_type, market_index)?
+                    .is_some(),
+            ErrorCode::InvalidPriceHistory,
+            "{} market {} price history not passed",
+            market_type,
+            market_index
+        )
+    }
+
+    /// Loads the oracle guard rails overrides and price histories passed to instructions that load
+    /// the oracle map with load_one
+    pub fn load_market_oracle_accounts(
+        &mut self,
+        account_infos: &[AccountInfo<'a>],
+    ) -> DriftResult {
+        for account_info in account_infos.iter() {
+            if load_ref::<PriceHistory>(account_info).is_ok() {
+                validate!(
+                    account_info.owner == &crate::id(),
+                    ErrorCode::InvalidPriceHistory,
+                    "price history not owned by program"
+                )?;
+
+                self.price_histories.push(account_info.clone());
+            } else {
+                self.load_oracle_guard_rails_overrides(std::slice::from_ref(account_info))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends a sample to the market's price history if it was passed writable and the oracle
+    /// price is valid for updating twaps
+    pub fn record_price_history(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        now: i64,
+        oracle_price: i64,
+        oracle_validity: OracleValidity,
+        mark_price: i64,
+    ) -> DriftResult {
+        if !is_oracle_valid_for_action(oracle_validity, Some(DriftAction::UpdateTwap))? {
+            return Ok(());
+        }
+
+        for account_info in self.price_histories.iter() {
+            if !account_info.is_writable {
+                continue;
+            }
+
+            let mut price_history = load_ref_mut::<PriceHistory>(account_info)
+                .or(Err(ErrorCode::InvalidPriceHistory))?;
+            if price_history.is_for_market(market_type, market_index) {
+                price_history.append(now, oracle_price, mark_price)?;
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Twap over the last `window` seconds from the market's price history. None if it wasn't
+    /// passed or doesn't cover the window
+    pub fn get_price_history_twap(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        now: i64,
+        window: i64,
+        price_type: PriceSampleType,
+    ) -> DriftResult<Option<i64>> {
+        match self.load_price_history(market_type, market_index)? {
+            Some(price_history) => price_history.calculate_twap(now, window, price_type),
+            None => Ok(None),
+        }
+    }
+
+    /// Realized volatility over the last `window` seconds from the market's price history. None if
+    /// it wasn't passed or doesn't cover the window
+    pub fn get_price_history_volatility(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+        now: i64,
+        window: i64,
+        price_type: PriceSampleType,
+    ) -> DriftResult<Option<u64>> {
+        match self.load_price_history(market_type, market_index)? {
+            Some(price_history) => price_history.calculate_volatility(now, window, price_type),
+            None => Ok(None),
+        }
+    }
+
+    fn load_price_history(
+        &self,
+        market_type: MarketType,
+        market_index: u16,
+    ) -> DriftResult<Option<Ref<PriceHistory>>> {
+        for account_info in self.price_histories.iter() {
+            let price_history =
+                load_ref::<PriceHistory>(account_info).or(Err(ErrorCode::InvalidPriceHistory))?;
+            if price_history.is_for_market(market_type, market_index) {
+                return Ok(Some(price_history));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub fn load<'c>(
         account_info_iter: &'c mut Peekable<Iter<AccountInfo<'a>>>,
         slot: u64,
         oracle_guard_rails: Option<OracleGuardRails>,
     ) -> DriftResult<OracleMap<'a>> {
         let mut oracles: BTreeMap<Pubkey, AccountInfo<'a>> = BTreeMap::new();
+        let mut fallback_oracles: Vec<AccountInfo<'a>> = vec![];
+        let mut oracle_guard_rails_overrides: Vec<OracleGuardRailsOverride> = vec![];
+        let mut price_histories: Vec<AccountInfo<'a>> = vec![];
 
         while let Some(account_info) = account_info_iter.peek() {
             if EXTERNAL_ORACLE_PROGRAM_IDS.contains(&account_info.owner) {
@@ -197,13 +411,44 @@ impl<'a> OracleMap<'a> {
                     UnableToLoadOracle
                 })?;
 
-                let expected_data_len = PrelaunchOracle::SIZE;
+                let expected_data_len = FallbackOracle::SIZE
+                    .min(OracleGuardRailsOverride::SIZE)
+                    .min(PriceHistory::SIZE)
+                    .min(PrelaunchOracle::SIZE)
+                    .min(PythLazerOracle::SIZE);
                 if data.len() < expected_data_len {
                     break;
                 }
 
                 let account_discriminator = array_ref![data, 0, 8];
-                if account_discriminator != &PrelaunchOracle::discriminator() {
+                if account_discriminator == &FallbackOracle::discriminator() {
+                    let account_info = account_info_iter.next().safe_unwrap()?;
+                    fallback_oracles.push(account_info.clone());
+
+                    continue;
+                }
+
+                if account_discriminator == &OracleGuardRailsOverride::discriminator() {
+                    let account_info = account_info_iter.next().safe_unwrap()?;
+                    oracle_guard_rails_overrides.push(
+                        *load_ref::<OracleGuardRailsOverride>(account_info)
+                            .or(Err(UnableToLoadOracle))?,
+                    );
+
+                    continue;
+                }
+
+                if account_discriminator == &PriceHistory::discriminator() {
+                    let account_info = account_info_iter.next().safe_unwrap()?;
+                    price_histories.push(account_info.clone());
+
+                    continue;
+                }
+
+                if account_discriminator != &PrelaunchOracle::discriminator()
+                    && account_discriminator != &MedianOracle::discriminator()
+                    && account_discriminator != &PythLazerOracle::discriminator()
+                {
                     break;
                 }
 
@@ -226,6 +471,9 @@ impl<'a> OracleMap<'a> {
 
         Ok(OracleMap {
             oracles,
+            fallback_oracles,
+            oracle_guard_rails_overrides,
+            price_histories,
             price_data: BTreeMap::new(),
             validity: BTreeMap::new(),
             slot,
@@ -256,14 +504,17 @@ impl<'a> OracleMap<'a> {
                 UnableToLoadOracle
             })?;
 
-            let expected_data_len = PrelaunchOracle::SIZE;
+            let expected_data_len = PrelaunchOracle::SIZE.min(PythLazerOracle::SIZE);
             if data.len() < expected_data_len {
                 msg!("Unexpected account data len loading oracle");
                 return Err(UnableToLoadOracle);
             }
 
             let account_discriminator = array_ref![data, 0, 8];
-            if account_discriminator != &PrelaunchOracle::discriminator() {
+            if account_discriminator != &PrelaunchOracle::discriminator()
+                && account_discriminator != &MedianOracle::discriminator()
+                && account_discriminator != &PythLazerOracle::discriminator()
+            {
                 msg!("Unexpected account discriminator");
                 return Err(UnableToLoadOracle);
             }
@@ -282,6 +533,9 @@ impl<'a> OracleMap<'a> {
 
         Ok(OracleMap {
             oracles,
+            fallback_oracles: vec![],
+            oracle_guard_rails_overrides: vec![],
+            price_histories: vec![],
             price_data: BTreeMap::new(),
             validity: BTreeMap::new(),
             slot,
@@ -295,6 +549,233 @@ impl<'a> OracleMap<'a> {
         })
     }
 
+    /// Checks every loaded market was passed with the oracle accounts it requires
+    pub fn validate_market_oracle_accounts(
+        &self,
+        perp_market_map: &PerpMarketMap,
+        spot_market_map: &SpotMarketMap,
+    ) -> DriftResult {
+        for market_index in perp_market_map.0.keys() {
+            let perp_market = perp_market_map.get_ref(market_index)?;
+            self.validate_oracle_guard_rails_override(
+                MarketType::Perp,
+                *market_index,
+                perp_market.has_oracle_guard_rails_override(),
+            )?;
+            self.validate_price_history(
+                MarketType::Perp,
+                *market_index,
+                perp_market.has_price_history(),
+            )?;
+        }
+
+        for market_index in spot_market_map.0.keys() {
+            let spot_market = spot_market_map.get_ref(market_index)?;
+            self.validate_oracle_guard_rails_override(
+                MarketType::Spot,
+                *market_index,
+                spot_market.has_oracle_guard_rails_override(),
+            )?;
+            self.validate_price_history(
+                MarketType::Spot,
+                *market_index,
+                spot_market.has_price_history(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Prices each market whose fallback oracle was passed in off that fallback while the market's
+    /// primary oracle is invalid for the requested action, the fallback is valid for it and within
+    /// its max divergence. The primary oracle must be passed in alongside its fallback. Markets
+    /// sharing a primary oracle share its price, so they only fail over together: each of them must
+    /// have the same fallback passed in and qualify for it. The fallback's price and validity are
+    /// cached under the primary oracle so every reader of the market's oracle sees the same price
+    /// for the rest of the instruction. Every switch over and back is recorded, and persisted when
+    /// the fallback oracle account is writable
+    pub fn update_fallback_oracles(
+        &mut self,
+        perp_market_map: &PerpMarketMap,
+        spot_market_map: &SpotMarketMap,
+        action: Option<DriftAction>,
+    ) -> DriftResult {
+        struct Failover<'b, 'c> {
+            account_info: &'b AccountInfo<'c>,
+            fallback_oracle: FallbackOracle,
+            oracle_id: OracleIdentifier,
+            primary: (i64, OracleValidity),
+            fallback: Option<(OraclePriceData, OracleValidity)>,
+            use_fallback: bool,
+        }
+
+        let fallback_oracles = self.fallback_oracles.clone();
+        let mut failovers: Vec<Failover> = Vec::with_capacity(fallback_oracles.len());
+
+        for account_info in fallback_oracles.iter() {
+            let fallback_oracle =
+                *load_ref::<FallbackOracle>(account_info).or(Err(UnableToLoadOracle))?;
+            let market_type = fallback_oracle.market_type;
+            let market_index = fallback_oracle.market_index;
+
+            let (oracle_id, last_oracle_price_twap, max_confidence_interval_multiplier) =
+                match market_type {
+                    MarketType::Perp => {
+                        if !perp_market_map.0.contains_key(&market_index) {
+                            continue;
+                        }
+
+                        let perp_market = perp_market_map.get_ref(&market_index)?;
+                        (
+                            perp_market.oracle_id(),
+                            perp_market
+                                .amm
+                                .historical_oracle_data
+                                .last_oracle_price_twap,
+                            perp_market.get_max_confidence_interval_multiplier()?,
+                        )
+                    }
+                    MarketType::Spot => {
+                        if !spot_market_map.0.contains_key(&market_index) {
+                            continue;
+                        }
+
+                        let spot_market = spot_market_map.get_ref(&market_index)?;
+                        (
+                            spot_market.oracle_id(),
+                            spot_market.historical_oracle_data.last_oracle_price_twap,
+                            spot_market.get_max_confidence_interval_multiplier()?,
+                        )
+                    }
+                };
+
+            // only fail over from a primary oracle that was read and found invalid
+            let primary = self
+                .get_price_data_and_validity(
+                    market_type,
+                    market_index,
+                    &oracle_id,
+                    last_oracle_price_twap,
+                    max_confidence_interval_multiplier,
+                )
+                .map(|(price_data, oracle_validity)| (price_data.price, oracle_validity))?;
+
+            let fallback = self
+                .get_price_data_and_validity(
+                    market_type,
+                    market_index,
+                    &fallback_oracle.oracle_id(),
+                    last_oracle_price_twap,
+                    max_confidence_interval_multiplier,
+                )
+                .map(|(price_data, oracle_validity)| (*price_data, oracle_validity))
+                .ok();
+
+            let use_fallback = should_use_fallback_oracle(
+                primary,
+                fallback.map(|(price_data, oracle_validity)| (price_data.price, oracle_validity)),
+                fallback_oracle.max_divergence,
+                action,
+            )?;
+
+            failovers.push(Failover {
+                account_info,
+                fallback_oracle,
+                oracle_id,
+                primary,
+                fallback,
+                use_fallback,
+            });
+        }
+
+        for i in 0..failovers.len() {
+            if !failovers[i].use_fallback {
+                continue;
+            }
+
+            let oracle_id = failovers[i].oracle_id;
+            let fallback_oracle_id = failovers[i].fallback_oracle.oracle_id();
+
+            let mut markets_sharing_oracle: Vec<(MarketType, u16)> = vec![];
+            for market_index in perp_market_map.0.keys() {
+                if perp_market_map.get_ref(market_index)?.oracle_id() == oracle_id {
+                    markets_sharing_oracle.push((MarketType::Perp, *market_index));
+                }
+            }
+            for market_index in spot_market_map.0.keys() {
+                if spot_market_map.get_ref(market_index)?.oracle_id() == oracle_id {
+                    markets_sharing_oracle.push((MarketType::Spot, *market_index));
+                }
+            }
+
+            let all_markets_fail_over =
+                markets_sharing_oracle
+                    .iter()
+                    .all(|(market_type, market_index)| {
+                        failovers.iter().any(|failover| {
+                            failover.oracle_id == oracle_id
+                                && failover.fallback_oracle.market_type == *market_type
+                                && failover.fallback_oracle.market_index == *market_index
+                                && failover.fallback_oracle.oracle_id() == fallback_oracle_id
+                                && failover.use_fallback
+                        })
+                    });
+
+            if !all_markets_fail_over {
+                msg!(
+                    "Not failing over oracle {} as not every market sharing it can fail over",
+                    oracle_id.0
+                );
+                for failover in failovers.iter_mut() {
+                    if failover.oracle_id == oracle_id {
+                        failover.use_fallback = false;
+                    }
+                }
+            }
+        }
+
+        for failover in failovers.iter() {
+            let market_type = failover.fallback_oracle.market_type;
+            let market_index = failover.fallback_oracle.market_index;
+
+            if let (true, Some((price_data, oracle_validity))) =
+                (failover.use_fallback, failover.fallback)
+            {
+                self.price_data.insert(failover.oracle_id, price_data);
+                self.validity.insert(
+                    (failover.oracle_id, market_type, market_index),
+                    oracle_validity,
+                );
+            }
+
+            if failover.use_fallback != failover.fallback_oracle.is_active {
+                if failover.account_info.is_writable {
+                    let mut fallback_oracle = load_ref_mut::<FallbackOracle>(failover.account_info)
+                        .or(Err(UnableToLoadOracle))?;
+                    fallback_oracle.is_active = failover.use_fallback;
+                    fallback_oracle.last_switch_slot = self.slot;
+                }
+
+                let (oracle_price, oracle_validity) = failover.primary;
+                emit!(OracleFailoverRecord {
+                    slot: self.slot,
+                    market_type,
+                    market_index,
+                    oracle: failover.oracle_id.0,
+                    fallback_oracle: failover.fallback_oracle.oracle,
+                    is_fallback_active: failover.use_fallback,
+                    oracle_validity,
+                    oracle_price,
+                    fallback_oracle_price: failover
+                        .fallback
+                        .map_or(0, |(price_data, _)| price_data.price),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn validate_oracle_account_info<'c>(account_info: &'c AccountInfo<'a>) -> DriftResult {
         if *account_info.key == Pubkey::default() {
             return Ok(());
@@ -313,6 +794,9 @@ impl<'a> OracleMap<'a> {
     pub fn empty() -> OracleMap<'a> {
         OracleMap {
             oracles: BTreeMap::new(),
+            fallback_oracles: vec![],
+            oracle_guard_rails_overrides: vec![],
+            price_histories: vec![],
             validity: BTreeMap::new(),
             price_data: BTreeMap::new(),
             slot: 0,
@@ -4,11 +4,12 @@ use crate::math::casting::Cast;
 use crate::math::safe_math::SafeMath;
 use crate::math::safe_unwrap::SafeUnwrap;
 use crate::state::events::OrderActionExplanation;
+use crate::state::parent_order::ParentOrderType;
 use crate::state::perp_market::{ContractTier, PerpMarket};
-use crate::state::user::{MarketType, OrderTriggerCondition, OrderType};
+use crate::state::user::{MarketType, OrderTriggerCondition, OrderTriggerSource, OrderType};
 use crate::{
-    MAX_PREDICTION_MARKET_PRICE_I64, ONE_HUNDRED_THOUSAND_QUOTE, PERCENTAGE_PRECISION_I64,
-    PERCENTAGE_PRECISION_U64, PRICE_PRECISION_I64,
+    ONE_HUNDRED_THOUSAND_QUOTE, PERCENTAGE_PRECISION_I64, PERCENTAGE_PRECISION_U64,
+    PRICE_PRECISION_I64,
 };
 use anchor_lang::prelude::*;
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -58,8 +59,9 @@ impl OrderParams {
             OrderParams::get_perp_baseline_start_price_offset(perp_market, self.direction)?;
         let mut new_auction_start_price = oracle_price.safe_add(auction_start_price_offset)?;
 
-        if perp_market.is_prediction_market() {
-            new_auction_start_price = new_auction_start_price.min(MAX_PREDICTION_MARKET_PRICE_I64);
+        if let Some(prediction_price_bounds) = perp_market.get_prediction_market_price_bounds() {
+            new_auction_start_price =
+                prediction_price_bounds.clamp_price_i64(new_auction_start_price)?;
         }
 
         if self.auction_duration.unwrap_or(0) == 0 {
@@ -436,9 +438,9 @@ impl OrderParams {
             }
         }
 
-        if perp_market.is_prediction_market() {
-            auction_start_price = auction_start_price.min(MAX_PREDICTION_MARKET_PRICE_I64);
-            auction_end_price = auction_end_price.min(MAX_PREDICTION_MARKET_PRICE_I64);
+        if let Some(prediction_price_bounds) = perp_market.get_prediction_market_price_bounds() {
+            auction_start_price = prediction_price_bounds.clamp_price_i64(auction_start_price)?;
+            auction_end_price = prediction_price_bounds.clamp_price_i64(auction_end_price)?;
         }
 
         let auction_duration = get_auction_duration(
@@ -781,6 +783,49 @@ pub enum PostOnlyParam {
     Slide,        // Modify price to be post only if can't be post only
 }
 
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq)]
+pub enum OrderLinkType {
+    /// Once any order in the group fills, triggers or is canceled, the rest are canceled
+    OneCancelsOther,
+    /// The first order is the entry. The rest are reduce only take profit / stop loss trigger
+    /// orders that can't trigger until the entry fills and then cancel each other
+    Bracket,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq)]
+pub enum TrailingStopDistance {
+    /// The trigger price trails the best oracle price by a fixed amount
+    /// precision: PRICE_PRECISION
+    Offset(u64),
+    /// The trigger price trails the best oracle price by a percentage of it
+    /// precision: PERCENTAGE_PRECISION
+    Percentage(u32),
+}
+
+impl TrailingStopDistance {
+    pub fn calculate_price_distance(&self, price: i64) -> DriftResult<i64> {
+        match self {
+            TrailingStopDistance::Offset(offset) => offset.cast(),
+            TrailingStopDistance::Percentage(percentage) => price
+                .safe_mul(percentage.cast()?)?
+                .safe_div(PERCENTAGE_PRECISION_I64),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub struct ParentOrderParams {
+    pub parent_order_type: ParentOrderType,
+    pub direction: PositionDirection,
+    pub market_index: u16,
+    pub base_asset_amount: u64,
+    pub slice_base_asset_amount: u64,
+    pub price: u64,
+    pub interval_slots: u64,
+    pub reduce_only: bool,
+    pub post_only: bool,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct ModifyOrderParams {
     pub direction: Option<PositionDirection>,
@@ -822,6 +867,9 @@ pub struct PlaceOrderOptions {
     pub risk_increasing: bool,
     pub explanation: OrderActionExplanation,
     pub is_rfq_order: bool,
+    pub trigger_source: OrderTriggerSource,
+    pub trigger_market_index: u16,
+    pub trailing_stop_distance: Option<TrailingStopDistance>,
 }
 
 impl Default for PlaceOrderOptions {
@@ -833,6 +881,9 @@ impl Default for PlaceOrderOptions {
             risk_increasing: false,
             explanation: OrderActionExplanation::None,
             is_rfq_order: false,
+            trigger_source: OrderTriggerSource::Oracle,
+            trigger_market_index: 0,
+            trailing_stop_distance: None,
         }
     }
 }
@@ -847,6 +898,21 @@ impl PlaceOrderOptions {
         self
     }
 
+    pub fn trigger_market(
+        mut self,
+        trigger_source: OrderTriggerSource,
+        trigger_market_index: u16,
+    ) -> Self {
+        self.trigger_source = trigger_source;
+        self.trigger_market_index = trigger_market_index;
+        self
+    }
+
+    pub fn trailing_stop(mut self, trailing_stop_distance: TrailingStopDistance) -> Self {
+        self.trailing_stop_distance = Some(trailing_stop_distance);
+        self
+    }
+
     pub fn is_liquidation(&self) -> bool {
         self.explanation == OrderActionExplanation::Liquidation
     }
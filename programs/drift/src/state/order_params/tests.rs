@@ -1040,7 +1040,7 @@ mod get_close_perp_params {
     use crate::state::perp_market::{PerpMarket, AMM};
     use crate::{ContractTier, PRICE_PRECISION_U64};
 
-    use crate::state::user::{Order, OrderStatus};
+    use crate::state::user::{Order, OrderStatus, OrderTriggerSource};
     use crate::test_utils::create_account_info;
     use crate::validation::order::validate_order;
     use crate::{
@@ -1380,7 +1380,8 @@ mod get_close_perp_params {
             auction_end_price: params.auction_end_price.unwrap_or(0),
             auction_duration: params.auction_duration.unwrap_or(0),
             max_ts: 100,
-            padding: [0; 3],
+            trigger_source: OrderTriggerSource::Oracle,
+            trigger_market_index: 0,
         }
     }
 
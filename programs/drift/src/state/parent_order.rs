@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::controller::position::PositionDirection;
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::safe_math::SafeMath;
+use crate::state::traits::Size;
+
+#[cfg(test)]
+mod tests;
+
+pub const PARENT_ORDERS_PDA_SEED: &str = "PARENT_ORDERS";
+
+pub const MAX_PARENT_ORDERS: usize = 8;
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum ParentOrderType {
+    /// Releases a slice every interval. A slice that hasn't filled by the next release is
+    /// canceled and its unfilled size goes back to the parent
+    #[default]
+    Twap,
+    /// Releases the next slice once the previous one has filled, so only the slice size is ever
+    /// on the book for makers and the dlob to see
+    Iceberg,
+}
+
+/// A large perp order held off book and released by keepers as child limit orders
+#[zero_copy(unsafe)]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct ParentOrder {
+    /// The total size to trade
+    /// precision: BASE_PRECISION
+    pub base_asset_amount: u64,
+    /// The size released as child orders, less what was left unfilled on replaced slices. A slice
+    /// canceled or modified outside its parent order counts as released in full
+    /// precision: BASE_PRECISION
+    pub base_asset_amount_released: u64,
+    /// The size of each child order
+    /// precision: BASE_PRECISION
+    pub slice_base_asset_amount: u64,
+    /// The limit price of each child order
+    /// precision: PRICE_PRECISION
+    pub price: u64,
+    /// The min slots between slices
+    pub interval_slots: u64,
+    /// The slot the last slice was released
+    pub last_release_slot: u64,
+    /// Taken from the user's order id space. 0 if unused
+    pub parent_order_id: u32,
+    /// The order id of the last slice released. 0 if no slice has been released
+    pub child_order_id: u32,
+    pub market_index: u16,
+    pub parent_order_type: ParentOrderType,
+    pub direction: PositionDirection,
+    pub reduce_only: bool,
+    /// Whether slices must be makers
+    pub post_only: bool,
+    pub padding: [u8; 2],
+}
+
+impl ParentOrder {
+    pub fn is_open(&self) -> bool {
+        self.parent_order_id != 0
+    }
+
+    pub fn get_base_asset_amount_unreleased(&self) -> DriftResult<u64> {
+        self.base_asset_amount
+            .safe_sub(self.base_asset_amount_released)
+    }
+
+    pub fn get_next_slice_base_asset_amount(&self) -> DriftResult<u64> {
+        Ok(self
+            .slice_base_asset_amount
+            .min(self.get_base_asset_amount_unreleased()?))
+    }
+
+    pub fn get_next_release_slot(&self) -> DriftResult<u64> {
+        if self.child_order_id == 0 {
+            return Ok(0);
+        }
+
+        self.last_release_slot.safe_add(self.interval_slots)
+    }
+}
+
+impl Size for UserParentOrders {
+    const SIZE: usize = 552;
+}
+
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct UserParentOrders {
+    pub user_pubkey: Pubkey,
+    pub parent_orders: [ParentOrder; MAX_PARENT_ORDERS],
+}
+
+impl UserParentOrders {
+    pub fn add_parent_order(&mut self, parent_order: ParentOrder) -> DriftResult {
+        let parent_order_slot = self
+            .parent_orders
+            .iter_mut()
+            .find(|parent_order| !parent_order.is_open())
+            .ok_or(ErrorCode::UserParentOrdersAccountFull)?;
+
+        *parent_order_slot = parent_order;
+
+        Ok(())
+    }
+
+    pub fn get_parent_order_mut(&mut self, parent_order_id: u32) -> DriftResult<&mut ParentOrder> {
+        self.parent_orders
+            .iter_mut()
+            .find(|parent_order| {
+                parent_order.is_open() && parent_order.parent_order_id == parent_order_id
+            })
+            .ok_or_else(|| {
+                msg!("parent order {} not found", parent_order_id);
+                ErrorCode::InvalidParentOrder
+            })
+    }
+}
@@ -0,0 +1,75 @@
+mod parent_order {
+    use crate::math::constants::BASE_PRECISION_U64;
+    use crate::state::parent_order::ParentOrder;
+
+    #[test]
+    fn next_slice() {
+        let mut parent_order = ParentOrder {
+            base_asset_amount: 5 * BASE_PRECISION_U64 / 2,
+            slice_base_asset_amount: BASE_PRECISION_U64,
+            interval_slots: 10,
+            parent_order_id: 1,
+            ..ParentOrder::default()
+        };
+
+        // first slice can be released right away
+        assert_eq!(parent_order.get_next_release_slot().unwrap(), 0);
+        assert_eq!(
+            parent_order.get_next_slice_base_asset_amount().unwrap(),
+            BASE_PRECISION_U64
+        );
+
+        parent_order.base_asset_amount_released = 2 * BASE_PRECISION_U64;
+        parent_order.child_order_id = 3;
+        parent_order.last_release_slot = 100;
+        assert_eq!(parent_order.get_next_release_slot().unwrap(), 110);
+        assert_eq!(
+            parent_order.get_next_slice_base_asset_amount().unwrap(),
+            BASE_PRECISION_U64 / 2
+        );
+
+        parent_order.base_asset_amount_released = parent_order.base_asset_amount;
+        assert_eq!(parent_order.get_next_slice_base_asset_amount().unwrap(), 0);
+    }
+}
+
+mod user_parent_orders {
+    use crate::error::ErrorCode;
+    use crate::state::parent_order::{ParentOrder, UserParentOrders, MAX_PARENT_ORDERS};
+
+    #[test]
+    fn add_and_get() {
+        let mut user_parent_orders = UserParentOrders::default();
+
+        for parent_order_id in 1..=MAX_PARENT_ORDERS as u32 {
+            user_parent_orders
+                .add_parent_order(ParentOrder {
+                    parent_order_id,
+                    ..ParentOrder::default()
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            user_parent_orders.add_parent_order(ParentOrder {
+                parent_order_id: 9,
+                ..ParentOrder::default()
+            }),
+            Err(ErrorCode::UserParentOrdersAccountFull)
+        );
+
+        *user_parent_orders.get_parent_order_mut(3).unwrap() = ParentOrder::default();
+        assert_eq!(
+            user_parent_orders.get_parent_order_mut(3),
+            Err(ErrorCode::InvalidParentOrder)
+        );
+
+        user_parent_orders
+            .add_parent_order(ParentOrder {
+                parent_order_id: 9,
+                ..ParentOrder::default()
+            })
+            .unwrap();
+        assert_eq!(user_parent_orders.parent_orders[2].parent_order_id, 9);
+    }
+}
@@ -13,10 +13,13 @@ use crate::math::constants::{
 };
 use crate::math::constants::{
     AMM_RESERVE_PRECISION_I128, AMM_TO_QUOTE_PRECISION_RATIO, BID_ASK_SPREAD_PRECISION,
-    BID_ASK_SPREAD_PRECISION_U128, DEFAULT_REVENUE_SINCE_LAST_FUNDING_SPREAD_RETREAT,
-    LIQUIDATION_FEE_PRECISION, LP_FEE_SLICE_DENOMINATOR, LP_FEE_SLICE_NUMERATOR, MARGIN_PRECISION,
-    MARGIN_PRECISION_U128, MAX_LIQUIDATION_MULTIPLIER, PEG_PRECISION, PERCENTAGE_PRECISION,
-    PERCENTAGE_PRECISION_I128, PERCENTAGE_PRECISION_I64, PERCENTAGE_PRECISION_U64, PRICE_PRECISION,
+    BID_ASK_SPREAD_PRECISION_U128, CALM_MARKET_ORACLE_VOLATILITY,
+    DEFAULT_PREDICTION_MARKET_MAX_BID, DEFAULT_PREDICTION_MARKET_MIN_ASK,
+    DEFAULT_REVENUE_SINCE_LAST_FUNDING_SPREAD_RETREAT, LIQUIDATION_FEE_PRECISION,
+    LP_FEE_SLICE_DENOMINATOR, LP_FEE_SLICE_NUMERATOR, MARGIN_PRECISION, MARGIN_PRECISION_U128,
+    MAX_LIQUIDATION_MULTIPLIER, MAX_PREDICTION_MARKET_PRICE, PEG_PRECISION, PERCENTAGE_PRECISION,
+    PERCENTAGE_PRECISION_I128, PERCENTAGE_PRECISION_I64, PERCENTAGE_PRECISION_U64,
+    PREDICTION_EVENT_PENDING_OUTCOMES, PREDICTION_MARKET_PRICE_BAND_PRECISION, PRICE_PRECISION,
     SPOT_WEIGHT_PRECISION, TWENTY_FOUR_HOUR,
 };
 use crate::math::helpers::get_proportion_i128;
@@ -27,14 +30,16 @@ use crate::math::margin::{
 use crate::math::safe_math::SafeMath;
 use crate::math::stats;
 use crate::state::events::OrderActionExplanation;
+use crate::state::market_resolution::ResolutionSource;
 use num_integer::Roots;
 
 use crate::state::oracle::{
-    get_prelaunch_price, get_sb_on_demand_price, get_switchboard_price, HistoricalOracleData,
-    OracleSource,
+    get_median_oracle_price, get_prelaunch_price, get_pyth_lazer_price, get_sb_on_demand_price,
+    get_switchboard_price, HistoricalOracleData, MarketOracleAccount, OracleSource,
 };
 use crate::state::spot_market::{AssetTier, SpotBalance, SpotBalanceType};
 use crate::state::traits::{MarketIndexOffset, Size};
+use crate::state::user::PerpPosition;
 use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::state::paused_operations::PerpOperation;
@@ -67,6 +72,9 @@ pub enum MarketStatus {
     Settlement,
     /// market has no remaining participants
     Delisted,
+    /// trading has halted ahead of resolution, the market awaits settlement
+    /// fills, order placement and lp changes are blocked. margin and liquidation continue
+    TradingHalted,
 }
 
 impl MarketStatus {
@@ -94,6 +102,64 @@ pub enum ContractType {
     Prediction,
 }
 
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum MarketVoidStatus {
+    #[default]
+    NotVoided,
+    /// positions settle at their quote entry amount, fees already paid are kept
+    Voided,
+    /// positions settle at their quote break even amount, fees are refunded from the fee pool
+    VoidedWithFeeRefund,
+}
+
+/// What notional the fee tier's rate is applied to on prediction market fills
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum PredictionFeeCurve {
+    /// fee is charged on the fill's quote amount
+    #[default]
+    Flat,
+    /// fee is charged on base * p * (1 - p), peaking at a 50/50 price
+    Variance,
+    /// fee is charged on base * min(p, 1 - p), the payout at risk on the cheaper side
+    PayoutAtRisk,
+}
+
+/// The price range a prediction market pays out within. Binary markets pay out within
+/// [0, MAX_PREDICTION_MARKET_PRICE], scalar markets within their configured band
+/// precision: PRICE_PRECISION
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub struct PredictionMarketPriceBounds {
+    pub lower: u64,
+    pub upper: u64,
+}
+
+impl Default for PredictionMarketPriceBounds {
+    fn default() -> Self {
+        PredictionMarketPriceBounds {
+            lower: 0,
+            upper: MAX_PREDICTION_MARKET_PRICE,
+        }
+    }
+}
+
+impl PredictionMarketPriceBounds {
+    pub fn clamp_price(&self, price: u64) -> u64 {
+        price.max(self.lower).min(self.upper)
+    }
+
+    pub fn clamp_price_i64(&self, price: i64) -> DriftResult<i64> {
+        Ok(price.max(self.lower.cast()?).min(self.upper.cast()?))
+    }
+
+    pub fn contains(&self, price: u64) -> bool {
+        (self.lower..=self.upper).contains(&price)
+    }
+
+    pub fn range(&self) -> DriftResult<u64> {
+        self.upper.safe_sub(self.lower)
+    }
+}
+
 #[derive(
     Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, PartialOrd, Ord, Default,
 )]
@@ -149,6 +215,15 @@ impl AMMLiquiditySplit {
     }
 }
 
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum AMMCurve {
+    /// constant product curve with spread reserves
+    #[default]
+    ConstantProduct,
+    /// logarithmic market scoring rule, only for binary prediction markets
+    Lmsr,
+}
+
 #[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, PartialOrd, Ord)]
 pub enum AMMAvailability {
     Immediate,
@@ -246,7 +321,44 @@ pub struct PerpMarket {
     pub pool_id: u8,
     pub high_leverage_margin_ratio_initial: u16,
     pub high_leverage_margin_ratio_maintenance: u16,
-    pub padding: [u8; 38],
+    /// The prediction event the market is an outcome of. Only valid if prediction_event_outcomes > 0
+    pub prediction_event_index: u16,
+    /// The number of mutually exclusive outcomes in the market's prediction event
+    /// 0 if the market isn't an outcome of a prediction event
+    /// PREDICTION_EVENT_PENDING_OUTCOMES until the event is activated and its outcomes are final
+    pub prediction_event_outcomes: u8,
+    /// Where the expiry price comes from when the market is settled
+    pub resolution_source: ResolutionSource,
+    /// Whether the market was voided instead of resolving to a price. Voided positions
+    /// settle at their own cost basis rather than the expiry price
+    pub void_status: MarketVoidStatus,
+    /// What notional fees are charged on for prediction market fills. Applied before fee_adjustment
+    pub prediction_fee_curve: PredictionFeeCurve,
+    /// The lowest price a scalar prediction market pays out at. Longs receive nothing at or below it
+    /// precision: PRICE_PRECISION
+    pub scalar_lower_bound: u64,
+    /// The highest price a scalar prediction market pays out at. Shorts receive nothing at or above it
+    /// 0 if the market is a binary prediction market paying out within [0, MAX_PREDICTION_MARKET_PRICE]
+    /// precision: PRICE_PRECISION
+    pub scalar_upper_bound: u64,
+    /// The lowest price the amm will ask in a prediction market, as a fraction of the payout range
+    /// 0 uses DEFAULT_PREDICTION_MARKET_MIN_ASK
+    /// precision: X/10000
+    pub prediction_min_ask: u16,
+    /// The highest price the amm will bid in a prediction market, as a fraction of the payout range
+    /// 0 uses DEFAULT_PREDICTION_MARKET_MAX_BID
+    /// precision: X/10000
+    pub prediction_max_bid: u16,
+    /// The lowest per share price the fee curve charges fees on, as a fraction of the payout range
+    /// precision: X/10000
+    pub prediction_fee_floor: u16,
+    /// The highest per share price the fee curve charges fees on, as a fraction of the payout range
+    /// 0 if the fee curve is uncapped
+    /// precision: X/10000
+    pub prediction_fee_cap: u16,
+    /// The ts when trading halts ahead of resolution. After it the market awaits settlement at expiry_ts
+    /// 0 if trading continues until expiry_ts
+    pub trading_halt_ts: i64,
 }
 
 impl Default for PerpMarket {
@@ -286,7 +398,18 @@ impl Default for PerpMarket {
             pool_id: 0,
             high_leverage_margin_ratio_initial: 0,
             high_leverage_margin_ratio_maintenance: 0,
-            padding: [0; 38],
+            prediction_event_index: 0,
+            prediction_event_outcomes: 0,
+            resolution_source: ResolutionSource::Oracle,
+            void_status: MarketVoidStatus::NotVoided,
+            prediction_fee_curve: PredictionFeeCurve::Flat,
+            scalar_lower_bound: 0,
+            scalar_upper_bound: 0,
+            prediction_min_ask: 0,
+            prediction_max_bid: 0,
+            prediction_fee_floor: 0,
+            prediction_fee_cap: 0,
+            trading_halt_ts: 0,
         }
     }
 }
@@ -313,6 +436,12 @@ impl PerpMarket {
         in_settlement || expired
     }
 
+    pub fn is_trading_halted(&self, now: i64) -> bool {
+        let halted = self.status == MarketStatus::TradingHalted;
+        let halt_reached = self.trading_halt_ts != 0 && now >= self.trading_halt_ts;
+        halted || halt_reached
+    }
+
     pub fn is_reduce_only(&self) -> DriftResult<bool> {
         Ok(self.status == MarketStatus::ReduceOnly)
     }
@@ -321,6 +450,20 @@ impl PerpMarket {
         PerpOperation::is_operation_paused(self.paused_operations, operation)
     }
 
+    pub fn has_oracle_guard_rails_override(&self) -> bool {
+        MarketOracleAccount::is_required(
+            self.amm.oracle_accounts,
+            MarketOracleAccount::GuardRailsOverride,
+        )
+    }
+
+    pub fn has_price_history(&self) -> bool {
+        MarketOracleAccount::is_required(
+            self.amm.oracle_accounts,
+            MarketOracleAccount::PriceHistory,
+        )
+    }
+
     pub fn can_skip_auction_duration(
         &self,
         state: &State,
@@ -348,6 +491,15 @@ impl PerpMarket {
     }
 
     pub fn has_too_much_drawdown(&self) -> DriftResult<bool> {
+        self.has_too_much_drawdown_for_oracle_volatility(None)
+    }
+
+    /// In calm markets (per the realized oracle volatility from the market's price history, if
+    /// passed) the percent drawdown limit is halved, as a drawdown then is more likely toxic flow
+    pub fn has_too_much_drawdown_for_oracle_volatility(
+        &self,
+        oracle_volatility: Option<u64>,
+    ) -> DriftResult<bool> {
         let quote_drawdown_limit_breached = match self.contract_tier {
             ContractTier::A | ContractTier::B => {
                 self.amm.net_revenue_since_last_funding
@@ -367,13 +519,22 @@ impl PerpMarket {
                 .safe_mul(PERCENTAGE_PRECISION_I128)?
                 .safe_div(self.amm.total_fee_minus_distributions.max(1))?;
 
-            let percent_drawdown_limit_breached = match self.contract_tier {
-                ContractTier::A => percent_drawdown <= -PERCENTAGE_PRECISION_I128 / 50,
-                ContractTier::B => percent_drawdown <= -PERCENTAGE_PRECISION_I128 / 33,
-                ContractTier::C => percent_drawdown <= -PERCENTAGE_PRECISION_I128 / 25,
-                _ => percent_drawdown <= -PERCENTAGE_PRECISION_I128 / 20,
+            let percent_drawdown_limit = match self.contract_tier {
+                ContractTier::A => PERCENTAGE_PRECISION_I128 / 50,
+                ContractTier::B => PERCENTAGE_PRECISION_I128 / 33,
+                ContractTier::C => PERCENTAGE_PRECISION_I128 / 25,
+                _ => PERCENTAGE_PRECISION_I128 / 20,
             };
 
+            let percent_drawdown_limit = match oracle_volatility {
+                Some(oracle_volatility) if oracle_volatility < CALM_MARKET_ORACLE_VOLATILITY => {
+                    percent_drawdown_limit / 2
+                }
+                _ => percent_drawdown_limit,
+            };
+
+            let percent_drawdown_limit_breached = percent_drawdown <= -percent_drawdown_limit;
+
             if percent_drawdown_limit_breached {
                 msg!("AMM has too much on-the-hour drawdown (percentage={}, quote={}) to accept fills",
                 percent_drawdown,
@@ -660,10 +821,99 @@ impl PerpMarket {
         self.contract_type == ContractType::Prediction
     }
 
+    pub fn is_voided(&self) -> bool {
+        self.void_status != MarketVoidStatus::NotVoided
+    }
+
+    /// The price a position is valued at once the market is in settlement. Voided markets
+    /// value each position at its own entry (or break even, if fees are refunded) price
+    pub fn get_settlement_price(&self, position: &PerpPosition) -> DriftResult<i64> {
+        match self.void_status {
+            MarketVoidStatus::NotVoided => Ok(self.expiry_price),
+            MarketVoidStatus::Voided => position.get_entry_price()?.cast(),
+            MarketVoidStatus::VoidedWithFeeRefund => position.get_breakeven_price()?.cast(),
+        }
+    }
+
+    pub fn has_optimistic_resolution(&self) -> bool {
+        self.resolution_source == ResolutionSource::Optimistic
+    }
+
+    pub fn is_prediction_event_outcome(&self) -> bool {
+        self.prediction_event_outcomes > 0
+    }
+
+    /// 0 until the prediction event is activated, as more outcomes can still be added
+    pub fn get_prediction_event_outcomes(&self) -> u8 {
+        if self.prediction_event_outcomes == PREDICTION_EVENT_PENDING_OUTCOMES {
+            0
+        } else {
+            self.prediction_event_outcomes
+        }
+    }
+
+    pub fn is_scalar_market(&self) -> bool {
+        self.is_prediction_market() && self.scalar_upper_bound != 0
+    }
+
+    /// None if the market isn't a prediction market
+    pub fn get_prediction_market_price_bounds(&self) -> Option<PredictionMarketPriceBounds> {
+        if !self.is_prediction_market() {
+            None
+        } else if self.is_scalar_market() {
+            Some(PredictionMarketPriceBounds {
+                lower: self.scalar_lower_bound,
+                upper: self.scalar_upper_bound,
+            })
+        } else {
+            Some(PredictionMarketPriceBounds::default())
+        }
+    }
+
+    /// The quote asset reserve at which the amm's reserve price equals `price`
+    pub fn calculate_quote_asset_reserve_for_price(&self, price: u64) -> DriftResult<u128> {
+        //precision scaling: 1e6 -> 1e12 -> 1e6
+        let price_sqrt = (price
+            .cast::<u128>()?
+            .safe_mul(self.amm.peg_multiplier)?
+            .saturating_add(1))
+        .nth_root(2)
+        .saturating_add(1);
+
+        self.amm
+            .sqrt_k
+            .safe_mul(price_sqrt)?
+            .safe_div(self.amm.peg_multiplier)
+    }
+
+    /// The amm's (min ask, max bid) as a fraction of the payout range
+    /// precision: PREDICTION_MARKET_PRICE_BAND_PRECISION
+    pub fn get_prediction_market_min_ask_max_bid(&self) -> (u64, u64) {
+        let min_ask = if self.prediction_min_ask == 0 {
+            DEFAULT_PREDICTION_MARKET_MIN_ASK
+        } else {
+            self.prediction_min_ask
+        };
+
+        let max_bid = if self.prediction_max_bid == 0 {
+            DEFAULT_PREDICTION_MARKET_MAX_BID
+        } else {
+            self.prediction_max_bid
+        };
+
+        (min_ask as u64, max_bid as u64)
+    }
+
     pub fn get_quote_asset_reserve_prediction_market_bounds(
         &self,
         direction: PositionDirection,
     ) -> DriftResult<(u128, u128)> {
+        if self.is_scalar_market() {
+            return self.get_quote_asset_reserve_scalar_market_bounds(direction);
+        }
+
+        let (min_ask, max_bid) = self.get_prediction_market_min_ask_max_bid();
+
         let mut quote_asset_reserve_lower_bound = 0_u128;
 
         //precision scaling: 1e6 -> 1e12 -> 1e6
@@ -683,21 +933,30 @@ impl PerpMarket {
             .safe_div(self.amm.peg_multiplier)?;
 
         // for price [0,1] maintain following invariants:
+        //precision scaling: 1e4 -> 1e10 -> 1e5
         if direction == PositionDirection::Long {
-            // lowest ask price is $0.05
+            // lowest ask price is min_ask, rounded up
+            let min_ask_sqrt = min_ask
+                .cast::<u128>()?
+                .safe_mul(1_000_000)?
+                .nth_root(2)
+                .saturating_add(1);
+
             quote_asset_reserve_lower_bound = self
                 .amm
                 .sqrt_k
-                .safe_mul(22361)?
+                .safe_mul(min_ask_sqrt)?
                 .safe_mul(peg_sqrt)?
                 .safe_div(100000)?
                 .safe_div(self.amm.peg_multiplier)?
         } else {
-            // highest bid price is $0.95
+            // highest bid price is max_bid, rounded down
+            let max_bid_sqrt = max_bid.cast::<u128>()?.safe_mul(1_000_000)?.nth_root(2);
+
             quote_asset_reserve_upper_bound = self
                 .amm
                 .sqrt_k
-                .safe_mul(97467)?
+                .safe_mul(max_bid_sqrt)?
                 .safe_mul(peg_sqrt)?
                 .safe_div(100000)?
                 .safe_div(self.amm.peg_multiplier)?
@@ -708,6 +967,44 @@ impl PerpMarket {
             quote_asset_reserve_upper_bound,
         ))
     }
+
+    fn get_quote_asset_reserve_scalar_market_bounds(
+        &self,
+        direction: PositionDirection,
+    ) -> DriftResult<(u128, u128)> {
+        let lower_bound = self.scalar_lower_bound;
+        let upper_bound = self.scalar_upper_bound;
+        let range = upper_bound.safe_sub(lower_bound)?;
+
+        let (min_ask, max_bid) = self.get_prediction_market_min_ask_max_bid();
+
+        // same invariants as binary markets, scaled to the band
+        let (lower_price, upper_price) = if direction == PositionDirection::Long {
+            let min_ask_price = range
+                .safe_mul(min_ask)?
+                .safe_div_ceil(PREDICTION_MARKET_PRICE_BAND_PRECISION)?;
+            (lower_bound.safe_add(min_ask_price)?, upper_bound)
+        } else {
+            let max_bid_price = range
+                .safe_mul(max_bid)?
+                .safe_div(PREDICTION_MARKET_PRICE_BAND_PRECISION)?;
+            (lower_bound, lower_bound.safe_add(max_bid_price)?)
+        };
+
+        let quote_asset_reserve_lower_bound = if lower_price == 0 {
+            0
+        } else {
+            self.calculate_quote_asset_reserve_for_price(lower_price)?
+        };
+
+        let quote_asset_reserve_upper_bound =
+            self.calculate_quote_asset_reserve_for_price(upper_price)?;
+
+        Ok((
+            quote_asset_reserve_lower_bound,
+            quote_asset_reserve_upper_bound,
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -1007,13 +1304,24 @@ pub struct AMM {
     pub target_base_asset_amount_per_lp: i32,
     /// expo for unit of per_lp, base 10 (if per_lp_base=X, then per_lp unit is 10^X)
     pub per_lp_base: i8,
-    pub padding1: u8,
-    pub padding2: u16,
+    /// the pricing curve used by the amm
+    pub curve: AMMCurve,
+    /// bitflags of the MarketOracleAccounts the market must be loaded with
+    pub oracle_accounts: u8,
+    /// k in the price -/+ k * confidence used to value pnl and liabilities for margin, 0 disables it
+    /// precision: CONFIDENCE_MARGIN_MULTIPLIER_PRECISION
+    pub confidence_margin_multiplier: u8,
     pub total_fee_earned_per_lp: u64,
     pub net_unsettled_funding_pnl: i64,
     pub quote_asset_amount_with_unsettled_lp: i64,
     pub reference_price_offset: i32,
-    pub padding: [u8; 12],
+    /// the lmsr liquidity parameter b. only used when curve is AMMCurve::Lmsr
+    /// the amm's loss is bounded by b * ln(2)
+    /// precision: whole base units
+    pub lmsr_liquidity: u32,
+    /// net user position whose counterparty is minted complete sets of a prediction event
+    /// precision: BASE_PRECISION
+    pub base_asset_amount_with_complete_sets: i64,
 }
 
 impl Default for AMM {
@@ -1096,13 +1404,15 @@ impl Default for AMM {
             last_oracle_valid: false,
             target_base_asset_amount_per_lp: 0,
             per_lp_base: 0,
-            padding1: 0,
-            padding2: 0,
+            curve: AMMCurve::ConstantProduct,
+            oracle_accounts: 0,
+            confidence_margin_multiplier: 0,
             total_fee_earned_per_lp: 0,
             net_unsettled_funding_pnl: 0,
             quote_asset_amount_with_unsettled_lp: 0,
             reference_price_offset: 0,
-            padding: [0; 12],
+            lmsr_liquidity: 0,
+            base_asset_amount_with_complete_sets: 0,
         }
     }
 }
@@ -1350,6 +1660,10 @@ impl AMM {
         }
     }
 
+    pub fn is_lmsr(&self) -> bool {
+        self.curve == AMMCurve::Lmsr
+    }
+
     pub fn amm_jit_is_active(&self) -> bool {
         self.amm_jit_intensity > 0
     }
@@ -1445,6 +1759,8 @@ impl AMM {
             OracleSource::Pyth1MPull => {
                 Ok(Some(self.get_pyth_twap(price_oracle, 1000000, true)?))
             }
+            OracleSource::Median => Ok(Some(get_median_oracle_price(price_oracle, slot)?.price)),
+            OracleSource::PythLazer => Ok(Some(get_pyth_lazer_price(price_oracle, slot)?.price)),
         }
     }
 
@@ -102,3 +102,124 @@ mod get_margin_ratio {
         assert_eq!(margin_ratio_maintenance, MARGIN_PRECISION / 100);
     }
 }
+
+mod get_quote_asset_reserve_prediction_market_bounds {
+    use crate::controller::position::PositionDirection;
+    use crate::math::amm::calculate_price;
+    use crate::state::perp_market::{ContractType, PerpMarket, AMM};
+    use crate::{AMM_RESERVE_PRECISION, PEG_PRECISION, PRICE_PRECISION_U64};
+
+    fn reserve_price_at_quote_asset_reserve(market: &PerpMarket, quote_asset_reserve: u128) -> u64 {
+        let base_asset_reserve = market.amm.sqrt_k * market.amm.sqrt_k / quote_asset_reserve;
+        calculate_price(
+            quote_asset_reserve,
+            base_asset_reserve,
+            market.amm.peg_multiplier,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn scalar_market() {
+        // pays out linearly between $2 and $4
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            scalar_lower_bound: 2 * PRICE_PRECISION_U64,
+            scalar_upper_bound: 4 * PRICE_PRECISION_U64,
+            amm: AMM {
+                sqrt_k: 1000 * AMM_RESERVE_PRECISION,
+                base_asset_reserve: 1000 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 1000 * AMM_RESERVE_PRECISION,
+                peg_multiplier: 3 * PEG_PRECISION,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        };
+
+        assert!(market.is_scalar_market());
+
+        let (lower_bound, upper_bound) = market
+            .get_quote_asset_reserve_prediction_market_bounds(PositionDirection::Long)
+            .unwrap();
+
+        // lowest ask is 5% into the band, highest ask is the upper bound
+        let lowest_ask = reserve_price_at_quote_asset_reserve(&market, lower_bound);
+        let highest_ask = reserve_price_at_quote_asset_reserve(&market, upper_bound);
+        assert!(lowest_ask.abs_diff(2_100_000) <= 10);
+        assert!(highest_ask.abs_diff(4_000_000) <= 10);
+
+        let (lower_bound, upper_bound) = market
+            .get_quote_asset_reserve_prediction_market_bounds(PositionDirection::Short)
+            .unwrap();
+
+        // lowest bid is the lower bound, highest bid is 95% into the band
+        let lowest_bid = reserve_price_at_quote_asset_reserve(&market, lower_bound);
+        let highest_bid = reserve_price_at_quote_asset_reserve(&market, upper_bound);
+        assert!(lowest_bid.abs_diff(2_000_000) <= 10);
+        assert!(highest_bid.abs_diff(3_900_000) <= 10);
+    }
+
+    #[test]
+    fn binary_market() {
+        let market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            amm: AMM {
+                sqrt_k: 1000 * AMM_RESERVE_PRECISION,
+                base_asset_reserve: 1000 * AMM_RESERVE_PRECISION,
+                quote_asset_reserve: 1000 * AMM_RESERVE_PRECISION,
+                peg_multiplier: PEG_PRECISION / 2,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        };
+
+        assert!(!market.is_scalar_market());
+
+        let (lower_bound, upper_bound) = market
+            .get_quote_asset_reserve_prediction_market_bounds(PositionDirection::Short)
+            .unwrap();
+
+        assert_eq!(lower_bound, 0);
+        let highest_bid = reserve_price_at_quote_asset_reserve(&market, upper_bound);
+        assert!(highest_bid.abs_diff(950_000) <= 100);
+    }
+}
+
+mod is_trading_halted {
+    use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket};
+
+    #[test]
+    fn halt_ts_before_expiry() {
+        let mut market = PerpMarket {
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::Active,
+            trading_halt_ts: 100,
+            expiry_ts: 200,
+            ..PerpMarket::default()
+        };
+
+        assert!(!market.is_trading_halted(99));
+        assert!(!market.is_in_settlement(99));
+
+        // trading halts at event start, settlement waits for resolution
+        assert!(market.is_trading_halted(100));
+        assert!(!market.is_in_settlement(150));
+        assert!(market.is_in_settlement(200));
+
+        market.status = MarketStatus::TradingHalted;
+        market.trading_halt_ts = 0;
+        assert!(market.is_trading_halted(0));
+    }
+
+    #[test]
+    fn no_halt_ts() {
+        let market = PerpMarket {
+            status: MarketStatus::ReduceOnly,
+            expiry_ts: 200,
+            ..PerpMarket::default()
+        };
+
+        assert!(!market.is_trading_halted(150));
+        assert!(!market.is_trading_halted(250));
+    }
+}
@@ -0,0 +1,233 @@
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::constants::{MAX_PREDICTION_EVENT_OUTCOMES, MAX_PREDICTION_MARKET_PRICE_I64};
+use crate::math::safe_math::SafeMath;
+use crate::state::perp_market::{MarketStatus, PerpMarket};
+use crate::state::traits::Size;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum PredictionEventStatus {
+    /// outcomes can be added to the event
+    #[default]
+    Initialized,
+    /// all outcomes have been linked, outcome markets can trade
+    Active,
+    /// winning outcome has been determined, outcome markets can be settled
+    Resolved,
+    /// every outcome market has been settled at the resolved outcome
+    Settled,
+}
+
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PredictionEvent {
+    /// Encoded display name for the event e.g. US-ELECTION-2028
+    pub name: [u8; 32],
+    /// The perp market index for each outcome. Only the first `number_of_outcomes` are set
+    pub outcome_market_indexes: [u16; 16],
+    /// The quote each outcome market's pnl pool holds for outstanding complete sets
+    /// Moved to the winning outcome's pnl pool when the event resolves
+    /// precision: QUOTE_PRECISION
+    pub complete_set_quote_asset_amounts: [u64; 16],
+    /// The event's index. It is used as the seed for the event's pda
+    pub event_index: u16,
+    /// The number of mutually exclusive outcomes linked to the event
+    pub number_of_outcomes: u8,
+    /// Index into outcome_market_indexes of the outcome that resolves to MAX_PREDICTION_MARKET_PRICE
+    /// Only set once the event is resolved
+    pub winning_outcome_index: u8,
+    /// Whether the event is adding outcomes, trading, resolved or settled
+    pub status: PredictionEventStatus,
+    pub padding: [u8; 27],
+}
+
+impl Size for PredictionEvent {
+    const SIZE: usize = 232;
+}
+
+impl PredictionEvent {
+    pub fn outcome_market_indexes(&self) -> &[u16] {
+        &self.outcome_market_indexes[..self.number_of_outcomes as usize]
+    }
+
+    pub fn get_outcome_index(&self, market_index: u16) -> DriftResult<usize> {
+        self.outcome_market_indexes()
+            .iter()
+            .position(|outcome_market_index| *outcome_market_index == market_index)
+            .ok_or_else(|| {
+                msg!(
+                    "perp market {} is not an outcome of prediction event {}",
+                    market_index,
+                    self.event_index
+                );
+                ErrorCode::InvalidPredictionEvent
+            })
+    }
+
+    pub fn add_outcome(&mut self, perp_market: &PerpMarket) -> DriftResult {
+        validate!(
+            self.status == PredictionEventStatus::Initialized,
+            ErrorCode::InvalidPredictionEvent,
+            "can only add outcomes to an initialized prediction event"
+        )?;
+
+        validate!(
+            perp_market.is_prediction_market(),
+            ErrorCode::InvalidPredictionEvent,
+            "perp market {} is not a prediction market",
+            perp_market.market_index
+        )?;
+
+        validate!(
+            !perp_market.is_prediction_event_outcome(),
+            ErrorCode::InvalidPredictionEvent,
+            "perp market {} is already an outcome of prediction event {}",
+            perp_market.market_index,
+            perp_market.prediction_event_index
+        )?;
+
+        validate!(
+            !perp_market.is_scalar_market(),
+            ErrorCode::InvalidPredictionEvent,
+            "perp market {} is a scalar market",
+            perp_market.market_index
+        )?;
+
+        validate!(
+            perp_market.expiry_ts == 0
+                && !matches!(
+                    perp_market.status,
+                    MarketStatus::ReduceOnly
+                        | MarketStatus::TradingHalted
+                        | MarketStatus::Settlement
+                        | MarketStatus::Delisted
+                ),
+            ErrorCode::InvalidPredictionEvent,
+            "perp market {} is already expiring",
+            perp_market.market_index
+        )?;
+
+        validate!(
+            (self.number_of_outcomes as usize) < MAX_PREDICTION_EVENT_OUTCOMES,
+            ErrorCode::InvalidPredictionEvent,
+            "prediction event already has max outcomes ({})",
+            MAX_PREDICTION_EVENT_OUTCOMES
+        )?;
+
+        self.outcome_market_indexes[self.number_of_outcomes as usize] = perp_market.market_index;
+        self.number_of_outcomes = self.number_of_outcomes.safe_add(1)?;
+
+        Ok(())
+    }
+
+    pub fn activate(&mut self) -> DriftResult {
+        validate!(
+            self.status == PredictionEventStatus::Initialized,
+            ErrorCode::InvalidPredictionEvent,
+            "prediction event must be initialized to activate"
+        )?;
+
+        validate!(
+            self.number_of_outcomes >= 2,
+            ErrorCode::InvalidPredictionEvent,
+            "prediction event must have at least 2 outcomes, has {}",
+            self.number_of_outcomes
+        )?;
+
+        self.status = PredictionEventStatus::Active;
+
+        Ok(())
+    }
+
+    pub fn resolve(&mut self, winning_outcome_index: u8) -> DriftResult {
+        validate!(
+            self.status == PredictionEventStatus::Active,
+            ErrorCode::InvalidPredictionEvent,
+            "prediction event must be active to resolve"
+        )?;
+
+        validate!(
+            winning_outcome_index < self.number_of_outcomes,
+            ErrorCode::InvalidPredictionEvent,
+            "winning outcome index {} >= number of outcomes {}",
+            winning_outcome_index,
+            self.number_of_outcomes
+        )?;
+
+        self.winning_outcome_index = winning_outcome_index;
+        self.status = PredictionEventStatus::Resolved;
+
+        Ok(())
+    }
+
+    pub fn settle(&mut self) -> DriftResult {
+        validate!(
+            self.status == PredictionEventStatus::Resolved,
+            ErrorCode::PredictionEventNotResolved,
+            "prediction event must be resolved to settle"
+        )?;
+
+        self.status = PredictionEventStatus::Settled;
+
+        Ok(())
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        matches!(
+            self.status,
+            PredictionEventStatus::Resolved | PredictionEventStatus::Settled
+        )
+    }
+
+    pub fn get_winning_market_index(&self) -> DriftResult<u16> {
+        validate!(
+            self.is_resolved(),
+            ErrorCode::PredictionEventNotResolved,
+            "prediction event {} is not resolved",
+            self.event_index
+        )?;
+
+        Ok(self.outcome_market_indexes[self.winning_outcome_index as usize])
+    }
+
+    /// exactly one outcome settles at MAX_PREDICTION_MARKET_PRICE, the rest settle at 0
+    pub fn get_outcome_expiry_price(&self, market_index: u16) -> DriftResult<i64> {
+        self.get_outcome_index(market_index)?;
+
+        if self.get_winning_market_index()? == market_index {
+            Ok(MAX_PREDICTION_MARKET_PRICE_I64)
+        } else {
+            Ok(0)
+        }
+    }
+
+    pub fn validate_outcome_settled(&self, perp_market: &PerpMarket) -> DriftResult {
+        let expected_expiry_price = self.get_outcome_expiry_price(perp_market.market_index)?;
+
+        validate!(
+            perp_market.status == MarketStatus::Settlement
+                || perp_market.status == MarketStatus::Delisted,
+            ErrorCode::PerpMarketNotInSettlement,
+            "outcome perp market {} not in settlement",
+            perp_market.market_index
+        )?;
+
+        validate!(
+            perp_market.expiry_price == expected_expiry_price,
+            ErrorCode::InvalidPredictionEvent,
+            "outcome perp market {} expiry price {} != {}",
+            perp_market.market_index,
+            perp_market.expiry_price,
+            expected_expiry_price
+        )?;
+
+        Ok(())
+    }
+}
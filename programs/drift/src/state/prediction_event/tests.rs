@@ -0,0 +1,159 @@
+mod add_outcome {
+    use crate::state::perp_market::{ContractType, MarketStatus, PerpMarket};
+    use crate::state::prediction_event::{PredictionEvent, PredictionEventStatus};
+
+    #[test]
+    fn add_and_activate() {
+        let mut prediction_event = PredictionEvent::default();
+
+        let market_0 = PerpMarket {
+            market_index: 0,
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::Active,
+            ..PerpMarket::default()
+        };
+
+        prediction_event.add_outcome(&market_0).unwrap();
+
+        // need at least two outcomes
+        assert!(prediction_event.activate().is_err());
+
+        let market_3 = PerpMarket {
+            market_index: 3,
+            ..market_0
+        };
+
+        prediction_event.add_outcome(&market_3).unwrap();
+
+        assert_eq!(prediction_event.outcome_market_indexes(), &[0, 3]);
+        assert_eq!(prediction_event.get_outcome_index(3).unwrap(), 1);
+        assert!(prediction_event.get_outcome_index(1).is_err());
+
+        prediction_event.activate().unwrap();
+        assert_eq!(prediction_event.status, PredictionEventStatus::Active);
+
+        // cant add outcomes once active
+        let market_4 = PerpMarket {
+            market_index: 4,
+            ..market_0
+        };
+        assert!(prediction_event.add_outcome(&market_4).is_err());
+    }
+
+    #[test]
+    fn invalid_outcome_markets() {
+        let mut prediction_event = PredictionEvent::default();
+
+        let perpetual_market = PerpMarket {
+            market_index: 0,
+            contract_type: ContractType::Perpetual,
+            status: MarketStatus::Active,
+            ..PerpMarket::default()
+        };
+        assert!(prediction_event.add_outcome(&perpetual_market).is_err());
+
+        let expiring_market = PerpMarket {
+            market_index: 1,
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::ReduceOnly,
+            expiry_ts: 100,
+            ..PerpMarket::default()
+        };
+        assert!(prediction_event.add_outcome(&expiring_market).is_err());
+
+        let linked_market = PerpMarket {
+            market_index: 2,
+            contract_type: ContractType::Prediction,
+            status: MarketStatus::Active,
+            prediction_event_index: 1,
+            prediction_event_outcomes: 2,
+            ..PerpMarket::default()
+        };
+        assert!(prediction_event.add_outcome(&linked_market).is_err());
+
+        assert_eq!(prediction_event.number_of_outcomes, 0);
+    }
+}
+
+mod resolve {
+    use crate::math::constants::MAX_PREDICTION_MARKET_PRICE_I64;
+    use crate::state::perp_market::{MarketStatus, PerpMarket};
+    use crate::state::prediction_event::{PredictionEvent, PredictionEventStatus};
+
+    fn active_prediction_event() -> PredictionEvent {
+        let mut outcome_market_indexes = [0; 16];
+        outcome_market_indexes[..3].copy_from_slice(&[2, 5, 7]);
+
+        PredictionEvent {
+            outcome_market_indexes,
+            number_of_outcomes: 3,
+            status: PredictionEventStatus::Active,
+            ..PredictionEvent::default()
+        }
+    }
+
+    #[test]
+    fn outcome_expiry_price() {
+        let mut prediction_event = active_prediction_event();
+
+        assert!(prediction_event.get_outcome_expiry_price(5).is_err());
+        assert!(prediction_event.resolve(3).is_err());
+
+        prediction_event.resolve(1).unwrap();
+        assert_eq!(prediction_event.get_winning_market_index().unwrap(), 5);
+
+        assert_eq!(prediction_event.get_outcome_expiry_price(2).unwrap(), 0);
+        assert_eq!(
+            prediction_event.get_outcome_expiry_price(5).unwrap(),
+            MAX_PREDICTION_MARKET_PRICE_I64
+        );
+        assert_eq!(prediction_event.get_outcome_expiry_price(7).unwrap(), 0);
+        assert!(prediction_event.get_outcome_expiry_price(3).is_err());
+
+        // cant resolve twice
+        assert!(prediction_event.resolve(0).is_err());
+    }
+
+    #[test]
+    fn settle() {
+        let mut prediction_event = active_prediction_event();
+
+        assert!(prediction_event.settle().is_err());
+
+        prediction_event.resolve(0).unwrap();
+
+        let mut winning_market = PerpMarket {
+            market_index: 2,
+            status: MarketStatus::ReduceOnly,
+            ..PerpMarket::default()
+        };
+        assert!(prediction_event
+            .validate_outcome_settled(&winning_market)
+            .is_err());
+
+        winning_market.status = MarketStatus::Settlement;
+        winning_market.expiry_price = MAX_PREDICTION_MARKET_PRICE_I64 - 1;
+        assert!(prediction_event
+            .validate_outcome_settled(&winning_market)
+            .is_err());
+
+        winning_market.expiry_price = MAX_PREDICTION_MARKET_PRICE_I64;
+        prediction_event
+            .validate_outcome_settled(&winning_market)
+            .unwrap();
+
+        let losing_market = PerpMarket {
+            market_index: 5,
+            status: MarketStatus::Settlement,
+            expiry_price: 0,
+            ..PerpMarket::default()
+        };
+        prediction_event
+            .validate_outcome_settled(&losing_market)
+            .unwrap();
+
+        prediction_event.settle().unwrap();
+        assert_eq!(prediction_event.status, PredictionEventStatus::Settled);
+        assert!(prediction_event.is_resolved());
+    }
+}
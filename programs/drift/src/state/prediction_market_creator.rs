@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::PERCENTAGE_PRECISION;
+use crate::math::safe_math::SafeMath;
+use crate::state::perp_market::{ContractTier, PerpMarket};
+use crate::state::traits::Size;
+use crate::validate;
+use crate::validation::margin::validate_margin;
+
+#[cfg(test)]
+mod tests;
+
+pub const PREDICTION_MARKET_CREATION_CONFIG_SEED: &[u8] = b"prediction_market_creation_config";
+pub const PREDICTION_MARKET_CREATOR_SEED: &[u8] = b"prediction_market_creator";
+
+/// Template of risk parameters every permissionlessly created prediction market starts with
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PredictionMarketCreationConfig {
+    /// The max open interest of a created market
+    /// precision: BASE_PRECISION
+    pub max_open_interest: u128,
+    /// The initial base and quote reserves of a created market's amm
+    /// precision: AMM_RESERVE_PRECISION
+    pub amm_base_asset_reserve: u128,
+    /// The bond the creator posts to create a market
+    /// precision: token mint precision
+    pub bond_amount: u64,
+    /// precision: BASE_PRECISION
+    pub order_step_size: u64,
+    /// precision: PRICE_PRECISION
+    pub order_tick_size: u64,
+    /// precision: BASE_PRECISION
+    pub min_order_size: u64,
+    /// precision: MARGIN_PRECISION
+    pub margin_ratio_initial: u32,
+    /// precision: MARGIN_PRECISION
+    pub margin_ratio_maintenance: u32,
+    /// precision: LIQUIDATOR_FEE_PRECISION
+    pub liquidator_fee: u32,
+    /// precision: PERCENTAGE_PRECISION
+    pub max_spread: u32,
+    /// The share of the market's exchange fees routed to the creator
+    /// precision: PERCENTAGE_PRECISION
+    pub creator_fee_share: u32,
+    /// The spot market the bond is denominated in
+    pub bond_spot_market_index: u16,
+    pub contract_tier: ContractTier,
+    pub paused: u8,
+    pub padding: [u8; 8],
+}
+
+impl Size for PredictionMarketCreationConfig {
+    const SIZE: usize = 104;
+}
+
+impl PredictionMarketCreationConfig {
+    pub fn validate(&self) -> DriftResult {
+        validate!(
+            matches!(
+                self.contract_tier,
+                ContractTier::HighlySpeculative | ContractTier::Isolated
+            ),
+            ErrorCode::InvalidPredictionMarketCreationConfig,
+            "contract tier must be HighlySpeculative or Isolated, got {:?}",
+            self.contract_tier
+        )?;
+
+        validate_margin(
+            self.margin_ratio_initial,
+            self.margin_ratio_maintenance,
+            0,
+            0,
+            self.liquidator_fee,
+            self.max_spread,
+        )?;
+
+        validate!(
+            self.max_open_interest > 0,
+            ErrorCode::InvalidPredictionMarketCreationConfig,
+            "max open interest must be capped"
+        )?;
+
+        validate!(
+            self.bond_amount > 0,
+            ErrorCode::InvalidPredictionMarketCreationConfig,
+            "bond amount must be positive"
+        )?;
+
+        validate!(
+            self.creator_fee_share.cast::<u128>()? <= PERCENTAGE_PRECISION,
+            ErrorCode::InvalidPredictionMarketCreationConfig,
+            "creator fee share {} > {}",
+            self.creator_fee_share,
+            PERCENTAGE_PRECISION
+        )?;
+
+        validate!(
+            self.order_step_size > 0
+                && self.order_tick_size > 0
+                && self.min_order_size >= self.order_step_size,
+            ErrorCode::InvalidPredictionMarketCreationConfig,
+            "invalid order step size {}, tick size {}, min order size {}",
+            self.order_step_size,
+            self.order_tick_size,
+            self.min_order_size
+        )?;
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused > 0
+    }
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum PredictionMarketCreatorStatus {
+    /// bond is held while the market is live
+    #[default]
+    Active,
+    /// market settled and the bond went back to the creator
+    BondReturned,
+    /// market was delisted by admin and the bond went to the revenue pool
+    Slashed,
+}
+
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PredictionMarketCreator {
+    /// The user account that created the market, posted the bond and receives the creator fees
+    pub creator: Pubkey,
+    /// The authority of the creator user account
+    pub authority: Pubkey,
+    /// The market's total_exchange_fee the creator has been paid up to
+    /// precision: QUOTE_PRECISION
+    pub total_exchange_fee_checkpoint: u128,
+    /// precision: token mint precision
+    pub bond_amount: u64,
+    /// The total creator fees paid out
+    /// precision: QUOTE_PRECISION
+    pub total_creator_fee_paid: u64,
+    /// precision: PERCENTAGE_PRECISION
+    pub creator_fee_share: u32,
+    pub market_index: u16,
+    pub bond_spot_market_index: u16,
+    pub status: PredictionMarketCreatorStatus,
+    pub padding: [u8; 23],
+}
+
+impl Size for PredictionMarketCreator {
+    const SIZE: usize = 136;
+}
+
+impl PredictionMarketCreator {
+    /// The creator's share of exchange fees the market collected since the last claim
+    /// precision: QUOTE_PRECISION
+    pub fn calculate_unclaimed_creator_fee(&self, perp_market: &PerpMarket) -> DriftResult<u64> {
+        if self.status == PredictionMarketCreatorStatus::Slashed {
+            return Ok(0);
+        }
+
+        perp_market
+            .amm
+            .total_exchange_fee
+            .saturating_sub(self.total_exchange_fee_checkpoint)
+            .safe_mul(self.creator_fee_share.cast()?)?
+            .safe_div(PERCENTAGE_PRECISION)?
+            .cast()
+    }
+
+    /// Advances the checkpoint past the exchange fees that produced `creator_fee`, so a partial
+    /// claim leaves the rest claimable
+    pub fn record_creator_fee_paid(&mut self, creator_fee: u64) -> DriftResult {
+        if creator_fee == 0 {
+            return Ok(());
+        }
+
+        // round up so repeated small claims can't be paid for more exchange fees than collected
+        let exchange_fee = creator_fee
+            .cast::<u128>()?
+            .safe_mul(PERCENTAGE_PRECISION)?
+            .safe_div_ceil(self.creator_fee_share.cast()?)?;
+
+        self.total_exchange_fee_checkpoint =
+            self.total_exchange_fee_checkpoint.safe_add(exchange_fee)?;
+        self.total_creator_fee_paid = self.total_creator_fee_paid.safe_add(creator_fee)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+mod validate {
+    use crate::math::constants::{
+        AMM_RESERVE_PRECISION, BASE_PRECISION, BASE_PRECISION_U64, PERCENTAGE_PRECISION_U64,
+        PRICE_PRECISION_U64, QUOTE_PRECISION_U64,
+    };
+    use crate::state::perp_market::ContractTier;
+    use crate::state::prediction_market_creator::PredictionMarketCreationConfig;
+
+    fn config() -> PredictionMarketCreationConfig {
+        PredictionMarketCreationConfig {
+            max_open_interest: 1000 * BASE_PRECISION,
+            amm_base_asset_reserve: 1000 * AMM_RESERVE_PRECISION,
+            bond_amount: 100 * QUOTE_PRECISION_U64,
+            order_step_size: BASE_PRECISION_U64,
+            order_tick_size: PRICE_PRECISION_U64 / 1000,
+            min_order_size: BASE_PRECISION_U64,
+            margin_ratio_initial: 10000,
+            margin_ratio_maintenance: 9999,
+            creator_fee_share: (PERCENTAGE_PRECISION_U64 / 10) as u32,
+            contract_tier: ContractTier::Isolated,
+            ..PredictionMarketCreationConfig::default()
+        }
+    }
+
+    #[test]
+    fn valid() {
+        assert!(config().validate().is_ok());
+
+        let config = PredictionMarketCreationConfig {
+            contract_tier: ContractTier::HighlySpeculative,
+            ..config()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn unsafe_contract_tier() {
+        let config = PredictionMarketCreationConfig {
+            contract_tier: ContractTier::A,
+            ..config()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn uncapped_open_interest() {
+        let config = PredictionMarketCreationConfig {
+            max_open_interest: 0,
+            ..config()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn creator_fee_share_above_100_percent() {
+        let config = PredictionMarketCreationConfig {
+            creator_fee_share: (PERCENTAGE_PRECISION_U64 + 1) as u32,
+            ..config()
+        };
+        assert!(config.validate().is_err());
+    }
+}
+
+mod creator_fee {
+    use crate::math::constants::{PERCENTAGE_PRECISION_U64, QUOTE_PRECISION, QUOTE_PRECISION_U64};
+    use crate::state::perp_market::{PerpMarket, AMM};
+    use crate::state::prediction_market_creator::{
+        PredictionMarketCreator, PredictionMarketCreatorStatus,
+    };
+
+    #[test]
+    fn claim_in_parts() {
+        let perp_market = PerpMarket {
+            amm: AMM {
+                total_exchange_fee: 100 * QUOTE_PRECISION,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        };
+
+        let mut creator = PredictionMarketCreator {
+            creator_fee_share: (PERCENTAGE_PRECISION_U64 / 10) as u32,
+            ..PredictionMarketCreator::default()
+        };
+
+        assert_eq!(
+            creator
+                .calculate_unclaimed_creator_fee(&perp_market)
+                .unwrap(),
+            10 * QUOTE_PRECISION_U64
+        );
+
+        // fee pool only covered part of it
+        creator
+            .record_creator_fee_paid(4 * QUOTE_PRECISION_U64)
+            .unwrap();
+        assert_eq!(creator.total_exchange_fee_checkpoint, 40 * QUOTE_PRECISION);
+        assert_eq!(creator.total_creator_fee_paid, 4 * QUOTE_PRECISION_U64);
+        assert_eq!(
+            creator
+                .calculate_unclaimed_creator_fee(&perp_market)
+                .unwrap(),
+            6 * QUOTE_PRECISION_U64
+        );
+
+        creator
+            .record_creator_fee_paid(6 * QUOTE_PRECISION_U64)
+            .unwrap();
+        assert_eq!(
+            creator
+                .calculate_unclaimed_creator_fee(&perp_market)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn small_claims_never_overpay() {
+        let perp_market = PerpMarket {
+            amm: AMM {
+                total_exchange_fee: 100,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        };
+
+        let mut creator = PredictionMarketCreator {
+            creator_fee_share: (PERCENTAGE_PRECISION_U64 * 3 / 10) as u32,
+            ..PredictionMarketCreator::default()
+        };
+
+        assert_eq!(
+            creator
+                .calculate_unclaimed_creator_fee(&perp_market)
+                .unwrap(),
+            30
+        );
+
+        // claim a single unit at a time
+        while creator
+            .calculate_unclaimed_creator_fee(&perp_market)
+            .unwrap()
+            > 0
+        {
+            creator.record_creator_fee_paid(1).unwrap();
+        }
+
+        assert_eq!(creator.total_exchange_fee_checkpoint, 100);
+        assert_eq!(creator.total_creator_fee_paid, 25);
+    }
+
+    #[test]
+    fn slashed_creator_earns_nothing() {
+        let perp_market = PerpMarket {
+            amm: AMM {
+                total_exchange_fee: 100 * QUOTE_PRECISION,
+                ..AMM::default()
+            },
+            ..PerpMarket::default()
+        };
+
+        let creator = PredictionMarketCreator {
+            creator_fee_share: (PERCENTAGE_PRECISION_U64 / 10) as u32,
+            status: PredictionMarketCreatorStatus::Slashed,
+            ..PredictionMarketCreator::default()
+        };
+
+        assert_eq!(
+            creator
+                .calculate_unclaimed_creator_fee(&perp_market)
+                .unwrap(),
+            0
+        );
+    }
+}
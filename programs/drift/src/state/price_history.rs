@@ -0,0 +1,237 @@
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use num_integer::Roots;
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::constants::PERCENTAGE_PRECISION_I128;
+use crate::math::safe_math::SafeMath;
+use crate::state::traits::Size;
+use crate::state::user::MarketType;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+pub const PERP_PRICE_HISTORY_SEED: &[u8] = b"perp_price_history";
+pub const SPOT_PRICE_HISTORY_SEED: &[u8] = b"spot_price_history";
+
+pub const PRICE_HISTORY_SAMPLES: usize = 360;
+
+#[zero_copy(unsafe)]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PriceSample {
+    pub ts: i64,
+    /// precision: PRICE_PRECISION
+    pub oracle_price: i64,
+    /// the amm reserve price for perp markets, 0 for spot markets
+    /// precision: PRICE_PRECISION
+    pub mark_price: i64,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum PriceSampleType {
+    #[default]
+    Oracle,
+    Mark,
+}
+
+impl PriceSample {
+    pub fn price(&self, price_type: PriceSampleType) -> i64 {
+        match price_type {
+            PriceSampleType::Oracle => self.oracle_price,
+            PriceSampleType::Mark => self.mark_price,
+        }
+    }
+}
+
+/// A ring of a market's oracle and mark prices, sampled at most every sample_interval seconds as
+/// the market's amm or twap stats are updated. Passed alongside the oracles in remaining accounts
+#[account(zero_copy(unsafe))]
+#[derive(Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PriceHistory {
+    pub samples: [PriceSample; PRICE_HISTORY_SAMPLES],
+    /// min seconds between samples
+    pub sample_interval: i64,
+    pub market_index: u16,
+    pub market_type: MarketType,
+    pub padding1: u8,
+    /// the index the next sample is written to
+    pub head: u16,
+    /// the number of samples written, up to PRICE_HISTORY_SAMPLES
+    pub len: u16,
+    pub padding: [u8; 8],
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        PriceHistory {
+            samples: [PriceSample::default(); PRICE_HISTORY_SAMPLES],
+            sample_interval: 0,
+            market_index: 0,
+            market_type: MarketType::default(),
+            padding1: 0,
+            head: 0,
+            len: 0,
+            padding: [0; 8],
+        }
+    }
+}
+
+impl Size for PriceHistory {
+    const SIZE: usize = 8672;
+}
+
+impl PriceHistory {
+    pub fn is_for_market(&self, market_type: MarketType, market_index: u16) -> bool {
+        self.market_type == market_type && self.market_index == market_index
+    }
+
+    pub fn validate(&self) -> DriftResult {
+        validate!(
+            self.sample_interval > 0,
+            ErrorCode::InvalidPriceHistory,
+            "sample interval must be positive"
+        )
+    }
+
+    /// Records a sample unless the last one is less than sample_interval old. Returns whether it did
+    pub fn append(&mut self, ts: i64, oracle_price: i64, mark_price: i64) -> DriftResult<bool> {
+        if let Some(latest) = self.iter().next() {
+            if ts < latest.ts.safe_add(self.sample_interval)? {
+                return Ok(false);
+            }
+        }
+
+        let head = self.head as usize % PRICE_HISTORY_SAMPLES;
+        self.samples[head] = PriceSample {
+            ts,
+            oracle_price,
+            mark_price,
+        };
+
+        self.head = ((head + 1) % PRICE_HISTORY_SAMPLES).cast()?;
+        self.len = (self.len as usize + 1).min(PRICE_HISTORY_SAMPLES).cast()?;
+
+        Ok(true)
+    }
+
+    /// Samples newest first
+    pub fn iter(&self) -> impl Iterator<Item = &PriceSample> + '_ {
+        let head = self.head as usize % PRICE_HISTORY_SAMPLES;
+        (0..(self.len as usize).min(PRICE_HISTORY_SAMPLES)).map(move |i| {
+            &self.samples[(head + PRICE_HISTORY_SAMPLES - 1 - i) % PRICE_HISTORY_SAMPLES]
+        })
+    }
+
+    /// Samples newest first back to the one in force at the window's start. None if the history
+    /// doesn't reach back to the window's start
+    fn samples_in_window(&self, now: i64, window: i64) -> DriftResult<Option<Vec<&PriceSample>>> {
+        let window_start = now.safe_sub(window)?;
+
+        let mut samples = vec![];
+        for sample in self.iter() {
+            if sample.ts > now {
+                continue;
+            }
+
+            samples.push(sample);
+
+            if sample.ts <= window_start {
+                return Ok(Some(samples));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Time weighted average price over the last `window` seconds
+    pub fn calculate_twap(
+        &self,
+        now: i64,
+        window: i64,
+        price_type: PriceSampleType,
+    ) -> DriftResult<Option<i64>> {
+        if window <= 0 {
+            return Ok(None);
+        }
+
+        let samples = match self.samples_in_window(now, window)? {
+            Some(samples) => samples,
+            None => return Ok(None),
+        };
+
+        let window_start = now.safe_sub(window)?;
+        let mut end = now;
+        let mut weighted_price_sum: i128 = 0;
+        for sample in samples.iter() {
+            let start = sample.ts.max(window_start);
+            weighted_price_sum = weighted_price_sum.safe_add(
+                sample
+                    .price(price_type)
+                    .cast::<i128>()?
+                    .safe_mul(end.safe_sub(start)?.cast()?)?,
+            )?;
+            end = start;
+        }
+
+        Ok(Some(weighted_price_sum.safe_div(window.cast()?)?.cast()?))
+    }
+
+    /// Realized volatility over the last `window` seconds, the root sum of squared sample returns
+    /// precision: PERCENTAGE_PRECISION
+    pub fn calculate_volatility(
+        &self,
+        now: i64,
+        window: i64,
+        price_type: PriceSampleType,
+    ) -> DriftResult<Option<u64>> {
+        let samples = match self.samples_in_window(now, window)? {
+            Some(samples) if samples.len() >= 2 => samples,
+            _ => return Ok(None),
+        };
+
+        let mut sum_of_squared_returns: u128 = 0;
+        for pair in samples.windows(2) {
+            let (newer, older) = (pair[0].price(price_type), pair[1].price(price_type));
+            if older <= 0 {
+                continue;
+            }
+
+            let sample_return = newer
+                .safe_sub(older)?
+                .cast::<i128>()?
+                .safe_mul(PERCENTAGE_PRECISION_I128)?
+                .safe_div(older.cast()?)?
+                .unsigned_abs();
+
+            sum_of_squared_returns =
+                sum_of_squared_returns.safe_add(sample_return.safe_mul(sample_return)?)?;
+        }
+
+        Ok(Some(sum_of_squared_returns.sqrt().cast()?))
+    }
+
+    /// Highest and lowest sampled price over the last `window` seconds
+    pub fn calculate_high_low(
+        &self,
+        now: i64,
+        window: i64,
+        price_type: PriceSampleType,
+    ) -> DriftResult<Option<(i64, i64)>> {
+        let samples = match self.samples_in_window(now, window)? {
+            Some(samples) => samples,
+            None => return Ok(None),
+        };
+
+        Ok(samples
+            .iter()
+            .map(|sample| sample.price(price_type))
+            .fold(None, |high_low, price| match high_low {
+                None => Some((price, price)),
+                Some((high, low)) => Some((high.max(price), low.min(price))),
+            }))
+    }
+}
@@ -0,0 +1,193 @@
+mod append {
+    use crate::state::price_history::{PriceHistory, PRICE_HISTORY_SAMPLES};
+
+    #[test]
+    fn respects_sample_interval() {
+        let mut price_history = PriceHistory {
+            sample_interval: 60,
+            ..PriceHistory::default()
+        };
+
+        assert!(price_history.append(0, 100, 101).unwrap());
+        assert!(!price_history.append(30, 200, 201).unwrap());
+        assert!(price_history.append(60, 300, 301).unwrap());
+
+        assert_eq!(price_history.len, 2);
+        let newest = price_history.iter().next().unwrap();
+        assert_eq!(newest.ts, 60);
+        assert_eq!(newest.oracle_price, 300);
+        assert_eq!(newest.mark_price, 301);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut price_history = PriceHistory {
+            sample_interval: 60,
+            ..PriceHistory::default()
+        };
+
+        for i in 0..400_i64 {
+            assert!(price_history.append(i * 60, i, i).unwrap());
+        }
+
+        assert_eq!(price_history.len as usize, PRICE_HISTORY_SAMPLES);
+        assert_eq!(price_history.iter().count(), PRICE_HISTORY_SAMPLES);
+        assert_eq!(price_history.iter().next().unwrap().ts, 399 * 60);
+        assert_eq!(price_history.iter().last().unwrap().ts, 40 * 60);
+    }
+}
+
+mod calculate {
+    use crate::state::price_history::{PriceHistory, PriceSampleType};
+
+    fn price_history(prices: &[i64]) -> PriceHistory {
+        let mut price_history = PriceHistory {
+            sample_interval: 60,
+            ..PriceHistory::default()
+        };
+
+        for (i, price) in prices.iter().enumerate() {
+            price_history
+                .append(i as i64 * 60, *price, *price * 2)
+                .unwrap();
+        }
+
+        price_history
+    }
+
+    #[test]
+    fn twap() {
+        let price_history = price_history(&[100, 200, 300]);
+
+        // 100 for 30s, 200 for 60s, 300 for 30s
+        let twap = price_history
+            .calculate_twap(150, 120, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(twap, Some(200));
+
+        let twap = price_history
+            .calculate_twap(150, 120, PriceSampleType::Mark)
+            .unwrap();
+        assert_eq!(twap, Some(400));
+
+        // history doesnt reach back far enough
+        let twap = price_history
+            .calculate_twap(150, 200, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(twap, None);
+
+        let twap = price_history
+            .calculate_twap(150, 0, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(twap, None);
+    }
+
+    #[test]
+    fn volatility() {
+        let price_history = price_history(&[100, 110, 99]);
+
+        // returns of 10% and -10%
+        let volatility = price_history
+            .calculate_volatility(120, 120, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(volatility, Some(141421));
+
+        // a single sample
+        let volatility = price_history
+            .calculate_volatility(120, 0, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(volatility, None);
+
+        let volatility = PriceHistory::default()
+            .calculate_volatility(120, 120, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(volatility, None);
+    }
+
+    #[test]
+    fn high_low() {
+        let price_history = price_history(&[100, 110, 99, 105]);
+
+        let high_low = price_history
+            .calculate_high_low(180, 120, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(high_low, Some((110, 99)));
+
+        let high_low = price_history
+            .calculate_high_low(180, 60, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(high_low, Some((105, 99)));
+
+        let high_low = price_history
+            .calculate_high_low(180, 500, PriceSampleType::Oracle)
+            .unwrap();
+        assert_eq!(high_low, None);
+    }
+}
+
+mod oracle_map {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Owner;
+
+    use crate::create_anchor_account_info;
+    use crate::math::oracle::OracleValidity;
+    use crate::state::load_ref::load_ref;
+    use crate::state::oracle_map::OracleMap;
+    use crate::state::price_history::PriceHistory;
+    use crate::state::user::MarketType;
+    use crate::test_utils::*;
+
+    #[test]
+    fn required_when_flagged_and_skips_invalid_prices() {
+        let mut price_history = PriceHistory {
+            sample_interval: 60,
+            market_index: 1,
+            market_type: MarketType::Perp,
+            ..PriceHistory::default()
+        };
+        create_anchor_account_info!(price_history, PriceHistory, price_history_account_info);
+
+        let mut oracle_map = OracleMap::empty();
+        assert!(oracle_map
+            .validate_price_history(MarketType::Perp, 1, true)
+            .is_err());
+        assert!(oracle_map
+            .validate_price_history(MarketType::Perp, 1, false)
+            .is_ok());
+
+        oracle_map
+            .load_market_oracle_accounts(std::slice::from_ref(&price_history_account_info))
+            .unwrap();
+        assert!(oracle_map
+            .validate_price_history(MarketType::Perp, 1, true)
+            .is_ok());
+        assert!(oracle_map
+            .validate_price_history(MarketType::Spot, 1, true)
+            .is_err());
+
+        oracle_map
+            .record_price_history(
+                MarketType::Perp,
+                1,
+                0,
+                100,
+                OracleValidity::NonPositive,
+                101,
+            )
+            .unwrap();
+        oracle_map
+            .record_price_history(
+                MarketType::Perp,
+                1,
+                60,
+                200,
+                OracleValidity::StaleForAMM,
+                201,
+            )
+            .unwrap();
+
+        let price_history = load_ref::<PriceHistory>(&price_history_account_info).unwrap();
+        assert_eq!(price_history.len, 1);
+        assert_eq!(price_history.iter().next().unwrap().oracle_price, 200);
+    }
+}
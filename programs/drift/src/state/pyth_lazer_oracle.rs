@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+use std::convert::TryInto;
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::math::casting::Cast;
+use crate::math::safe_math::SafeMath;
+use crate::state::traits::Size;
+use crate::validate;
+
+#[cfg(test)]
+mod tests;
+
+pub const PYTH_LAZER_ORACLE_SEED: &[u8] = b"pyth_lazer";
+
+pub const PYTH_LAZER_PAYLOAD_MAGIC: u32 = 2479346549;
+
+const PYTH_LAZER_PROPERTY_PRICE: u8 = 0;
+const PYTH_LAZER_PROPERTY_BEST_BID_PRICE: u8 = 1;
+const PYTH_LAZER_PROPERTY_BEST_ASK_PRICE: u8 = 2;
+const PYTH_LAZER_PROPERTY_PUBLISHER_COUNT: u8 = 3;
+const PYTH_LAZER_PROPERTY_EXPONENT: u8 = 4;
+const PYTH_LAZER_PROPERTY_CONFIDENCE: u8 = 5;
+
+/// The target slot duration, used to date a message's timestamp to a slot
+const PYTH_LAZER_MICROSECONDS_PER_SLOT: u64 = 400_000;
+
+/// A Pyth Lazer price feed, written from signed price messages verified in
+/// `update_pyth_lazer_oracle`
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct PythLazerOracle {
+    /// The key messages must be signed by
+    pub signer: Pubkey,
+    pub price: i64,
+    /// The message timestamp in microseconds
+    pub publish_time: u64,
+    /// The slot the message was published at, estimated from its timestamp against the clock
+    pub publish_slot: u64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub feed_id: u32,
+    pub padding: [u8; 16],
+}
+
+impl Size for PythLazerOracle {
+    const SIZE: usize = 96;
+}
+
+impl PythLazerOracle {
+    pub fn update(
+        &mut self,
+        feed: &PythLazerPriceFeed,
+        publish_time: u64,
+        clock_slot: u64,
+        clock_unix_timestamp: i64,
+    ) -> DriftResult {
+        validate!(
+            feed.feed_id == self.feed_id,
+            ErrorCode::InvalidPythLazerMessage,
+            "message feed id {} != oracle feed id {}",
+            feed.feed_id,
+            self.feed_id
+        )?;
+
+        validate!(
+            publish_time > self.publish_time,
+            ErrorCode::StalePythLazerMessage,
+            "message publish time {} not newer than oracle publish time {}",
+            publish_time,
+            self.publish_time
+        )?;
+
+        // a message timestamped ahead of the clock is dated to the current slot
+        let clock_time = clock_unix_timestamp.cast::<u64>()?.safe_mul(1_000_000)?;
+        let slots_since_publish = clock_time
+            .saturating_sub(publish_time)
+            .safe_div(PYTH_LAZER_MICROSECONDS_PER_SLOT)?;
+
+        self.price = feed.price;
+        self.conf = feed.confidence;
+        self.exponent = feed.exponent;
+        self.publish_time = publish_time;
+        self.publish_slot = clock_slot.saturating_sub(slots_since_publish);
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PythLazerPriceFeed {
+    pub feed_id: u32,
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct PythLazerPayload {
+    /// microseconds
+    pub timestamp: u64,
+    pub feeds: Vec<PythLazerPriceFeed>,
+}
+
+impl PythLazerPayload {
+    /// Deserializes the little endian price payload a Pyth Lazer message signs over
+    pub fn deserialize(data: &[u8]) -> DriftResult<Self> {
+        let mut reader = PayloadReader { data, offset: 0 };
+
+        let magic = u32::from_le_bytes(reader.read()?);
+        validate!(
+            magic == PYTH_LAZER_PAYLOAD_MAGIC,
+            ErrorCode::InvalidPythLazerMessage,
+            "invalid payload magic {}",
+            magic
+        )?;
+
+        let timestamp = u64::from_le_bytes(reader.read()?);
+        let _channel = reader.read::<1>()?;
+        let num_feeds = reader.read::<1>()?[0];
+
+        let mut feeds = Vec::with_capacity(num_feeds as usize);
+        for _ in 0..num_feeds {
+            let feed_id = u32::from_le_bytes(reader.read()?);
+            let num_properties = reader.read::<1>()?[0];
+
+            let mut price = None;
+            let mut confidence = 0_u64;
+            let mut exponent = None;
+            for _ in 0..num_properties {
+                match reader.read::<1>()?[0] {
+                    PYTH_LAZER_PROPERTY_PRICE => price = Some(i64::from_le_bytes(reader.read()?)),
+                    PYTH_LAZER_PROPERTY_BEST_BID_PRICE | PYTH_LAZER_PROPERTY_BEST_ASK_PRICE => {
+                        reader.read::<8>()?;
+                    }
+                    PYTH_LAZER_PROPERTY_PUBLISHER_COUNT => {
+                        reader.read::<2>()?;
+                    }
+                    PYTH_LAZER_PROPERTY_EXPONENT => {
+                        exponent = Some(i16::from_le_bytes(reader.read()?).cast::<i32>()?)
+                    }
+                    PYTH_LAZER_PROPERTY_CONFIDENCE => {
+                        confidence = i64::from_le_bytes(reader.read()?).unsigned_abs()
+                    }
+                    property => {
+                        msg!("unsupported pyth lazer property {}", property);
+                        return Err(ErrorCode::InvalidPythLazerMessage);
+                    }
+                }
+            }
+
+            let (price, exponent) = match (price, exponent) {
+                (Some(price), Some(exponent)) if price > 0 => (price, exponent),
+                _ => {
+                    msg!("pyth lazer feed {} missing price or exponent", feed_id);
+                    return Err(ErrorCode::InvalidPythLazerMessage);
+                }
+            };
+
+            feeds.push(PythLazerPriceFeed {
+                feed_id,
+                price,
+                confidence,
+                exponent,
+            });
+        }
+
+        validate!(
+            reader.offset == data.len(),
+            ErrorCode::InvalidPythLazerMessage,
+            "{} trailing bytes in payload",
+            data.len().saturating_sub(reader.offset)
+        )?;
+
+        Ok(PythLazerPayload { timestamp, feeds })
+    }
+}
+
+struct PayloadReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PayloadReader<'a> {
+    fn read<const N: usize>(&mut self) -> DriftResult<[u8; N]> {
+        let end = self.offset.safe_add(N)?;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(ErrorCode::InvalidPythLazerMessage)?
+            .try_into()
+            .map_err(|_| ErrorCode::InvalidPythLazerMessage)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,136 @@
+mod deserialize {
+    use crate::state::pyth_lazer_oracle::{
+        PythLazerPayload, PythLazerPriceFeed, PYTH_LAZER_PAYLOAD_MAGIC,
+    };
+
+    fn payload(feeds: &[(u32, Vec<(u8, Vec<u8>)>)]) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&PYTH_LAZER_PAYLOAD_MAGIC.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000_000_000_u64.to_le_bytes());
+        data.push(1); // channel
+        data.push(feeds.len() as u8);
+        for (feed_id, properties) in feeds.iter() {
+            data.extend_from_slice(&feed_id.to_le_bytes());
+            data.push(properties.len() as u8);
+            for (property, value) in properties.iter() {
+                data.push(*property);
+                data.extend_from_slice(value);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn price_feeds() {
+        let data = payload(&[
+            (
+                1,
+                vec![
+                    (0, 100_000_000_000_i64.to_le_bytes().to_vec()),
+                    (3, 10_u16.to_le_bytes().to_vec()),
+                    (4, (-8_i16).to_le_bytes().to_vec()),
+                    (5, 5_000_000_i64.to_le_bytes().to_vec()),
+                ],
+            ),
+            (
+                6,
+                vec![
+                    (1, 99_i64.to_le_bytes().to_vec()),
+                    (2, 101_i64.to_le_bytes().to_vec()),
+                    (4, (-2_i16).to_le_bytes().to_vec()),
+                    (0, 100_i64.to_le_bytes().to_vec()),
+                ],
+            ),
+        ]);
+
+        let payload = PythLazerPayload::deserialize(&data).unwrap();
+        assert_eq!(payload.timestamp, 1_700_000_000_000_000);
+        assert_eq!(
+            payload.feeds,
+            vec![
+                PythLazerPriceFeed {
+                    feed_id: 1,
+                    price: 100_000_000_000,
+                    confidence: 5_000_000,
+                    exponent: -8,
+                },
+                PythLazerPriceFeed {
+                    feed_id: 6,
+                    price: 100,
+                    confidence: 0,
+                    exponent: -2,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_payloads() {
+        // missing exponent
+        let data = payload(&[(1, vec![(0, 100_i64.to_le_bytes().to_vec())])]);
+        assert!(PythLazerPayload::deserialize(&data).is_err());
+
+        // unknown property
+        let data = payload(&[(1, vec![(42, 100_i64.to_le_bytes().to_vec())])]);
+        assert!(PythLazerPayload::deserialize(&data).is_err());
+
+        let data = payload(&[(
+            1,
+            vec![
+                (0, 100_i64.to_le_bytes().to_vec()),
+                (4, (-2_i16).to_le_bytes().to_vec()),
+            ],
+        )]);
+        assert!(PythLazerPayload::deserialize(&data).is_ok());
+
+        // truncated
+        assert!(PythLazerPayload::deserialize(&data[..data.len() - 1]).is_err());
+
+        // trailing bytes
+        let mut trailing = data.clone();
+        trailing.push(0);
+        assert!(PythLazerPayload::deserialize(&trailing).is_err());
+
+        // bad magic
+        let mut bad_magic = data;
+        bad_magic[0] ^= 1;
+        assert!(PythLazerPayload::deserialize(&bad_magic).is_err());
+    }
+}
+
+mod update {
+    use crate::state::pyth_lazer_oracle::{PythLazerOracle, PythLazerPriceFeed};
+
+    #[test]
+    fn rejects_messages_not_newer() {
+        let mut oracle = PythLazerOracle {
+            feed_id: 1,
+            ..PythLazerOracle::default()
+        };
+
+        let feed = PythLazerPriceFeed {
+            feed_id: 1,
+            price: 100,
+            confidence: 1,
+            exponent: -2,
+        };
+
+        // published 2 slots before the clock
+        oracle.update(&feed, 9_200_000, 10, 10).unwrap();
+        assert_eq!(oracle.price, 100);
+        assert_eq!(oracle.conf, 1);
+        assert_eq!(oracle.exponent, -2);
+        assert_eq!(oracle.publish_time, 9_200_000);
+        assert_eq!(oracle.publish_slot, 8);
+
+        assert!(oracle.update(&feed, 9_199_999, 11, 10).is_err());
+        assert!(oracle.update(&feed, 9_200_000, 11, 10).is_err());
+
+        // timestamped ahead of the clock
+        oracle.update(&feed, 10_100_000, 11, 10).unwrap();
+        assert_eq!(oracle.publish_slot, 11);
+
+        let other_feed = PythLazerPriceFeed { feed_id: 2, ..feed };
+        assert!(oracle.update(&other_feed, 20_000_000, 12, 20).is_err());
+    }
+}
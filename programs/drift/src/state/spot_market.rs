@@ -19,7 +19,9 @@ use crate::math::safe_math::SafeMath;
 use crate::math::spot_balance::{calculate_utilization, get_token_amount, get_token_value};
 
 use crate::math::stats::calculate_new_twap;
-use crate::state::oracle::{HistoricalIndexData, HistoricalOracleData, OracleSource};
+use crate::state::oracle::{
+    HistoricalIndexData, HistoricalOracleData, MarketOracleAccount, OracleSource,
+};
 use crate::state::paused_operations::{InsuranceFundOperation, SpotOperation};
 use crate::state::perp_market::{MarketStatus, PoolBalance};
 use crate::state::traits::{MarketIndexOffset, Size};
@@ -204,7 +206,12 @@ pub struct SpotMarket {
     pub fuel_boost_insurance: u8,
     pub token_program: u8,
     pub pool_id: u8,
-    pub padding: [u8; 40],
+    /// bitflags of the MarketOracleAccounts the market must be loaded with
+    pub oracle_accounts: u8,
+    /// k in the price -/+ k * confidence used to value deposits and borrows for margin, 0 disables it
+    /// precision: CONFIDENCE_MARGIN_MULTIPLIER_PRECISION
+    pub confidence_margin_multiplier: u8,
+    pub padding: [u8; 38],
 }
 
 impl Default for SpotMarket {
@@ -273,7 +280,9 @@ impl Default for SpotMarket {
             fuel_boost_insurance: 0,
             token_program: 0,
             pool_id: 0,
-            padding: [0; 40],
+            oracle_accounts: 0,
+            confidence_margin_multiplier: 0,
+            padding: [0; 38],
         }
     }
 }
@@ -308,6 +317,17 @@ impl SpotMarket {
         SpotOperation::is_operation_paused(self.paused_operations, operation)
     }
 
+    pub fn has_oracle_guard_rails_override(&self) -> bool {
+        MarketOracleAccount::is_required(
+            self.oracle_accounts,
+            MarketOracleAccount::GuardRailsOverride,
+        )
+    }
+
+    pub fn has_price_history(&self) -> bool {
+        MarketOracleAccount::is_required(self.oracle_accounts, MarketOracleAccount::PriceHistory)
+    }
+
     pub fn is_insurance_fund_operation_paused(&self, operation: InsuranceFundOperation) -> bool {
         InsuranceFundOperation::is_operation_paused(self.if_paused_operations, operation)
     }
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+
+use crate::error::{DriftResult, ErrorCode};
+use crate::state::order_params::TrailingStopDistance;
+use crate::state::traits::Size;
+use crate::state::user::{OrderType, User};
+
+#[cfg(test)]
+mod tests;
+
+pub const TRAILING_STOPS_PDA_SEED: &str = "TRAILING_STOPS";
+
+pub const MAX_TRAILING_STOPS: usize = 8;
+
+/// How far a perp trigger market order's trigger price trails the best oracle price seen since
+/// it was placed. Ratcheted when a keeper triggers the order or fills the user
+#[zero_copy(unsafe)]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct TrailingStop {
+    /// The best oracle price seen since the order was placed: the highest for stops triggered
+    /// below, the lowest for stops triggered above
+    /// precision: PRICE_PRECISION
+    pub water_mark: i64,
+    /// The fixed distance the trigger price trails the water mark by. 0 if it trails by a percentage
+    /// precision: PRICE_PRECISION
+    pub offset: u64,
+    /// The id of the trailing stop order. 0 if unused
+    pub order_id: u32,
+    /// The percentage of the water mark the trigger price trails it by. 0 if it trails by an offset
+    /// precision: PERCENTAGE_PRECISION
+    pub percentage: u32,
+}
+
+impl TrailingStop {
+    pub fn new(
+        order_id: u32,
+        trailing_stop_distance: TrailingStopDistance,
+        water_mark: i64,
+    ) -> Self {
+        let (offset, percentage) = match trailing_stop_distance {
+            TrailingStopDistance::Offset(offset) => (offset, 0),
+            TrailingStopDistance::Percentage(percentage) => (0, percentage),
+        };
+
+        TrailingStop {
+            water_mark,
+            offset,
+            order_id,
+            percentage,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.order_id != 0
+    }
+
+    pub fn get_distance(&self) -> TrailingStopDistance {
+        if self.offset != 0 {
+            TrailingStopDistance::Offset(self.offset)
+        } else {
+            TrailingStopDistance::Percentage(self.percentage)
+        }
+    }
+
+    /// Whether the user's order is still an untriggered trailing stop. Once it's triggered,
+    /// filled or canceled the trailing stop's slot can be reused
+    pub fn is_for_open_order(&self, user: &User) -> bool {
+        self.is_open()
+            && user
+                .get_order_index(self.order_id)
+                .map_or(false, |order_index| {
+                    let order = &user.orders[order_index];
+                    order.order_type == OrderType::TriggerMarket && !order.triggered()
+                })
+    }
+}
+
+impl Size for UserTrailingStops {
+    const SIZE: usize = 232;
+}
+
+#[account(zero_copy(unsafe))]
+#[derive(Default, Eq, PartialEq, Debug)]
+#[repr(C)]
+pub struct UserTrailingStops {
+    pub user_pubkey: Pubkey,
+    pub trailing_stops: [TrailingStop; MAX_TRAILING_STOPS],
+}
+
+impl UserTrailingStops {
+    pub fn add_trailing_stop(&mut self, user: &User, trailing_stop: TrailingStop) -> DriftResult {
+        let trailing_stop_slot = self
+            .trailing_stops
+            .iter_mut()
+            .find(|trailing_stop| !trailing_stop.is_for_open_order(user))
+            .ok_or(ErrorCode::UserTrailingStopsAccountFull)?;
+
+        *trailing_stop_slot = trailing_stop;
+
+        Ok(())
+    }
+}
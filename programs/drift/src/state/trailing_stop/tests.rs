@@ -0,0 +1,100 @@
+mod trailing_stop {
+    use crate::math::constants::{PERCENTAGE_PRECISION_U64, PRICE_PRECISION_U64};
+    use crate::state::order_params::TrailingStopDistance;
+    use crate::state::trailing_stop::TrailingStop;
+
+    #[test]
+    fn get_distance() {
+        let trailing_stop = TrailingStop::new(
+            1,
+            TrailingStopDistance::Offset(PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_U64 as i64,
+        );
+        assert_eq!(
+            trailing_stop.get_distance(),
+            TrailingStopDistance::Offset(PRICE_PRECISION_U64)
+        );
+
+        let percentage = (PERCENTAGE_PRECISION_U64 / 20) as u32;
+        let trailing_stop = TrailingStop::new(
+            1,
+            TrailingStopDistance::Percentage(percentage),
+            100 * PRICE_PRECISION_U64 as i64,
+        );
+        assert_eq!(
+            trailing_stop.get_distance(),
+            TrailingStopDistance::Percentage(percentage)
+        );
+    }
+}
+
+mod user_trailing_stops {
+    use crate::error::ErrorCode;
+    use crate::get_orders;
+    use crate::math::constants::PRICE_PRECISION_U64;
+    use crate::state::order_params::TrailingStopDistance;
+    use crate::state::trailing_stop::{TrailingStop, UserTrailingStops, MAX_TRAILING_STOPS};
+    use crate::state::user::{Order, OrderStatus, OrderTriggerCondition, OrderType, User};
+
+    fn trailing_stop_order(order_id: u32) -> Order {
+        Order {
+            status: OrderStatus::Open,
+            order_type: OrderType::TriggerMarket,
+            trigger_condition: OrderTriggerCondition::Below,
+            order_id,
+            ..Order::default()
+        }
+    }
+
+    fn trailing_stop(order_id: u32) -> TrailingStop {
+        TrailingStop::new(
+            order_id,
+            TrailingStopDistance::Offset(PRICE_PRECISION_U64),
+            100 * PRICE_PRECISION_U64 as i64,
+        )
+    }
+
+    #[test]
+    fn add_reuses_stale_slots() {
+        let mut user = User {
+            orders: get_orders!(
+                trailing_stop_order(1),
+                trailing_stop_order(2),
+                trailing_stop_order(3),
+                trailing_stop_order(4),
+                trailing_stop_order(5),
+                trailing_stop_order(6),
+                trailing_stop_order(7),
+                trailing_stop_order(8),
+                trailing_stop_order(9)
+            ),
+            ..User::default()
+        };
+        let mut user_trailing_stops = UserTrailingStops::default();
+
+        for order_id in 1..=MAX_TRAILING_STOPS as u32 {
+            user_trailing_stops
+                .add_trailing_stop(&user, trailing_stop(order_id))
+                .unwrap();
+        }
+
+        assert_eq!(
+            user_trailing_stops.add_trailing_stop(&user, trailing_stop(9)),
+            Err(ErrorCode::UserTrailingStopsAccountFull)
+        );
+
+        // triggered order frees its slot
+        user.orders[2].trigger_condition = OrderTriggerCondition::TriggeredBelow;
+        user_trailing_stops
+            .add_trailing_stop(&user, trailing_stop(9))
+            .unwrap();
+        assert_eq!(user_trailing_stops.trailing_stops[2].order_id, 9);
+
+        // canceled order frees its slot
+        user.orders[4] = Order::default();
+        user_trailing_stops
+            .add_trailing_stop(&user, trailing_stop(10))
+            .unwrap();
+        assert_eq!(user_trailing_stops.trailing_stops[4].order_id, 10);
+    }
+}
@@ -2,9 +2,11 @@ mod size {
     use crate::state::events::OrderActionRecord;
     use crate::state::fulfillment_params::serum::SerumV3FulfillmentConfig;
     use crate::state::insurance_fund_stake::InsuranceFundStake;
+    use crate::state::parent_order::UserParentOrders;
     use crate::state::perp_market::PerpMarket;
     use crate::state::spot_market::SpotMarket;
     use crate::state::state::State;
+    use crate::state::trailing_stop::UserTrailingStops;
     use crate::state::traits::Size;
     use crate::state::user::{User, UserStats};
 
@@ -63,6 +65,20 @@ mod size {
         let actual_size = InsuranceFundStake::SIZE;
         assert_eq!(actual_size, expected_size);
     }
+
+    #[test]
+    fn user_trailing_stops() {
+        let expected_size = std::mem::size_of::<UserTrailingStops>() + 8;
+        let actual_size = UserTrailingStops::SIZE;
+        assert_eq!(actual_size, expected_size);
+    }
+
+    #[test]
+    fn user_parent_orders() {
+        let expected_size = std::mem::size_of::<UserParentOrders>() + 8;
+        let actual_size = UserParentOrders::SIZE;
+        assert_eq!(actual_size, expected_size);
+    }
 }
 
 mod market_index_offset {
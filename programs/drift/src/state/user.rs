@@ -20,13 +20,13 @@ use crate::math::spot_balance::{
 };
 use crate::math::stats::calculate_rolling_sum;
 use crate::state::oracle::StrictOraclePrice;
-use crate::state::perp_market::{ContractType, PerpMarket};
+use crate::state::perp_market::{PerpMarket, PredictionMarketPriceBounds};
 use crate::state::spot_market::{SpotBalance, SpotBalanceType, SpotMarket};
 use crate::state::traits::Size;
+use crate::validate;
 use crate::{get_then_update_id, ID, PERCENTAGE_PRECISION_I64, QUOTE_PRECISION_U64};
 use crate::{math_error, SPOT_WEIGHT_PRECISION_I128};
 use crate::{safe_increment, SPOT_WEIGHT_PRECISION};
-use crate::{validate, MAX_PREDICTION_MARKET_PRICE};
 use anchor_lang::prelude::*;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::msg;
@@ -55,6 +55,7 @@ pub enum UserStatus {
     ReduceOnly = 0b00000100,
     AdvancedLp = 0b00001000,
     ProtectedMakerOrders = 0b00010000,
+    HeartbeatArmed = 0b00100000,
 }
 
 // implement SIZE const for User
@@ -129,9 +130,19 @@ pub struct User {
     pub has_open_auction: bool,
     pub margin_mode: MarginMode,
     pub pool_id: u8,
-    pub padding1: [u8; 3],
+    /// What happens when an order from this user would trade with one from another sub account
+    /// of the same authority
+    pub self_trade_prevention_mode: SelfTradePreventionMode,
+    pub padding1: [u8; 2],
     pub last_fuel_bonus_update_ts: u32,
-    pub padding: [u8; 12],
+    /// Bitmask of the order slots linked into one-cancels-other groups. A group is a run of
+    /// consecutive order ids; bracket take profit / stop loss groups follow their entry order's id.
+    /// A slot's bit is cleared whenever the slot is freed
+    pub linked_order_mask: u32,
+    /// When the heartbeat is armed, the ts after which any keeper can cancel the user's orders and
+    /// parent orders stop releasing slices. Pushed back every time the authority or delegate
+    /// refreshes the heartbeat
+    pub heartbeat_deadline_ts: i64,
 }
 
 impl User {
@@ -151,10 +162,35 @@ impl User {
         self.status & (UserStatus::AdvancedLp as u8) > 0
     }
 
+    /// The self trade prevention mode for a taker and maker of the same authority: the taker's
+    /// mode, or the maker's if the taker hasn't set one. None if the orders can trade
+    pub fn get_self_trade_prevention_mode(&self, maker: &User) -> Option<SelfTradePreventionMode> {
+        if self.authority != maker.authority {
+            return None;
+        }
+
+        match (
+            self.self_trade_prevention_mode,
+            maker.self_trade_prevention_mode,
+        ) {
+            (SelfTradePreventionMode::None, SelfTradePreventionMode::None) => None,
+            (SelfTradePreventionMode::None, maker_mode) => Some(maker_mode),
+            (taker_mode, _) => Some(taker_mode),
+        }
+    }
+
     pub fn is_protected_maker(&self) -> bool {
         self.status & (UserStatus::ProtectedMakerOrders as u8) > 0
     }
 
+    pub fn is_heartbeat_armed(&self) -> bool {
+        self.status & (UserStatus::HeartbeatArmed as u8) > 0
+    }
+
+    pub fn is_heartbeat_expired(&self, now: i64) -> bool {
+        self.is_heartbeat_armed() && now > self.heartbeat_deadline_ts
+    }
+
     pub fn add_user_status(&mut self, status: UserStatus) {
         self.status |= status as u8;
     }
@@ -274,6 +310,117 @@ impl User {
             .ok_or(ErrorCode::OrderDoesNotExist)
     }
 
+    pub fn is_linked_order(&self, order_index: usize) -> bool {
+        self.linked_order_mask & (1 << order_index) != 0
+    }
+
+    fn get_linked_order_index(&self, order_id: u32) -> Option<usize> {
+        self.get_order_index(order_id)
+            .ok()
+            .filter(|order_index| self.is_linked_order(*order_index))
+    }
+
+    /// Bitmask of the order slots in the same one-cancels-other group as the order at
+    /// `order_index`, including it
+    pub fn get_linked_order_group_mask(&self, order_index: usize) -> u32 {
+        if !self.is_linked_order(order_index) {
+            return 0;
+        }
+
+        let order_id = self.orders[order_index].order_id;
+        let mut linked_order_mask = 1 << order_index;
+
+        let mut prev_order_id = order_id;
+        while let Some(linked_order_index) = prev_order_id
+            .checked_sub(1)
+            .and_then(|order_id| self.get_linked_order_index(order_id))
+        {
+            linked_order_mask |= 1 << linked_order_index;
+            prev_order_id -= 1;
+        }
+
+        let mut next_order_id = order_id;
+        while let Some(linked_order_index) = next_order_id
+            .checked_add(1)
+            .and_then(|order_id| self.get_linked_order_index(order_id))
+        {
+            linked_order_mask |= 1 << linked_order_index;
+            next_order_id += 1;
+        }
+
+        linked_order_mask
+    }
+
+    /// Whether the order at `order_index` is a bracket order whose entry order hasn't filled yet
+    pub fn is_pending_bracket_order(&self, order_index: usize) -> bool {
+        let linked_order_mask = self.get_linked_order_group_mask(order_index);
+
+        let first_order_id = self
+            .orders
+            .iter()
+            .enumerate()
+            .filter(|(linked_order_index, _)| linked_order_mask & (1 << linked_order_index) != 0)
+            .map(|(_, order)| order.order_id)
+            .min();
+
+        first_order_id
+            .and_then(|order_id| order_id.checked_sub(1))
+            .and_then(|entry_order_id| self.get_order_index(entry_order_id).ok())
+            .map_or(false, |entry_order_index| {
+                !self.is_linked_order(entry_order_index)
+                    && self.orders[entry_order_index].base_asset_amount_filled == 0
+            })
+    }
+
+    /// Bitmask of the bracket orders still waiting on the entry order at `order_index` to fill
+    pub fn get_pending_bracket_order_mask(&self, order_index: usize) -> u32 {
+        let order = &self.orders[order_index];
+        if self.is_linked_order(order_index) || order.base_asset_amount_filled != 0 {
+            return 0;
+        }
+
+        order
+            .order_id
+            .checked_add(1)
+            .and_then(|order_id| self.get_linked_order_index(order_id))
+            .map_or(0, |linked_order_index| {
+                self.get_linked_order_group_mask(linked_order_index)
+            })
+    }
+
+    /// Whether the linked orders in `linked_order_mask` are a perp take profit / stop loss bracket,
+    /// i.e. reduce only trigger orders in the same direction
+    pub fn is_bracket_order_group(&self, linked_order_mask: u32) -> bool {
+        let mut linked_orders = self
+            .orders
+            .iter()
+            .enumerate()
+            .filter(|(linked_order_index, _)| linked_order_mask & (1 << linked_order_index) != 0)
+            .map(|(_, order)| order);
+
+        let first_order = match linked_orders.next() {
+            Some(order) => *order,
+            None => return false,
+        };
+
+        first_order.market_type == MarketType::Perp
+            && first_order.reduce_only
+            && first_order.must_be_triggered()
+            && linked_orders.all(|order| {
+                order.reduce_only
+                    && order.must_be_triggered()
+                    && order.direction == first_order.direction
+            })
+    }
+
+    pub fn link_orders(&mut self, linked_order_mask: u32) {
+        self.linked_order_mask |= linked_order_mask;
+    }
+
+    pub fn unlink_orders(&mut self, linked_order_mask: u32) {
+        self.linked_order_mask &= !linked_order_mask;
+    }
+
     pub fn get_order(&self, order_id: u32) -> Option<&Order> {
         self.orders.iter().find(|order| order.order_id == order_id)
     }
@@ -416,6 +563,20 @@ impl User {
         Ok(())
     }
 
+    /// Arms the heartbeat with a deadline `heartbeat_timeout` seconds from now. A timeout of 0
+    /// disarms it
+    pub fn update_heartbeat(&mut self, now: i64, heartbeat_timeout: u32) -> DriftResult {
+        if heartbeat_timeout == 0 {
+            self.remove_user_status(UserStatus::HeartbeatArmed);
+            self.heartbeat_deadline_ts = 0;
+        } else {
+            self.add_user_status(UserStatus::HeartbeatArmed);
+            self.heartbeat_deadline_ts = now.safe_add(heartbeat_timeout.cast()?)?;
+        }
+
+        Ok(())
+    }
+
     pub fn update_protected_maker_orders_status(
         &mut self,
         protected_maker_orders: bool,
@@ -1073,16 +1234,16 @@ impl PerpPosition {
     pub fn worst_case_base_asset_amount(
         &self,
         oracle_price: i64,
-        contract_type: ContractType,
+        prediction_price_bounds: Option<PredictionMarketPriceBounds>,
     ) -> DriftResult<i128> {
-        self.worst_case_liability_value(oracle_price, contract_type)
+        self.worst_case_liability_value(oracle_price, prediction_price_bounds)
             .map(|v| v.0)
     }
 
     pub fn worst_case_liability_value(
         &self,
         oracle_price: i64,
-        contract_type: ContractType,
+        prediction_price_bounds: Option<PredictionMarketPriceBounds>,
     ) -> DriftResult<(i128, u128)> {
         let base_asset_amount_all_bids_fill = self
             .base_asset_amount
@@ -1096,13 +1257,13 @@ impl PerpPosition {
         let liability_value_all_bids_fill = calculate_perp_liability_value(
             base_asset_amount_all_bids_fill,
             oracle_price,
-            contract_type,
+            prediction_price_bounds,
         )?;
 
         let liability_value_all_asks_fill = calculate_perp_liability_value(
             base_asset_amount_all_asks_fill,
             oracle_price,
-            contract_type,
+            prediction_price_bounds,
         )?;
 
         if liability_value_all_asks_fill >= liability_value_all_bids_fill {
@@ -1286,7 +1447,11 @@ pub struct Order {
     pub trigger_condition: OrderTriggerCondition,
     /// How many slots the auction lasts
     pub auction_duration: u8,
-    pub padding: [u8; 3],
+    /// What price the trigger condition is checked against. Only relevant for trigger orders
+    pub trigger_source: OrderTriggerSource,
+    /// The perp market whose oracle or outcome triggers the order. Only relevant for trigger orders
+    /// with a linked market trigger source
+    pub trigger_market_index: u16,
 }
 
 #[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug)]
@@ -1310,7 +1475,7 @@ impl Order {
         fallback_price: Option<u64>,
         slot: u64,
         tick_size: u64,
-        is_prediction_market: bool,
+        prediction_price_bounds: Option<PredictionMarketPriceBounds>,
     ) -> DriftResult<Option<u64>> {
         let price = if self.has_auction_price(self.slot, self.auction_duration, slot)? {
             Some(calculate_auction_price(
@@ -1318,7 +1483,7 @@ impl Order {
                 slot,
                 tick_size,
                 valid_oracle_price,
-                is_prediction_market,
+                prediction_price_bounds,
             )?)
         } else if self.has_oracle_price_offset() {
             let oracle_price = valid_oracle_price.ok_or_else(|| {
@@ -1331,8 +1496,8 @@ impl Order {
                 .max(tick_size.cast()?)
                 .cast::<u64>()?;
 
-            if is_prediction_market {
-                limit_price = limit_price.min(MAX_PREDICTION_MARKET_PRICE)
+            if let Some(prediction_price_bounds) = prediction_price_bounds {
+                limit_price = prediction_price_bounds.clamp_price(limit_price)
             }
 
             Some(standardize_price(limit_price, tick_size, self.direction)?)
@@ -1356,14 +1521,14 @@ impl Order {
         fallback_price: Option<u64>,
         slot: u64,
         tick_size: u64,
-        is_prediction_market: bool,
+        prediction_price_bounds: Option<PredictionMarketPriceBounds>,
     ) -> DriftResult<u64> {
         match self.get_limit_price(
             valid_oracle_price,
             fallback_price,
             slot,
             tick_size,
-            is_prediction_market,
+            prediction_price_bounds,
         )? {
             Some(price) => Ok(price),
             None => {
@@ -1466,6 +1631,10 @@ impl Order {
         )
     }
 
+    pub fn is_triggered_by_linked_market(&self) -> bool {
+        self.trigger_source != OrderTriggerSource::Oracle
+    }
+
     pub fn triggered(&self) -> bool {
         matches!(
             self.trigger_condition,
@@ -1538,7 +1707,8 @@ impl Default for Order {
             auction_end_price: 0,
             auction_duration: 0,
             max_ts: 0,
-            padding: [0; 3],
+            trigger_source: OrderTriggerSource::Oracle,
+            trigger_market_index: 0,
         }
     }
 }
@@ -1575,7 +1745,20 @@ pub enum OrderTriggerCondition {
     TriggeredBelow, // below condition has been triggered
 }
 
-#[derive(Default, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq)]
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum OrderTriggerSource {
+    /// the oracle price of the order's market
+    #[default]
+    Oracle,
+    /// the oracle price of the trigger market
+    LinkedMarketOracle,
+    /// the expiry price of the trigger market once it has resolved
+    LinkedMarketOutcome,
+}
+
+#[derive(
+    Default, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, PartialOrd, Ord,
+)]
 pub enum MarketType {
     #[default]
     Spot,
@@ -1900,3 +2083,18 @@ pub enum MarginMode {
     Default,
     HighLeverage,
 }
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Debug, Eq, Default)]
+pub enum SelfTradePreventionMode {
+    /// Sub accounts of the same authority can trade with each other
+    #[default]
+    None,
+    /// The maker order is canceled and the taker order keeps filling
+    CancelMaker,
+    /// The taker order is canceled
+    CancelTaker,
+    /// Both orders are canceled
+    CancelBoth,
+    /// The smaller order is canceled and the larger one is reduced by its size
+    DecrementAndCancel,
+}
@@ -626,6 +626,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -651,6 +652,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -676,6 +678,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -723,6 +726,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
 
         let OrderFillSimulation {
@@ -749,6 +753,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
 
         let OrderFillSimulation {
@@ -775,6 +780,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
 
         let OrderFillSimulation {
@@ -823,6 +829,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -848,6 +855,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -873,6 +881,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -920,6 +929,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -945,6 +955,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -970,6 +981,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1017,6 +1029,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1042,6 +1055,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1067,6 +1081,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1114,6 +1129,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1139,6 +1155,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1164,6 +1181,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1211,6 +1229,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1236,6 +1255,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1261,6 +1281,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1308,6 +1329,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: None,
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1333,6 +1355,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(110 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1358,6 +1381,7 @@ mod get_worst_case_fill_simulation {
         let strict_price = StrictOraclePrice {
             current: oracle_price_data.price,
             twap_5min: Some(90 * PRICE_PRECISION_I64),
+            confidence_haircut: 0,
         };
         let OrderFillSimulation {
             token_amount: worst_case_token_amount,
@@ -1894,16 +1918,17 @@ mod fuel {
 }
 
 mod worst_case_liability_value {
-    use crate::state::perp_market::ContractType;
+    use crate::state::perp_market::PredictionMarketPriceBounds;
     use crate::state::user::PerpPosition;
     use crate::{
         BASE_PRECISION_I128, BASE_PRECISION_I64, MAX_PREDICTION_MARKET_PRICE_I64,
-        MAX_PREDICTION_MARKET_PRICE_U128, PRICE_PRECISION_I64, QUOTE_PRECISION,
+        MAX_PREDICTION_MARKET_PRICE_U128, PRICE_PRECISION_I64, PRICE_PRECISION_U64,
+        QUOTE_PRECISION,
     };
 
     #[test]
     fn prediction() {
-        let contract_type = ContractType::Prediction;
+        let prediction_price_bounds = Some(PredictionMarketPriceBounds::default());
         let position = PerpPosition {
             base_asset_amount: 0,
             open_bids: BASE_PRECISION_I64,
@@ -1914,7 +1939,7 @@ mod worst_case_liability_value {
         let price = MAX_PREDICTION_MARKET_PRICE_I64 * 3 / 4;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, BASE_PRECISION_I128);
@@ -1923,7 +1948,7 @@ mod worst_case_liability_value {
         let price = MAX_PREDICTION_MARKET_PRICE_I64 / 4;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, -BASE_PRECISION_I128);
@@ -1939,7 +1964,7 @@ mod worst_case_liability_value {
         let price = MAX_PREDICTION_MARKET_PRICE_I64 / 100;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, -BASE_PRECISION_I128);
@@ -1955,16 +1980,61 @@ mod worst_case_liability_value {
         let price = MAX_PREDICTION_MARKET_PRICE_I64 * 99 / 100;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, BASE_PRECISION_I128);
         assert_eq!(worst_case_loss, MAX_PREDICTION_MARKET_PRICE_U128 * 99 / 100);
     }
 
+    #[test]
+    fn scalar_prediction() {
+        // pays out linearly between $2 and $4
+        let prediction_price_bounds = Some(PredictionMarketPriceBounds {
+            lower: 2 * PRICE_PRECISION_U64,
+            upper: 4 * PRICE_PRECISION_U64,
+        });
+        let position = PerpPosition {
+            base_asset_amount: 0,
+            open_bids: BASE_PRECISION_I64,
+            open_asks: -BASE_PRECISION_I64,
+            ..PerpPosition::default()
+        };
+
+        // long can lose down to the lower bound
+        let price = 7 * PRICE_PRECISION_I64 / 2;
+
+        let (worst_case_base_asset_amount, worst_case_loss) = position
+            .worst_case_liability_value(price, prediction_price_bounds)
+            .unwrap();
+
+        assert_eq!(worst_case_base_asset_amount, BASE_PRECISION_I128);
+        assert_eq!(worst_case_loss, 3 * QUOTE_PRECISION / 2);
+
+        // short can lose up to the upper bound
+        let price = 5 * PRICE_PRECISION_I64 / 2;
+
+        let (worst_case_base_asset_amount, worst_case_loss) = position
+            .worst_case_liability_value(price, prediction_price_bounds)
+            .unwrap();
+
+        assert_eq!(worst_case_base_asset_amount, -BASE_PRECISION_I128);
+        assert_eq!(worst_case_loss, 3 * QUOTE_PRECISION / 2);
+
+        // price outside of the band
+        let price = 5 * PRICE_PRECISION_I64;
+
+        let (worst_case_base_asset_amount, worst_case_loss) = position
+            .worst_case_liability_value(price, prediction_price_bounds)
+            .unwrap();
+
+        assert_eq!(worst_case_base_asset_amount, BASE_PRECISION_I128);
+        assert_eq!(worst_case_loss, 3 * QUOTE_PRECISION);
+    }
+
     #[test]
     fn perp() {
-        let contract_type = ContractType::Perpetual;
+        let prediction_price_bounds = None;
         let position = PerpPosition {
             base_asset_amount: 0,
             open_bids: BASE_PRECISION_I64,
@@ -1975,13 +2045,12 @@ mod worst_case_liability_value {
         let price = 100 * PRICE_PRECISION_I64;
 
         let (worst_case_base_asset_amount, worst_case_liability) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, -BASE_PRECISION_I128);
         assert_eq!(worst_case_liability, 100 * QUOTE_PRECISION);
 
-        let contract_type = ContractType::Perpetual;
         let position = PerpPosition {
             base_asset_amount: 0,
             open_bids: 2 * BASE_PRECISION_I64,
@@ -1992,7 +2061,7 @@ mod worst_case_liability_value {
         let price = 100 * PRICE_PRECISION_I64;
 
         let (worst_case_base_asset_amount, worst_case_liability) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, 2 * BASE_PRECISION_I128);
@@ -2008,7 +2077,7 @@ mod worst_case_liability_value {
         let price = 100 * PRICE_PRECISION_I64;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, 98 * BASE_PRECISION_I128);
@@ -2024,7 +2093,7 @@ mod worst_case_liability_value {
         let price = 100 * PRICE_PRECISION_I64;
 
         let (worst_case_base_asset_amount, worst_case_loss) = position
-            .worst_case_liability_value(price, contract_type)
+            .worst_case_liability_value(price, prediction_price_bounds)
             .unwrap();
 
         assert_eq!(worst_case_base_asset_amount, -98 * BASE_PRECISION_I128);
@@ -2033,8 +2102,12 @@ mod worst_case_liability_value {
 }
 
 mod get_limit_price {
+    use crate::state::perp_market::PredictionMarketPriceBounds;
     use crate::state::user::{Order, OrderType};
-    use crate::{PositionDirection, MAX_PREDICTION_MARKET_PRICE, MAX_PREDICTION_MARKET_PRICE_I64};
+    use crate::{
+        PositionDirection, MAX_PREDICTION_MARKET_PRICE, MAX_PREDICTION_MARKET_PRICE_I64,
+        PRICE_PRECISION_I64, PRICE_PRECISION_U64,
+    };
 
     #[test]
     fn prediction_market() {
@@ -2047,7 +2120,13 @@ mod get_limit_price {
         let oracle_price = Some(MAX_PREDICTION_MARKET_PRICE_I64 / 2);
 
         let limit_price = order
-            .get_limit_price(oracle_price, None, 0, 1, true)
+            .get_limit_price(
+                oracle_price,
+                None,
+                0,
+                1,
+                Some(PredictionMarketPriceBounds::default()),
+            )
             .unwrap();
 
         assert_eq!(limit_price, Some(MAX_PREDICTION_MARKET_PRICE));
@@ -2059,7 +2138,13 @@ mod get_limit_price {
         };
 
         let limit_price = order
-            .get_limit_price(oracle_price, None, 0, 1, true)
+            .get_limit_price(
+                oracle_price,
+                None,
+                0,
+                1,
+                Some(PredictionMarketPriceBounds::default()),
+            )
             .unwrap();
 
         assert_eq!(limit_price, Some(1));
@@ -2075,7 +2160,13 @@ mod get_limit_price {
         };
 
         let limit_price = order
-            .get_limit_price(oracle_price, None, 2, 1, true)
+            .get_limit_price(
+                oracle_price,
+                None,
+                2,
+                1,
+                Some(PredictionMarketPriceBounds::default()),
+            )
             .unwrap();
 
         assert_eq!(limit_price, Some(MAX_PREDICTION_MARKET_PRICE));
@@ -2092,11 +2183,52 @@ mod get_limit_price {
         };
 
         let limit_price = order
-            .get_limit_price(oracle_price, None, 2, 1, true)
+            .get_limit_price(
+                oracle_price,
+                None,
+                2,
+                1,
+                Some(PredictionMarketPriceBounds::default()),
+            )
             .unwrap();
 
         assert_eq!(limit_price, Some(1));
     }
+
+    #[test]
+    fn scalar_prediction_market() {
+        let prediction_price_bounds = Some(PredictionMarketPriceBounds {
+            lower: 2 * PRICE_PRECISION_U64,
+            upper: 4 * PRICE_PRECISION_U64,
+        });
+
+        let oracle_price = Some(3 * PRICE_PRECISION_I64);
+
+        let order = Order {
+            order_type: OrderType::Limit,
+            oracle_price_offset: 2 * PRICE_PRECISION_I64 as i32,
+            ..Order::default()
+        };
+
+        let limit_price = order
+            .get_limit_price(oracle_price, None, 0, 1, prediction_price_bounds)
+            .unwrap();
+
+        assert_eq!(limit_price, Some(4 * PRICE_PRECISION_U64));
+
+        let order = Order {
+            order_type: OrderType::Limit,
+            direction: PositionDirection::Short,
+            oracle_price_offset: -2 * PRICE_PRECISION_I64 as i32,
+            ..Order::default()
+        };
+
+        let limit_price = order
+            .get_limit_price(oracle_price, None, 0, 1, prediction_price_bounds)
+            .unwrap();
+
+        assert_eq!(limit_price, Some(2 * PRICE_PRECISION_U64));
+    }
 }
 
 mod update_referrer_status {
@@ -2137,3 +2269,68 @@ mod update_referrer_status {
         assert_eq!(user_stats.referrer_status, 1);
     }
 }
+
+mod get_self_trade_prevention_mode {
+    use crate::state::user::{SelfTradePreventionMode, User};
+    use anchor_lang::prelude::Pubkey;
+
+    #[test]
+    fn test() {
+        let authority = Pubkey::new_unique();
+
+        let mut taker = User {
+            authority,
+            ..User::default()
+        };
+        let mut maker = User {
+            authority,
+            ..User::default()
+        };
+
+        assert_eq!(taker.get_self_trade_prevention_mode(&maker), None);
+
+        maker.self_trade_prevention_mode = SelfTradePreventionMode::CancelMaker;
+        assert_eq!(
+            taker.get_self_trade_prevention_mode(&maker),
+            Some(SelfTradePreventionMode::CancelMaker)
+        );
+
+        // the taker's mode takes precedence
+        taker.self_trade_prevention_mode = SelfTradePreventionMode::DecrementAndCancel;
+        assert_eq!(
+            taker.get_self_trade_prevention_mode(&maker),
+            Some(SelfTradePreventionMode::DecrementAndCancel)
+        );
+
+        // only evaluated for the same authority
+        maker.authority = Pubkey::new_unique();
+        assert_eq!(taker.get_self_trade_prevention_mode(&maker), None);
+    }
+}
+
+mod update_heartbeat {
+    use crate::state::user::User;
+
+    #[test]
+    fn test() {
+        let mut user = User::default();
+        assert!(!user.is_heartbeat_armed());
+        assert!(!user.is_heartbeat_expired(i64::MAX));
+
+        user.update_heartbeat(100, 30).unwrap();
+        assert!(user.is_heartbeat_armed());
+        assert_eq!(user.heartbeat_deadline_ts, 130);
+        assert!(!user.is_heartbeat_expired(130));
+        assert!(user.is_heartbeat_expired(131));
+
+        // refreshing pushes the deadline back
+        user.update_heartbeat(120, 30).unwrap();
+        assert_eq!(user.heartbeat_deadline_ts, 150);
+        assert!(!user.is_heartbeat_expired(131));
+
+        user.update_heartbeat(140, 0).unwrap();
+        assert!(!user.is_heartbeat_armed());
+        assert_eq!(user.heartbeat_deadline_ts, 0);
+        assert!(!user.is_heartbeat_expired(i64::MAX));
+    }
+}
@@ -10,7 +10,7 @@ use crate::math::orders::{
 use crate::state::paused_operations::PerpOperation;
 use crate::state::perp_market::PerpMarket;
 use crate::state::user::{Order, OrderTriggerCondition, OrderType};
-use crate::{validate, MAX_PREDICTION_MARKET_PRICE};
+use crate::validate;
 
 #[cfg(test)]
 mod test;
@@ -41,29 +41,35 @@ pub fn validate_order(
         }
     }
 
-    if market.is_prediction_market() {
+    if let Some(prediction_price_bounds) = market.get_prediction_market_price_bounds() {
+        let max_price = prediction_price_bounds.upper;
+
         validate!(
-            order.price <= MAX_PREDICTION_MARKET_PRICE,
+            order.price <= max_price,
             ErrorCode::InvalidPredictionMarketOrder,
-            "prediction market price must be <= 1"
+            "prediction market price must be <= {}",
+            max_price
         )?;
 
         validate!(
-            order.auction_start_price.unsigned_abs() <= MAX_PREDICTION_MARKET_PRICE,
+            order.auction_start_price.unsigned_abs() <= max_price,
             ErrorCode::InvalidPredictionMarketOrder,
-            "prediction market auction start price abs must be <= 1"
+            "prediction market auction start price abs must be <= {}",
+            max_price
         )?;
 
         validate!(
-            order.auction_end_price.unsigned_abs() <= MAX_PREDICTION_MARKET_PRICE,
+            order.auction_end_price.unsigned_abs() <= max_price,
             ErrorCode::InvalidPredictionMarketOrder,
-            "prediction market auction end price abs must be <= 1"
+            "prediction market auction end price abs must be <= {}",
+            max_price
         )?;
 
         validate!(
-            order.oracle_price_offset.unsigned_abs() <= MAX_PREDICTION_MARKET_PRICE as u32,
+            order.oracle_price_offset.unsigned_abs().cast::<u64>()? <= max_price,
             ErrorCode::InvalidPredictionMarketOrder,
-            "prediction market auction end price abs must be <= 1"
+            "prediction market auction end price abs must be <= {}",
+            max_price
         )?;
     }
 
@@ -219,7 +225,7 @@ fn validate_post_only_order(
         None,
         slot,
         market.amm.order_tick_size,
-        market.is_prediction_market(),
+        market.get_prediction_market_price_bounds(),
     )?;
 
     let base_asset_amount_market_can_fill = calculate_base_asset_amount_to_fill_up_to_limit_price(
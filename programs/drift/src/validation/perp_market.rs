@@ -1,10 +1,12 @@
 use crate::controller::position::PositionDirection;
 use crate::error::{DriftResult, ErrorCode};
 use crate::math::casting::Cast;
-use crate::math::constants::MAX_BASE_ASSET_AMOUNT_WITH_AMM;
+use crate::math::constants::{
+    MAX_BASE_ASSET_AMOUNT_WITH_AMM, PREDICTION_MARKET_PRICE_BAND_PRECISION,
+};
 use crate::math::safe_math::SafeMath;
 
-use crate::state::perp_market::{MarketStatus, PerpMarket, AMM};
+use crate::state::perp_market::{MarketStatus, PerpMarket, PredictionFeeCurve, AMM};
 use crate::{validate, BID_ASK_SPREAD_PRECISION};
 use solana_program::msg;
 
@@ -32,18 +34,21 @@ pub fn validate_perp_market(market: &PerpMarket) -> DriftResult {
     validate!(
         (market.amm.base_asset_amount_long + market.amm.base_asset_amount_short)
             == market.amm.base_asset_amount_with_amm
-                + market.amm.base_asset_amount_with_unsettled_lp,
+                + market.amm.base_asset_amount_with_unsettled_lp
+                + market.amm.base_asset_amount_with_complete_sets as i128,
         ErrorCode::InvalidAmmDetected,
         "Market NET_BAA Error: 
         market.amm.base_asset_amount_long={}, 
         + market.amm.base_asset_amount_short={} 
         != 
         market.amm.base_asset_amount_with_amm={}
-        +  market.amm.base_asset_amount_with_unsettled_lp={}",
+        +  market.amm.base_asset_amount_with_unsettled_lp={}
+        +  market.amm.base_asset_amount_with_complete_sets={}",
         market.amm.base_asset_amount_long,
         market.amm.base_asset_amount_short,
         market.amm.base_asset_amount_with_amm,
         market.amm.base_asset_amount_with_unsettled_lp,
+        market.amm.base_asset_amount_with_complete_sets,
     )?;
 
     validate!(
@@ -59,7 +64,54 @@ pub fn validate_perp_market(market: &PerpMarket) -> DriftResult {
         "peg_multiplier out of wack"
     )?;
 
-    if market.status != MarketStatus::ReduceOnly {
+    if market.is_prediction_market() {
+        let (min_ask, max_bid) = market.get_prediction_market_min_ask_max_bid();
+        validate!(
+            min_ask < max_bid && max_bid < PREDICTION_MARKET_PRICE_BAND_PRECISION,
+            ErrorCode::InvalidPredictionMarketPriceBounds,
+            "invalid prediction market min ask {} / max bid {}",
+            min_ask,
+            max_bid
+        )?;
+    }
+
+    if market.prediction_fee_curve != PredictionFeeCurve::Flat {
+        validate!(
+            market.is_prediction_market(),
+            ErrorCode::InvalidPredictionFeeCurve,
+            "fee curve {:?} requires a prediction market",
+            market.prediction_fee_curve
+        )?;
+
+        let fee_cap = if market.prediction_fee_cap == 0 {
+            PREDICTION_MARKET_PRICE_BAND_PRECISION
+        } else {
+            market.prediction_fee_cap.cast()?
+        };
+
+        validate!(
+            market.prediction_fee_floor.cast::<u64>()? <= fee_cap
+                && fee_cap <= PREDICTION_MARKET_PRICE_BAND_PRECISION,
+            ErrorCode::InvalidPredictionFeeCurve,
+            "invalid prediction fee floor {} / cap {}",
+            market.prediction_fee_floor,
+            market.prediction_fee_cap
+        )?;
+    }
+
+    if market.is_scalar_market() {
+        validate!(
+            market.scalar_lower_bound < market.scalar_upper_bound
+                && !market.is_prediction_event_outcome(),
+            ErrorCode::InvalidPredictionMarketPriceBounds,
+            "invalid scalar market bounds [{}, {}]",
+            market.scalar_lower_bound,
+            market.scalar_upper_bound
+        )?;
+    }
+
+    // lmsr depth comes from its liquidity parameter rather than k
+    if market.status != MarketStatus::ReduceOnly && !market.amm.is_lmsr() {
         validate!(
             market.amm.sqrt_k > market.amm.base_asset_amount_with_amm.unsigned_abs(),
             ErrorCode::InvalidAmmDetected,